@@ -0,0 +1,107 @@
+#ifndef GLTFGEN_H
+#define GLTFGEN_H
+
+/* This file is generated by cbindgen. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Frames to be exported along with the options used to export them.
+typedef struct GltfgenContext GltfgenContext;
+
+// Creates a new context with default options.
+//
+// The context must be freed with [`gltfgen_context_free`].
+GltfgenContext *gltfgen_context_new(void);
+
+// Frees a context created with [`gltfgen_context_new`].
+//
+// # Safety
+//
+// `ctx` must be null or a pointer returned by [`gltfgen_context_new`] that
+// has not been freed yet.
+void gltfgen_context_free(GltfgenContext *ctx);
+
+// Returns the message of the last error, or null if the last call succeeded.
+//
+// The message is owned by the context and valid until the next call using it.
+//
+// # Safety
+//
+// `ctx` must be a valid context.
+const char *gltfgen_last_error(const GltfgenContext *ctx);
+
+// Sets the export options from a JSON string.
+//
+// Field names and defaults match those of the configuration file, e.g.
+// `{"fps": 30, "attributes": {"pressure": "f32"}}`.
+//
+// # Safety
+//
+// `ctx` must be a valid context and `json` a null terminated string.
+int gltfgen_set_config(GltfgenContext *ctx, const char *json);
+
+// Adds a frame of the object `name` from raw arrays.
+//
+// `positions` holds `3 * num_vertices` coordinates. `indices` holds
+// `3 * num_triangles` vertex indices, or is null to add a point cloud.
+//
+// # Safety
+//
+// `ctx` must be a valid context, `name` a null terminated string, and the
+// arrays must hold the given number of elements.
+int gltfgen_add_frame(GltfgenContext *ctx,
+                      const char *name,
+                      uint32_t frame,
+                      const float *positions,
+                      uintptr_t num_vertices,
+                      const uint32_t *indices,
+                      uintptr_t num_triangles);
+
+// Adds a vertex attribute to the most recently added frame.
+//
+// `data` holds `num_components` values for each vertex of the frame, where
+// `num_components` is between 1 and 4.
+//
+// # Safety
+//
+// `ctx` must be a valid context, `name` a null terminated string, and `data`
+// must hold `num_components` values per vertex.
+int gltfgen_add_attribute(GltfgenContext *ctx,
+                          const char *name,
+                          const float *data,
+                          uintptr_t num_components);
+
+// Writes all added frames to a glTF file at `path`.
+//
+// The format is determined by the extension as on the command line. The
+// frames are removed from the context even if the export fails, so it can be
+// reused for another export.
+//
+// # Safety
+//
+// `ctx` must be a valid context and `path` a null terminated string.
+int gltfgen_export_to_path(GltfgenContext *ctx, const char *path);
+
+// Builds a GLB file from all added frames in memory.
+//
+// The frames are removed from the context even if the export fails. On
+// success `data` and `len` are set to the contents, which must be freed with
+// [`gltfgen_buffer_free`].
+//
+// # Safety
+//
+// `ctx` must be a valid context, and `data` and `len` valid pointers.
+int gltfgen_export_to_buffer(GltfgenContext *ctx, uint8_t **data, uintptr_t *len);
+
+// Frees a buffer returned by [`gltfgen_export_to_buffer`].
+//
+// # Safety
+//
+// `data` and `len` must be null or exactly as returned by
+// [`gltfgen_export_to_buffer`].
+void gltfgen_buffer_free(uint8_t *data, uintptr_t len);
+
+#endif /* GLTFGEN_H */
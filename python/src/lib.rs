@@ -0,0 +1,310 @@
+//! Python bindings for `gltfgen`.
+//!
+//! Build and install into the active environment with `maturin develop` from
+//! this directory, then:
+//!
+//! ```python
+//! import gltfgen
+//! import numpy as np
+//!
+//! frames = [gltfgen.Mesh(positions, triangles) for positions in simulation]
+//! gltfgen.export_named_meshes([("cloth", mesh) for mesh in frames], "cloth.glb", fps=30)
+//! ```
+//!
+//! The tests in `tests` are run with `pytest` against the installed module.
+
+#![cfg(feature = "python")]
+
+use std::path::PathBuf;
+
+use gltfgen::export::{self, ExportConfig};
+use gltfgen::mesh::{Mesh, RawAttribute};
+use gltfgen::time::FrameRate;
+use gltfgen::{AttribConfig, AttributeInfo, LoadConfig, TextureAttributeInfo};
+use numpy::ndarray::{ArrayView2, ArrayViewD, Axis};
+use numpy::{AllowTypeChange, PyArrayLike2, PyArrayLikeDyn};
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// A triangle mesh or point cloud.
+#[pyclass(name = "Mesh", module = "gltfgen")]
+#[derive(Clone)]
+struct PyMesh {
+    mesh: Mesh,
+}
+
+#[pymethods]
+impl PyMesh {
+    /// Creates a triangle mesh from an `(n, 3)` array of vertex positions and an
+    /// `(m, 3)` array of vertex indices, or a point cloud if no triangles are given.
+    ///
+    /// Vertex attributes are given as a dict of float arrays of shape `(n,)` or
+    /// `(n, k)` with `k` between 2 and 4.
+    #[new]
+    #[pyo3(signature = (positions, triangles=None, attributes=None))]
+    fn new(
+        positions: PyArrayLike2<'_, f32, AllowTypeChange>,
+        triangles: Option<PyArrayLike2<'_, i64, AllowTypeChange>>,
+        attributes: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let positions: Vec<[f32; 3]> = rows(positions.as_array(), "positions")?;
+        let attributes = raw_attributes(attributes, positions.len())?;
+        let mesh = match triangles {
+            Some(triangles) => {
+                let triangles: Vec<[i64; 3]> = rows(triangles.as_array(), "triangles")?;
+                let indices = triangles
+                    .into_iter()
+                    .map(|tri| {
+                        let mut out = [0; 3];
+                        for (o, &i) in out.iter_mut().zip(tri.iter()) {
+                            *o = u32::try_from(i).map_err(|_| {
+                                PyValueError::new_err(format!("Invalid triangle index {}", i))
+                            })?;
+                        }
+                        Ok(out)
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+                Mesh::from_raw(positions, indices, attributes)
+            }
+            None => Mesh::from_raw_points(positions, attributes),
+        }
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyMesh { mesh })
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.mesh {
+            Mesh::TriMesh(_) => "Mesh(triangles)".to_string(),
+            Mesh::PointCloud(_) => "Mesh(points)".to_string(),
+        }
+    }
+}
+
+/// Converts a 2D array into a vector of fixed size rows.
+fn rows<T: Copy + Default, const N: usize>(
+    array: ArrayView2<'_, T>,
+    name: &str,
+) -> PyResult<Vec<[T; N]>> {
+    if array.ncols() != N {
+        return Err(PyValueError::new_err(format!(
+            "'{}' must have shape (n, {})",
+            name, N
+        )));
+    }
+    Ok(array
+        .axis_iter(Axis(0))
+        .map(|row| {
+            let mut out = [T::default(); N];
+            out.iter_mut().zip(row.iter()).for_each(|(o, &v)| *o = v);
+            out
+        })
+        .collect())
+}
+
+/// Converts the given float arrays into vertex attributes.
+fn raw_attributes(
+    attributes: Option<&Bound<'_, PyDict>>,
+    num_vertices: usize,
+) -> PyResult<Vec<(String, RawAttribute)>> {
+    let Some(attributes) = attributes else {
+        return Ok(Vec::new());
+    };
+    let mut raw = Vec::new();
+    for (name, value) in attributes.iter() {
+        let name: String = name.extract()?;
+        let array: PyArrayLikeDyn<'_, f32, AllowTypeChange> = value.extract()?;
+        let view: ArrayViewD<'_, f32> = array.as_array();
+        let shape_error = || {
+            PyValueError::new_err(format!(
+                "Attribute '{}' must have shape ({},) or ({}, k) with k between 2 and 4",
+                name, num_vertices, num_vertices
+            ))
+        };
+        if view.shape().first() != Some(&num_vertices) {
+            return Err(shape_error());
+        }
+        let values = match view.ndim() {
+            1 => RawAttribute::F32(view.iter().copied().collect()),
+            2 => {
+                let view = view.into_dimensionality().map_err(|_| shape_error())?;
+                match view.ncols() {
+                    2 => RawAttribute::from(rows::<f32, 2>(view, &name)?),
+                    3 => RawAttribute::from(rows::<f32, 3>(view, &name)?),
+                    4 => RawAttribute::from(rows::<f32, 4>(view, &name)?),
+                    _ => return Err(shape_error()),
+                }
+            }
+            _ => return Err(shape_error()),
+        };
+        raw.push((name, values));
+    }
+    Ok(raw)
+}
+
+/// Loads a mesh from a file in any of the formats supported by `gltfgen`.
+#[pyfunction]
+#[pyo3(signature = (path, reverse=false, invert_tets=false))]
+fn load_mesh(path: PathBuf, reverse: bool, invert_tets: bool) -> PyResult<PyMesh> {
+    let config = LoadConfig::builder()
+        .reverse(reverse)
+        .invert_tets(invert_tets)
+        .build();
+    gltfgen::load_mesh(&path, config)
+        .map(|mesh| PyMesh { mesh })
+        .ok_or_else(|| PyIOError::new_err(format!("Failed to load mesh: {}", path.display())))
+}
+
+/// Keyword options of `export_named_meshes`.
+struct ExportOptions {
+    fps: f64,
+    time_step: Option<f32>,
+    attributes: String,
+    colors: String,
+    texcoords: String,
+    material_attribute: String,
+    insert_vanishing_frames: bool,
+    animate_normals: bool,
+    animate_tangents: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            fps: 24.0,
+            time_step: None,
+            attributes: "{\"N\":Vec3(f32)}".to_string(),
+            colors: "{}".to_string(),
+            texcoords: "{\"uv\":f32}".to_string(),
+            material_attribute: "mtl_id".to_string(),
+            insert_vanishing_frames: false,
+            animate_normals: true,
+            animate_tangents: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Overrides the defaults with the given keyword arguments.
+    fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut options = ExportOptions::default();
+        for (key, value) in kwargs.into_iter().flatten() {
+            let key: String = key.extract()?;
+            match key.as_str() {
+                "fps" => options.fps = value.extract()?,
+                "time_step" => options.time_step = value.extract()?,
+                "attributes" => options.attributes = value.extract()?,
+                "colors" => options.colors = value.extract()?,
+                "texcoords" => options.texcoords = value.extract()?,
+                "material_attribute" => options.material_attribute = value.extract()?,
+                "insert_vanishing_frames" => options.insert_vanishing_frames = value.extract()?,
+                "animate_normals" => options.animate_normals = value.extract()?,
+                "animate_tangents" => options.animate_tangents = value.extract()?,
+                _ => {
+                    return Err(PyTypeError::new_err(format!(
+                        "export_named_meshes() got an unexpected keyword argument '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+        if !(options.fps.is_finite() && options.fps > 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "fps must be a positive number, got {}",
+                options.fps
+            )));
+        }
+        Ok(options)
+    }
+}
+
+/// Exports sequences of meshes as an animated glTF.
+///
+/// `meshes` is a list of `(name, mesh)` pairs, where the meshes of each name
+/// are the frames of its animation in order. Attribute specifications use the
+/// same syntax as the command line, e.g. `attributes='{"pressure": f32}'`.
+///
+/// Keyword options and their defaults are `fps=24`, `time_step=None`,
+/// `attributes='{"N":Vec3(f32)}'`, `colors='{}'`, `texcoords='{"uv":f32}'`,
+/// `material_attribute='mtl_id'`, `insert_vanishing_frames=False`,
+/// `animate_normals=True` and `animate_tangents=True`. Fractional frame rates
+/// like `fps=29.97` are allowed, but `fps` must be positive.
+///
+/// Returns a dict summarizing the written output.
+#[pyfunction]
+#[pyo3(signature = (meshes, output, **options))]
+fn export_named_meshes<'py>(
+    py: Python<'py>,
+    meshes: Vec<(String, PyRef<'py, PyMesh>)>,
+    output: PathBuf,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let ExportOptions {
+        fps,
+        time_step,
+        attributes,
+        colors,
+        texcoords,
+        material_attribute,
+        insert_vanishing_frames,
+        animate_normals,
+        animate_tangents,
+    } = ExportOptions::from_kwargs(options)?;
+
+    let parse_error = |name: &str, e: &dyn std::fmt::Display| {
+        PyValueError::new_err(format!("Invalid {}: {}", name, e))
+    };
+    let attributes: AttributeInfo = attributes
+        .parse()
+        .map_err(|e| parse_error("attributes", &e))?;
+    let colors: AttributeInfo = colors.parse().map_err(|e| parse_error("colors", &e))?;
+    let texcoords: TextureAttributeInfo = texcoords
+        .parse()
+        .map_err(|e| parse_error("texcoords", &e))?;
+
+    let meshes: Vec<_> = meshes
+        .into_iter()
+        .map(|(name, mesh)| (name, mesh.mesh.clone()))
+        .collect();
+
+    let export_config = ExportConfig::builder()
+        .output(output)
+        .time_step(time_step.unwrap_or(FrameRate(fps).time_step() as f32))
+        .insert_vanishing_frames(insert_vanishing_frames)
+        .animate_normals(animate_normals)
+        .animate_tangents(animate_tangents)
+        .quiet(true)
+        .build();
+
+    let report = py
+        .allow_threads(|| {
+            let attrib_config = AttribConfig {
+                attributes: &attributes,
+                colors: &colors,
+                texcoords: &texcoords,
+                material_attribute: &material_attribute,
+            };
+            export::export_named_meshes(meshes, attrib_config, export_config)
+        })
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    let summary = PyDict::new_bound(py);
+    summary.set_item("output", report.output.clone())?;
+    summary.set_item("skipped_attributes", report.skipped_attributes.clone())?;
+    summary.set_item("dropped_textures", report.dropped_textures.clone())?;
+    summary.set_item("nodes", report.nodes)?;
+    summary.set_item("primitives", report.primitives)?;
+    summary.set_item("accessors", report.accessors)?;
+    summary.set_item("buffer_bytes", report.buffer_bytes)?;
+    summary.set_item("file_bytes", report.file_bytes)?;
+    Ok(summary)
+}
+
+#[pymodule]
+#[pyo3(name = "gltfgen")]
+fn gltfgen_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMesh>()?;
+    m.add_function(wrap_pyfunction!(load_mesh, m)?)?;
+    m.add_function(wrap_pyfunction!(export_named_meshes, m)?)?;
+    Ok(())
+}
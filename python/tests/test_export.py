@@ -0,0 +1,45 @@
+import json
+import struct
+
+import numpy as np
+import pytest
+
+import gltfgen
+
+
+def triangle_frames(count):
+    triangles = np.array([[0, 1, 2]])
+    return [
+        gltfgen.Mesh(np.array([[0.0, 0.0, z], [1.0, 0.0, z], [0.0, 1.0, z]]), triangles)
+        for z in range(count)
+    ]
+
+
+def read_glb_json(path):
+    with open(path, "rb") as f:
+        magic, _version, _length = struct.unpack("<4sII", f.read(12))
+        assert magic == b"glTF"
+        chunk_length, chunk_type = struct.unpack("<I4s", f.read(8))
+        assert chunk_type == b"JSON"
+        return json.loads(f.read(chunk_length))
+
+
+def test_fractional_fps(tmp_path):
+    output = tmp_path / "triangle.glb"
+    frames = triangle_frames(3)
+    gltfgen.export_named_meshes([("triangle", mesh) for mesh in frames], output, fps=29.97)
+
+    doc = read_glb_json(output)
+    sampler = doc["animations"][0]["samplers"][0]
+    times = doc["accessors"][sampler["input"]]
+    assert times["count"] == 3
+    assert times["max"][0] - times["min"][0] == pytest.approx(2 / 29.97, rel=1e-5)
+
+
+@pytest.mark.parametrize("fps", [0, -24, 0.0, float("inf"), float("nan")])
+def test_invalid_fps(tmp_path, fps):
+    output = tmp_path / "triangle.glb"
+    frames = triangle_frames(2)
+    with pytest.raises(ValueError, match="fps must be a positive number"):
+        gltfgen.export_named_meshes([("triangle", mesh) for mesh in frames], output, fps=fps)
+    assert not output.exists()
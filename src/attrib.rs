@@ -1,4 +1,4 @@
-use crate::config::{NORMAL_ATTRIB_NAME, TANGENT_ATTRIB_NAME};
+use crate::config::{NORMAL_ATTRIB_NAME, POLYGON_ID_ATTRIB_NAME, TANGENT_ATTRIB_NAME};
 use crate::mesh::Mesh;
 use crate::AttribConfig;
 use gltf::json;
@@ -14,6 +14,8 @@ type MaterialIdMap = IndexMap<u32, Vec<usize>>;
 pub enum AttribError {
     InvalidTexCoordAttribType(ComponentType),
     InvalidVector3AttribType(Type),
+    /// A material ID in the named attribute is negative or too large.
+    InvalidMaterialId(String),
     Mesh(meshx::attrib::Error),
 }
 
@@ -32,6 +34,11 @@ impl std::fmt::Display for AttribError {
                 "Invalid 3D vector attribute type detected: {:?}. Skipping...",
                 t
             ),
+            AttribError::InvalidMaterialId(name) => write!(
+                f,
+                "Material ID in attribute '{}' does not fit into an unsigned 32 bit integer. Skipping...",
+                name
+            ),
             AttribError::Mesh(e) => write!(f, "Mesh: {}", e),
         }
     }
@@ -56,6 +63,57 @@ pub enum MaterialIds {
     Global { map: MaterialIdMap },
 }
 
+impl MaterialIds {
+    /// Describes how the material assignment changed from `before` to `after`.
+    ///
+    /// Returns `None` if the assignments are equal.
+    pub fn difference(before: Option<&MaterialIds>, after: Option<&MaterialIds>) -> Option<String> {
+        // Finds the first material whose faces differ between the two maps.
+        fn first_changed<K: std::hash::Hash + Eq + std::fmt::Debug>(
+            before: &IndexMap<K, Vec<usize>>,
+            after: &IndexMap<K, Vec<usize>>,
+        ) -> Option<String> {
+            before
+                .keys()
+                .chain(after.keys().filter(|key| !before.contains_key(*key)))
+                .find_map(|key| {
+                    let faces_before = before.get(key).map_or(&[][..], Vec::as_slice);
+                    let faces_after = after.get(key).map_or(&[][..], Vec::as_slice);
+                    (faces_before != faces_after).then(|| {
+                        format!(
+                            "faces with material {:?} changed ({} to {} faces)",
+                            key,
+                            faces_before.len(),
+                            faces_after.len()
+                        )
+                    })
+                })
+        }
+        match (before, after) {
+            (None, None) => None,
+            (None, Some(_)) => Some("material ids were added".to_string()),
+            (Some(_), None) => Some("material ids were removed".to_string()),
+            (
+                Some(MaterialIds::Global { map: before }),
+                Some(MaterialIds::Global { map: after }),
+            ) => first_changed(before, after),
+            (Some(MaterialIds::Local { map: before }), Some(MaterialIds::Local { map: after })) => {
+                let names = |map: &MaterialMap| -> IndexMap<String, Vec<usize>> {
+                    map.iter()
+                        .map(|(mtl, faces)| (mtl.name.clone(), faces.clone()))
+                        .collect()
+                };
+                first_changed(&names(before), &names(after)).or_else(|| {
+                    (before != after).then(|| "material properties changed".to_string())
+                })
+            }
+            (Some(_), Some(_)) => {
+                Some("material ids changed between local and global materials".to_string())
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AttribTransfer {
     pub attribs_to_keep: Vec<Attribute>,
@@ -64,37 +122,200 @@ pub struct AttribTransfer {
     pub material_ids: Option<MaterialIds>,
     pub normal_attrib: Vec<[f32; 3]>,
     pub tangent_attrib: Vec<[f32; 3]>,
+    /// Index of the source polygon of each face, or empty if not recorded.
+    pub polygon_ids: Vec<u32>,
+    /// Hash of the mesh topology (see [`Mesh::topo_hash`]) computed when the
+    /// mesh was cleaned, or `None` if unknown.
+    pub topo_hash: Option<u64>,
+}
+
+impl AttribTransfer {
+    /// Extract the attributes at the given vertices and material assignments at the given faces.
+    ///
+    /// This is used to split a large mesh into smaller parts. The given `faces` are expected to be
+    /// sorted in increasing order. Materials not used by any of the given faces are dropped.
+    pub fn subset(&self, faces: &[usize], vertices: &[usize]) -> AttribTransfer {
+        let subset_attrib = |attrib: &Attribute| Attribute {
+            name: attrib.name.clone(),
+            type_: attrib.type_,
+            attribute: subset_vertex_attribute(&attrib.attribute, vertices),
+        };
+        let subset_vec3 = |v: &[[f32; 3]]| {
+            if v.is_empty() {
+                Vec::new()
+            } else {
+                vertices.iter().map(|&i| v[i]).collect()
+            }
+        };
+        AttribTransfer {
+            attribs_to_keep: self.attribs_to_keep.iter().map(subset_attrib).collect(),
+            color_attribs_to_keep: self
+                .color_attribs_to_keep
+                .iter()
+                .map(subset_attrib)
+                .collect(),
+            tex_attribs_to_keep: self
+                .tex_attribs_to_keep
+                .iter()
+                .map(|attrib| TextureAttribute {
+                    id: attrib.id,
+                    name: attrib.name.clone(),
+                    component_type: attrib.component_type,
+                    attribute: subset_vertex_attribute(&attrib.attribute, vertices),
+                })
+                .collect(),
+            material_ids: self.material_ids.as_ref().map(|ids| match ids {
+                MaterialIds::Local { map } => MaterialIds::Local {
+                    map: subset_material_map(map, faces),
+                },
+                MaterialIds::Global { map } => MaterialIds::Global {
+                    map: subset_material_map(map, faces),
+                },
+            }),
+            normal_attrib: subset_vec3(&self.normal_attrib),
+            tangent_attrib: subset_vec3(&self.tangent_attrib),
+            polygon_ids: if self.polygon_ids.is_empty() {
+                Vec::new()
+            } else {
+                faces.iter().map(|&f| self.polygon_ids[f]).collect()
+            },
+            topo_hash: None,
+        }
+    }
+
+    /// Names of the custom, color and texture coordinate attributes found in
+    /// only one of `self` and `other`.
+    pub fn mismatched_attributes(&self, other: &AttribTransfer) -> Vec<String> {
+        let names = |transfer: &AttribTransfer| -> Vec<String> {
+            transfer
+                .attribs_to_keep
+                .iter()
+                .chain(transfer.color_attribs_to_keep.iter())
+                .map(|attrib| attrib.name.clone())
+                .chain(
+                    transfer
+                        .tex_attribs_to_keep
+                        .iter()
+                        .map(|attrib| attrib.name.clone()),
+                )
+                .collect()
+        };
+        let (ours, theirs) = (names(self), names(other));
+        let missing = |a: &[String], b: &[String]| -> Vec<String> {
+            a.iter().filter(|name| !b.contains(name)).cloned().collect()
+        };
+        let mut mismatched = missing(&ours, &theirs);
+        mismatched.extend(missing(&theirs, &ours));
+        mismatched
+    }
+
+    /// Removes the custom, color and texture coordinate attributes with the given names.
+    pub fn remove_attributes(&mut self, names: &[String]) {
+        self.attribs_to_keep
+            .retain(|attrib| !names.contains(&attrib.name));
+        self.color_attribs_to_keep
+            .retain(|attrib| !names.contains(&attrib.name));
+        self.tex_attribs_to_keep
+            .retain(|attrib| !names.contains(&attrib.name));
+    }
+
+    /// Adds the attributes of `other` missing from `self` with all values set to zero.
+    ///
+    /// `num_vertices` is the number of vertices of the mesh these attributes belong to.
+    pub fn fill_missing_with_zeros(&mut self, other: &AttribTransfer, num_vertices: usize) {
+        let fill = |ours: &mut Vec<Attribute>, theirs: &[Attribute]| {
+            for attrib in theirs {
+                if !ours.iter().any(|a| a.name == attrib.name) {
+                    ours.push(Attribute {
+                        name: attrib.name.clone(),
+                        type_: attrib.type_,
+                        attribute: zero_vertex_attribute(attrib.type_, num_vertices),
+                    });
+                }
+            }
+        };
+        fill(&mut self.attribs_to_keep, &other.attribs_to_keep);
+        fill(
+            &mut self.color_attribs_to_keep,
+            &other.color_attribs_to_keep,
+        );
+        for attrib in other.tex_attribs_to_keep.iter() {
+            if self
+                .tex_attribs_to_keep
+                .iter()
+                .any(|a| a.name == attrib.name)
+            {
+                continue;
+            }
+            let attribute = match attrib.component_type {
+                ComponentType::U8 => {
+                    zero_vertex_attribute(Type::Vec2(ComponentType::U8), num_vertices)
+                }
+                ComponentType::U16 => {
+                    zero_vertex_attribute(Type::Vec2(ComponentType::U16), num_vertices)
+                }
+                _ => zero_vertex_attribute(Type::Vec2(ComponentType::F32), num_vertices),
+            };
+            self.tex_attribs_to_keep.push(TextureAttribute {
+                id: attrib.id,
+                name: attrib.name.clone(),
+                component_type: attrib.component_type,
+                attribute,
+            });
+        }
+        // Texture coordinates are written in the order they were configured.
+        self.tex_attribs_to_keep.sort_by_key(|attrib| attrib.id);
+    }
+}
+
+/// Build a new vertex attribute from the values of `attrib` at the given vertices.
+fn subset_vertex_attribute(attrib: &VertexAttribute, vertices: &[usize]) -> VertexAttribute {
+    attrib.duplicate_with(|new, old| {
+        for &v in vertices {
+            new.push_cloned(old.get(v));
+        }
+    })
+}
+
+/// Re-index faces in the given material map to correspond to positions within `faces`.
+fn subset_material_map<K: Clone + std::hash::Hash + Eq>(
+    map: &IndexMap<K, Vec<usize>>,
+    faces: &[usize],
+) -> IndexMap<K, Vec<usize>> {
+    map.iter()
+        .filter_map(|(mtl, face_indices)| {
+            let new_face_indices: Vec<_> = face_indices
+                .iter()
+                .filter_map(|f| faces.binary_search(f).ok())
+                .collect();
+            if new_face_indices.is_empty() {
+                None
+            } else {
+                Some((mtl.clone(), new_face_indices))
+            }
+        })
+        .collect()
 }
 
 /// Find per face material IDs in the given mesh by probing a given integer type `I`.
+///
+/// Returns `None` if there is no material attribute of type `I`, and an error if
+/// any of its values does not fit into an unsigned 32 bit integer.
 fn find_material_ids<I: Clone + num_traits::ToPrimitive + 'static>(
     mesh: &Mesh,
     attrib_name: &str,
-) -> Option<Vec<u32>> {
+) -> Option<Result<Vec<u32>, AttribError>> {
     use meshx::attrib::Attrib;
+    let invalid = || AttribError::InvalidMaterialId(attrib_name.to_string());
     match mesh {
         Mesh::TriMesh(mesh) => mesh
             .attrib_iter::<I, FaceIndex>(attrib_name)
             .ok()
-            .map(|iter| {
-                iter.map(|x| {
-                    x.to_u32()
-                        .expect("Material ID does not fit into an unsigned 32 bit integer.")
-                })
-                .collect()
-            }),
-        Mesh::PointCloud(ptcloud) => {
-            ptcloud
-                .attrib_iter::<I, VertexIndex>(attrib_name)
-                .ok()
-                .map(|iter| {
-                    iter.map(|x| {
-                        x.to_u32()
-                            .expect("Material ID does not fit into an unsigned 32 bit integer.")
-                    })
-                    .collect()
-                })
-        }
+            .map(|iter| iter.map(|x| x.to_u32().ok_or_else(invalid)).collect()),
+        Mesh::PointCloud(ptcloud) => ptcloud
+            .attrib_iter::<I, VertexIndex>(attrib_name)
+            .ok()
+            .map(|iter| iter.map(|x| x.to_u32().ok_or_else(invalid)).collect()),
     }
 }
 
@@ -203,13 +424,28 @@ pub fn clean_mesh(
                     Some(MaterialIds::Local { map })
                 }
             },
-            |ids| {
-                Some(MaterialIds::Global {
+            |ids| match ids {
+                Ok(ids) => Some(MaterialIds::Global {
                     map: group_mtls(ids.as_slice()),
-                })
+                }),
+                Err(e) => {
+                    process_attrib_error(e);
+                    None
+                }
             },
         );
 
+    // Source polygons are only recorded for triangle meshes.
+    let polygon_ids = match mesh {
+        Mesh::TriMesh(mesh) => {
+            use meshx::attrib::Attrib;
+            mesh.attrib_as_slice::<u32, FaceIndex>(POLYGON_ID_ATTRIB_NAME)
+                .map(<[u32]>::to_vec)
+                .unwrap_or_default()
+        }
+        Mesh::PointCloud(_) => Vec::new(),
+    };
+
     // Remove all attributes from the mesh.
     // It is important to delete these attributes, because they could cause a huge memory overhead.
     match mesh {
@@ -233,6 +469,8 @@ pub fn clean_mesh(
         material_ids,
         normal_attrib,
         tangent_attrib,
+        polygon_ids,
+        topo_hash: Some(mesh.topo_hash()),
     }
 }
 
@@ -395,6 +633,39 @@ pub struct TextureAttribute {
     pub attribute: VertexAttribute,
 }
 
+impl TextureAttribute {
+    /// Values of this texture coordinate attribute as floating point UV coordinates.
+    ///
+    /// Integer components are normalized and the third component of 3D
+    /// coordinates is ignored. Returns `None` if the attribute has an
+    /// unsupported type.
+    pub(crate) fn uvs(&self) -> Option<Vec<[f32; 2]>> {
+        fn normalize<T: num_traits::ToPrimitive + Copy + 'static>(
+            attrib: &VertexAttribute,
+            max: f32,
+        ) -> Option<Vec<[f32; 2]>> {
+            let uv = |u: T, v: T| {
+                [
+                    u.to_f32().unwrap_or(0.0) / max,
+                    v.to_f32().unwrap_or(0.0) / max,
+                ]
+            };
+            if let Ok(values) = attrib.as_slice::<[T; 2]>() {
+                Some(values.iter().map(|&[u, v]| uv(u, v)).collect())
+            } else {
+                let values = attrib.as_slice::<[T; 3]>().ok()?;
+                Some(values.iter().map(|&[u, v, _]| uv(u, v)).collect())
+            }
+        }
+        match self.component_type {
+            ComponentType::U8 => normalize::<u8>(&self.attribute, u8::MAX as f32),
+            ComponentType::U16 => normalize::<u16>(&self.attribute, u16::MAX as f32),
+            ComponentType::F32 => normalize::<f32>(&self.attribute, 1.0),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Attribute {
     pub name: String,
@@ -402,6 +673,62 @@ pub struct Attribute {
     pub attribute: VertexAttribute,
 }
 
+impl Attribute {
+    /// Values of this attribute as floating point scalars.
+    ///
+    /// Returns `None` if this is not a scalar attribute.
+    pub(crate) fn scalars(&self) -> Option<Vec<f32>> {
+        fn convert<T: num_traits::ToPrimitive + 'static>(
+            attrib: &VertexAttribute,
+        ) -> Option<Vec<f32>> {
+            let values = attrib.as_slice::<T>().ok()?;
+            Some(values.iter().map(|x| x.to_f32().unwrap_or(0.0)).collect())
+        }
+        match self.type_ {
+            Type::I8 | Type::Scalar(ComponentType::I8) => convert::<i8>(&self.attribute),
+            Type::U8 | Type::Scalar(ComponentType::U8) => convert::<u8>(&self.attribute),
+            Type::I16 | Type::Scalar(ComponentType::I16) => convert::<i16>(&self.attribute),
+            Type::U16 | Type::Scalar(ComponentType::U16) => convert::<u16>(&self.attribute),
+            Type::U32 | Type::Scalar(ComponentType::U32) => convert::<u32>(&self.attribute),
+            Type::F32 | Type::Scalar(ComponentType::F32) => convert::<f32>(&self.attribute),
+            _ => None,
+        }
+    }
+
+    /// Values of this color attribute as RGBA colors with components in `[0, 1]`.
+    ///
+    /// Integer components are normalized and a missing alpha is set to one.
+    /// Returns `None` if the type of this attribute is not a valid color type.
+    pub(crate) fn rgba_colors(&self) -> Option<Vec<[f32; 4]>> {
+        fn normalize<T: num_traits::ToPrimitive + 'static, const N: usize>(
+            attrib: &VertexAttribute,
+            max: f32,
+        ) -> Option<Vec<[f32; 4]>> {
+            let values = attrib.as_slice::<[T; N]>().ok()?;
+            let rgba = values
+                .iter()
+                .map(|color| {
+                    let mut rgba = [1.0; 4];
+                    for (out, c) in rgba.iter_mut().zip(color.iter()) {
+                        *out = c.to_f32().unwrap_or(0.0) / max;
+                    }
+                    rgba
+                })
+                .collect();
+            Some(rgba)
+        }
+        match self.type_ {
+            Type::Vec3(ComponentType::U8) => normalize::<u8, 3>(&self.attribute, u8::MAX as f32),
+            Type::Vec3(ComponentType::U16) => normalize::<u16, 3>(&self.attribute, u16::MAX as f32),
+            Type::Vec3(ComponentType::F32) => normalize::<f32, 3>(&self.attribute, 1.0),
+            Type::Vec4(ComponentType::U8) => normalize::<u8, 4>(&self.attribute, u8::MAX as f32),
+            Type::Vec4(ComponentType::U16) => normalize::<u16, 4>(&self.attribute, u16::MAX as f32),
+            Type::Vec4(ComponentType::F32) => normalize::<f32, 4>(&self.attribute, 1.0),
+            _ => None,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! call_typed_fn {
     ($type:expr => $prefix:ident :: $fn:ident :: <_$(,$params:ident)*> $args:tt ) => {
@@ -460,6 +787,14 @@ macro_rules! call_typed_fn {
     };
 }
 
+/// A vertex attribute of the given type with `len` zero values.
+fn zero_vertex_attribute(type_: Type, len: usize) -> VertexAttribute {
+    fn zeros<T: meshx::attrib::AttributeValue + Default>(len: usize) -> VertexAttribute {
+        VertexAttribute::direct_from_vec(vec![T::default(); len])
+    }
+    call_typed_fn!(type_ => self::zeros::<_>(len))
+}
+
 /*
  * Parsing attributes from command line
  */
@@ -608,11 +943,83 @@ impl Default for AttributeInfo {
 }
 
 impl std::str::FromStr for AttributeInfo {
-    type Err = ron::de::Error;
+    type Err = crate::lint::ParseError;
     fn from_str(input: &str) -> Result<AttributeInfo, Self::Err> {
-        let idx_map: Result<IndexMap<String, Type>, Self::Err> =
+        crate::lint::parse_ron::<IndexMap<String, Type>>(
+            input,
+            "{\"pressure\": f32, \"velocity\": Vec3(f32)}",
+        )
+        .map(AttributeInfo)
+    }
+}
+
+/// Output names of custom attributes, overriding the names derived from the input.
+///
+/// Keys are attribute names in the input mesh files, and values are the
+/// corresponding glTF semantics, with or without the leading underscore.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttributeNames(pub IndexMap<String, String>);
+
+impl std::str::FromStr for AttributeNames {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<AttributeNames, Self::Err> {
+        let idx_map: Result<IndexMap<String, String>, Self::Err> =
             ron::de::from_str(input).map_err(Self::Err::from);
-        idx_map.map(AttributeInfo)
+        idx_map.map(AttributeNames)
+    }
+}
+
+/// Assigns glTF semantics to custom attributes.
+///
+/// Each input attribute name is given the same semantic in every primitive,
+/// and distinct names are always given distinct semantics: if two names
+/// convert to the same semantic, the later one gets a numeric suffix.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AttributeSemantics {
+    overrides: AttributeNames,
+    /// Semantics (without the leading underscore) assigned to each input name.
+    assigned: IndexMap<String, String>,
+}
+
+impl AttributeSemantics {
+    pub(crate) fn new(overrides: AttributeNames) -> Self {
+        AttributeSemantics {
+            overrides,
+            assigned: IndexMap::new(),
+        }
+    }
+
+    /// Returns the semantic of the given input attribute name, without the leading underscore.
+    pub(crate) fn get(&mut self, name: &str, msgs: &mut Vec<(usize, String)>) -> String {
+        use heck::ToShoutySnakeCase;
+
+        if let Some(semantic) = self.assigned.get(name) {
+            return semantic.clone();
+        }
+        let pinned = self.overrides.0.get(name);
+        let base = match pinned {
+            Some(semantic) => semantic.strip_prefix('_').unwrap_or(semantic).to_string(),
+            None => name.to_shouty_snake_case(),
+        };
+        // Semantics pinned for other attributes are reserved even before they are assigned.
+        let is_taken = |semantic: &str| {
+            self.assigned.values().any(|s| s == semantic)
+                || (pinned.is_none()
+                    && self.overrides.0.iter().any(|(other, s)| {
+                        other != name && s.strip_prefix('_').unwrap_or(s) == semantic
+                    }))
+        };
+        let mut semantic = base.clone();
+        let mut suffix = 0;
+        while is_taken(&semantic) {
+            suffix += 1;
+            semantic = format!("{}_{}", base, suffix);
+        }
+        if suffix > 0 {
+            log!(msgs; "Attribute '{}' would be named '_{}' in the output, which is already used. Naming it '_{}' instead", name, base, semantic);
+        }
+        self.assigned.insert(name.to_string(), semantic.clone());
+        semantic
     }
 }
 
@@ -624,3 +1031,118 @@ impl std::str::FromStr for TextureAttributeInfo {
         idx_map.map(TextureAttributeInfo)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AttributeMismatchPolicy;
+    use crate::export::{ExportConfig, SequenceBuilder};
+
+    #[test]
+    fn attribute_name_collisions() {
+        let mut msgs = Vec::new();
+        let mut semantics = AttributeSemantics::default();
+        assert_eq!(semantics.get("fooBar", &mut msgs), "FOO_BAR");
+        assert_eq!(semantics.get("foo_bar", &mut msgs), "FOO_BAR_1");
+        assert_eq!(semantics.get("FooBar", &mut msgs), "FOO_BAR_2");
+        // Names keep their semantic across primitives.
+        assert_eq!(semantics.get("foo_bar", &mut msgs), "FOO_BAR_1");
+        assert_eq!(msgs.len(), 2);
+
+        // Pinned names are reserved for their attributes.
+        let overrides: AttributeNames = r#"{"temp": "_FOO_BAR", "p": "PRESSURE"}"#.parse().unwrap();
+        let mut msgs = Vec::new();
+        let mut semantics = AttributeSemantics::new(overrides);
+        assert_eq!(semantics.get("fooBar", &mut msgs), "FOO_BAR_1");
+        assert_eq!(semantics.get("temp", &mut msgs), "FOO_BAR");
+        assert_eq!(semantics.get("p", &mut msgs), "PRESSURE");
+        assert_eq!(semantics.get("pressure", &mut msgs), "PRESSURE_1");
+    }
+
+    #[test]
+    fn attribute_mismatch() {
+        use meshx::attrib::Attrib;
+
+        let attributes: AttributeInfo = r#"{"pressure": F32}"#.parse().unwrap();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let build = |policy: AttributeMismatchPolicy| {
+            let config = ExportConfig::builder()
+                .attribute_mismatch(policy)
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            for frame in 1..=3 {
+                let mut trimesh = meshx::TriMesh::new(
+                    vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                    vec![[0, 1, 2]],
+                );
+                // The second frame is missing the pressure attribute.
+                if frame != 2 {
+                    trimesh
+                        .insert_attrib_data::<f32, VertexIndex>("pressure", vec![1.0; 3])
+                        .unwrap();
+                }
+                sequence
+                    .add_frame("tri", frame, Mesh::from(trimesh))
+                    .unwrap();
+            }
+            sequence.build().unwrap().0.root
+        };
+        let has_pressure = |root: &gltf::json::Root| {
+            root.accessors
+                .iter()
+                .any(|acc| acc.name.as_deref() == Some("pressure"))
+        };
+
+        let root = build(AttributeMismatchPolicy::Drop);
+        assert_eq!(root.meshes.len(), 1);
+        assert!(!has_pressure(&root));
+
+        let root = build(AttributeMismatchPolicy::Zeros);
+        assert_eq!(root.meshes.len(), 1);
+        assert!(has_pressure(&root));
+
+        // Each change in the set of attributes starts a new node.
+        let root = build(AttributeMismatchPolicy::Split);
+        assert_eq!(root.meshes.len(), 3);
+        assert!(has_pressure(&root));
+    }
+
+    #[test]
+    fn invalid_material_ids() {
+        use meshx::attrib::Attrib;
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let mut trimesh = meshx::TriMesh::new(
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            vec![[0, 1, 2]],
+        );
+        trimesh
+            .insert_attrib_data::<i32, FaceIndex>("mtl_id", vec![-1])
+            .unwrap();
+
+        // Negative IDs are reported and the material attribute is ignored.
+        let mut errors = Vec::new();
+        let attrib_transfer = clean_mesh(&mut Mesh::from(trimesh), attrib_config, |e| {
+            errors.push(e.to_string())
+        });
+        assert_eq!(attrib_transfer.material_ids, None);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("'mtl_id'"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Shared plumbing for the WebAssembly and C bindings.
+//!
+//! Both take their options as JSON and meshes as raw data rather than paths.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::attrib::clean_mesh;
+use crate::export::ExportConfig;
+use crate::mesh::Mesh;
+use crate::time::{FrameRate, Seconds};
+use crate::{
+    AttribConfig, AttribTransfer, AttributeInfo, MaterialInfo, TextureAttributeInfo, TextureInfo,
+};
+
+/// Conversion options given as JSON.
+///
+/// Field names and defaults match those of the configuration file.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct Options {
+    fps: FrameRate,
+    time_step: Option<Seconds>,
+    reverse: bool,
+    invert_tets: bool,
+    attributes: AttributeInfo,
+    colors: AttributeInfo,
+    texcoords: TextureAttributeInfo,
+    textures: Vec<TextureInfo>,
+    materials: Vec<MaterialInfo>,
+    material_attribute: String,
+    insert_vanishing_frames: bool,
+    no_animated_normals: bool,
+    no_animated_tangents: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            fps: FrameRate::default(),
+            time_step: None,
+            reverse: false,
+            invert_tets: false,
+            attributes: "{\"N\":Vec3(f32)}".parse().unwrap(),
+            colors: AttributeInfo::default(),
+            texcoords: "{\"uv\":f32}".parse().unwrap(),
+            textures: Vec::new(),
+            materials: Vec::new(),
+            material_attribute: "mtl_id".to_string(),
+            insert_vanishing_frames: false,
+            no_animated_normals: false,
+            no_animated_tangents: false,
+        }
+    }
+}
+
+impl Options {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn load_config(&self) -> crate::LoadConfig {
+        crate::LoadConfig::builder()
+            .reverse(self.reverse)
+            .invert_tets(self.invert_tets)
+            .build()
+    }
+
+    /// Cleans the given frames and builds the matching export configuration.
+    ///
+    /// Progress is not reported, and the output path is only used when writing to a file.
+    pub(crate) fn prepare(
+        self,
+        meshes: Vec<(String, u32, Mesh)>,
+        output: Option<PathBuf>,
+    ) -> (Vec<(String, u32, Mesh, AttribTransfer)>, ExportConfig) {
+        let attrib_config = AttribConfig {
+            attributes: &self.attributes,
+            colors: &self.colors,
+            texcoords: &self.texcoords,
+            material_attribute: &self.material_attribute,
+        };
+
+        let meshes = meshes
+            .into_iter()
+            .map(|(name, frame, mut mesh)| {
+                let attrib_transfer = clean_mesh(&mut mesh, attrib_config, |e| {
+                    log::warn!("{}, Skipping...", e)
+                });
+                (name, frame, mesh, attrib_transfer)
+            })
+            .collect();
+
+        let time_step = crate::time::frame_time_step(self.fps, self.time_step, None, 0, 1);
+        let mut builder = ExportConfig::builder()
+            .textures(self.textures)
+            .materials(self.materials)
+            .time_step(time_step)
+            .insert_vanishing_frames(self.insert_vanishing_frames)
+            .animate_normals(!self.no_animated_normals)
+            .animate_tangents(!self.no_animated_tangents)
+            .progress(std::sync::Arc::new(crate::progress::LogProgress));
+        if let Some(output) = output {
+            builder = builder.output(output);
+        }
+        (meshes, builder.build())
+    }
+}
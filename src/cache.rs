@@ -0,0 +1,557 @@
+//! On-disk cache of loaded and cleaned frames.
+//!
+//! Re-exporting a sequence after a few frames were added or modified only needs
+//! to reprocess the changed files: every other frame is read back from a cache
+//! entry keyed by the input path and validated against the file's modification
+//! time and size, as well as the load and attribute configuration used to
+//! produce it.
+//!
+//! The same cache is used to checkpoint frames of an export run with
+//! `--resume` in the directory given by [`checkpoint_dir`].
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use meshx::mesh::vertex_positions::VertexPositions;
+use meshx::{PointCloud, TriMesh};
+
+use crate::attrib::*;
+use crate::export::{ReadBytes, WriteBytes};
+use crate::mesh::Mesh;
+use crate::{AttribConfig, LoadConfig};
+
+const MAGIC: &[u8; 4] = b"GGFC";
+/// Bumped whenever the layout of cache entries changes.
+const FORMAT_VERSION: u32 = 2;
+
+/// 64-bit FNV-1a hash.
+///
+/// Unlike `DefaultHasher`, this is stable across Rust versions, so cache
+/// entries remain valid after upgrading the toolchain.
+fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x100_0000_01b3);
+    }
+    hash
+}
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// A directory of processed frames.
+#[derive(Clone, Debug)]
+pub struct FrameCache {
+    dir: PathBuf,
+    /// Hash of everything other than the input file that affects a cached frame.
+    config_key: u64,
+}
+
+/// Identifies the state of an input file when it was cached.
+#[derive(Debug, PartialEq)]
+struct Stamp {
+    path: String,
+    secs: u64,
+    nanos: u32,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> io::Result<Stamp> {
+        let metadata = fs::metadata(path)?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Stamp {
+            path: path.to_string_lossy().into_owned(),
+            secs: mtime.as_secs(),
+            nanos: mtime.subsec_nanos(),
+            len: metadata.len(),
+        })
+    }
+}
+
+impl FrameCache {
+    /// Opens the cache in the given directory, creating it if necessary.
+    ///
+    /// Entries written with a different load or attribute configuration are
+    /// ignored.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        load_config: LoadConfig,
+        attrib_config: AttribConfig,
+    ) -> io::Result<FrameCache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(FrameCache {
+            dir,
+            config_key: config_key(load_config, attrib_config),
+        })
+    }
+
+    /// Returns a cache in the same directory for frames loaded with a different configuration.
+    pub fn with_config(&self, load_config: LoadConfig, attrib_config: AttribConfig) -> FrameCache {
+        FrameCache {
+            dir: self.dir.clone(),
+            config_key: config_key(load_config, attrib_config),
+        }
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let hash = fnv1a(path.to_string_lossy().as_bytes(), self.config_key);
+        self.dir.join(format!("{:016x}.frame", hash))
+    }
+
+    /// Returns the cached frame for the file at `path` if it hasn't changed
+    /// since it was cached.
+    pub fn get(&self, path: &Path) -> Option<(Mesh, AttribTransfer)> {
+        let stamp = Stamp::of(path).ok()?;
+        let bytes = fs::read(self.entry_path(path)).ok()?;
+        let mut data = bytes.as_slice();
+        match read_entry(&mut data, self.config_key, &stamp) {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::debug!(
+                    "Ignoring corrupt cache entry for {}: {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Stores a cleaned frame loaded from the file at `path`.
+    ///
+    /// Frames with materials read from OBJ files are not cached.
+    pub fn insert(
+        &self,
+        path: &Path,
+        mesh: &Mesh,
+        attrib_transfer: &AttribTransfer,
+    ) -> io::Result<()> {
+        if matches!(
+            attrib_transfer.material_ids,
+            Some(MaterialIds::Local { .. })
+        ) {
+            return Ok(());
+        }
+        let stamp = Stamp::of(path)?;
+        let mut data = Vec::new();
+        write_entry(&mut data, self.config_key, &stamp, mesh, attrib_transfer)?;
+
+        // Write to a temporary file first so that concurrent or interrupted
+        // exports never observe a partial entry.
+        let entry_path = self.entry_path(path);
+        let tmp_path = entry_path.with_extension(format!("tmp{}", std::process::id()));
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&data)?;
+        drop(file);
+        fs::rename(&tmp_path, &entry_path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+        })
+    }
+}
+
+/// Directory where frames are checkpointed when exporting to `output` with `--resume`.
+///
+/// For an output at `out.glb`, frames are checkpointed to `out.glb.resume`.
+pub fn checkpoint_dir(output: &Path) -> PathBuf {
+    let mut dir = output.as_os_str().to_os_string();
+    dir.push(".resume");
+    PathBuf::from(dir)
+}
+
+/// Hashes the configuration used to produce cached frames.
+fn config_key(load_config: LoadConfig, attrib_config: AttribConfig) -> u64 {
+    let config = format!(
+        "{}{}{:?}{:?}",
+        env!("CARGO_PKG_VERSION"),
+        FORMAT_VERSION,
+        load_config,
+        attrib_config
+    );
+    fnv1a(config.as_bytes(), FNV_OFFSET)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_len(data: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    data.write_u64::<LE>(len as u64)
+}
+
+fn read_len(data: &mut &[u8]) -> io::Result<usize> {
+    let len = usize::try_from(data.read_u64::<LE>()?).map_err(|_| invalid("length overflow"))?;
+    // Guard against allocating huge buffers for corrupt entries.
+    if len > data.len() {
+        return Err(invalid("truncated entry"));
+    }
+    Ok(len)
+}
+
+fn write_str(data: &mut Vec<u8>, s: &str) -> io::Result<()> {
+    write_len(data, s.len())?;
+    data.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn read_str(data: &mut &[u8]) -> io::Result<String> {
+    let len = read_len(data)?;
+    let (s, rest) = data.split_at(len);
+    *data = rest;
+    String::from_utf8(s.to_vec()).map_err(|_| invalid("invalid string"))
+}
+
+fn write_vec<T: WriteBytes>(
+    data: &mut Vec<u8>,
+    values: impl ExactSizeIterator<Item = T>,
+) -> io::Result<()> {
+    write_len(data, values.len())?;
+    for v in values {
+        v.write_bytes(data);
+    }
+    Ok(())
+}
+
+fn read_vec<T: ReadBytes>(data: &mut &[u8]) -> io::Result<Vec<T>> {
+    let len = read_len(data)?;
+    (0..len).map(|_| T::read_bytes(data)).collect()
+}
+
+fn write_attribute<T: WriteBytes + Copy + 'static>(
+    data: &mut Vec<u8>,
+    attribute: &VertexAttribute,
+) -> io::Result<()> {
+    let values = attribute
+        .as_slice::<T>()
+        .map_err(|_| invalid("unexpected attribute type"))?;
+    write_vec(data, values.iter().copied())
+}
+
+fn read_attribute<T: ReadBytes + meshx::attrib::AttributeValue + Default>(
+    data: &mut &[u8],
+) -> io::Result<VertexAttribute> {
+    Ok(VertexAttribute::direct_from_vec(read_vec::<T>(data)?))
+}
+
+fn write_attributes(data: &mut Vec<u8>, attribs: &[Attribute]) -> io::Result<()> {
+    write_len(data, attribs.len())?;
+    for attrib in attribs {
+        write_str(data, &attrib.name)?;
+        write_str(data, &serde_json::to_string(&attrib.type_)?)?;
+        call_typed_fn!(attrib.type_ => self::write_attribute::<_>(data, &attrib.attribute))?;
+    }
+    Ok(())
+}
+
+fn read_attributes(data: &mut &[u8]) -> io::Result<Vec<Attribute>> {
+    let len = read_len(data)?;
+    (0..len)
+        .map(|_| {
+            let name = read_str(data)?;
+            let type_: Type =
+                serde_json::from_str(&read_str(data)?).map_err(|_| invalid("unknown type"))?;
+            let attribute = call_typed_fn!(type_ => self::read_attribute::<_>(data))?;
+            Ok(Attribute {
+                name,
+                type_,
+                attribute,
+            })
+        })
+        .collect()
+}
+
+/// Writes texture coordinates stored as either 2D or 3D vectors.
+fn write_tex_attribute<T: WriteBytes + Copy + 'static>(
+    data: &mut Vec<u8>,
+    attribute: &VertexAttribute,
+) -> io::Result<()> {
+    if attribute.check::<[T; 2]>().is_ok() {
+        data.write_u8(2)?;
+        write_attribute::<[T; 2]>(data, attribute)
+    } else {
+        data.write_u8(3)?;
+        write_attribute::<[T; 3]>(data, attribute)
+    }
+}
+
+fn read_tex_attribute<T: ReadBytes + meshx::attrib::AttributeValue + Copy + Default>(
+    data: &mut &[u8],
+) -> io::Result<VertexAttribute> {
+    match data.read_u8()? {
+        2 => read_attribute::<[T; 2]>(data),
+        3 => read_attribute::<[T; 3]>(data),
+        _ => Err(invalid("invalid texture coordinate dimension")),
+    }
+}
+
+fn write_tex_attributes(data: &mut Vec<u8>, attribs: &[TextureAttribute]) -> io::Result<()> {
+    write_len(data, attribs.len())?;
+    for attrib in attribs {
+        data.write_u32::<LE>(attrib.id)?;
+        write_str(data, &attrib.name)?;
+        write_str(data, &serde_json::to_string(&attrib.component_type)?)?;
+        match attrib.component_type {
+            ComponentType::U8 => write_tex_attribute::<u8>(data, &attrib.attribute)?,
+            ComponentType::U16 => write_tex_attribute::<u16>(data, &attrib.attribute)?,
+            ComponentType::F32 => write_tex_attribute::<f32>(data, &attrib.attribute)?,
+            _ => return Err(invalid("unsupported texture coordinate type")),
+        }
+    }
+    Ok(())
+}
+
+fn read_tex_attributes(data: &mut &[u8]) -> io::Result<Vec<TextureAttribute>> {
+    let len = read_len(data)?;
+    (0..len)
+        .map(|_| {
+            let id = data.read_u32::<LE>()?;
+            let name = read_str(data)?;
+            let component_type: ComponentType = serde_json::from_str(&read_str(data)?)
+                .map_err(|_| invalid("unknown component type"))?;
+            let attribute = match component_type {
+                ComponentType::U8 => read_tex_attribute::<u8>(data)?,
+                ComponentType::U16 => read_tex_attribute::<u16>(data)?,
+                ComponentType::F32 => read_tex_attribute::<f32>(data)?,
+                _ => return Err(invalid("unsupported texture coordinate type")),
+            };
+            Ok(TextureAttribute {
+                id,
+                name,
+                component_type,
+                attribute,
+            })
+        })
+        .collect()
+}
+
+fn write_entry(
+    data: &mut Vec<u8>,
+    config_key: u64,
+    stamp: &Stamp,
+    mesh: &Mesh,
+    attrib_transfer: &AttribTransfer,
+) -> io::Result<()> {
+    data.extend_from_slice(MAGIC);
+    data.write_u32::<LE>(FORMAT_VERSION)?;
+    data.write_u64::<LE>(config_key)?;
+    write_str(data, &stamp.path)?;
+    data.write_u64::<LE>(stamp.secs)?;
+    data.write_u32::<LE>(stamp.nanos)?;
+    data.write_u64::<LE>(stamp.len)?;
+
+    match mesh {
+        Mesh::TriMesh(mesh) => {
+            data.write_u8(0)?;
+            write_vec(data, mesh.vertex_positions().iter().copied())?;
+            write_len(data, mesh.indices.len())?;
+            for tri in mesh.indices.iter() {
+                for &i in tri {
+                    data.write_u64::<LE>(i as u64)?;
+                }
+            }
+        }
+        Mesh::PointCloud(ptcloud) => {
+            data.write_u8(1)?;
+            write_vec(data, ptcloud.vertex_positions().iter().copied())?;
+        }
+    }
+
+    let AttribTransfer {
+        attribs_to_keep,
+        color_attribs_to_keep,
+        tex_attribs_to_keep,
+        material_ids,
+        normal_attrib,
+        tangent_attrib,
+        polygon_ids,
+        topo_hash,
+    } = attrib_transfer;
+
+    write_vec(data, normal_attrib.iter().copied())?;
+    write_vec(data, tangent_attrib.iter().copied())?;
+    write_vec(data, polygon_ids.iter().copied())?;
+    match topo_hash {
+        Some(hash) => {
+            data.write_u8(1)?;
+            data.write_u64::<LE>(*hash)?;
+        }
+        None => data.write_u8(0)?,
+    }
+    match material_ids {
+        None => data.write_u8(0)?,
+        Some(MaterialIds::Global { map }) => {
+            data.write_u8(1)?;
+            write_len(data, map.len())?;
+            for (id, faces) in map {
+                data.write_u32::<LE>(*id)?;
+                write_len(data, faces.len())?;
+                for &face in faces {
+                    data.write_u64::<LE>(face as u64)?;
+                }
+            }
+        }
+        Some(MaterialIds::Local { .. }) => return Err(invalid("local materials are not cached")),
+    }
+    write_attributes(data, attribs_to_keep)?;
+    write_attributes(data, color_attribs_to_keep)?;
+    write_tex_attributes(data, tex_attribs_to_keep)
+}
+
+/// Reads a cache entry, returning `Ok(None)` if it is stale.
+fn read_entry(
+    data: &mut &[u8],
+    config_key: u64,
+    stamp: &Stamp,
+) -> io::Result<Option<(Mesh, AttribTransfer)>> {
+    let mut magic = [0; 4];
+    io::Read::read_exact(data, &mut magic)?;
+    if &magic != MAGIC || data.read_u32::<LE>()? != FORMAT_VERSION {
+        return Ok(None);
+    }
+    if data.read_u64::<LE>()? != config_key {
+        return Ok(None);
+    }
+    let cached = Stamp {
+        path: read_str(data)?,
+        secs: data.read_u64::<LE>()?,
+        nanos: data.read_u32::<LE>()?,
+        len: data.read_u64::<LE>()?,
+    };
+    if &cached != stamp {
+        return Ok(None);
+    }
+
+    let read_index = |data: &mut &[u8]| -> io::Result<usize> {
+        usize::try_from(data.read_u64::<LE>()?).map_err(|_| invalid("index overflow"))
+    };
+
+    let mesh = match data.read_u8()? {
+        0 => {
+            let positions = read_vec::<[f32; 3]>(data)?;
+            let num_tris = read_len(data)?;
+            let indices = (0..num_tris)
+                .map(|_| Ok([read_index(data)?, read_index(data)?, read_index(data)?]))
+                .collect::<io::Result<Vec<_>>>()?;
+            if indices.iter().flatten().any(|&i| i >= positions.len()) {
+                return Err(invalid("vertex index out of bounds"));
+            }
+            Mesh::from(TriMesh::new(positions, indices))
+        }
+        1 => Mesh::PointCloud(PointCloud::new(read_vec::<[f32; 3]>(data)?)),
+        _ => return Err(invalid("unknown mesh type")),
+    };
+
+    let normal_attrib = read_vec(data)?;
+    let tangent_attrib = read_vec(data)?;
+    let polygon_ids = read_vec(data)?;
+    let topo_hash = match data.read_u8()? {
+        0 => None,
+        _ => Some(data.read_u64::<LE>()?),
+    };
+    let material_ids = match data.read_u8()? {
+        0 => None,
+        _ => {
+            let len = read_len(data)?;
+            let map = (0..len)
+                .map(|_| {
+                    let id = data.read_u32::<LE>()?;
+                    let num_faces = read_len(data)?;
+                    let faces = (0..num_faces)
+                        .map(|_| read_index(data))
+                        .collect::<io::Result<Vec<_>>>()?;
+                    Ok((id, faces))
+                })
+                .collect::<io::Result<_>>()?;
+            Some(MaterialIds::Global { map })
+        }
+    };
+    let attribs_to_keep = read_attributes(data)?;
+    let color_attribs_to_keep = read_attributes(data)?;
+    let tex_attribs_to_keep = read_tex_attributes(data)?;
+
+    Ok(Some((
+        mesh,
+        AttribTransfer {
+            attribs_to_keep,
+            color_attribs_to_keep,
+            tex_attribs_to_keep,
+            material_ids,
+            normal_attrib,
+            tangent_attrib,
+            polygon_ids,
+            topo_hash,
+        },
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_and_clean_mesh;
+
+    #[test]
+    fn frame_cache_round_trip() {
+        let dir = std::env::temp_dir().join("gltfgen_frame_cache_round_trip");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("box_rotate_1.vtk");
+        fs::copy("assets/box_rotate_1.vtk", &path).unwrap();
+
+        let attributes = "{\"pressure\": f32}".parse().unwrap();
+        let colors = "{\"Cd\": vec3(f32)}".parse().unwrap();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let load_config = LoadConfig::default();
+        let cache = FrameCache::open(dir.join("cache"), load_config, attrib_config).unwrap();
+        assert!(cache.get(&path).is_none());
+
+        let (mesh, attrib_transfer) =
+            load_and_clean_mesh(&path, load_config, attrib_config, |e| panic!("{}", e)).unwrap();
+        cache.insert(&path, &mesh, &attrib_transfer).unwrap();
+
+        let (cached_mesh, cached_transfer) = cache.get(&path).unwrap();
+        assert!(cached_mesh.eq_topo(&mesh));
+        assert_eq!(cached_mesh.vertex_positions(), mesh.vertex_positions());
+        assert_eq!(cached_mesh.topo_hash(), mesh.topo_hash());
+        assert_eq!(
+            cached_transfer.attribs_to_keep,
+            attrib_transfer.attribs_to_keep
+        );
+        assert_eq!(
+            cached_transfer.color_attribs_to_keep,
+            attrib_transfer.color_attribs_to_keep
+        );
+        assert_eq!(cached_transfer.normal_attrib, attrib_transfer.normal_attrib);
+        assert_eq!(cached_transfer.topo_hash, attrib_transfer.topo_hash);
+        assert!(matches!(
+            cached_transfer.material_ids,
+            Some(MaterialIds::Global { .. })
+        ));
+
+        // A different configuration doesn't reuse the entry.
+        let other_attributes = AttributeInfo::default();
+        let other_config = AttribConfig {
+            attributes: &other_attributes,
+            ..attrib_config
+        };
+        let other = FrameCache::open(dir.join("cache"), load_config, other_config).unwrap();
+        assert!(other.get(&path).is_none());
+
+        // Modifying the input invalidates the entry.
+        let mut contents = fs::read(&path).unwrap();
+        contents.push(b'\n');
+        fs::write(&path, contents).unwrap();
+        assert!(cache.get(&path).is_none());
+    }
+}
@@ -1,15 +1,26 @@
-use std::{io::BufReader, path::Path};
+use std::{
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 
+#[cfg(feature = "cli")]
 use clap::{ArgMatches, Parser};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-use crate::{AttributeInfo, Error, MaterialInfo, TextureAttributeInfo, TextureInfo};
+use crate::time::{FrameRate, Seconds};
+use crate::{
+    AttributeInfo, AttributeNames, Error, ExtrasInfo, LayoutInfo, LightInfo, MaterialIdRemap,
+    MaterialInfo, PointerAnimationInfo, TextureAttributeInfo, TextureInfo,
+};
 
 // Only a single normal and tangent attributes are supported on input meshes.
 // If the input mesh format uses special attributes to store these quantities, then this
 // name is inconsequential.
 pub const NORMAL_ATTRIB_NAME: &str = "N";
 pub const TANGENT_ATTRIB_NAME: &str = "T";
+// Face attribute holding the index of the source polygon of each triangle (see `--polygon-ids`).
+pub const POLYGON_ID_ATTRIB_NAME: &str = "_POLY_ID";
 
 // Additional attribute (really accessor) names to be used on the output mainly for debugging.
 // This helps to identify which accessors represent which data in the output gltf.
@@ -18,11 +29,15 @@ pub const POSITION_ATTRIB_NAME: &str = "P";
 pub const POSITION_DISPLACEMENT_ATTRIB_NAME: &str = "dP";
 pub const NORMAL_DISPLACEMENT_ATTRIB_NAME: &str = "dN";
 pub const TANGENT_DISPLACEMENT_ATTRIB_NAME: &str = "dT";
+pub const COLOR_DISPLACEMENT_ATTRIB_NAME: &str = "dCd";
 pub const TIME_ATTRIB_NAME: &str = "time";
 pub const WEIGHTS_ATTRIB_NAME: &str = "weights";
 
-fn default_fps() -> u32 {
-    24
+// Semantic of the per-point radius attribute selected with `--point-radius`.
+pub const POINT_RADIUS_SEMANTIC: &str = "_RADIUS";
+
+fn default_fps() -> FrameRate {
+    FrameRate::default()
 }
 fn default_step() -> u32 {
     1
@@ -31,13 +46,308 @@ fn default_mtl_id() -> String {
     "mtl_id".to_string()
 }
 
+/// Order used to assign frame numbers to files when the pattern has no frame number.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FrameOrder {
+    /// Files are ordered by name.
+    #[serde(alias = "name")]
+    Name,
+    /// Files are ordered by name, with numbers ordered by value (e.g. 'a2' before 'a10').
+    #[serde(alias = "natural")]
+    Natural,
+    /// Files are ordered by modification time.
+    #[serde(alias = "mtime")]
+    Mtime,
+}
+
+/// What to do with frames containing NaN or infinite values.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NonFinitePolicy {
+    /// Stop with an error.
+    #[default]
+    #[serde(alias = "error")]
+    Error,
+    /// Skip the frame.
+    #[serde(alias = "drop")]
+    Drop,
+    /// Replace non-finite values with those of the previous frame.
+    #[serde(alias = "previous")]
+    Previous,
+}
+
+/// What to do with files whose frame number cannot be parsed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum UnparsableFramePolicy {
+    /// Skip the file.
+    #[default]
+    #[serde(alias = "skip")]
+    Skip,
+    /// Place the file at frame zero.
+    #[serde(alias = "zero")]
+    Zero,
+    /// Stop with an error.
+    #[serde(alias = "error")]
+    Error,
+}
+
+/// What to do with frames missing from the middle of a sequence.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GapPolicy {
+    /// Keep the shape of the frame before the gap until the frame after it.
+    #[serde(alias = "hold")]
+    Hold,
+    /// Blend linearly through the gap.
+    #[serde(alias = "interpolate")]
+    Interpolate,
+    /// Stop with an error.
+    #[serde(alias = "error")]
+    Error,
+    /// Blend linearly through the gap and print a warning.
+    #[default]
+    #[serde(alias = "warn")]
+    Warn,
+}
+
+/// How polygons with more than three vertices are split into triangles.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Triangulation {
+    /// Connect every vertex of a polygon to its first vertex.
+    #[default]
+    #[serde(alias = "fan")]
+    Fan,
+    /// Repeatedly clip ears off of each polygon, which handles concave polygons.
+    #[serde(alias = "ear_clip")]
+    EarClip,
+    /// Clip ears and then flip diagonals to avoid thin triangles.
+    #[serde(alias = "delaunay")]
+    Delaunay,
+}
+
+/// How the winding of triangles in input meshes is fixed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WindingFix {
+    /// Keep triangles as they are.
+    #[default]
+    #[serde(alias = "off")]
+    Off,
+    /// Orient the triangles of each connected component consistently with the majority.
+    #[serde(alias = "consistent")]
+    Consistent,
+    /// Orient the triangles of each connected component consistently and facing outward.
+    #[serde(alias = "outward")]
+    Outward,
+}
+
+/// What to do with attributes found in only some frames of an animation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum AttributeMismatchPolicy {
+    /// Fill the attribute with zeros in frames where it is missing.
+    #[serde(alias = "zeros")]
+    Zeros,
+    /// Drop the attribute from the whole node and print a warning.
+    #[default]
+    #[serde(alias = "drop")]
+    Drop,
+    /// Start a new node whenever the set of attributes changes.
+    #[serde(alias = "split")]
+    Split,
+}
+
+/// Shape of the mesh instanced at every point of a point cloud.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PointsAs {
+    /// Three perpendicular quads through each point.
+    #[serde(alias = "billboard")]
+    Billboard,
+    /// A sphere with the given radius.
+    #[serde(alias = "sphere")]
+    Sphere(f32),
+    /// An axis aligned cube.
+    #[serde(alias = "cube")]
+    Cube,
+}
+
+/// How animation channels are grouped into glTF animations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum AnimationGrouping {
+    /// All channels are placed in a single unnamed animation.
+    #[default]
+    #[serde(alias = "single")]
+    Single,
+    /// One animation per node name, i.e. per match of the '{}' groups in the pattern.
+    #[serde(alias = "by_name")]
+    ByName,
+    /// Animations named by the given map from node names to animation names.
+    ///
+    /// Nodes missing from the map get an animation of their own.
+    #[serde(alias = "groups")]
+    Groups(IndexMap<String, String>),
+}
+
+impl AnimationGrouping {
+    /// Name of the animation containing the channels of the node with the given name.
+    ///
+    /// Returns `None` if all channels are placed in a single animation.
+    pub fn group<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        match self {
+            AnimationGrouping::Single => None,
+            AnimationGrouping::ByName => Some(name),
+            AnimationGrouping::Groups(groups) => {
+                Some(groups.get(name).map_or(name, String::as_str))
+            }
+        }
+    }
+}
+
+/// Digital content creation tool for which the output is tuned.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Dcc {
+    /// Blender, which imports morph targets as shape keys.
+    #[serde(alias = "blender")]
+    Blender,
+}
+
+impl std::str::FromStr for Dcc {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<Dcc, Self::Err> {
+        ron::de::from_str::<Dcc>(input).map_err(Self::Err::from)
+    }
+}
+
+/// Shape of the collision proxies generated for each mesh node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionProxy {
+    /// Axis aligned bounding box of the vertices.
+    #[serde(alias = "box")]
+    Box,
+    /// Convex hull of the vertices.
+    #[serde(alias = "hull")]
+    Hull,
+}
+
+impl std::str::FromStr for CollisionProxy {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<CollisionProxy, Self::Err> {
+        ron::de::from_str::<CollisionProxy>(input).map_err(Self::Err::from)
+    }
+}
+
+fn default_dcc_max_targets() -> u32 {
+    64
+}
+
+impl std::str::FromStr for AnimationGrouping {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<AnimationGrouping, Self::Err> {
+        ron::de::from_str::<AnimationGrouping>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for PointsAs {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<PointsAs, Self::Err> {
+        ron::de::from_str::<PointsAs>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for GapPolicy {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<GapPolicy, Self::Err> {
+        ron::de::from_str::<GapPolicy>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for Triangulation {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<Triangulation, Self::Err> {
+        ron::de::from_str::<Triangulation>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for WindingFix {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<WindingFix, Self::Err> {
+        ron::de::from_str::<WindingFix>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for AttributeMismatchPolicy {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<AttributeMismatchPolicy, Self::Err> {
+        ron::de::from_str::<AttributeMismatchPolicy>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for NonFinitePolicy {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<NonFinitePolicy, Self::Err> {
+        ron::de::from_str::<NonFinitePolicy>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for UnparsableFramePolicy {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<UnparsableFramePolicy, Self::Err> {
+        ron::de::from_str::<UnparsableFramePolicy>(input).map_err(Self::Err::from)
+    }
+}
+
+impl std::str::FromStr for FrameOrder {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<FrameOrder, Self::Err> {
+        ron::de::from_str::<FrameOrder>(input).map_err(Self::Err::from)
+    }
+}
+
+/// Settings changed for a single animation in the `overrides` field of a configuration file.
+///
+/// Fields that are not given keep the values of the rest of the configuration.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnimationOverride {
+    /// Step by the given number of frames instead of 'step'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<u32>,
+    /// Name of one of the specified materials bound to all primitives of the
+    /// animation regardless of their material ids.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+    /// Attributes exported instead of 'attributes'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<AttributeInfo>,
+    /// Color attributes exported instead of 'colors'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colors: Option<AttributeInfo>,
+    /// Texture coordinate attributes exported instead of 'texcoords'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texcoords: Option<TextureAttributeInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<[f32; 3]>,
+    /// Rotation given as a unit quaternion `(x, y, z, w)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<[f32; 4]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<[f32; 3]>,
+}
+
+impl AnimationOverride {
+    /// Returns `true` if this override changes the attributes loaded from meshes.
+    pub fn changes_attributes(&self) -> bool {
+        self.attributes.is_some() || self.colors.is_some() || self.texcoords.is_some()
+    }
+}
+
 /// Output configuration for the generated glTF.
-#[derive(Parser, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(Parser))]
 pub struct Config {
     /// A glob pattern matching input mesh files.
     ///
     /// Use # to match a frame number. If more than one '#' is used, the first
-    /// match will correspond to the frame number. Note that the glob pattern
+    /// match will correspond to the frame number. Use '#{N}' to match frame
+    /// numbers with exactly N digits, e.g. '#{4}' matches '0001' but not '1'
+    /// or '10001'. Use '#.#' to match frame numbers with an optional
+    /// fractional part written by sub-stepping solvers (e.g. '0012.25'), see
+    /// 'substeps'. Note that the glob pattern
     /// should generally by provided as a quoted string to prevent the terminal
     /// from evaluating it.
     ///
@@ -47,38 +357,104 @@ pub struct Config {
     /// strings within will be concatenated to produce a unique name.  Note that
     /// for the time being, '{' '}' are ignored when the glob pattern is
     /// matched.
-    #[clap(name = "PATTERN", default_value = "./#.obj")]
+    ///
+    /// Alternatives can be given as comma separated strings between braces
+    /// (e.g. '{cloth,body}_#.vtk'), in which case the matched alternative is
+    /// used as the name, or with '@(...)' (e.g. 'cloth_#.@(vtk|obj)'), which
+    /// doesn't affect the name.
+    #[cfg_attr(feature = "cli", clap(name = "PATTERN", default_value = "./#.obj"))]
     pub pattern: String,
 
+    /// Match the pattern against file names ignoring case.
+    ///
+    /// This affects all characters including non-ASCII letters, so for
+    /// instance './#.obj' also matches '1.OBJ' and '1.Obj'.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub case_insensitive: bool,
+
     /// Output glTF file.
-    #[clap(short, long, default_value = "./out.glb")]
+    #[cfg_attr(feature = "cli", clap(short, long, default_value = "./out.glb"))]
     pub output: std::path::PathBuf,
 
     /// Frames per second.
     ///
     /// 1/fps gives the time step between discrete frames. If 'time_step' is also provided, this
-    /// parameter is ignored.
-    #[clap(value_name = "FPS", short, long, default_value = "24")]
+    /// parameter is ignored. Fractional rates can be given as decimals or fractions, e.g. '29.97'
+    /// or '30000/1001'.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "FPS", short, long, default_value = "24")
+    )]
     #[serde(default = "default_fps")]
-    pub fps: u32,
+    pub fps: FrameRate,
 
-    /// Time step in seconds between discrete frames.
+    /// Time step between discrete frames.
     ///
     /// Specifying this option overrides the time step that would be computed from 'fps', which is
     /// set to 24 by default.  This means that the default 'time_step' is equivalently 1/24.
-    #[clap(value_name = "TIMESTEP", short, long)]
-    pub time_step: Option<f32>,
+    /// The time step is given in seconds as a number or fraction (e.g. '1/30'), optionally
+    /// followed by a unit: 'ms', 's', 'min' or 'h' (e.g. '40ms').
+    #[cfg_attr(feature = "cli", clap(value_name = "TIMESTEP", short, long))]
+    pub time_step: Option<Seconds>,
+
+    /// Total duration of the animation, e.g. '5s' or '2.5min'.
+    ///
+    /// The time step is computed so that the last frame is shown the given time after the first,
+    /// which overrides both 'fps' and 'time_step'. Durations are given like 'time_step'.
+    #[cfg_attr(feature = "cli", clap(value_name = "TIME", long))]
+    #[serde(default)]
+    pub duration: Option<Seconds>,
 
     /// Reverse polygon orientations on output meshes.
-    #[clap(short, long)]
+    #[cfg_attr(feature = "cli", clap(short, long))]
     #[serde(default)]
     pub reverse: bool,
 
+    /// Fix triangles wound inconsistently with their neighbours on input meshes.
+    ///
+    /// Some meshing tools emit triangles with mixed winding, which shows up as
+    /// dark patches. 'Consistent' flips the triangles of each connected
+    /// component that disagree with the majority across shared edges.
+    /// 'Outward' additionally flips whole components enclosing a negative
+    /// volume so that they face outward, which is meant for closed surfaces.
+    /// Meshes are fixed before '--reverse' is applied.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "MODE", long, default_value = "Off")
+    )]
+    #[serde(default)]
+    pub fix_winding: WindingFix,
+
     /// Invert tetrahedra orientations on input meshes.
-    #[clap(short, long)]
+    #[cfg_attr(feature = "cli", clap(short, long))]
     #[serde(default)]
     pub invert_tets: bool,
 
+    /// How polygons with more than three vertices are split into triangles.
+    ///
+    /// 'Fan' connects every vertex of a polygon to its first vertex, which is
+    /// fast but produces slivers and overlapping triangles on concave polygons.
+    /// 'EarClip' follows the outline of each polygon, and 'Delaunay' further
+    /// flips diagonals to maximize the smallest angles for the cleanest shading.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "STRATEGY", long, default_value = "Fan")
+    )]
+    #[serde(default)]
+    pub triangulation: Triangulation,
+
+    /// Record which quad or n-gon each triangle came from.
+    ///
+    /// The index of the source polygon of each triangle is written to the
+    /// primitive extras under "polygonIds", in the order triangles appear in
+    /// the primitive indices, so that tools can draw the edges of the
+    /// original polygons instead of the triangulated wireframe. Input meshes
+    /// may also provide these indices in a '_POLY_ID' face attribute.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub polygon_ids: bool,
+
     /// Step by the given number of frames.
     ///
     /// In other words, read frames in increments of 'step'.  Note that this
@@ -88,10 +464,108 @@ pub struct Config {
     /// For example for frames 1 to 10, a 'step' value of 3 will read frames 1,
     /// 4, 7, and 10.
     ///
-    #[clap(value_name = "STEPS", short, long, default_value = "1")]
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "STEPS", short, long, default_value = "1")
+    )]
     #[serde(default = "default_step")]
     pub step: u32,
 
+    /// Offset added to every frame number parsed from file names.
+    ///
+    /// For example, frames numbered from 1001 can be remapped to start at 0
+    /// with '--frame-offset=-1001'. Files whose frame number would become
+    /// negative are skipped.
+    #[cfg_attr(
+        feature = "cli",
+        clap(
+            value_name = "OFFSET",
+            long,
+            default_value = "0",
+            allow_negative_numbers = true
+        )
+    )]
+    #[serde(default)]
+    pub frame_offset: i64,
+
+    /// Number of substeps per frame for fractional frame numbers matched by '#.#'.
+    ///
+    /// Each frame is divided into the given number of substeps, which are
+    /// placed at fractional keyframe times. For example, with '--substeps 4'
+    /// the files 'frame_0012.vtk', 'frame_0012.25.vtk' and 'frame_0012.5.vtk'
+    /// are placed a quarter of a time step apart. Files whose fractional frame
+    /// doesn't fall on a substep are skipped. Note that 'step' then counts
+    /// substeps rather than frames.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "SUBSTEPS", long, default_value = "1")
+    )]
+    #[serde(default = "default_step")]
+    pub substeps: u32,
+
+    /// What to do with files whose frame number cannot be parsed.
+    ///
+    /// This happens for instance when the number matched by '#' is too large.
+    /// With 'Skip' such files are skipped with a warning, 'Zero' places them
+    /// at frame zero and 'Error' stops the conversion.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "POLICY", long, default_value = "Skip")
+    )]
+    #[serde(default)]
+    pub unparsable_frames: UnparsableFramePolicy,
+
+    /// Assign frame numbers from the order of files when the pattern has no frame number.
+    ///
+    /// Without a '#' in the pattern, all files with the same name are placed
+    /// at frame zero. With this option, frames are numbered sequentially from
+    /// zero (plus 'frame_offset') by sorting the files of each animation by
+    /// name, natural order ('natural', where 'a2' comes before 'a10') or
+    /// modification time ('mtime').
+    #[cfg_attr(feature = "cli", clap(value_name = "ORDER", long))]
+    #[serde(default)]
+    pub order: Option<FrameOrder>,
+
+    /// Only convert animations whose names match the given regular expression.
+    ///
+    /// The expression is matched against the whole name built from the '{}'
+    /// groups in the pattern, so for instance 'hero.*' keeps 'hero' and
+    /// 'hero_cape' but not 'superhero'.
+    #[cfg_attr(feature = "cli", clap(value_name = "REGEX", long))]
+    #[serde(default)]
+    pub include_names: Option<String>,
+
+    /// Skip animations whose names match the given regular expression.
+    ///
+    /// Like 'include_names', the expression must match the whole name.
+    /// Animations matching both are skipped.
+    #[cfg_attr(feature = "cli", clap(value_name = "REGEX", long))]
+    #[serde(default)]
+    pub exclude_names: Option<String>,
+
+    /// Additional patterns whose sequences are played after the main one.
+    ///
+    /// Each sequence is shifted in time to start right after the last frame
+    /// of the previous sequence, so several simulation passes can be played
+    /// back to back. Animations with the same name in consecutive sequences
+    /// (e.g. both named by '{cloth}') continue on the same node as long as
+    /// their topology matches.
+    #[cfg_attr(feature = "cli", clap(value_name = "PATTERN", long))]
+    #[serde(default)]
+    pub concat: Vec<String>,
+
+    /// Number of frames used to blend between concatenated sequences.
+    ///
+    /// These frames are left between the last frame of a sequence and the
+    /// first frame of the next one, and are blended linearly regardless of
+    /// '--gap-policy'.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "FRAMES", long, default_value = "0")
+    )]
+    #[serde(default)]
+    pub crossfade: u32,
+
     /// A dictionary of color attributes and their types.
     ///
     /// The dictionary string should have the following pattern:
@@ -113,6 +587,11 @@ pub struct Config {
     ///
     /// Note that component type names may be specified in lower case as well.
     ///
+    /// If this flag is omitted, then gltfgen looks for a vertex attribute with
+    /// a common color name like "Cd", "color" or "RGB" in the first frame,
+    /// holding 3 or 4 integer components or float components between 0 and 1,
+    /// unless '--no-auto-attributes' is given.
+    ///
     /// LIMITATIONS:
     ///
     /// See LIMITATIONS section for the '--attributes' flag.
@@ -123,7 +602,10 @@ pub struct Config {
     ///
     /// '{"diffuse": Vec3(f32), "bump": Vec3(F32)}'
     ///
-    #[clap(value_name = "ATTRIBS", short, long, default_value = "{}")]
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "ATTRIBS", short, long, default_value = "{}")
+    )]
     #[serde(default)]
     pub colors: AttributeInfo,
 
@@ -145,8 +627,9 @@ pub struct Config {
     /// an underscore as required by the glTF 2.0 specifications.
     ///
     /// For example an attribute named "temperatureKelvin" will be stored as
-    /// "_TEMPERATURE_KELVIN" in the output. There are no guarantees for
-    /// collision resolution resulting from this conversion.
+    /// "_TEMPERATURE_KELVIN" in the output. Names that collide after this
+    /// conversion are disambiguated with a numeric suffix, and output names can
+    /// be pinned with 'attribute_names'.
     ///
     /// The associated types must have the format 'type(component)' where 'type'
     /// is one of [Scalar, Vec2, Vec3, Vec4, Mat2, Mat3, or Mat4].
@@ -182,15 +665,35 @@ pub struct Config {
     ///
     /// '{"temperature":F32, "force":Vec3(F32), "material":Scalar(u32)}'
     ///
-    #[clap(
-        value_name = "ATTRIBS",
-        short,
-        long,
-        default_value = "{\"N\":Vec3(f32)}"
+    #[cfg_attr(
+        feature = "cli",
+        clap(
+            value_name = "ATTRIBS",
+            short,
+            long,
+            default_value = "{\"N\":Vec3(f32)}"
+        )
     )]
     #[serde(default)]
     pub attributes: AttributeInfo,
 
+    /// A dictionary of output names for custom attributes.
+    ///
+    /// By default custom attributes are named by converting their input names
+    /// to SCREAMING_SNAKE case. Use this to pin the output name of an
+    /// attribute instead, e.g. '{"temperatureKelvin": "_TEMPERATURE"}'. The
+    /// leading underscore required by glTF is added if missing.
+    ///
+    /// If two attributes would end up with the same name in the output, the
+    /// later one gets a numeric suffix (e.g. '_FOO_1') and a warning is
+    /// printed.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "NAMES", long, default_value = "{}")
+    )]
+    #[serde(default)]
+    pub attribute_names: AttributeNames,
+
     /// A dictionary of texture coordinate attributes and their types.
     ///
     /// The dictionary string should have the following pattern:
@@ -210,6 +713,10 @@ pub struct Config {
     /// If this flag is omitted, then gltfgen looks for texture attributes
     /// named "uv" by default. This will pick up dedicated texture attributes in
     /// formats like 'vt' in '.obj' files and TEXTURE_COORDINATES in '.vtk' files.
+    /// If the first frame has no "uv" attribute, a 2-component float attribute
+    /// with a common texture coordinate name like "st", "TEXCOORD" or
+    /// "TCoords" is used instead when found, unless '--no-auto-attributes' is
+    /// given.
     ///
     ///
     /// Note that component type names may be specified in lower case as well.
@@ -224,15 +731,28 @@ pub struct Config {
     ///
     /// '{"uv": f32, "bump": F32}'
     ///
-    #[clap(
-        value_name = "TEXCOORDS",
-        short = 'u',
-        long,
-        default_value = "{\"uv\":f32}"
+    #[cfg_attr(
+        feature = "cli",
+        clap(
+            value_name = "TEXCOORDS",
+            short = 'u',
+            long,
+            default_value = "{\"uv\":f32}"
+        )
     )]
     #[serde(default)]
     pub texcoords: TextureAttributeInfo,
 
+    /// Don't look for color and texture coordinate attributes in the input.
+    ///
+    /// By default, colors are picked up from attributes with common color
+    /// names when '--colors' is omitted, and texture coordinates from
+    /// attributes with common texture coordinate names when '--texcoords' is
+    /// omitted and no "uv" attribute exists.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub no_auto_attributes: bool,
+
     /// A tuple of texture parameters.
     ///
     /// Each struct should have the following pattern:
@@ -257,7 +777,8 @@ pub struct Config {
     /// where 'path_to_image' is the path to a 'png' or a 'jpeg' image which
     /// will be either referenced ('Uri') or embedded ('Embed') into the gltf
     /// file itself. Images specified 'Auto' will be referenced for `.gltf`
-    /// outputs and embedded for `.glb` outputs.
+    /// outputs and embedded for `.glb` outputs. KTX2 images are rejected, since
+    /// gltfgen can't encode them or generate their mip chains.
     ///
     /// The remaining optional fields describe the sampler and can take on the
     /// following values:
@@ -282,7 +803,7 @@ pub struct Config {
     /// '(image: Uri("./texture.png")) (image: Embed("./texture2.png"), wrap_s:
     /// Repeat wrap_t: mirrored_repeat)'
     ///
-    #[clap(value_name = "TEXTURES", short = 'x', long)]
+    #[cfg_attr(feature = "cli", clap(value_name = "TEXTURES", short = 'x', long))]
     #[serde(default)]
     pub textures: Vec<TextureInfo>,
 
@@ -327,7 +848,7 @@ pub struct Config {
     /// produces a material named "material0" with the specified base_color and
     /// metallic factor.
     ///
-    #[clap(value_name = "MATERIALS", short, long)]
+    #[cfg_attr(feature = "cli", clap(value_name = "MATERIALS", short, long))]
     #[serde(default)]
     pub materials: Vec<MaterialInfo>,
 
@@ -338,10 +859,53 @@ pub struct Config {
     /// This attribute must be an integer (at most 64 bit) and must index materials specified by
     /// the '-m' or '--materials' flag.
     ///
-    #[clap(value_name = "MTL-ATTRIB", short = 'e', long, default_value = "mtl_id")]
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "MTL-ATTRIB", short = 'e', long, default_value = "mtl_id")
+    )]
     #[serde(default = "default_mtl_id")]
     pub material_attribute: String,
 
+    /// A dictionary remapping material ids found in meshes to material indices.
+    ///
+    /// Ids not in the dictionary index the materials directly. For example
+    /// '{5: 0, 7: 1}' assigns the first material to faces with material id 5
+    /// and the second material to faces with id 7.
+    #[cfg_attr(feature = "cli", clap(value_name = "MAP", long, default_value = "{}"))]
+    #[serde(default)]
+    pub material_id_map: MaterialIdRemap,
+
+    /// Bind a default material to faces whose material id does not refer to
+    /// any of the specified materials.
+    ///
+    /// Without this flag such faces are left without a material.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub default_material: bool,
+
+    /// Move each material of a mesh into its own child node.
+    ///
+    /// Viewers toggle visibility per node, so this allows hiding, for
+    /// example, interior surfaces independently of exterior ones. The mesh
+    /// node keeps its name and transform, while its children are named after
+    /// the mesh node and the material.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub split_materials: bool,
+
+    /// Pack embedded PNG base color textures into a single texture atlas at
+    /// most SIZE pixels wide and tall.
+    ///
+    /// Texture coordinates are moved into the region of their image in the
+    /// atlas, and materials that then differ only by name are merged, which
+    /// reduces the number of draw calls of meshes with many small textures.
+    /// Textures sampled outside of their bounds, with different samplers, or
+    /// used for anything but base colors are kept separate.
+    ///
+    #[cfg_attr(feature = "cli", clap(value_name = "SIZE", long))]
+    #[serde(default)]
+    pub texture_atlas: Option<u32>,
+
     /// Inserts additional frames before and after an animation sequence with
     /// all vertex positions at the origin.
     ///
@@ -355,7 +919,7 @@ pub struct Config {
     /// When viewing the animation at a higher frame rate than what was
     /// originally specified (to gltfgen) the meshes will blend in and out of the origin between frames
     /// which have different topologies, which breaks the illusion.
-    #[clap(long)]
+    #[cfg_attr(feature = "cli", clap(long))]
     #[serde(default)]
     pub insert_vanishing_frames: bool,
 
@@ -363,7 +927,7 @@ pub struct Config {
     ///
     /// Normals are still transferred for the base mesh for each output node if
     /// '"N": Vec3(f32)' is specified in the '--attributes' option.
-    #[clap(long)]
+    #[cfg_attr(feature = "cli", clap(long))]
     #[serde(default)]
     pub no_animated_normals: bool,
 
@@ -371,40 +935,852 @@ pub struct Config {
     ///
     /// Tangents are still transferred for the base mesh for each output node if
     /// '"T": Vec3(f32)' is specified in the '--attributes' option.
-    #[clap(long)]
+    #[cfg_attr(feature = "cli", clap(long))]
     #[serde(default)]
     pub no_animated_tangents: bool,
+
+    /// Animate vertex colors that change between frames.
+    ///
+    /// By default only the colors of the first frame of each node are exported.
+    /// With this flag, changes in the first color attribute ('COLOR_0') are
+    /// stored as 'COLOR_0' morph target deltas driven by the same weights as the
+    /// positions. This is useful for particle simulations colored by quantities
+    /// like temperature or speed.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub animate_colors: bool,
+
+    /// Recompute smooth vertex normals from the positions of every frame.
+    ///
+    /// Normals stored in the input files are replaced by area weighted
+    /// averages of the adjacent face normals, and their changes between frames
+    /// are exported as morph targets unless '--no-animated-normals' is given.
+    /// This keeps lighting in sync with deforming geometry when the input
+    /// files don't store normals. Point clouds are left unchanged.
+    ///
+    /// Tangents of meshes with texture coordinates are recomputed from the
+    /// first texture coordinate attribute as well, so that normal mapped
+    /// surfaces shade correctly while deforming. Tangents are left unchanged if
+    /// '--no-animated-tangents' is given.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub recompute_normals: bool,
+
+    /// Split vertices along edges sharper than the given angle in degrees.
+    ///
+    /// Where the normals of two adjacent triangles differ by more than this
+    /// angle, their shared edge is shaded as a hard edge by giving each side
+    /// its own vertices, while the remaining surface stays smooth. Normals are
+    /// then recomputed from the positions of every frame as with
+    /// '--recompute-normals'. Vertices are split the same way in all frames of
+    /// a node, based on the first of them, so creases stay consistent across
+    /// morph targets. A value of 30 works well for most mechanical parts.
+    #[cfg_attr(feature = "cli", clap(value_name = "DEGREES", long))]
+    #[serde(default)]
+    pub crease_angle: Option<f32>,
+
+    /// Bake the values of the given scalar vertex attributes at every frame into textures.
+    ///
+    /// glTF can't animate custom vertex attributes, so fields like temperature
+    /// or pressure are instead stored in a PNG texture per attribute and mesh.
+    /// Each frame occupies 'rowsPerFrame' consecutive rows of texels, and each
+    /// value is normalized to the range of the field and packed into 16 bits,
+    /// with the high byte in the red channel and the low byte in the green
+    /// channel.
+    ///
+    /// Baked meshes get a '_FIELD_TEXEL' vertex attribute with the column and
+    /// row of each vertex within a frame, and a "bakedFields" list in the mesh
+    /// extras with the 'name', 'texture', 'min', 'max', 'frames' and
+    /// 'rowsPerFrame' of each field. A shader reads the value at frame index i
+    /// with
+    ///
+    /// texelFetch(field, ivec2(texel.x, texel.y + i * rowsPerFrame), 0)
+    ///
+    /// and decodes it as min + (max - min) * (256 * R + G) / 65535, where R
+    /// and G are the channels as 8 bit integers.
+    ///
+    /// Baked attributes must also be selected with '--attributes' and are not
+    /// exported as vertex attributes.
+    ///
+    #[cfg_attr(feature = "cli", clap(value_name = "ATTRIB", long))]
+    #[serde(default)]
+    pub bake_fields: Vec<String>,
+
+    /// Maximum number of vertices in a single output primitive.
+    ///
+    /// Meshes with more vertices are split into multiple primitives with
+    /// re-based indices. Attributes and morph targets are split accordingly.
+    /// Some engines refuse primitives referencing more than 65535 vertices, in
+    /// which case this can be set to 65535.
+    ///
+    /// By default, meshes are split only if vertex indices don't fit into 32
+    /// bit unsigned integers.
+    #[cfg_attr(feature = "cli", clap(value_name = "COUNT", long))]
+    #[serde(default)]
+    pub max_primitive_vertices: Option<u32>,
+
+    /// Size of rendered points in pixels.
+    ///
+    /// Most viewers draw point primitives as single pixel dots. This size is
+    /// stored as a hint under "pointSize" in the extras of every mesh with
+    /// point primitives, for viewers and scripts that make use of it.
+    #[cfg_attr(feature = "cli", clap(value_name = "SIZE", long))]
+    #[serde(default)]
+    pub point_size: Option<f32>,
+
+    /// Name of a scalar vertex attribute holding the radius of each point.
+    ///
+    /// The attribute is transferred as a 32-bit float and written with the
+    /// '_RADIUS' semantic, unless it is already listed in '--attributes' or
+    /// renamed with '--attribute-names'.
+    #[cfg_attr(feature = "cli", clap(value_name = "ATTRIB", long))]
+    #[serde(default)]
+    pub point_radius: Option<String>,
+
+    /// Replace each point of a point cloud with a small instanced mesh.
+    ///
+    /// One of 'billboard', 'sphere(RADIUS)' or 'cube'. Points are rendered
+    /// using the EXT_mesh_gpu_instancing extension for engines that don't
+    /// render point primitives. Instances are scaled by the attribute given in
+    /// '--point-radius' if any, and are otherwise sized by the given radius, or
+    /// by a small fraction of the extent of the point cloud for billboards and
+    /// cubes. Colors are taken from the first color attribute. Animated point
+    /// clouds are shown as a sequence of instanced nodes, each visible for the
+    /// duration of its frame.
+    #[cfg_attr(feature = "cli", clap(value_name = "SHAPE", long))]
+    #[serde(default)]
+    pub points_as: Option<PointsAs>,
+
+    /// How animations are split into separately playable clips.
+    ///
+    /// By default ('single'), all nodes are animated by a single animation.
+    /// With 'by_name', each animation is named after its node, so that the
+    /// sequences matched by '{}' groups (e.g. "cloth", "fluid" and "debris")
+    /// are listed as separate clips in viewers. Nodes can also be grouped
+    /// explicitly with a map from node names to animation names, e.g.
+    ///
+    /// 'groups({"cloth": "sim", "fluid": "sim"})'
+    ///
+    /// where nodes missing from the map get an animation of their own.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "GROUPING", long, default_value = "single")
+    )]
+    #[serde(default)]
+    pub animation_groups: AnimationGrouping,
+
+    /// Tune the output for importing into a content creation tool instead of realtime playback.
+    ///
+    /// With 'blender', morph targets are named after their frames (stored
+    /// as "targetNames" in the mesh extras), so they show up as named shape
+    /// keys, weights use step interpolation instead of blending between
+    /// frames, each node gets its own animation (imported as an action)
+    /// unless '--animation-groups' is given, and the number of morph targets
+    /// per mesh is limited by '--dcc-max-targets'.
+    #[cfg_attr(feature = "cli", clap(value_name = "TOOL", long))]
+    #[serde(default)]
+    pub dcc: Option<Dcc>,
+
+    /// Maximum number of morph targets per mesh when exporting with '--dcc'.
+    ///
+    /// Longer animations keep this many frames evenly spaced in time.
+    #[cfg_attr(feature = "cli", clap(value_name = "N", long, default_value = "64"))]
+    #[serde(default = "default_dcc_max_targets")]
+    pub dcc_max_targets: u32,
+
+    /// Add a simplified proxy of each mesh for collision and selection.
+    ///
+    /// Each mesh node gets a child node named after it with a '_proxy'
+    /// suffix, holding either the axis aligned bounding 'Box' or the convex
+    /// 'Hull' of its vertices over all frames. Proxy nodes are tagged with
+    /// "collisionProxy" in their extras, which engines can use to find and
+    /// hide them.
+    #[cfg_attr(feature = "cli", clap(value_name = "SHAPE", long))]
+    #[serde(default)]
+    pub collision_proxy: Option<CollisionProxy>,
+
+    /// Animate collision proxies to follow their meshes.
+    ///
+    /// Instead of enclosing all frames, each proxy takes the shape of the
+    /// first frame and is translated and scaled to the bounding box of every
+    /// frame.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub animate_proxy: bool,
+
+    /// Maximum number of morph targets per mesh.
+    ///
+    /// Longer animations are split into consecutive segments, each exported
+    /// as a separate node and mesh with at most this many morph targets. Only
+    /// one segment is visible at a time; the others are hidden with a
+    /// step-animated scale of zero. Many WebGL viewers blend at most 8 morph
+    /// targets, in which case this can be set to 8. Must be at least 2.
+    #[cfg_attr(feature = "cli", clap(value_name = "N", long))]
+    #[serde(default)]
+    pub max_morph_targets: Option<u32>,
+
+    /// A tuple of punctual lights to add to the scene.
+    ///
+    /// Lights are emitted using the KHR_lights_punctual extension and are each
+    /// attached to their own node in the scene. Lights are specified by a 'type'
+    /// (one of 'Directional', 'Point' or 'Spot'), a linear RGB 'color', an
+    /// 'intensity' (lux for directional lights and candela for point and spot
+    /// lights), an optional 'range' and the node 'translation' and 'rotation'
+    /// quaternion ((x, y, z, w)). Spot lights additionally accept
+    /// 'inner_cone_angle' and 'outer_cone_angle' in radians. Directional and
+    /// spot lights point down the local -Z axis.
+    ///
+    /// For example
+    ///
+    /// '(name:"sun", type:Directional, intensity:3.0, rotation:(-0.3826834, 0.0, 0.0, 0.9238795))'
+    ///
+    /// produces a directional light named "sun" tilted 45 degrees downward.
+    ///
+    /// '(type:Spot, outer_cone_angle:0.5, color:(1.0, 0.8, 0.6), translation:(0.0, 2.0, 0.0))'
+    ///
+    /// produces a warm spot light positioned 2 units above the origin.
+    ///
+    #[cfg_attr(feature = "cli", clap(value_name = "LIGHTS", long))]
+    #[serde(default)]
+    pub lights: Vec<LightInfo>,
+
+    /// Add a three-point rig of spot lights framing the scene.
+    ///
+    /// Key, fill and rim lights named 'key_light', 'fill_light' and
+    /// 'rim_light' are placed around the bounding box of all frames and aimed
+    /// at its center, with intensities scaled to its size. They follow any
+    /// lights given with '--lights', and can be animated by name.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub studio_lighting: bool,
+
+    /// Add a perspective camera framing the scene, and record its bounding
+    /// box in the asset extras.
+    ///
+    /// The bounding box encloses all frames and is stored as
+    /// '"sceneBounds": {"min": [x, y, z], "max": [x, y, z]}', which helps to
+    /// check the unit scale of the exported model. The camera, named
+    /// 'camera', looks at the center of the box from the front and slightly
+    /// above, far enough away to see the whole animation.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub frame_camera: bool,
+
+    /// A tuple of material and light properties animated by values given per frame.
+    ///
+    /// Each property is specified by a JSON 'pointer' into the output document
+    /// and its 'values' keyed by frame number, given either as scalars or as
+    /// lists of up to 4 components. Nodes, materials and lights may be referred
+    /// to by name in place of their index. Values are interpolated linearly
+    /// unless 'step' is set, and an optional 'group' gives the name of the node
+    /// whose animation the property joins when '--animation-groups' is used.
+    /// Properties are animated with the KHR_animation_pointer extension, which
+    /// is not supported by all viewers.
+    ///
+    /// For example
+    ///
+    /// '(pointer:"/materials/lava/emissiveFactor", values:{1:[0.0, 0.0, 0.0], 48:[1.0, 0.4, 0.0]})'
+    ///
+    /// makes the "lava" material glow over the first two seconds at 24 fps, and
+    ///
+    /// '(pointer:"/extensions/KHR_lights_punctual/lights/sun/intensity", values:{1:3.0, 48:0.5}, step:true)'
+    ///
+    /// dims the light named "sun" at frame 48.
+    ///
+    #[cfg_attr(feature = "cli", clap(value_name = "PROPERTIES", long))]
+    #[serde(default)]
+    pub pointer_animations: Vec<PointerAnimationInfo>,
+
+    /// A path to a RON or JSON file describing the node hierarchy of the output scene.
+    ///
+    /// The file defines a tree of named nodes, each with an optional
+    /// 'translation', 'rotation' (quaternion) and 'scale', and a list of
+    /// 'children'. Nodes whose names match generated animated nodes (or lights)
+    /// are slotted into the tree at that position, while the remaining nodes
+    /// become empty groups. Generated nodes not mentioned in the file are
+    /// placed at the root of the scene.
+    ///
+    /// For example, the following RON file
+    ///
+    /// (nodes: [(name:"car", translation:Some((0.0, 1.0, 0.0)), children:[(name:"body"), (name:"wheels")])])
+    ///
+    /// places the generated "body" and "wheels" nodes under a new "car" group.
+    ///
+    /// Root nodes can also be split into multiple named 'scenes', with a
+    /// 'default_scene' index selecting the scene shown first. Roots not listed
+    /// in any scene, including those of attached files, appear in all of them.
+    /// For example
+    ///
+    /// (scenes: [(name:"high", nodes:["car_hi"]), (name:"low", nodes:["car_lo"])], default_scene:Some(1))
+    ///
+    /// creates a scene per level of detail, showing the low detail car by default.
+    ///
+    #[cfg_attr(feature = "cli", clap(value_name = "FILE", long))]
+    #[serde(default)]
+    pub scene: Option<PathBuf>,
+
+    /// Automatic placement of the generated animated nodes.
+    ///
+    /// By default all nodes are placed at the origin, which causes multiple
+    /// animations to overlap. With a grid layout, each named node is
+    /// translated onto a grid in the XZ plane centered at the origin, so
+    /// animations can be compared side by side. Translations given in the
+    /// '--scene' description take precedence.
+    ///
+    /// For example
+    ///
+    /// 'Grid(spacing: 2.0)'
+    ///
+    /// places nodes 2 units apart on a square grid, and
+    ///
+    /// 'Grid(spacing: 2.0, columns: Some(1))'
+    ///
+    /// places them in a single column.
+    ///
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "LAYOUT", long, default_value = "None")
+    )]
+    #[serde(default)]
+    pub layout: LayoutInfo,
+
+    /// Paths to existing glTF ('.gltf' or '.glb') files to merge into the output scene.
+    ///
+    /// This is useful for adding static context like a ground plane, a room scan or CAD
+    /// geometry alongside the generated animations. Buffers, materials, textures and
+    /// all other objects from the given files are merged into the output, and the nodes
+    /// of their default scenes are added to the output scene. Images referenced by
+    /// relative paths are embedded into the output buffer.
+    ///
+    #[cfg_attr(feature = "cli", clap(value_name = "FILE", long))]
+    #[serde(default)]
+    pub attach: Vec<PathBuf>,
+
+    /// Arbitrary JSON metadata to attach to the output glTF.
+    ///
+    /// The metadata is stored in the 'extras' field of the 'asset', the
+    /// 'scene', as well as nodes and materials matched by name. Values are
+    /// given as RON, which are converted to JSON in the output.
+    ///
+    /// For example
+    ///
+    /// '(asset:Some({"shot":"sh010", "units":"m"}), nodes:{"box":{"solver":"v2.1"}})'
+    ///
+    /// attaches the shot name and units to the asset and the solver version to
+    /// the node named "box".
+    ///
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "EXTRAS", long, default_value = "()")
+    )]
+    #[serde(default)]
+    pub extras: ExtrasInfo,
+
+    /// Attach per-frame metadata from JSON sidecar files.
+    ///
+    /// For each mesh file, a file with the same stem and a '.json' extension
+    /// (e.g. 'frame_0012.json' next to 'frame_0012.obj') is read if it exists.
+    /// Its contents are stored in the extras of the corresponding node under
+    /// "frames", keyed by frame number.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub sidecar: bool,
+
+    /// Copyright message crediting the content creator.
+    ///
+    /// This is stored in the 'copyright' field of the glTF asset.
+    #[cfg_attr(feature = "cli", clap(value_name = "TEXT", long))]
+    #[serde(default)]
+    pub copyright: Option<String>,
+
+    /// Text appended to the generator name stored in the glTF asset.
+    ///
+    /// This can be used to identify the pipeline that produced the output,
+    /// e.g. 'gltfgen v0.8.0 (studio-pipeline 1.2)'.
+    #[cfg_attr(feature = "cli", clap(value_name = "TEXT", long))]
+    #[serde(default)]
+    pub generator_suffix: Option<String>,
+
+    /// License under which the output is distributed (e.g. 'CC-BY-4.0').
+    ///
+    /// Since glTF has no dedicated license field, this is stored under the
+    /// "license" key in the asset 'extras'.
+    #[cfg_attr(feature = "cli", clap(value_name = "LICENSE", long))]
+    #[serde(default)]
+    pub license: Option<String>,
+
+    /// Validate the structure of the output before and after writing it to file.
+    ///
+    /// This checks that all indices are valid, that accessors fit into their buffer
+    /// views and are properly aligned, that recorded min/max bounds match the
+    /// data, and that vertex indices are within range. Any problems are reported
+    /// as errors.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub validate: bool,
+
+    /// Treat warnings as errors.
+    ///
+    /// When set, the conversion fails if any frames are skipped, any attributes
+    /// fail to transfer or any other warning is reported. The exit code
+    /// identifies the kind of failure.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub strict: bool,
+
+    /// Number of threads used to load and process meshes.
+    ///
+    /// By default (or if set to 0), one thread per logical CPU is used. Use
+    /// '--threads 1' to process frames sequentially, which produces log
+    /// messages in order.
+    #[cfg_attr(feature = "cli", clap(value_name = "N", long))]
+    #[serde(default)]
+    pub threads: Option<usize>,
+
+    /// Fail instead of replacing an existing output file.
+    ///
+    /// Output is always written to a temporary file first and then renamed, so
+    /// an interrupted run never leaves a partially written file behind. Use
+    /// '--force' to overwrite existing output regardless of this setting.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub no_overwrite: bool,
+
+    /// Share buffer views with identical contents.
+    ///
+    /// Static sequences and repeated data such as identical texture
+    /// coordinates or vertex indices across nodes produce many byte-identical
+    /// buffer regions. With this option each distinct region is stored only
+    /// once, which shrinks the output without changing how it looks.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub dedup_buffers: bool,
+
+    /// Write the data of each node into a separate '.bin' file.
+    ///
+    /// Only applies to '.gltf' output. Vertex data, morph targets and
+    /// animation samplers of each node are stored in a buffer named after the
+    /// node (e.g. 'out_cloth.bin'), so that web clients can fetch only the
+    /// animations they need. Data shared between nodes, such as images, stays
+    /// in the main '.bin' file.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub split_buffers: bool,
+
+    /// Omit the names of accessors, buffer views, meshes and nodes.
+    ///
+    /// Accessors are named after the data they hold (e.g. "P" or "dP") to
+    /// make the output easier to inspect, which adds up in files with many
+    /// frames. Names of animations, materials and textures are kept.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub strip_names: bool,
+
+    /// Produce the smallest possible output for production use.
+    ///
+    /// This implies '--strip-names', drops all extras (including those given
+    /// with '--extras') and writes the JSON of '.gltf' output without
+    /// indentation.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub minimal: bool,
+
+    /// Write a JSON manifest next to the output for reproducibility audits.
+    ///
+    /// For an output named 'out.glb', the manifest is written to
+    /// 'out.manifest.json'. It records the gltfgen version, the configuration
+    /// with all overrides applied, and the path, size and SHA-256 digest of
+    /// every loaded input file and every written output file.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub manifest: bool,
+
+    /// Produce byte-identical output for identical inputs.
+    ///
+    /// Nodes are sorted by name and first frame, and the materials, textures
+    /// and accessors they introduce follow the same order, so the output
+    /// doesn't depend on the number of threads. Can't be combined with
+    /// '--cache' or '--resume', since cached frames may come from a different
+    /// version of the inputs.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub deterministic: bool,
+
+    /// Checkpoint processed frames so that an interrupted export can be resumed.
+    ///
+    /// Frames are checkpointed to a directory next to the output named after
+    /// it with a '.resume' suffix (e.g. 'out.glb.resume'). Running the same
+    /// export again with '--resume' after a crash only processes the frames
+    /// that weren't checkpointed before assembling the output. The directory
+    /// is removed once the export succeeds. If '--cache' is given, frames are
+    /// checkpointed to the cache instead.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[serde(default)]
+    pub resume: bool,
+
+    /// Directory used to cache processed frames between runs.
+    ///
+    /// Each input file is cached after it is loaded and cleaned, keyed by its
+    /// path and validated against its modification time and size. Re-running
+    /// the same conversion after new frames arrive only reprocesses new or
+    /// modified files. Changing any loading or attribute options invalidates
+    /// the cache.
+    #[cfg_attr(feature = "cli", clap(value_name = "DIR", long))]
+    #[serde(default)]
+    pub cache: Option<PathBuf>,
+
+    /// What to do with frames containing NaN or infinite values.
+    ///
+    /// Positions, normals, tangents and all exported attributes are checked.
+    /// With 'Error' the conversion stops, 'Drop' skips the offending frame,
+    /// and 'Previous' replaces the non-finite values with those of the
+    /// previous frame of the same animation. Frames that cannot be repaired
+    /// (e.g. the first frame or after a change in topology) are skipped.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "POLICY", long, default_value = "Error")
+    )]
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+
+    /// What to do with frames missing from the middle of a sequence.
+    ///
+    /// The expected spacing between frames is the smallest spacing found in
+    /// each sequence, so a missing frame is detected as a larger jump in frame
+    /// numbers. With 'Hold' the frame before the gap is shown until the frame
+    /// after it, 'Interpolate' blends linearly through the gap, 'Warn' does the
+    /// same but prints a warning, and 'Error' stops the conversion.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "POLICY", long, default_value = "Warn")
+    )]
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+
+    /// What to do with attributes found in only some frames of an animation.
+    ///
+    /// Attributes of a node are taken from its first frame, so an attribute
+    /// missing from later frames, or only present in them, would otherwise be
+    /// exported inconsistently. With 'Zeros' the attribute is filled with
+    /// zeros where it is missing, 'Drop' removes it from the node and prints a
+    /// warning, and 'Split' starts a new node whenever the set of colors,
+    /// texture coordinates or custom attributes changes.
+    #[cfg_attr(
+        feature = "cli",
+        clap(value_name = "POLICY", long, default_value = "Drop")
+    )]
+    #[serde(default)]
+    pub attribute_mismatch: AttributeMismatchPolicy,
+
+    /// Settings changed for specific animations, keyed by animation name.
+    ///
+    /// Only available in configuration files. For example, the RON
+    /// configuration
+    ///
+    /// (pattern: "./{cloth,fluid}_#.vtk", materials: [(name: "water"), (name: "fabric")],
+    ///  overrides: { "fluid": (material: Some("water"), step: Some(2)) })
+    ///
+    /// binds the "water" material to the "fluid" animation and keeps every
+    /// other frame of it, while the "cloth" animation uses the rest of the
+    /// configuration.
+    #[cfg_attr(feature = "cli", clap(skip))]
+    #[serde(default)]
+    pub overrides: IndexMap<String, AnimationOverride>,
 }
 
-impl Config {
-    pub fn load_with_override(
-        path: impl AsRef<Path>,
-        other: &Config,
-        matches: &ArgMatches,
-    ) -> Result<Config, Error> {
+/// Fields specified in a single configuration file.
+///
+/// Unlike `Config`, all fields are optional so that a file can override only
+/// some of the fields of the files it includes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConfigLayer {
+    /// Configuration files this file is based on, relative to this file.
+    ///
+    /// Later files override earlier ones, and this file overrides all of them.
+    #[serde(default, skip_serializing)]
+    include: Vec<PathBuf>,
+    /// Named sets of overrides selected with `--profile`.
+    #[serde(default, skip_serializing)]
+    profiles: IndexMap<String, ConfigLayer>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    case_insensitive: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fps: Option<FrameRate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_step: Option<Seconds>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<Seconds>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reverse: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fix_winding: Option<WindingFix>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    invert_tets: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    triangulation: Option<Triangulation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    polygon_ids: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    step: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    frame_offset: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    substeps: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unparsable_frames: Option<UnparsableFramePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order: Option<FrameOrder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    include_names: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exclude_names: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    concat: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crossfade: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    colors: Option<AttributeInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attributes: Option<AttributeInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attribute_names: Option<AttributeNames>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    texcoords: Option<TextureAttributeInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_auto_attributes: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    textures: Option<Vec<TextureInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    materials: Option<Vec<MaterialInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    material_attribute: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    material_id_map: Option<MaterialIdRemap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_material: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    split_materials: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    texture_atlas: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    insert_vanishing_frames: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_animated_normals: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_animated_tangents: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    animate_colors: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recompute_normals: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crease_angle: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bake_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_primitive_vertices: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    point_size: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    point_radius: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    points_as: Option<PointsAs>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    animation_groups: Option<AnimationGrouping>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dcc: Option<Dcc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dcc_max_targets: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    collision_proxy: Option<CollisionProxy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    animate_proxy: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_morph_targets: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lights: Option<Vec<LightInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    studio_lighting: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    frame_camera: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pointer_animations: Option<Vec<PointerAnimationInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scene: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<LayoutInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attach: Option<Vec<PathBuf>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    extras: Option<ExtrasInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sidecar: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    copyright: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generator_suffix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    validate: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    strict: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    threads: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_overwrite: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dedup_buffers: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    split_buffers: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    strip_names: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    minimal: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    manifest: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deterministic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    non_finite: Option<NonFinitePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gap_policy: Option<GapPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attribute_mismatch: Option<AttributeMismatchPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    overrides: Option<IndexMap<String, AnimationOverride>>,
+}
+
+type FieldMap = serde_json::Map<String, serde_json::Value>;
+
+impl ConfigLayer {
+    fn read(path: &Path) -> Result<ConfigLayer, Error> {
         use std::fs::File;
         let ext = path
-            .as_ref()
             .extension()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
-        let mut loaded_config: crate::config::Config =
-            File::open(path).map_err(Error::from).and_then(|f| {
-                let reader = BufReader::new(f);
-                if ext == "json" {
-                    Ok(serde_json::de::from_reader(reader)?)
-                } else if ext == "ron" {
-                    Ok(ron::de::from_reader(reader)?)
-                } else {
-                    Err(Error::ConfigUnsupported(ext))
+        File::open(path).map_err(Error::ConfigLoad).and_then(|f| {
+            let reader = BufReader::new(f);
+            match ext.as_str() {
+                "json" => Ok(serde_json::de::from_reader(reader)?),
+                "ron" => {
+                    // Fields are optional in a layer, but are written without `Some` in RON files.
+                    let options = ron::Options::default()
+                        .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+                    Ok(options.from_reader(reader)?)
+                }
+                #[cfg(feature = "config-formats")]
+                "toml" => {
+                    let input = std::io::read_to_string(reader).map_err(Error::ConfigLoad)?;
+                    Ok(toml::from_str(&input)?)
                 }
-            })?;
+                #[cfg(feature = "config-formats")]
+                "yaml" | "yml" => Ok(serde_yaml::from_reader(reader)?),
+                #[cfg(not(feature = "config-formats"))]
+                "toml" | "yaml" | "yml" => Err(Error::FeatureDisabled("config-formats")),
+                _ => Err(Error::ConfigUnsupported(ext)),
+            }
+        })
+    }
+
+    /// Fields set in this layer.
+    fn fields(&self) -> Result<FieldMap, Error> {
+        match serde_json::to_value(self)? {
+            serde_json::Value::Object(map) => Ok(map),
+            _ => Ok(FieldMap::new()),
+        }
+    }
+
+    /// Loads the configuration file at `path` along with all files it includes.
+    ///
+    /// Returns the fields set by the combined files, and the fields set by each profile.
+    fn load(
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(FieldMap, IndexMap<String, FieldMap>), Error> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            return Err(Error::ConfigIncludeCycle(path.to_path_buf()));
+        }
+        let layer = ConfigLayer::read(path)?;
+        stack.push(canonical);
+
+        let mut fields = FieldMap::new();
+        let mut profiles: IndexMap<String, FieldMap> = IndexMap::new();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for include in layer.include.iter() {
+            let (included_fields, included_profiles) =
+                ConfigLayer::load(&dir.join(include), stack)?;
+            fields.extend(included_fields);
+            for (name, profile) in included_profiles {
+                profiles.entry(name).or_default().extend(profile);
+            }
+        }
+        fields.extend(layer.fields()?);
+        for (name, profile) in layer.profiles.iter() {
+            profiles
+                .entry(name.clone())
+                .or_default()
+                .extend(profile.fields()?);
+        }
+
+        stack.pop();
+        Ok((fields, profiles))
+    }
+}
+
+impl Config {
+    /// Loads the configuration file at `path` with the given profile applied.
+    ///
+    /// Configuration files may include other configuration files via the
+    /// `include` field, and define named sets of overrides in the `profiles`
+    /// field.
+    pub fn load_profile(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Config, Error> {
+        let (mut fields, mut profiles) = ConfigLayer::load(path.as_ref(), &mut Vec::new())?;
+        if let Some(name) = profile {
+            let profile = profiles
+                .swap_remove(name)
+                .ok_or_else(|| Error::ConfigProfileNotFound(name.to_string()))?;
+            fields.extend(profile);
+        }
+        Ok(serde_json::from_value(serde_json::Value::Object(fields))?)
+    }
+
+    #[cfg(feature = "cli")]
+    pub fn load_with_override(
+        path: impl AsRef<Path>,
+        other: &Config,
+        matches: &ArgMatches,
+    ) -> Result<Config, Error> {
+        Config::load_profile_with_override(path, None, other, matches)
+    }
+
+    /// Loads the configuration file at `path` with the given profile applied,
+    /// and overrides it with matches from the command line.
+    #[cfg(feature = "cli")]
+    pub fn load_profile_with_override(
+        path: impl AsRef<Path>,
+        profile: Option<&str>,
+        other: &Config,
+        matches: &ArgMatches,
+    ) -> Result<Config, Error> {
+        let mut loaded_config = Config::load_profile(path, profile)?;
         loaded_config.override_from_matches(other, matches);
         Ok(loaded_config)
     }
 
     /// Override this configuration with matches from the command line.
+    #[cfg(feature = "cli")]
     pub fn override_from_matches(&mut self, other: &Config, matches: &ArgMatches) {
         // TODO: Figure out how to do this automatically. Otherwise we need to
         // add fields here every time we change the Config struct.
@@ -416,24 +1792,175 @@ impl Config {
             }
             match id.as_str() {
                 "PATTERN" => self.pattern = other.pattern.clone(),
+                "case_insensitive" => self.case_insensitive = other.case_insensitive,
                 "output" => self.output = other.output.clone(),
                 "fps" => self.fps = other.fps,
                 "time_step" => self.time_step = other.time_step,
+                "duration" => self.duration = other.duration,
                 "reverse" => self.reverse = other.reverse,
+                "fix_winding" => self.fix_winding = other.fix_winding,
                 "invert_tets" => self.invert_tets = other.invert_tets,
+                "triangulation" => self.triangulation = other.triangulation,
+                "polygon_ids" => self.polygon_ids = other.polygon_ids,
                 "step" => self.step = other.step,
+                "frame_offset" => self.frame_offset = other.frame_offset,
+                "substeps" => self.substeps = other.substeps,
+                "unparsable_frames" => self.unparsable_frames = other.unparsable_frames,
+                "order" => self.order = other.order,
+                "include_names" => self.include_names = other.include_names.clone(),
+                "exclude_names" => self.exclude_names = other.exclude_names.clone(),
+                "concat" => self.concat = other.concat.clone(),
+                "crossfade" => self.crossfade = other.crossfade,
                 "colors" => self.colors = other.colors.clone(),
                 "attributes" => self.attributes = other.attributes.clone(),
+                "attribute_names" => self.attribute_names = other.attribute_names.clone(),
                 "texcoords" => self.texcoords = other.texcoords.clone(),
+                "no_auto_attributes" => self.no_auto_attributes = other.no_auto_attributes,
                 "textures" => self.textures = other.textures.clone(),
                 "materials" => self.materials = other.materials.clone(),
                 "material_attribute" => self.material_attribute = other.material_attribute.clone(),
+                "material_id_map" => self.material_id_map = other.material_id_map.clone(),
+                "default_material" => self.default_material = other.default_material,
+                "split_materials" => self.split_materials = other.split_materials,
+                "texture_atlas" => self.texture_atlas = other.texture_atlas,
                 "insert_vanishing_frames" => self.insert_vanishing_frames = other.insert_vanishing_frames,
                 "no_animated_normals" => self.no_animated_normals = other.no_animated_normals,
                 "no_animated_tangents" => self.no_animated_tangents = other.no_animated_tangents,
-                "config_path" | "print_json_config" | "print_ron_config" | "print_full_config" => {} // Ignored
+                "animate_colors" => self.animate_colors = other.animate_colors,
+                "recompute_normals" => self.recompute_normals = other.recompute_normals,
+                "crease_angle" => self.crease_angle = other.crease_angle,
+                "bake_fields" => self.bake_fields = other.bake_fields.clone(),
+                "max_primitive_vertices" => self.max_primitive_vertices = other.max_primitive_vertices,
+                "point_size" => self.point_size = other.point_size,
+                "point_radius" => self.point_radius = other.point_radius.clone(),
+                "points_as" => self.points_as = other.points_as,
+                "animation_groups" => self.animation_groups = other.animation_groups.clone(),
+                "dcc" => self.dcc = other.dcc,
+                "dcc_max_targets" => self.dcc_max_targets = other.dcc_max_targets,
+                "collision_proxy" => self.collision_proxy = other.collision_proxy,
+                "animate_proxy" => self.animate_proxy = other.animate_proxy,
+                "max_morph_targets" => self.max_morph_targets = other.max_morph_targets,
+                "lights" => self.lights = other.lights.clone(),
+                "studio_lighting" => self.studio_lighting = other.studio_lighting,
+                "frame_camera" => self.frame_camera = other.frame_camera,
+                "pointer_animations" => self.pointer_animations = other.pointer_animations.clone(),
+                "scene" => self.scene = other.scene.clone(),
+                "layout" => self.layout = other.layout,
+                "attach" => self.attach = other.attach.clone(),
+                "extras" => self.extras = other.extras.clone(),
+                "sidecar" => self.sidecar = other.sidecar,
+                "copyright" => self.copyright = other.copyright.clone(),
+                "generator_suffix" => self.generator_suffix = other.generator_suffix.clone(),
+                "license" => self.license = other.license.clone(),
+                "validate" => self.validate = other.validate,
+                "strict" => self.strict = other.strict,
+                "threads" => self.threads = other.threads,
+                "no_overwrite" => self.no_overwrite = other.no_overwrite,
+                "dedup_buffers" => self.dedup_buffers = other.dedup_buffers,
+                "split_buffers" => self.split_buffers = other.split_buffers,
+                "strip_names" => self.strip_names = other.strip_names,
+                "minimal" => self.minimal = other.minimal,
+                "manifest" => self.manifest = other.manifest,
+                "deterministic" => self.deterministic = other.deterministic,
+                "resume" => self.resume = other.resume,
+                "cache" => self.cache = other.cache.clone(),
+                "non_finite" => self.non_finite = other.non_finite,
+                "gap_policy" => self.gap_policy = other.gap_policy,
+                "attribute_mismatch" => self.attribute_mismatch = other.attribute_mismatch,
+                "config_path" | "print_json_config" | "print_ron_config" | "print_toml_config" | "print_yaml_config" | "print_config_schema" | "print_full_config" | "profile" | "dry_run" | "report" | "size_report" | "force" | "serve" => {} // Ignored
                 id => log::warn!("Given argument ({:?}) was not overridden with the commandline option. Please submit an issue to https://github.com/elrnv/gltfgen.", id),
             }
         }
     }
 }
+
+/// Serialization of maps keyed by integers, like material id remappings.
+///
+/// TOML tables only have string keys, so keys are written as strings in all
+/// formats. Both integers and strings holding integers are accepted as keys
+/// when reading.
+pub(crate) mod integer_keys {
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    use serde::de::{self, Deserialize, Deserializer, MapAccess, Unexpected, Visitor};
+    use serde::{Serialize, Serializer};
+
+    pub fn serialize<'a, M, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a M: IntoIterator<Item = (&'a u32, &'a V)>,
+        V: Serialize + 'a,
+        S: Serializer,
+    {
+        serializer.collect_map(map.into_iter().map(|(key, value)| (key.to_string(), value)))
+    }
+
+    pub fn deserialize<'de, M, V, D>(deserializer: D) -> Result<M, D::Error>
+    where
+        M: FromIterator<(u32, V)>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+
+    struct MapVisitor<M, V>(PhantomData<(M, V)>);
+
+    impl<'de, M, V> Visitor<'de> for MapVisitor<M, V>
+    where
+        M: FromIterator<(u32, V)>,
+        V: Deserialize<'de>,
+    {
+        type Value = M;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a map keyed by non-negative integers")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<M, A::Error> {
+            let mut entries = Vec::new();
+            while let Some((Key(key), value)) = access.next_entry()? {
+                entries.push((key, value));
+            }
+            Ok(entries.into_iter().collect())
+        }
+    }
+
+    /// Integer key given either as a number or as a string.
+    struct Key(u32);
+
+    impl<'de> Deserialize<'de> for Key {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Key, D::Error> {
+            deserializer.deserialize_any(KeyVisitor)
+        }
+    }
+
+    struct KeyVisitor;
+
+    impl<'de> Visitor<'de> for KeyVisitor {
+        type Value = Key;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a non-negative 32 bit integer")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Key, E> {
+            u32::try_from(value)
+                .map(Key)
+                .map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Key, E> {
+            u32::try_from(value)
+                .map(Key)
+                .map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Key, E> {
+            value
+                .parse()
+                .map(Key)
+                .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+        }
+    }
+}
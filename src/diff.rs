@@ -0,0 +1,161 @@
+//! Structural and numeric comparison of glTF documents.
+
+use std::path::Path;
+
+use gltf::json;
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::validate::read_accessor;
+
+/// JSON properties describing how data is laid out in buffers rather than the data itself.
+///
+/// These are skipped when comparing documents, since the data they refer to is
+/// compared directly.
+const LAYOUT_PROPERTIES: &[&str] = &["bufferView", "byteOffset", "byteStride", "sparse"];
+
+/// Top level JSON properties that are not compared.
+///
+/// Buffer layout is compared through the accessor data, and the asset
+/// information contains the version of the generator.
+const IGNORED_ROOT_PROPERTIES: &[&str] = &["asset", "buffers", "bufferViews"];
+
+/// Compares two glTF documents along with their binary buffer data.
+///
+/// Documents are equal if they have the same structure (including topology,
+/// materials and animation channels), and all numbers, including accessor
+/// data, agree within a relative tolerance of `epsilon`.
+///
+/// Returns a list of differences found, which is empty if the documents are equal.
+pub fn diff(
+    (a, a_buffers): (&json::Root, &[&[u8]]),
+    (b, b_buffers): (&json::Root, &[&[u8]]),
+    epsilon: f64,
+) -> Result<Vec<String>, Error> {
+    let mut diffs = Vec::new();
+
+    let to_value = |root: &json::Root| serde_json::to_value(root).map_err(Error::GltfSerialize);
+    let (mut a_json, mut b_json) = (to_value(a)?, to_value(b)?);
+    for json in [&mut a_json, &mut b_json] {
+        if let Value::Object(map) = json {
+            for &prop in IGNORED_ROOT_PROPERTIES {
+                map.remove(prop);
+            }
+        }
+    }
+    diff_values("", &a_json, &b_json, epsilon, &mut diffs);
+
+    // Structural differences make the comparison of accessor data unreliable.
+    if !diffs.is_empty() {
+        return Ok(diffs);
+    }
+
+    for i in 0..a.accessors.len() {
+        let path = format!("accessors[{}]", i);
+        match (
+            read_accessor(a, a_buffers, i),
+            read_accessor(b, b_buffers, i),
+        ) {
+            (Some(a_values), Some(b_values)) => {
+                let mismatch = a_values
+                    .iter()
+                    .zip(b_values.iter())
+                    .enumerate()
+                    .find(|(_, (&x, &y))| !approx_eq(x, y, epsilon));
+                if a_values.len() != b_values.len() {
+                    diffs.push(format!(
+                        "{}: {} values != {} values",
+                        path,
+                        a_values.len(),
+                        b_values.len()
+                    ));
+                } else if let Some((c, (x, y))) = mismatch {
+                    diffs.push(format!("{}: component {}: {} != {}", path, c, x, y));
+                }
+            }
+            (None, None) => {}
+            _ => diffs.push(format!("{}: failed to read accessor data", path)),
+        }
+    }
+
+    for (i, (a_image, b_image)) in a.images.iter().zip(b.images.iter()).enumerate() {
+        if image_bytes(a, a_buffers, a_image) != image_bytes(b, b_buffers, b_image) {
+            diffs.push(format!("images[{}]: image data differs", i));
+        }
+    }
+
+    Ok(diffs)
+}
+
+/// Reads the glTF files at the given paths along with their buffers and compares them.
+pub fn diff_files(a: &Path, b: &Path, epsilon: f64) -> Result<Vec<String>, Error> {
+    let (a_doc, a_buffers, _) = gltf::import(a)?;
+    let (b_doc, b_buffers, _) = gltf::import(b)?;
+    let a_buffers: Vec<&[u8]> = a_buffers.iter().map(|b| b.0.as_slice()).collect();
+    let b_buffers: Vec<&[u8]> = b_buffers.iter().map(|b| b.0.as_slice()).collect();
+    diff(
+        (a_doc.as_json(), &a_buffers),
+        (b_doc.as_json(), &b_buffers),
+        epsilon,
+    )
+}
+
+fn approx_eq(a: f64, b: f64, epsilon: f64) -> bool {
+    a == b || (a - b).abs() <= epsilon * a.abs().max(b.abs()).max(1.0)
+}
+
+fn image_bytes<'a>(
+    root: &json::Root,
+    buffers: &[&'a [u8]],
+    image: &json::Image,
+) -> Option<&'a [u8]> {
+    let view = &root.buffer_views[image.buffer_view?.value()];
+    let start = view.byte_offset.map_or(0, |o| o.0) as usize;
+    buffers
+        .get(view.buffer.value())?
+        .get(start..start + view.byte_length.0 as usize)
+}
+
+fn diff_values(path: &str, a: &Value, b: &Value, epsilon: f64, diffs: &mut Vec<String>) {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            if !approx_eq(x, y, epsilon) {
+                diffs.push(format!("{}: {} != {}", path, x, y));
+            }
+        }
+        (Value::Array(x), Value::Array(y)) => {
+            if x.len() != y.len() {
+                diffs.push(format!("{}: {} items != {} items", path, x.len(), y.len()));
+                return;
+            }
+            for (i, (x, y)) in x.iter().zip(y.iter()).enumerate() {
+                diff_values(&format!("{}[{}]", path, i), x, y, epsilon, diffs);
+            }
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            let is_layout = |key: &String| LAYOUT_PROPERTIES.contains(&key.as_str());
+            for (key, x_value) in x.iter().filter(|(k, _)| !is_layout(k)) {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match y.get(key) {
+                    Some(y_value) => diff_values(&child, x_value, y_value, epsilon, diffs),
+                    None => diffs.push(format!("{}: missing in second file", child)),
+                }
+            }
+            for key in y.keys().filter(|k| !is_layout(k) && !x.contains_key(*k)) {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diffs.push(format!("{}: missing in first file", child));
+            }
+        }
+        _ if a != b => diffs.push(format!("{}: {} != {}", path, a, b)),
+        _ => {}
+    }
+}
@@ -0,0 +1,580 @@
+//! Discovery of mesh files and their frame numbers from an input pattern.
+//!
+//! Patterns are globs where `#` (or `#{N}` for exactly `N` digits) marks the
+//! frame number, `#.#` marks a frame number with an optional fractional part
+//! for sub-stepped sequences, `{...}` marks parts of the path that make up the name of the
+//! animation, and `{a,b}` or `@(a|b)` match one of several alternatives.
+//! Patterns are normalized with [`crate::pattern`] first, so `\` and `/` are
+//! both accepted as separators.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use regex::Regex;
+
+use crate::config::{FrameOrder, UnparsableFramePolicy};
+use crate::pattern::{bracket_escapes, normalize_path, normalize_pattern};
+use crate::utils::*;
+use crate::Error;
+
+/// Options controlling how files matched by a pattern are assigned frames.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DiscoverOptions {
+    /// Match file names ignoring case.
+    pub case_insensitive: bool,
+    /// Number added to every frame number.
+    pub frame_offset: i64,
+    /// Order used to number frames when the pattern has no `#`.
+    pub order: Option<FrameOrder>,
+    /// Number of frame numbers assigned to each whole frame.
+    ///
+    /// A fractional frame `N.F` matched by `#.#` is assigned the frame number
+    /// `N * substeps + 0.F * substeps`. Zero is treated as one.
+    pub substeps: u32,
+    /// What to do with files whose frame number cannot be parsed.
+    pub unparsable_frames: UnparsableFramePolicy,
+}
+
+/// Index of the substep given by the fractional digits of a frame number.
+///
+/// Returns `None` if the fraction doesn't fall exactly on a substep.
+fn substep_index(digits: &str, substeps: u32) -> Option<u32> {
+    let denominator = 10u128.checked_pow(u32::try_from(digits.len()).ok()?)?;
+    let numerator = digits.parse::<u128>().ok()? * u128::from(substeps);
+    (numerator % denominator == 0).then(|| (numerator / denominator) as u32)
+}
+
+/// A mesh file belonging to an animation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameMeta {
+    /// Name of the animation, built from the `{}` groups in the pattern.
+    pub name: String,
+    pub frame: u32,
+    pub path: PathBuf,
+}
+
+/// Selects animations by matching their names against regular expressions.
+#[derive(Clone, Debug, Default)]
+pub struct NameFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl NameFilter {
+    /// Builds a filter keeping names matching `include` but not `exclude`.
+    ///
+    /// Expressions must match the whole name.
+    pub fn new(include: Option<&str>, exclude: Option<&str>) -> Result<NameFilter, Error> {
+        let whole = |expr: &str| Regex::new(&format!("^(?:{})$", expr)).map_err(Error::NameFilter);
+        Ok(NameFilter {
+            include: include.map(whole).transpose()?,
+            exclude: exclude.map(whole).transpose()?,
+        })
+    }
+
+    /// Returns `true` if the animation with the given name is kept.
+    pub fn matches(&self, name: &str) -> bool {
+        self.include.as_ref().map_or(true, |re| re.is_match(name))
+            && !self.exclude.as_ref().is_some_and(|re| re.is_match(name))
+    }
+}
+
+/// Reason recorded for files skipped since their frame number could not be parsed.
+pub const UNPARSABLE_FRAME: &str = "unparsable frame number";
+
+/// A file matched by the glob that was not assigned a frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: &'static str,
+}
+
+/// Finds all files matching `pattern` and determines their names and frame numbers.
+///
+/// Files are returned sorted by path, unless `options.order` is used to
+/// number them.
+pub fn discover_frames(pattern: &str, options: DiscoverOptions) -> Result<Vec<FrameMeta>, Error> {
+    let mut warnings = Vec::new();
+    let discovered = discover_frames_with_skipped(pattern, options, &mut warnings);
+    print_warnings(warnings);
+    discovered.map(|(frames, _)| frames)
+}
+
+/// Same as [`discover_frames`] but also returns the matched files that were skipped.
+///
+/// Warnings about the matched files are added to `warnings` instead of being printed.
+pub fn discover_frames_with_skipped(
+    pattern: &str,
+    options: DiscoverOptions,
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<(Vec<FrameMeta>, Vec<SkippedFile>), Error> {
+    let pattern = normalize_pattern(pattern);
+
+    let regex = glob_to_regex(&pattern, options.case_insensitive)?;
+    let pattern = if options.case_insensitive {
+        relax_non_ascii_letters(&pattern)
+    } else {
+        pattern
+    };
+    let pattern = expand_frame_widths(&bracket_escapes(&pattern))
+        .replace("#.#", "#")
+        .replace("*#*", "*")
+        .replace("*#", "*")
+        .replace("#*", "*")
+        .replace('#', "*");
+    let glob_options = glob::MatchOptions {
+        case_sensitive: !options.case_insensitive,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    // The glob crate doesn't support alternatives, so each alternative is globbed separately.
+    let patterns: Vec<_> = expand_alternatives(&pattern)
+        .iter()
+        .map(|pattern| remove_braces(pattern))
+        .collect();
+    let mut entries = Vec::new();
+    for pattern in patterns.iter() {
+        entries.extend(glob::glob_with(pattern, glob_options)?.flatten());
+    }
+    entries.sort();
+    entries.dedup();
+
+    let mut skipped = Vec::new();
+    let mut unparsable = None;
+
+    if entries.is_empty() && !options.case_insensitive {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob_options
+        };
+        let matches_ignoring_case = patterns.iter().any(|pattern| {
+            glob::glob_with(pattern, options).is_ok_and(|mut paths| paths.next().is_some())
+        });
+        if matches_ignoring_case {
+            crate::log!(warnings;
+                "No files matched the pattern, but some match when ignoring case. Use '--case-insensitive' to include them."
+            );
+        }
+    }
+
+    let mut frames: Vec<_> = entries
+        .into_iter()
+        .filter_map(|path| {
+            let path_str = normalize_path(&path);
+            let caps = match regex.captures(&path_str) {
+                Some(caps) => caps,
+                None => {
+                    crate::log!(warnings;
+                        "Path '{}' skipped since regex '{}' did not match.",
+                        &path_str,
+                        regex.as_str(),
+                    );
+                    skipped.push(SkippedFile {
+                        path: path.clone(),
+                        reason: "pattern did not match",
+                    });
+                    return None;
+                }
+            };
+            let frame_cap = caps.name("frame");
+            let substep_cap = caps.name("substep");
+            let frame = match frame_cap {
+                Some(frame_match) => {
+                    let frame = match frame_match.as_str().parse::<u32>() {
+                        Ok(frame) => frame,
+                        Err(_) => match options.unparsable_frames {
+                            UnparsableFramePolicy::Zero => {
+                                crate::log!(warnings;
+                                    "Path '{}' placed at frame zero since its frame number could not be parsed.",
+                                    &path_str,
+                                );
+                                0
+                            }
+                            UnparsableFramePolicy::Skip => {
+                                crate::log!(warnings;
+                                    "Path '{}' skipped since its frame number could not be parsed.",
+                                    &path_str,
+                                );
+                                skipped.push(SkippedFile {
+                                    path: path.clone(),
+                                    reason: UNPARSABLE_FRAME,
+                                });
+                                return None;
+                            }
+                            UnparsableFramePolicy::Error => {
+                                unparsable.get_or_insert_with(|| {
+                                    Error::UnparsableFrame(
+                                        path.clone(),
+                                        frame_match.as_str().to_string(),
+                                    )
+                                });
+                                return None;
+                            }
+                        },
+                    };
+                    let substeps = options.substeps.max(1);
+                    let substep = match substep_cap {
+                        Some(substep) => match substep_index(substep.as_str(), substeps) {
+                            Some(substep) => substep,
+                            None => {
+                                crate::log!(warnings;
+                                    "Path '{}' skipped since its fractional frame number doesn't fall on one of {} substep(s) per frame. Use '--substeps' to change the number of substeps.",
+                                    &path_str,
+                                    substeps,
+                                );
+                                skipped.push(SkippedFile {
+                                    path: path.clone(),
+                                    reason: "fractional frame between substeps",
+                                });
+                                return None;
+                            }
+                        },
+                        None => 0,
+                    };
+                    let frame = (frame as i64 + options.frame_offset) * i64::from(substeps)
+                        + i64::from(substep);
+                    let Ok(frame) = u32::try_from(frame) else {
+                        crate::log!(warnings;
+                            "Path '{}' skipped since its frame number is negative after applying the frame offset.",
+                            &path_str,
+                        );
+                        skipped.push(SkippedFile {
+                            path: path.clone(),
+                            reason: "negative frame number",
+                        });
+                        return None;
+                    };
+                    frame
+                }
+                None => 0,
+            };
+
+            // Find a unique name for this mesh in the filename.
+            let mut name = String::new();
+            for cap in caps
+                .iter()
+                .skip(1)
+                .filter(|&cap| cap != frame_cap && cap != substep_cap)
+                .flatten()
+            {
+                name.push_str(cap.as_str());
+            }
+            Some(FrameMeta { name, frame, path })
+        })
+        .collect();
+
+    if regex.capture_names().all(|name| name != Some("frame")) {
+        if let Some(order) = options.order {
+            order_frames(&mut frames, order, options.frame_offset, &mut skipped);
+        } else if frames.len()
+            > frames
+                .iter()
+                .map(|meta| &meta.name)
+                .collect::<HashSet<_>>()
+                .len()
+        {
+            crate::log!(warnings;
+                "Multiple files share the same name and are all placed at frame zero. Use '#' in the pattern or '--order' to animate them."
+            );
+        }
+    }
+
+    if let Some(err) = unparsable {
+        return Err(err);
+    }
+
+    Ok((frames, skipped))
+}
+
+/// Numbers the frames of each animation in the given order starting from `frame_offset`.
+fn order_frames(
+    frames: &mut Vec<FrameMeta>,
+    order: FrameOrder,
+    frame_offset: i64,
+    skipped: &mut Vec<SkippedFile>,
+) {
+    let modified = |path: &PathBuf| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+    };
+    frames.sort_by(|a, b| {
+        let by_name = || natural_cmp(&a.path.to_string_lossy(), &b.path.to_string_lossy());
+        a.name.cmp(&b.name).then_with(|| match order {
+            FrameOrder::Name => a.path.cmp(&b.path),
+            FrameOrder::Natural => by_name(),
+            FrameOrder::Mtime => modified(&a.path).cmp(&modified(&b.path)).then_with(by_name),
+        })
+    });
+
+    let mut index = 0;
+    let mut prev_name = None;
+    for FrameMeta { name, path, .. } in std::mem::take(frames) {
+        if prev_name.as_ref() != Some(&name) {
+            index = 0;
+            prev_name = Some(name.clone());
+        }
+        match u32::try_from(index + frame_offset) {
+            Ok(frame) => frames.push(FrameMeta { name, frame, path }),
+            Err(_) => skipped.push(SkippedFile {
+                path,
+                reason: "negative frame number",
+            }),
+        }
+        index += 1;
+    }
+}
+
+/// Concatenates sequences discovered from separate patterns into one timeline.
+///
+/// The frames of each sequence are shifted to start right after the last
+/// frame of the previous sequence, with `crossfade` frames left in between
+/// for blending from one into the other. Animations with the same name in
+/// consecutive sequences continue on the same timeline.
+///
+/// Returns the shifted frames along with the name and last frame of each
+/// animation that continues into the next sequence.
+pub fn concat_sequences(
+    sequences: Vec<Vec<FrameMeta>>,
+    crossfade: u32,
+) -> (Vec<FrameMeta>, Vec<(String, u32)>) {
+    let mut frames: Vec<FrameMeta> = Vec::new();
+    let mut transitions = Vec::new();
+    let mut previous: Vec<FrameMeta> = Vec::new();
+    for sequence in sequences {
+        let (Some(first), Some(end)) = (
+            sequence.iter().map(|meta| meta.frame).min(),
+            previous.iter().map(|meta| meta.frame).max(),
+        ) else {
+            if !sequence.is_empty() {
+                frames.append(&mut previous);
+                previous = sequence;
+            }
+            continue;
+        };
+
+        // Keep the frame spacing of the previous sequence across the transition.
+        let mut previous_frames = previous.iter().map(|meta| meta.frame).collect::<Vec<_>>();
+        previous_frames.sort_unstable();
+        previous_frames.dedup();
+        let spacing = previous_frames
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .min()
+            .unwrap_or(1);
+        let start = end.saturating_add(spacing.saturating_mul(crossfade.saturating_add(1)));
+
+        let names: HashSet<_> = sequence.iter().map(|meta| meta.name.as_str()).collect();
+        let mut ends = std::collections::BTreeMap::new();
+        for meta in previous
+            .iter()
+            .filter(|meta| names.contains(meta.name.as_str()))
+        {
+            let last = ends.entry(meta.name.clone()).or_insert(meta.frame);
+            *last = (*last).max(meta.frame);
+        }
+        transitions.extend(ends);
+
+        frames.append(&mut previous);
+        previous = sequence
+            .into_iter()
+            .map(|meta| FrameMeta {
+                frame: meta.frame - first + start,
+                ..meta
+            })
+            .collect();
+    }
+    frames.append(&mut previous);
+    (frames, transitions)
+}
+
+/// Frames missing between two consecutive frames of an animation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameGap {
+    /// Last frame before the gap.
+    pub last: u32,
+    /// First frame after the gap.
+    pub next: u32,
+    /// Expected number of frames between consecutive frames.
+    pub spacing: u32,
+}
+
+/// Finds gaps in the given frames of a single animation.
+///
+/// The expected spacing is the smallest difference between consecutive
+/// frames, so sequences saved every few frames are not reported as having
+/// gaps. The frames are assumed to be sorted.
+pub fn find_frame_gaps(frames: &[u32]) -> Vec<FrameGap> {
+    let Some(spacing) = frames
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|&d| d > 0)
+        .min()
+    else {
+        return Vec::new();
+    };
+    frames
+        .windows(2)
+        .filter(|w| w[1] - w[0] > spacing)
+        .map(|w| FrameGap {
+            last: w[0],
+            next: w[1],
+            spacing,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discover_frames_from_pattern() {
+        let options = DiscoverOptions {
+            frame_offset: -1,
+            ..Default::default()
+        };
+        let mut frames = discover_frames("./assets/{box_rotate}_#.vtk", options).unwrap();
+        frames.sort_by_key(|meta| meta.frame);
+        assert_eq!(frames.len(), 12);
+        assert_eq!(
+            frames[0],
+            FrameMeta {
+                name: String::from("box_rotate"),
+                frame: 0,
+                path: PathBuf::from("assets/box_rotate_1.vtk"),
+            }
+        );
+        assert!(frames
+            .iter()
+            .enumerate()
+            .all(|(i, meta)| meta.frame == i as u32 && meta.name == "box_rotate"));
+
+        let options = DiscoverOptions {
+            order: Some(FrameOrder::Natural),
+            ..Default::default()
+        };
+        let frames = discover_frames("./assets/{box}_rotate_*.vtk", options).unwrap();
+        assert_eq!(frames.len(), 12);
+        assert_eq!(frames[1].path, PathBuf::from("assets/box_rotate_2.vtk"));
+        assert_eq!(frames[1].frame, 1);
+    }
+
+    #[test]
+    fn discover_substeps() {
+        let dir = PathBuf::from("./tests/artifacts/substeps");
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["12", "12.25", "12.5", "12.3", "13"] {
+            std::fs::write(dir.join(format!("frame_{}.vtk", name)), "").unwrap();
+        }
+        let options = DiscoverOptions {
+            substeps: 4,
+            ..Default::default()
+        };
+        let (frames, skipped) = discover_frames_with_skipped(
+            "./tests/artifacts/substeps/{frame}_#.#.vtk",
+            options,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        let mut frames: Vec<_> = frames
+            .iter()
+            .map(|meta| (meta.name.as_str(), meta.frame))
+            .collect();
+        frames.sort();
+        assert_eq!(
+            frames,
+            vec![("frame", 48), ("frame", 49), ("frame", 50), ("frame", 52)]
+        );
+        // 12.3 doesn't fall on a quarter frame.
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].path.ends_with("frame_12.3.vtk"));
+    }
+
+    #[test]
+    fn discover_unparsable_frames() {
+        let dir = PathBuf::from("./tests/artifacts/unparsable");
+        std::fs::create_dir_all(&dir).unwrap();
+        for frame in ["1", "99999999999"] {
+            std::fs::write(dir.join(format!("frame_{}.vtk", frame)), "").unwrap();
+        }
+        let pattern = "./tests/artifacts/unparsable/frame_#.vtk";
+        let discover = |unparsable_frames| {
+            let options = DiscoverOptions {
+                unparsable_frames,
+                ..Default::default()
+            };
+            discover_frames_with_skipped(pattern, options, &mut Vec::new())
+        };
+
+        let (frames, skipped) = discover(UnparsableFramePolicy::Skip).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].frame, 1);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].reason, UNPARSABLE_FRAME);
+
+        let (frames, skipped) = discover(UnparsableFramePolicy::Zero).unwrap();
+        let mut frames: Vec<_> = frames.iter().map(|meta| meta.frame).collect();
+        frames.sort();
+        assert_eq!(frames, vec![0, 1]);
+        assert!(skipped.is_empty());
+
+        assert!(matches!(
+            discover(UnparsableFramePolicy::Error),
+            Err(Error::UnparsableFrame(_, frame)) if frame == "99999999999"
+        ));
+    }
+
+    #[test]
+    fn name_filter() {
+        let filter = NameFilter::new(Some("hero.*"), Some(".*_cape")).unwrap();
+        assert!(filter.matches("hero"));
+        assert!(filter.matches("hero_sword"));
+        assert!(!filter.matches("hero_cape"));
+        assert!(!filter.matches("superhero"));
+        assert!(NameFilter::default().matches("anything"));
+        assert!(matches!(
+            NameFilter::new(Some("(hero"), None),
+            Err(Error::NameFilter(_))
+        ));
+    }
+
+    #[test]
+    fn concat_sequences() {
+        let sequence = |name: &str, frames: &[u32]| -> Vec<FrameMeta> {
+            frames
+                .iter()
+                .map(|&frame| FrameMeta {
+                    name: name.to_string(),
+                    frame,
+                    path: PathBuf::from(format!("{}_{}.vtk", name, frame)),
+                })
+                .collect()
+        };
+        let (frames, transitions) = concat_sequences(
+            vec![
+                sequence("cloth", &[1, 3, 5]),
+                sequence("cloth", &[1, 3]),
+                sequence("debris", &[10]),
+            ],
+            2,
+        );
+        let frames: Vec<_> = frames
+            .iter()
+            .map(|meta| (meta.name.as_str(), meta.frame))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                ("cloth", 1),
+                ("cloth", 3),
+                ("cloth", 5),
+                ("cloth", 11),
+                ("cloth", 13),
+                ("debris", 19),
+            ]
+        );
+        assert_eq!(transitions, vec![(String::from("cloth"), 5)]);
+    }
+}
@@ -9,13 +9,104 @@ pub enum Error {
     #[error("No valid meshes were found")]
     NoMeshesFound,
     #[error("Configuration load error: {}", .0)]
-    ConfigLoad(#[from] std::io::Error),
-    #[error("Only JSON and RON configuration formats are supported. Unknown config extension: {}", .0)]
+    ConfigLoad(std::io::Error),
+    #[error("Only JSON, RON, TOML and YAML configuration formats are supported. Unknown config extension: {}", .0)]
     ConfigUnsupported(String),
     #[error("Configuration RON deserialization error: {}", .0)]
     ConfigDeserializeRON(#[from] ron::error::SpannedError),
     #[error("Configuration JSON deserialization error: {}", .0)]
     ConfigDeserializeJSON(#[from] serde_json::error::Error),
+    #[cfg(feature = "config-formats")]
+    #[error("Configuration TOML deserialization error: {}", .0)]
+    ConfigDeserializeTOML(#[from] toml::de::Error),
+    #[cfg(feature = "config-formats")]
+    #[error("Configuration TOML serialization error: {}", .0)]
+    ConfigSerializeTOML(#[from] toml::ser::Error),
+    #[cfg(feature = "config-formats")]
+    #[error("Configuration YAML error: {}", .0)]
+    ConfigYAML(#[from] serde_yaml::Error),
+    #[error("Configuration includes itself: {}", .0.display())]
+    ConfigIncludeCycle(std::path::PathBuf),
+    #[error("Configuration profile not found: {}", .0)]
+    ConfigProfileNotFound(String),
+    #[error("{} problem(s) found in the configuration", .0)]
+    ConfigLint(usize),
     #[error("Configuration RON serialization error: {}", .0)]
     ConfigSerializeRON(#[from] ron::error::Error),
+    #[error("Scene description load error: {}", .0)]
+    SceneLoad(std::io::Error),
+    #[error("Only JSON and RON scene description formats are supported. Unknown scene extension: {}", .0)]
+    SceneUnsupported(String),
+    #[error("glTF error: {}", .0)]
+    Gltf(#[from] gltf::Error),
+    #[error("Failed to serialize glTF JSON: {}", .0)]
+    GltfSerialize(serde_json::Error),
+    #[error("Found {} difference(s) between glTF files", .0)]
+    GltfMismatch(usize),
+    #[error("Failed to create output directory: {}", .0)]
+    OutputDirectory(std::io::Error),
+    #[error("Failed to load mesh: {}", .0.display())]
+    MeshLoad(std::path::PathBuf),
+    #[error("Failed to save mesh: {}", .0)]
+    MeshSave(#[from] meshx::io::Error),
+    #[error("Failed to write output: {}", .0)]
+    Write(std::io::Error),
+    #[error("Failed to serve output: {}", .0)]
+    Serve(std::io::Error),
+    #[error("Output file already exists: {} (use --force to overwrite)", .0.display())]
+    OutputExists(std::path::PathBuf),
+    #[error("Invalid output path: {}", .0.display())]
+    InvalidOutputPath(std::path::PathBuf),
+    #[error("Attribute '{}' does not have the specified type {:?}", .0, .1)]
+    UnsupportedAttribute(String, crate::Type),
+    #[error("Number of {} does not fit into a 32 bit unsigned integer", .0)]
+    IndexOverflow(&'static str),
+    #[error("Invalid mesh data: {}", .0)]
+    InvalidMeshData(String),
+    #[error("Frame {} of '{}' was added after a later frame", .1, .0)]
+    FrameOutOfOrder(String, u32),
+    #[error("Frame {} of '{}' has non-finite values: {}", .1, .0, .2)]
+    NonFinite(String, u32, String),
+    #[error("Frame {} of '{}' was dropped since it has non-finite values: {}", .1, .0, .2)]
+    NonFiniteDropped(String, u32, String),
+    #[error("Frames between {} and {} of '{}' are missing", .1, .2, .0)]
+    FrameGap(String, u32, u32),
+    #[error("Failed to parse the frame number '{}' of {}", .1, .0.display())]
+    UnparsableFrame(std::path::PathBuf, String),
+    #[error("Invalid name filter: {}", .0)]
+    NameFilter(regex::Error),
+    #[error("Invalid file pattern '{}': {}", .0, .1)]
+    InvalidPattern(String, regex::Error),
+    #[error("Failed to read sidecar {}: {}", .0.display(), .1)]
+    Sidecar(std::path::PathBuf, String),
+    #[error("Failed to bake field '{}': {}", .0, .1)]
+    FieldBake(String, String),
+    #[error("{} frame(s) were skipped", .0)]
+    FramesSkipped(usize),
+    #[error("{} attribute error(s) occurred", .0)]
+    AttributeErrors(usize),
+    #[error("{} warning(s) were reported in strict mode", .0)]
+    Warnings(usize),
+    #[error("gltfgen was built without the '{}' feature", .0)]
+    FeatureDisabled(&'static str),
+}
+
+impl Error {
+    /// Exit code reported by the command line tool for this error.
+    ///
+    /// Distinct codes are used for missing meshes, skipped frames, attribute
+    /// errors and write failures. All other errors exit with 1. Exit code 2 is
+    /// reserved for invalid command line arguments.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NoMeshesFound => 3,
+            Error::FramesSkipped(_) => 4,
+            Error::AttributeErrors(_) => 5,
+            Error::Write(_)
+            | Error::OutputExists(_)
+            | Error::OutputDirectory(_)
+            | Error::MeshSave(_) => 6,
+            _ => 1,
+        }
+    }
 }
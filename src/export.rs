@@ -1,34 +1,86 @@
-use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use byteorder::{WriteBytesExt, LE};
 use gltf::json;
 use json::accessor::ComponentType as GltfComponentType;
 use json::accessor::Type as GltfType;
-use json::validation::Checked::Valid; // For colouring log messages.
+use json::validation::{Checked::Valid, USize64};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::Serialize; // For colouring log messages.
 
 use meshx::mesh::vertex_positions::VertexPositions;
+use meshx::topology::NumVertices;
 
 mod animation;
+mod animation_pointer;
+#[cfg(feature = "textures")]
+mod atlas;
 mod builders;
+mod camera;
+mod collision;
+mod document;
+mod fields;
+mod instancing;
+mod material_nodes;
+mod minimal;
+mod polygon_ids;
 mod primitives;
+mod sequence;
+mod split;
+mod target_colors;
 
 use animation::*;
+pub use animation_pointer::KHR_ANIMATION_POINTER;
+use animation_pointer::{build_pointer_sampler, PointerSampler};
+#[cfg(feature = "textures")]
+use atlas::pack_atlas;
 pub(crate) use builders::*;
+pub use camera::SCENE_BOUNDS_KEY;
+use camera::{build_framing_camera, with_scene_bounds};
+pub use collision::COLLISION_PROXY_KEY;
+use collision::{build_proxy_mesh, build_proxy_node, build_proxy_samplers};
+use document::write_root;
+pub use document::{Document, PointerChannel, TargetColors};
+use fields::{
+    bake_field, build_field_image, field_extras, field_sampler, field_texel_attribute, BakedField,
+};
+pub use fields::{FieldSeries, BAKED_FIELDS_KEY, FIELD_TEXEL_ATTRIB_NAME};
+use instancing::*;
+use material_nodes::split_material_nodes;
 use num_traits::ToPrimitive;
+pub use polygon_ids::POLYGON_IDS_KEY;
+use polygon_ids::{primitive_polygon_ids, with_polygon_ids};
 use primitives::*;
+pub use sequence::*;
+use target_colors::{take_target_colors, target_colors_extras};
 
 use crate::attrib::*;
-use crate::clean_named_meshes;
 use crate::config::NORMAL_ATTRIB_NAME;
 use crate::config::POSITION_ATTRIB_NAME;
 use crate::config::TANGENT_ATTRIB_NAME;
+use crate::config::{
+    AnimationGrouping, AttributeMismatchPolicy, CollisionProxy, Dcc, GapPolicy, NonFinitePolicy,
+    PointsAs, POINT_RADIUS_SEMANTIC,
+};
+use crate::discover::find_frame_gaps;
+use crate::extras::*;
+use crate::hooks::ExportHooks;
+use crate::light::*;
 use crate::material::*;
-use crate::mesh::Mesh;
+use crate::merge::{append_document, dedup_buffer_views, merge_documents, shift, AnimationMerge};
+use crate::mesh::{CreaseSplit, Mesh};
+use crate::progress::*;
+use crate::proxy::Proxy;
+use crate::scene::{LayoutInfo, SceneInfo};
 use crate::texture::*;
+use crate::uri::relative_uri;
 use crate::utils::*;
 use crate::AttribConfig;
+use crate::Error;
 
 #[derive(Clone)]
 enum Output {
@@ -61,22 +113,14 @@ impl Output {
             }
         }
     }
-}
-
-fn align_to_multiple_of_four(n: u32) -> u32 {
-    (n + 3) & !3
-}
 
-fn to_padded_byte_vector<T>(vec: Vec<T>) -> Vec<u8> {
-    let byte_length = vec.len() * mem::size_of::<T>();
-    let byte_capacity = vec.capacity() * mem::size_of::<T>();
-    let alloc = vec.into_boxed_slice();
-    let ptr = Box::<[T]>::into_raw(alloc) as *mut u8;
-    let mut new_vec = unsafe { Vec::from_raw_parts(ptr, byte_length, byte_capacity) };
-    while new_vec.len() % 4 != 0 {
-        new_vec.push(0); // pad to multiple of four bytes
+    /// Path to the main output file.
+    fn path(&self) -> &Path {
+        match self {
+            Output::Standard { gltf_path, .. } => gltf_path,
+            Output::Binary { glb_path } => glb_path,
+        }
     }
-    new_vec
 }
 
 /// A single morph target.
@@ -86,11 +130,11 @@ pub struct Morph {
     pub position_disp: Vec<[f32; 3]>,
     pub normal_disp: Vec<[f32; 3]>,
     pub tangent_disp: Vec<[f32; 3]>,
+    /// Deltas of the first color attribute as normalized RGBA values.
+    pub color_disp: Vec<[f32; 4]>,
     // Currently unsupported by the gltf crate:
     // /// Texcoord displacements
     // pub texcoord_disp: Vec<[f32; 3]>,
-    // /// Color deltas
-    // pub color_disp: Vec<[f32; 4]>,
 }
 
 impl Morph {
@@ -104,8 +148,8 @@ impl Morph {
             position_disp,
             normal_disp: Vec::new(),
             tangent_disp: Vec::new(),
+            color_disp: Vec::new(),
             // texcoord_disp: vec![[0.0; 3]; n],
-            // color_disp: vec![[0.0; 4]; n],
         }
     }
 }
@@ -117,107 +161,487 @@ pub struct Node {
     pub mesh: Mesh,
     pub attrib_transfer: AttribTransfer,
     pub morphs: Vec<Morph>,
+    /// Frames at which the weights of the preceding keyframe are repeated to
+    /// hold its shape through a gap in the sequence.
+    pub holds: Vec<u32>,
+    /// Visibility of this node if it is one of several segments of a longer animation.
+    pub segment: Option<Segment>,
+    /// Animated scalar vertex attributes to be baked into textures.
+    pub fields: Vec<FieldSeries>,
+    /// Vertex splits along creases applied to every frame of this node, if any.
+    pub creases: Option<Arc<CreaseSplit>>,
 }
 
-/// Split a sequence of keyframed trimeshes by changes in topology.
-fn into_nodes(
-    meshes: Vec<(String, u32, Mesh, AttribTransfer)>,
-    insert_vanishing_frames: bool,
-    animate_normals: bool,
-    animate_tangents: bool,
-    quiet: bool,
-) -> Vec<Node> {
-    let pb = new_progress_bar(quiet, meshes.len());
-    pb.set_message("Extracting Animation");
-
-    // Create displacements with all vertices put at the origin
-    let vanishing_disp = |mesh: &Mesh| -> Vec<[f32; 3]> {
-        mesh.vertex_position_iter()
-            .map(|p| [-p[0], -p[1], -p[2]])
-            .collect()
-    };
+/// One segment of an animation split across multiple nodes to limit the
+/// number of morph targets per mesh.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    /// Position of the segment within the animation.
+    pub index: usize,
+    /// Frame at which the segment replaces the previous one.
+    pub show: u32,
+    /// Frame at which the segment is replaced by the next one, if any.
+    pub hide: Option<u32>,
+}
 
-    let mut out = Vec::new();
-    let mut mesh_iter = meshes.into_iter();
+impl Node {
+    /// Bounding box of the vertex positions over all frames of this node.
+    ///
+    /// Returns `None` if the node has no vertices.
+    pub(crate) fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let positions = self.mesh.vertex_positions();
+        let displaced = self.morphs.iter().flat_map(|morph| {
+            positions
+                .iter()
+                .zip(morph.position_disp.iter())
+                .map(|(p, d)| [p[0] + d[0], p[1] + d[1], p[2] + d[2]])
+        });
+        positions
+            .iter()
+            .copied()
+            .chain(displaced)
+            .fold(None, |bounds, p| {
+                let (min, max) = bounds.unwrap_or((p, p));
+                Some((
+                    std::array::from_fn(|i| f32::min(min[i], p[i])),
+                    std::array::from_fn(|i| f32::max(max[i], p[i])),
+                ))
+            })
+    }
 
-    if let Some((name, first_frame, mesh, attrib_transfer)) = mesh_iter.next() {
-        let morphs = if insert_vanishing_frames && first_frame > 0 {
-            vec![Morph::new(first_frame - 1, vanishing_disp(&mesh))]
+    /// Keeps at most `max_targets` morph targets, evenly spaced in time.
+    ///
+    /// The first and last morph targets are always kept. Returns the number of
+    /// dropped morph targets.
+    fn decimate_morphs(&mut self, max_targets: usize) -> usize {
+        let n = self.morphs.len();
+        if n <= max_targets {
+            return 0;
+        }
+        let keep: Vec<usize> = if max_targets == 1 {
+            vec![n - 1]
         } else {
-            Vec::new()
+            (0..max_targets)
+                .map(|i| i * (n - 1) / (max_targets - 1))
+                .collect()
         };
+        self.morphs = mem::take(&mut self.morphs)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| keep.binary_search(i).is_ok())
+            .map(|(_, morph)| morph)
+            .collect();
+        n - max_targets
+    }
 
-        out.push(Node {
-            name,
-            first_frame,
-            mesh,
-            attrib_transfer,
-            morphs,
-        });
+    /// Splits this node into segments each with at most `max_targets` morph targets.
+    ///
+    /// Consecutive segments share the morph target at which one replaces the
+    /// other, so the animation stays continuous when visibility is swapped.
+    fn split_by_morph_count(self, max_targets: usize) -> Vec<Node> {
+        let max_targets = max_targets.max(2);
+        if self.morphs.len() <= max_targets {
+            return vec![self];
+        }
 
-        for (next_name, frame, next_mesh, next_attrib_transfer) in mesh_iter {
-            pb.tick();
-            let Node {
-                ref name,
-                ref mesh,
-                ref attrib_transfer,
-                ref mut morphs,
-                ..
-            } = *out.last_mut().unwrap();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        loop {
+            let end = (start + max_targets).min(self.morphs.len());
+            let hide = (end < self.morphs.len()).then(|| self.morphs[end - 1].frame);
+            // Later segments start from a rest pose one frame before they are shown.
+            let (first_frame, show) = if start == 0 {
+                (self.first_frame, self.first_frame)
+            } else {
+                let show = self.morphs[start].frame;
+                (show - 1, show)
+            };
+            segments.push(Node {
+                name: self.name.clone(),
+                first_frame,
+                mesh: self.mesh.clone(),
+                attrib_transfer: self.attrib_transfer.clone(),
+                morphs: self.morphs[start..end].to_vec(),
+                holds: self
+                    .holds
+                    .iter()
+                    .copied()
+                    .filter(|&hold| hold >= show && hide.map_or(true, |hide| hold < hide))
+                    .collect(),
+                segment: Some(Segment {
+                    index: segments.len(),
+                    show,
+                    hide,
+                }),
+                fields: self.fields.clone(),
+                creases: self.creases.clone(),
+            });
+            if hide.is_none() {
+                return segments;
+            }
+            start = end - 1;
+        }
+    }
 
-            // Check if topology, mesh name or material has changed in this frame.
-            if mesh.eq_topo(&next_mesh)
-                && name == &next_name
-                && attrib_transfer.material_ids == next_attrib_transfer.material_ids
-            {
-                // Convert positions to displacements.
-                let position_disp: Vec<_> = next_mesh
-                    .vertex_position_iter()
-                    .zip(mesh.vertex_position_iter())
-                    .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2]])
-                    .collect();
-                let mut morph = Morph::new(frame, position_disp);
+    /// Split this node into parts each with at most `max_vertices` vertices.
+    ///
+    /// Each part has its own re-based vertex indices, attributes and morph targets, and is
+    /// intended to be exported as a separate primitive of the same glTF mesh.
+    fn split_by_vertex_count(self, max_vertices: usize) -> Vec<Node> {
+        let trimesh = match &self.mesh {
+            Mesh::TriMesh(trimesh) if trimesh.num_vertices() > max_vertices => trimesh,
+            _ => return vec![self],
+        };
 
-                if animate_normals {
-                    morph.normal_disp = next_attrib_transfer
-                        .normal_attrib
-                        .iter()
-                        .zip(attrib_transfer.normal_attrib.iter())
-                        .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2]])
-                        .collect();
-                }
-                if animate_tangents {
-                    morph.tangent_disp = next_attrib_transfer
-                        .tangent_attrib
-                        .iter()
-                        .zip(attrib_transfer.tangent_attrib.iter())
-                        .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2]])
-                        .collect();
+        let chunks = crate::mesh::partition_faces_by_vertex_count(
+            trimesh.indices.as_slice(),
+            trimesh.num_vertices(),
+            max_vertices,
+        );
+
+        fn subset_disp<T: Copy>(disp: &[T], vertices: &[usize]) -> Vec<T> {
+            if disp.is_empty() {
+                Vec::new()
+            } else {
+                vertices.iter().map(|&v| disp[v]).collect()
+            }
+        }
+
+        chunks
+            .iter()
+            .map(|(faces, vertices)| Node {
+                name: self.name.clone(),
+                first_frame: self.first_frame,
+                mesh: self.mesh.subset(faces, vertices),
+                attrib_transfer: self.attrib_transfer.subset(faces, vertices),
+                morphs: self
+                    .morphs
+                    .iter()
+                    .map(|morph| Morph {
+                        frame: morph.frame,
+                        position_disp: subset_disp(&morph.position_disp, vertices),
+                        normal_disp: subset_disp(&morph.normal_disp, vertices),
+                        tangent_disp: subset_disp(&morph.tangent_disp, vertices),
+                        color_disp: subset_disp(&morph.color_disp, vertices),
+                    })
+                    .collect(),
+                holds: self.holds.clone(),
+                segment: self.segment,
+                // Fields are baked for the whole node before it is split.
+                fields: Vec::new(),
+                creases: None,
+            })
+            .collect()
+    }
+}
+
+/// Options controlling how consecutive frames are combined into morph targets.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct MorphOptions<'a> {
+    pub insert_vanishing_frames: bool,
+    pub animate_normals: bool,
+    pub animate_tangents: bool,
+    pub animate_colors: bool,
+    pub recompute_normals: bool,
+    /// Dihedral angle in degrees above which edges are split before computing normals.
+    pub crease_angle: Option<f32>,
+    /// Names of scalar vertex attributes baked into textures instead of being exported directly.
+    pub bake_fields: &'a [String],
+    pub attribute_mismatch: AttributeMismatchPolicy,
+}
+
+impl MorphOptions<'_> {
+    /// Appends the given frame to the last node in `out` if the topology, mesh name and material
+    /// are unchanged, and starts a new node otherwise.
+    ///
+    /// Attributes found in only one of the node and the frame are reconciled according to
+    /// `attribute_mismatch`. Frames must be given in increasing order for each name.
+    pub(crate) fn push_frame(
+        self,
+        out: &mut Vec<Node>,
+        next_name: String,
+        frame: u32,
+        mut next_mesh: Mesh,
+        mut next_attrib_transfer: AttribTransfer,
+        warnings: &mut Vec<(usize, String)>,
+    ) {
+        // Frames with the topology of the previous frame are split like it, so
+        // that creases stay consistent across morph targets.
+        let mut creases = None;
+        if let Some(crease_angle) = self.crease_angle {
+            let topo_hash = next_attrib_transfer
+                .topo_hash
+                .unwrap_or_else(|| next_mesh.topo_hash());
+            let split = out
+                .last()
+                .filter(|node| node.name == next_name)
+                .and_then(|node| node.creases.clone())
+                .filter(|split| split.topo_hash() == topo_hash)
+                .or_else(|| next_mesh.crease_split(crease_angle).map(Arc::new));
+            if let Some(split) = split {
+                let faces: Vec<_> = (0..split.num_faces()).collect();
+                next_attrib_transfer = next_attrib_transfer.subset(&faces, split.vertices());
+                next_mesh = next_mesh.split_creases(&split);
+                next_attrib_transfer.topo_hash = Some(next_mesh.topo_hash());
+                creases = Some(split);
+            }
+        }
+
+        if self.recompute_normals || self.crease_angle.is_some() {
+            if let Some(normals) = next_mesh.vertex_normals() {
+                // Tangents follow the recomputed normals for normal mapped surfaces.
+                let uvs = next_attrib_transfer
+                    .tex_attribs_to_keep
+                    .first()
+                    .and_then(TextureAttribute::uvs);
+                if let Some(tangents) = uvs
+                    .filter(|_| self.animate_tangents)
+                    .and_then(|uvs| next_mesh.vertex_tangents(&normals, &uvs))
+                {
+                    next_attrib_transfer.tangent_attrib = tangents;
                 }
-                morphs.push(morph);
+                next_attrib_transfer.normal_attrib = normals;
+            }
+        }
+
+        // Baked fields are recorded separately from the attributes written to vertex buffers.
+        let field_values: Vec<(&String, Option<Vec<f32>>)> = self
+            .bake_fields
+            .iter()
+            .map(|name| {
+                let attribs = &mut next_attrib_transfer.attribs_to_keep;
+                let values = attribs
+                    .iter()
+                    .position(|attrib| &attrib.name == name)
+                    .and_then(|i| attribs.remove(i).scalars());
+                (name, values)
+            })
+            .collect();
+        let new_fields = |field_values: Vec<(&String, Option<Vec<f32>>)>| -> Vec<FieldSeries> {
+            field_values
+                .into_iter()
+                .filter_map(|(name, values)| {
+                    let mut series = FieldSeries {
+                        name: name.clone(),
+                        ..Default::default()
+                    };
+                    series.push(frame, values);
+                    (!series.frames.is_empty()).then_some(series)
+                })
+                .collect()
+        };
+
+        // Create displacements with all vertices put at the origin
+        let vanishing_disp = |mesh: &Mesh| -> Vec<[f32; 3]> {
+            mesh.vertex_position_iter()
+                .map(|p| [-p[0], -p[1], -p[2]])
+                .collect()
+        };
+
+        let Some(Node {
+            ref name,
+            ref first_frame,
+            ref mesh,
+            ref mut attrib_transfer,
+            ref mut morphs,
+            ref mut fields,
+            ..
+        }) = out.last_mut()
+        else {
+            let morphs = if self.insert_vanishing_frames && frame > 0 {
+                vec![Morph::new(frame - 1, vanishing_disp(&next_mesh))]
             } else {
-                let next_morphs = if insert_vanishing_frames {
-                    // First insert another vanishing frame at the end of the previous sequence.
-                    morphs.push(Morph::new(frame, vanishing_disp(mesh)));
-                    // Return initial morph target with all vertices put at the origin.
-                    vec![Morph::new(frame - 1, vanishing_disp(&next_mesh))]
+                Vec::new()
+            };
+
+            out.push(Node {
+                name: next_name,
+                first_frame: frame,
+                mesh: next_mesh,
+                attrib_transfer: next_attrib_transfer,
+                morphs,
+                holds: Vec::new(),
+                segment: None,
+                fields: new_fields(field_values),
+                creases,
+            });
+            return;
+        };
+
+        // Compare topology hashes computed during loading first, since comparing indices is slow
+        // on large meshes.
+        let same_topo = match (attrib_transfer.topo_hash, next_attrib_transfer.topo_hash) {
+            (Some(hash), Some(next_hash)) if hash != next_hash => false,
+            _ => mesh.eq_topo(&next_mesh),
+        };
+
+        let mismatched = attrib_transfer.mismatched_attributes(&next_attrib_transfer);
+        let split_attributes =
+            self.attribute_mismatch == AttributeMismatchPolicy::Split && !mismatched.is_empty();
+
+        // Check if topology, mesh name, material or the set of attributes has changed in this
+        // frame.
+        if same_topo
+            && name == &next_name
+            && attrib_transfer.material_ids == next_attrib_transfer.material_ids
+            && !split_attributes
+        {
+            if !mismatched.is_empty() {
+                if self.attribute_mismatch == AttributeMismatchPolicy::Zeros {
+                    let num_vertices = mesh.vertex_positions().len();
+                    attrib_transfer.fill_missing_with_zeros(&next_attrib_transfer, num_vertices);
+                    next_attrib_transfer.fill_missing_with_zeros(attrib_transfer, num_vertices);
+                    log!(warnings; "Filled attribute(s) {:?} of '{}' with zeros in frames where they are missing", mismatched, name);
                 } else {
-                    Vec::new()
-                };
+                    // Color morph targets are relative to the first color attribute.
+                    let first_color = |transfer: &AttribTransfer| {
+                        transfer
+                            .color_attribs_to_keep
+                            .first()
+                            .map(|a| a.name.clone())
+                    };
+                    let colors = first_color(attrib_transfer);
+                    attrib_transfer.remove_attributes(&mismatched);
+                    next_attrib_transfer.remove_attributes(&mismatched);
+                    if first_color(attrib_transfer) != colors {
+                        morphs.iter_mut().for_each(|morph| morph.color_disp.clear());
+                    }
+                    log!(warnings; "Dropped attribute(s) {:?} of '{}' since they are missing from some of its frames", mismatched, name);
+                }
+            }
 
-                // Different topology, instantiate a new mesh.
-                out.push(Node {
-                    name: next_name,
-                    first_frame: frame,
-                    mesh: next_mesh,
-                    attrib_transfer: next_attrib_transfer,
-                    morphs: next_morphs,
-                });
+            // Convert positions to displacements.
+            let position_disp: Vec<_> = next_mesh
+                .vertex_position_iter()
+                .zip(mesh.vertex_position_iter())
+                .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2]])
+                .collect();
+            let mut morph = Morph::new(frame, position_disp);
+
+            if self.animate_normals {
+                morph.normal_disp = next_attrib_transfer
+                    .normal_attrib
+                    .iter()
+                    .zip(attrib_transfer.normal_attrib.iter())
+                    .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2]])
+                    .collect();
+            }
+            if self.animate_tangents {
+                morph.tangent_disp = next_attrib_transfer
+                    .tangent_attrib
+                    .iter()
+                    .zip(attrib_transfer.tangent_attrib.iter())
+                    .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2]])
+                    .collect();
+            }
+            if self.animate_colors {
+                morph.color_disp = color_deltas(attrib_transfer, &next_attrib_transfer);
             }
+            morphs.push(morph);
+            for (name, values) in field_values {
+                if let Some(series) = fields.iter_mut().find(|series| &series.name == name) {
+                    series.push(frame, values);
+                }
+            }
+        } else {
+            if name != &next_name {
+                log::debug!("Starting node '{}' at frame {}", next_name, frame);
+            } else if log::log_enabled!(log::Level::Info) {
+                let last_frame = morphs
+                    .iter()
+                    .map(|morph| morph.frame)
+                    .filter(|&f| f >= *first_frame && f < frame)
+                    .max()
+                    .unwrap_or(*first_frame);
+                let reasons: Vec<_> = mesh
+                    .topo_difference(&next_mesh)
+                    .into_iter()
+                    .chain(MaterialIds::difference(
+                        attrib_transfer.material_ids.as_ref(),
+                        next_attrib_transfer.material_ids.as_ref(),
+                    ))
+                    .chain(
+                        split_attributes.then(|| format!("attribute(s) {:?} changed", mismatched)),
+                    )
+                    .collect();
+                log::info!(
+                    "Starting a new node for '{}' at frame {} since it differs from frame {}: {}",
+                    next_name,
+                    frame,
+                    last_frame,
+                    reasons.join("; ")
+                );
+            }
+            let next_morphs = if self.insert_vanishing_frames {
+                // First insert another vanishing frame at the end of the previous sequence.
+                morphs.push(Morph::new(frame, vanishing_disp(mesh)));
+                // Return initial morph target with all vertices put at the origin.
+                vec![Morph::new(frame - 1, vanishing_disp(&next_mesh))]
+            } else {
+                Vec::new()
+            };
+
+            // Different topology, instantiate a new mesh.
+            out.push(Node {
+                name: next_name,
+                first_frame: frame,
+                mesh: next_mesh,
+                attrib_transfer: next_attrib_transfer,
+                morphs: next_morphs,
+                holds: Vec::new(),
+                segment: None,
+                fields: new_fields(field_values),
+                creases,
+            });
         }
     }
+}
+
+/// Computes the change in the first color attribute between two frames.
+///
+/// Returns an empty `Vec` if either frame has no colors or if the colors are unchanged.
+fn color_deltas(base: &AttribTransfer, next: &AttribTransfer) -> Vec<[f32; 4]> {
+    let colors = |transfer: &AttribTransfer| {
+        transfer
+            .color_attribs_to_keep
+            .first()
+            .and_then(Attribute::rgba_colors)
+    };
+    let (Some(base), Some(next)) = (colors(base), colors(next)) else {
+        return Vec::new();
+    };
+    if base.len() != next.len() || base == next {
+        return Vec::new();
+    }
+    next.iter()
+        .zip(base.iter())
+        .map(|(a, b)| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]])
+        .collect()
+}
+
+/// Split a sequence of keyframed trimeshes by changes in topology.
+fn into_nodes(
+    meshes: Vec<(String, u32, Mesh, AttribTransfer)>,
+    options: MorphOptions<'_>,
+    progress: &dyn ProgressSink,
+    report: &mut ExportReport,
+) -> Vec<Node> {
+    let stage = Stage::start(
+        progress,
+        "Extracting Animation",
+        meshes.len() as u64,
+        ProgressUnit::Items,
+    );
+
+    let mut out = Vec::new();
+    let mut warnings = Vec::new();
+    for (name, frame, mesh, attrib_transfer) in meshes {
+        stage.inc(1);
+        options.push_frame(&mut out, name, frame, mesh, attrib_transfer, &mut warnings);
+    }
 
-    pb.finish_with_message("Done extracting animation");
+    stage.finish("Done extracting animation");
+    report.warnings += report_warnings(progress, warnings);
     out
 }
 
@@ -225,6 +649,8 @@ struct TextureData {
     samplers: Vec<json::texture::Sampler>,
     images: Vec<json::image::Image>,
     textures: Vec<json::texture::Texture>,
+    /// Images of the textures that were skipped.
+    dropped: Vec<String>,
 }
 
 fn process_auto_textures(textures: &mut [TextureInfo], output: &Output) {
@@ -248,6 +674,7 @@ fn build_texture_data(
     // Populate images, samplers and textures
     let mut samplers = Vec::new();
     let mut images = Vec::new();
+    let mut dropped = Vec::new();
     let textures: Vec<_> = textures
         .into_iter()
         .filter_map(
@@ -284,6 +711,7 @@ fn build_texture_data(
                                 "Image must be in png or jpg format: {:?}. Skipping...",
                                 &path
                             );
+                            dropped.push(path.display().to_string());
                             return None;
                         }
 
@@ -292,6 +720,7 @@ fn build_texture_data(
                         // Read the image directly into the buffer.
                         if let Ok(mut file) = std::fs::File::open(&path) {
                             use std::io::Read;
+                            align_data(data);
                             let orig_len = data.len();
                             if let Ok(bytes_read) = file.read_to_end(data) {
                                 // Instead of guessing the size of the image we just wait until reading is
@@ -315,6 +744,7 @@ fn build_texture_data(
                                     "Failed to read image: {:?}. Skipping...",
                                     &path
                                 );
+                                dropped.push(path.display().to_string());
                                 return None;
                             }
                         } else {
@@ -322,6 +752,7 @@ fn build_texture_data(
                                 "Failed to read image: {:?}. Skipping...",
                                 &path
                             );
+                            dropped.push(path.display().to_string());
                             return None;
                         }
                     }
@@ -357,6 +788,7 @@ fn build_texture_data(
         samplers,
         images,
         textures,
+        dropped,
     }
 }
 
@@ -369,14 +801,17 @@ fn extract_local_materials_and_textures(
     attrib_transfer: &mut AttribTransfer,
     materials: &mut Vec<MaterialInfo>,
     textures: &mut Vec<TextureInfo>,
-) {
+) -> Result<(), Error> {
     if let Some(MaterialIds::Local { map }) = &mut attrib_transfer.material_ids {
         let mut global_map = indexmap::IndexMap::new();
         for (mtl, indices) in map.iter_mut() {
             let orig_indices = global_map
-                .entry(materials.len().to_u32().expect(
-                    "Number of materials loaded does not fit into a 32 bit unsigned integer.",
-                ))
+                .entry(
+                    materials
+                        .len()
+                        .to_u32()
+                        .ok_or(Error::IndexOverflow("materials"))?,
+                )
                 .or_insert_with(Vec::new);
             orig_indices.append(indices);
 
@@ -388,7 +823,10 @@ fn extract_local_materials_and_textures(
                 // Use the first texture attrib if it exists
                 if !attrib_transfer.tex_attribs_to_keep.is_empty() {
                     mtl_info.base_texture = TextureRef::Some {
-                        index: textures.len().to_u32().expect("Number of textures loaded does not fit into a 32 bit unsigned integer."), // New texture added below
+                        index: textures
+                            .len()
+                            .to_u32()
+                            .ok_or(Error::IndexOverflow("textures"))?, // New texture added below
                         texcoord: 0,
                     };
                 }
@@ -402,12 +840,61 @@ fn extract_local_materials_and_textures(
         // Local materials promoted to global, save them as such.
         attrib_transfer.material_ids = Some(MaterialIds::Global { map: global_map });
     }
+    Ok(())
+}
+
+/// Attribution metadata stored in the glTF asset.
+#[derive(Clone, Debug, Default)]
+pub struct AssetInfo {
+    /// Copyright message crediting the content creator.
+    pub copyright: Option<String>,
+    /// Text appended to the generator name.
+    pub generator_suffix: Option<String>,
+    /// License of the content, stored in the asset extras under "license".
+    pub license: Option<String>,
+}
+
+impl AssetInfo {
+    /// Builds the glTF asset, merging the license into the given asset extras.
+    fn build(
+        &self,
+        extras: Option<&serde_json::Value>,
+        warnings: &mut Vec<(usize, String)>,
+    ) -> Result<json::Asset, Error> {
+        let mut generator = format!("gltfgen v{}", env!("CARGO_PKG_VERSION"));
+        if let Some(suffix) = &self.generator_suffix {
+            generator.push(' ');
+            generator.push_str(suffix);
+        }
+
+        let mut extras = extras.cloned();
+        if let Some(license) = &self.license {
+            match extras.get_or_insert_with(|| serde_json::json!({})) {
+                serde_json::Value::Object(map) => {
+                    map.insert("license".to_string(), license.clone().into());
+                }
+                _ => log!(warnings; "Asset extras is not a JSON object. Skipping license..."),
+            }
+        }
+
+        Ok(json::Asset {
+            copyright: self.copyright.clone(),
+            generator: Some(generator),
+            extras: match &extras {
+                Some(extras) => to_extras(extras)?,
+                None => None,
+            },
+            ..Default::default()
+        })
+    }
 }
 
 /// Config struct for exporting gltf files.
 ///
 /// This is a subset of the options available in the main Config.
+/// Use [`ExportConfig::builder`] to construct it.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct ExportConfig {
     pub textures: Vec<TextureInfo>,
     pub materials: Vec<MaterialInfo>,
@@ -416,7 +903,473 @@ pub struct ExportConfig {
     pub insert_vanishing_frames: bool,
     pub animate_normals: bool,
     pub animate_tangents: bool,
+    /// Store changes in the first color attribute as morph targets.
+    pub animate_colors: bool,
+    /// Replace the normals of triangle meshes with smooth normals computed from each frame.
+    pub recompute_normals: bool,
+    /// Split vertices along edges whose dihedral angle in degrees exceeds this angle, and
+    /// recompute normals from each frame.
+    ///
+    /// Vertices are split the same way in all frames of a node.
+    pub crease_angle: Option<f32>,
+    /// Names of scalar vertex attributes whose values at every frame are baked into textures.
+    ///
+    /// Baked attributes are not exported as vertex attributes. See the [`BAKED_FIELDS_KEY`]
+    /// mesh extras for how to sample them.
+    pub bake_fields: Vec<String>,
+    /// Maximum number of vertices in a single primitive.
+    ///
+    /// Larger meshes are split into multiple primitives. If `None`, meshes are
+    /// split only when vertex indices don't fit into 32 bits.
+    pub max_primitive_vertices: Option<u32>,
+    /// Size of rendered points in pixels, stored in the extras of meshes with point primitives.
+    pub point_size: Option<f32>,
+    /// Replace points of point clouds with instances of the given shape.
+    pub points_as: Option<PointsAs>,
+    /// How animation channels are grouped into separate animations.
+    pub animation_groups: AnimationGrouping,
+    /// Content creation tool for which the output is tuned.
+    pub dcc: Option<Dcc>,
+    /// Maximum number of morph targets per mesh when `dcc` is set.
+    pub dcc_max_targets: u32,
+    /// Shape of the collision proxy added as a child of each mesh node, if any.
+    pub collision_proxy: Option<CollisionProxy>,
+    /// Translate and scale collision proxies to follow the bounding box of every frame.
+    pub animate_proxy: bool,
+    /// Maximum number of morph targets per mesh.
+    ///
+    /// Longer animations are split into segments shown one at a time. If
+    /// `None`, each mesh holds all of its morph targets.
+    pub max_morph_targets: Option<u32>,
+    /// Punctual lights added to the scene alongside the exported meshes.
+    pub lights: Vec<LightInfo>,
+    /// Add key, fill and rim lights framing the bounding box of the scene.
+    pub studio_lighting: bool,
+    /// Add a camera framing the scene and record its bounding box in the asset extras.
+    pub frame_camera: bool,
+    /// Material and light properties animated with KHR_animation_pointer.
+    pub pointer_animations: Vec<PointerAnimationInfo>,
+    /// Node hierarchy into which the generated nodes are slotted by name.
+    pub scene: SceneInfo,
+    /// Automatic placement of the generated animated nodes.
+    pub layout: LayoutInfo,
+    /// Existing glTF files merged into the output scene.
+    pub attach: Vec<PathBuf>,
+    /// Custom metadata attached to the asset, scene, nodes and materials.
+    pub extras: ExtrasInfo,
+    /// Attribution metadata stored in the glTF asset.
+    pub asset: AssetInfo,
+    /// Validate the glTF structure before and after writing it to file.
+    pub validate: bool,
+    /// Fail instead of replacing existing output files.
+    pub no_overwrite: bool,
+    /// Share buffer views with identical contents.
+    pub dedup_buffers: bool,
+    /// Write the data of each node into a separate buffer file for `.gltf` output.
+    pub split_buffers: bool,
+    /// Omit the names of accessors, buffer views, meshes and nodes.
+    pub strip_names: bool,
+    /// Omit names and extras, and write compact JSON.
+    pub minimal: bool,
+    /// Lay out nodes in the order of their names and first frames, and
+    /// materials of each node in the order of their IDs, so that identical
+    /// inputs produce identical output regardless of how frames were added.
+    pub deterministic: bool,
+    /// Fail with [`Error::Warnings`] instead of writing the output if any
+    /// warnings are reported while building it.
+    pub strict: bool,
+    /// What to do with frames containing NaN or infinite values.
+    pub non_finite: NonFinitePolicy,
+    /// What to do with frames missing from the middle of a sequence.
+    pub gap_policy: GapPolicy,
+    /// What to do with attributes found in only some frames of a node.
+    pub attribute_mismatch: AttributeMismatchPolicy,
+    /// Names and last frames of animations that continue with a concatenated sequence.
+    ///
+    /// Frames missing right after these are blended regardless of `gap_policy`.
+    pub transitions: Vec<(String, u32)>,
+    /// Output names of custom attributes.
+    pub attribute_names: AttributeNames,
+    /// Remapping of material ids found in meshes to material indices.
+    pub material_id_map: MaterialIdRemap,
+    /// Bind a default material to faces whose material id does not refer to any material.
+    pub default_material: bool,
+    /// Move each material of a mesh into its own child node.
+    pub split_materials: bool,
+    /// Materials bound to all primitives of the named nodes regardless of their material ids.
+    pub node_materials: HashMap<String, u32>,
+    /// Maximum size in pixels of an atlas packing embedded base color textures, if any.
+    pub texture_atlas: Option<u32>,
     pub quiet: bool,
+    /// Receiver of progress updates and warnings.
+    ///
+    /// If `None`, progress bars are drawn in the terminal unless `quiet` is set.
+    pub progress: Option<Arc<dyn ProgressSink>>,
+    /// Callbacks run on the finished document before it is written.
+    pub hooks: ExportHooks,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig {
+            textures: Vec::new(),
+            materials: Vec::new(),
+            output: PathBuf::from("./out.glb"),
+            time_step: 1.0 / 24.0,
+            insert_vanishing_frames: false,
+            animate_normals: true,
+            animate_tangents: true,
+            animate_colors: false,
+            recompute_normals: false,
+            crease_angle: None,
+            bake_fields: Vec::new(),
+            max_primitive_vertices: None,
+            point_size: None,
+            points_as: None,
+            animation_groups: AnimationGrouping::Single,
+            dcc: None,
+            dcc_max_targets: 64,
+            collision_proxy: None,
+            animate_proxy: false,
+            max_morph_targets: None,
+            lights: Vec::new(),
+            studio_lighting: false,
+            frame_camera: false,
+            pointer_animations: Vec::new(),
+            scene: SceneInfo::default(),
+            layout: LayoutInfo::default(),
+            attach: Vec::new(),
+            extras: ExtrasInfo::default(),
+            asset: AssetInfo::default(),
+            validate: false,
+            no_overwrite: false,
+            dedup_buffers: false,
+            split_buffers: false,
+            strip_names: false,
+            minimal: false,
+            deterministic: false,
+            strict: false,
+            non_finite: NonFinitePolicy::Error,
+            gap_policy: GapPolicy::Warn,
+            attribute_mismatch: AttributeMismatchPolicy::Drop,
+            transitions: Vec::new(),
+            attribute_names: AttributeNames::default(),
+            material_id_map: MaterialIdRemap::default(),
+            default_material: false,
+            split_materials: false,
+            texture_atlas: None,
+            node_materials: HashMap::new(),
+            quiet: false,
+            progress: None,
+            hooks: ExportHooks::default(),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+fn default_progress_sink(quiet: bool) -> Arc<dyn ProgressSink> {
+    Arc::new(IndicatifProgress::new(quiet))
+}
+
+#[cfg(not(feature = "cli"))]
+fn default_progress_sink(_quiet: bool) -> Arc<dyn ProgressSink> {
+    Arc::new(LogProgress)
+}
+
+impl ExportConfig {
+    pub(crate) fn morph_options(&self) -> MorphOptions<'_> {
+        MorphOptions {
+            insert_vanishing_frames: self.insert_vanishing_frames,
+            animate_normals: self.animate_normals,
+            animate_tangents: self.animate_tangents,
+            animate_colors: self.animate_colors,
+            recompute_normals: self.recompute_normals,
+            crease_angle: self.crease_angle,
+            bake_fields: &self.bake_fields,
+            attribute_mismatch: self.attribute_mismatch,
+        }
+    }
+
+    pub(crate) fn progress_sink(&self) -> Arc<dyn ProgressSink> {
+        self.progress
+            .clone()
+            .unwrap_or_else(|| default_progress_sink(self.quiet))
+    }
+
+    /// Starts building an export configuration from the defaults used by the
+    /// command line tool.
+    pub fn builder() -> ExportConfigBuilder {
+        ExportConfigBuilder::default()
+    }
+}
+
+/// Builder for [`ExportConfig`].
+#[derive(Clone, Debug, Default)]
+pub struct ExportConfigBuilder {
+    config: ExportConfig,
+}
+
+impl ExportConfigBuilder {
+    pub fn textures(mut self, textures: Vec<TextureInfo>) -> Self {
+        self.config.textures = textures;
+        self
+    }
+    pub fn materials(mut self, materials: Vec<MaterialInfo>) -> Self {
+        self.config.materials = materials;
+        self
+    }
+    pub fn output(mut self, output: impl Into<PathBuf>) -> Self {
+        self.config.output = output.into();
+        self
+    }
+    pub fn time_step(mut self, time_step: f32) -> Self {
+        self.config.time_step = time_step;
+        self
+    }
+    pub fn insert_vanishing_frames(mut self, insert_vanishing_frames: bool) -> Self {
+        self.config.insert_vanishing_frames = insert_vanishing_frames;
+        self
+    }
+    pub fn animate_normals(mut self, animate_normals: bool) -> Self {
+        self.config.animate_normals = animate_normals;
+        self
+    }
+    pub fn animate_tangents(mut self, animate_tangents: bool) -> Self {
+        self.config.animate_tangents = animate_tangents;
+        self
+    }
+    pub fn animate_colors(mut self, animate_colors: bool) -> Self {
+        self.config.animate_colors = animate_colors;
+        self
+    }
+    pub fn recompute_normals(mut self, recompute_normals: bool) -> Self {
+        self.config.recompute_normals = recompute_normals;
+        self
+    }
+    pub fn crease_angle(mut self, crease_angle: Option<f32>) -> Self {
+        self.config.crease_angle = crease_angle;
+        self
+    }
+    pub fn max_primitive_vertices(mut self, max_primitive_vertices: Option<u32>) -> Self {
+        self.config.max_primitive_vertices = max_primitive_vertices;
+        self
+    }
+    pub fn point_size(mut self, point_size: Option<f32>) -> Self {
+        self.config.point_size = point_size;
+        self
+    }
+    pub fn points_as(mut self, points_as: Option<PointsAs>) -> Self {
+        self.config.points_as = points_as;
+        self
+    }
+    pub fn animation_groups(mut self, animation_groups: AnimationGrouping) -> Self {
+        self.config.animation_groups = animation_groups;
+        self
+    }
+    pub fn dcc(mut self, dcc: Option<Dcc>) -> Self {
+        self.config.dcc = dcc;
+        self
+    }
+    pub fn dcc_max_targets(mut self, dcc_max_targets: u32) -> Self {
+        self.config.dcc_max_targets = dcc_max_targets;
+        self
+    }
+    pub fn collision_proxy(mut self, collision_proxy: Option<CollisionProxy>) -> Self {
+        self.config.collision_proxy = collision_proxy;
+        self
+    }
+    pub fn animate_proxy(mut self, animate_proxy: bool) -> Self {
+        self.config.animate_proxy = animate_proxy;
+        self
+    }
+    pub fn max_morph_targets(mut self, max_morph_targets: Option<u32>) -> Self {
+        self.config.max_morph_targets = max_morph_targets;
+        self
+    }
+    pub fn lights(mut self, lights: Vec<LightInfo>) -> Self {
+        self.config.lights = lights;
+        self
+    }
+    pub fn studio_lighting(mut self, studio_lighting: bool) -> Self {
+        self.config.studio_lighting = studio_lighting;
+        self
+    }
+    pub fn frame_camera(mut self, frame_camera: bool) -> Self {
+        self.config.frame_camera = frame_camera;
+        self
+    }
+    pub fn bake_fields(mut self, bake_fields: Vec<String>) -> Self {
+        self.config.bake_fields = bake_fields;
+        self
+    }
+    pub fn pointer_animations(mut self, pointer_animations: Vec<PointerAnimationInfo>) -> Self {
+        self.config.pointer_animations = pointer_animations;
+        self
+    }
+    pub fn scene(mut self, scene: SceneInfo) -> Self {
+        self.config.scene = scene;
+        self
+    }
+    pub fn layout(mut self, layout: LayoutInfo) -> Self {
+        self.config.layout = layout;
+        self
+    }
+    pub fn attach(mut self, attach: Vec<PathBuf>) -> Self {
+        self.config.attach = attach;
+        self
+    }
+    pub fn extras(mut self, extras: ExtrasInfo) -> Self {
+        self.config.extras = extras;
+        self
+    }
+    pub fn asset(mut self, asset: AssetInfo) -> Self {
+        self.config.asset = asset;
+        self
+    }
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.config.validate = validate;
+        self
+    }
+    pub fn no_overwrite(mut self, no_overwrite: bool) -> Self {
+        self.config.no_overwrite = no_overwrite;
+        self
+    }
+    pub fn dedup_buffers(mut self, dedup_buffers: bool) -> Self {
+        self.config.dedup_buffers = dedup_buffers;
+        self
+    }
+    pub fn split_buffers(mut self, split_buffers: bool) -> Self {
+        self.config.split_buffers = split_buffers;
+        self
+    }
+    pub fn strip_names(mut self, strip_names: bool) -> Self {
+        self.config.strip_names = strip_names;
+        self
+    }
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.config.deterministic = deterministic;
+        self
+    }
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+    pub fn minimal(mut self, minimal: bool) -> Self {
+        self.config.minimal = minimal;
+        self
+    }
+    pub fn non_finite(mut self, non_finite: NonFinitePolicy) -> Self {
+        self.config.non_finite = non_finite;
+        self
+    }
+    pub fn gap_policy(mut self, gap_policy: GapPolicy) -> Self {
+        self.config.gap_policy = gap_policy;
+        self
+    }
+    pub fn attribute_mismatch(mut self, attribute_mismatch: AttributeMismatchPolicy) -> Self {
+        self.config.attribute_mismatch = attribute_mismatch;
+        self
+    }
+    pub fn transitions(mut self, transitions: Vec<(String, u32)>) -> Self {
+        self.config.transitions = transitions;
+        self
+    }
+    pub fn attribute_names(mut self, attribute_names: AttributeNames) -> Self {
+        self.config.attribute_names = attribute_names;
+        self
+    }
+    pub fn material_id_map(mut self, material_id_map: MaterialIdRemap) -> Self {
+        self.config.material_id_map = material_id_map;
+        self
+    }
+    pub fn default_material(mut self, default_material: bool) -> Self {
+        self.config.default_material = default_material;
+        self
+    }
+    pub fn split_materials(mut self, split_materials: bool) -> Self {
+        self.config.split_materials = split_materials;
+        self
+    }
+    pub fn texture_atlas(mut self, texture_atlas: Option<u32>) -> Self {
+        self.config.texture_atlas = texture_atlas;
+        self
+    }
+    /// Bind the material with the given index to all primitives of the named nodes.
+    pub fn node_materials(mut self, node_materials: HashMap<String, u32>) -> Self {
+        self.config.node_materials = node_materials;
+        self
+    }
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.config.quiet = quiet;
+        self
+    }
+    pub fn progress(mut self, progress: Arc<dyn ProgressSink>) -> Self {
+        self.config.progress = Some(progress);
+        self
+    }
+    pub fn hooks(mut self, hooks: ExportHooks) -> Self {
+        self.config.hooks = hooks;
+        self
+    }
+    pub fn build(self) -> ExportConfig {
+        self.config
+    }
+}
+
+/// A material id found on a mesh and the material it was bound to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MaterialIdBinding {
+    pub id: u32,
+    /// Index of the bound material, or `None` if the id does not refer to any material.
+    pub material: Option<u32>,
+}
+
+/// Summary of a successful export.
+#[derive(Clone, Debug, Default)]
+pub struct ExportReport {
+    /// Path to the written glTF or glb file.
+    pub output: PathBuf,
+    /// Attributes that could not be exported, along with the reason.
+    pub skipped_attributes: Vec<String>,
+    /// Images of textures that could not be read or have an unsupported format.
+    pub dropped_textures: Vec<String>,
+    /// Material ids found on each node along with the materials they were bound to.
+    pub material_ids: Vec<(String, Vec<MaterialIdBinding>)>,
+    /// Number of nodes in the output, including light and attached nodes.
+    pub nodes: usize,
+    /// Number of mesh primitives in the output.
+    pub primitives: usize,
+    /// Number of accessors in the output.
+    pub accessors: usize,
+    /// Size of the binary buffer in bytes.
+    pub buffer_bytes: usize,
+    /// Total size of all written files in bytes.
+    pub file_bytes: u64,
+    /// Paths of all written files, including external buffers.
+    pub files: Vec<PathBuf>,
+    /// Number of warnings reported while exporting, counting repeats.
+    pub warnings: usize,
+}
+
+impl ExportReport {
+    /// Records the structure of the document about to be written to `output`.
+    fn record_output(&mut self, root: &json::Root, data: &[u8], output: &Output) {
+        self.output = output.path().to_path_buf();
+        self.nodes = root.nodes.len();
+        self.primitives = root.meshes.iter().map(|m| m.primitives.len()).sum();
+        self.accessors = root.accessors.len();
+        self.buffer_bytes = data.len();
+    }
+
+    /// Records the paths and sizes of the written files.
+    fn record_file_sizes(&mut self, paths: &[PathBuf]) {
+        self.file_bytes = paths
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        self.files = paths.to_vec();
+    }
 }
 
 /// Exports meshx meshes which have not yet been processed/cleaned.
@@ -429,24 +1382,64 @@ pub fn export_named_meshes(
     meshes: Vec<(String, Mesh)>,
     attrib_config: AttribConfig,
     export_config: ExportConfig,
-) {
-    let meshes = clean_named_meshes(meshes, attrib_config);
-    export_clean_meshes(meshes, export_config);
+) -> Result<ExportReport, Error> {
+    let skipped = std::sync::Mutex::new(Vec::new());
+    let meshes = crate::clean_named_meshes_with(meshes, attrib_config, |e| {
+        log::warn!("{}, Skipping...", e);
+        skipped.lock().unwrap().push(e.to_string());
+    });
+    let mut report = export_clean_meshes(meshes, export_config)?;
+    let mut skipped = skipped.into_inner().unwrap();
+    skipped.dedup();
+    skipped.append(&mut report.skipped_attributes);
+    report.skipped_attributes = skipped;
+    Ok(report)
 }
 
 pub fn export_clean_meshes(
+    meshes: Vec<(String, u32, Mesh, AttribTransfer)>,
+    export_config: ExportConfig,
+) -> Result<ExportReport, Error> {
+    let progress = export_config.progress_sink();
+    let mut report = ExportReport::default();
+    let (document, data, output) =
+        build_gltf_impl(meshes, &export_config, &*progress, &mut report)?;
+    write_gltf(document, data, output, &export_config, &*progress, report)
+}
+
+/// Builds the glTF document and its binary buffer in memory without writing any files.
+///
+/// This allows the result to be post-processed (e.g. to attach extensions) or
+/// served directly. The `output` path in the config only determines whether
+/// the buffer is referenced by an external `.bin` file or embedded as in a
+/// `.glb`, and how auto textures are stored.
+///
+/// Channels animating material and light properties (see
+/// [`ExportConfig::pointer_animations`]) are returned in
+/// [`Document::pointer_channels`], since [`json::Root`] can't represent them.
+/// Likewise, color deltas of morph targets (see [`ExportConfig::animate_colors`])
+/// are returned in [`Document::target_colors`]. [`Document::to_json`] and
+/// [`to_glb`] include both in the output.
+pub fn build_gltf(
+    meshes: Vec<(String, u32, Mesh, AttribTransfer)>,
+    export_config: ExportConfig,
+) -> Result<(Document, Vec<u8>), Error> {
+    let progress = export_config.progress_sink();
+    build_gltf_impl(
+        meshes,
+        &export_config,
+        &*progress,
+        &mut ExportReport::default(),
+    )
+    .map(|(document, data, _)| (document, data))
+}
+
+fn build_gltf_impl(
     mut meshes: Vec<(String, u32, Mesh, AttribTransfer)>,
-    ExportConfig {
-        mut textures,
-        mut materials,
-        output,
-        time_step,
-        insert_vanishing_frames,
-        animate_normals,
-        animate_tangents,
-        quiet,
-    }: ExportConfig,
-) {
+    export_config: &ExportConfig,
+    progress: &dyn ProgressSink,
+    report: &mut ExportReport,
+) -> Result<(Document, Vec<u8>, Output), Error> {
     meshes.sort_by(|(name_a, frame_a, _, _), (name_b, frame_b, _, _)| {
         // First sort by name
         name_a.cmp(name_b).then(frame_a.cmp(frame_b))
@@ -454,53 +1447,281 @@ pub fn export_clean_meshes(
 
     // Convert sequence of meshes into meshes with morph targets by erasing repeating topology
     // data.
-    let mut morphed_meshes = into_nodes(
-        meshes,
-        insert_vanishing_frames,
-        animate_normals,
-        animate_tangents,
-        quiet,
-    );
+    let morphed_meshes = into_nodes(meshes, export_config.morph_options(), progress, report);
 
-    // Load local materials from loaded objs into our configuration array.
-    for Node {
-        ref mut attrib_transfer,
-        ..
-    } in morphed_meshes.iter_mut()
-    {
-        extract_local_materials_and_textures(attrib_transfer, &mut materials, &mut textures);
-    }
+    build_gltf_from_nodes(morphed_meshes, export_config, progress, report)
+}
 
-    export_nodes(
-        morphed_meshes,
+/// Detects frames missing from the animations in `nodes` and handles them according to `policy`.
+///
+/// Gaps are found among the frames of all nodes with the same name. With
+/// [`GapPolicy::Hold`], gaps between two keyframes of the same node are filled
+/// by holding the shape of the earlier keyframe. Gaps following one of the
+/// given `transitions` are always blended.
+fn fill_frame_gaps(
+    nodes: &mut [Node],
+    policy: GapPolicy,
+    transitions: &[(String, u32)],
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<(), Error> {
+    if policy == GapPolicy::Interpolate {
+        return Ok(());
+    }
+    let mut frames: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+    for node in nodes.iter() {
+        // Vanishing frames inserted before the first frame are not part of the sequence.
+        let node_frames = frames.entry(node.name.clone()).or_default();
+        node_frames.push(node.first_frame);
+        node_frames.extend(
+            node.morphs
+                .iter()
+                .map(|morph| morph.frame)
+                .filter(|&frame| frame >= node.first_frame),
+        );
+    }
+    for (name, mut frames) in frames {
+        frames.sort_unstable();
+        frames.dedup();
+        for gap in find_frame_gaps(&frames) {
+            if transitions.contains(&(name.clone(), gap.last)) {
+                continue;
+            }
+            match policy {
+                GapPolicy::Error => return Err(Error::FrameGap(name, gap.last, gap.next)),
+                GapPolicy::Warn => {
+                    log!(warnings; "Frames between {} and {} of '{}' are missing and will be interpolated", gap.last, gap.next, name);
+                }
+                GapPolicy::Hold => {
+                    let node = nodes.iter_mut().find(|node| {
+                        node.name == name
+                            && (node.first_frame..gap.next).contains(&gap.last)
+                            && node.morphs.iter().any(|morph| morph.frame == gap.next)
+                    });
+                    if let Some(node) = node {
+                        node.holds.push(gap.next - gap.spacing);
+                    }
+                }
+                GapPolicy::Interpolate => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds the glTF document from nodes with morph targets already extracted.
+fn build_gltf_from_nodes(
+    mut morphed_meshes: Vec<Node>,
+    export_config: &ExportConfig,
+    progress: &dyn ProgressSink,
+    report: &mut ExportReport,
+) -> Result<(Document, Vec<u8>, Output), Error> {
+    let &ExportConfig {
+        dcc,
+        dcc_max_targets,
+        max_morph_targets,
+        points_as,
+        deterministic,
+        gap_policy,
+        strip_names,
+        minimal,
+        ..
+    } = export_config;
+
+    let mut warnings = Vec::new();
+    fill_frame_gaps(
+        &mut morphed_meshes,
+        gap_policy,
+        &export_config.transitions,
+        &mut warnings,
+    )?;
+
+    // Content creation tools import a limited number of shape keys per mesh.
+    if dcc.is_some() {
+        for node in morphed_meshes.iter_mut() {
+            let dropped = node.decimate_morphs(dcc_max_targets.max(1) as usize);
+            if dropped > 0 {
+                log!(warnings; "Dropped {} of the morph targets of '{}' to stay within {} targets per mesh", dropped, node.name, dcc_max_targets);
+            }
+        }
+    }
+    report.warnings += report_warnings(progress, warnings);
+
+    // Constrained renderers only blend a few morph targets, so long animations are played back
+    // by swapping between meshes. Instanced point clouds are animated without morph targets.
+    if let Some(max_targets) = max_morph_targets {
+        morphed_meshes = morphed_meshes
+            .into_iter()
+            .flat_map(|node| {
+                if points_as.is_some() && matches!(node.mesh, Mesh::PointCloud(_)) {
+                    vec![node]
+                } else {
+                    node.split_by_morph_count(max_targets as usize)
+                }
+            })
+            .collect();
+    }
+
+    // Nodes, and with them the materials, textures and accessors they introduce,
+    // are laid out in the order of their names and frames.
+    if deterministic {
+        morphed_meshes.sort_by(|a, b| a.name.cmp(&b.name).then(a.first_frame.cmp(&b.first_frame)));
+        for node in morphed_meshes.iter_mut() {
+            if let Some(MaterialIds::Global { map }) = &mut node.attrib_transfer.material_ids {
+                map.sort_keys();
+            }
+        }
+    }
+
+    // Load local materials from loaded objs into our configuration array.
+    let mut materials = export_config.materials.clone();
+    let mut textures = export_config.textures.clone();
+    for Node {
+        ref mut attrib_transfer,
+        ..
+    } in morphed_meshes.iter_mut()
+    {
+        extract_local_materials_and_textures(attrib_transfer, &mut materials, &mut textures)?;
+    }
+
+    let (mut document, data, output) = build_gltf_parts(
+        morphed_meshes,
         textures,
         materials,
-        output,
-        time_step,
-        insert_vanishing_frames,
-        quiet,
-    );
+        export_config,
+        progress,
+        report,
+    )?;
+
+    let mut warnings = Vec::new();
+    for name in export_config.hooks.apply(&mut document.root) {
+        log!(warnings; "No node or material named '{}' found to apply export hooks to. Skipping...", name);
+    }
+    report.warnings += report_warnings(progress, warnings);
+
+    // Names are stripped last since extras and hooks are matched by name.
+    if strip_names || minimal {
+        minimal::strip_names(&mut document.root);
+    }
+    if minimal {
+        minimal::strip_extras(&mut document.root);
+    }
+
+    Ok((document, data, output))
 }
 
+/// Builds the glTF document from nodes with morph targets already extracted and
+/// writes it to the output specified in the export configuration.
 pub fn export_nodes(
     morphed_meshes: Vec<Node>,
-    textures: Vec<TextureInfo>,
-    materials: Vec<MaterialInfo>,
+    export_config: ExportConfig,
+) -> Result<ExportReport, Error> {
+    let progress = export_config.progress_sink();
+    let mut report = ExportReport::default();
+    let (document, data, output) =
+        build_gltf_from_nodes(morphed_meshes, &export_config, &*progress, &mut report)?;
+    write_gltf(document, data, output, &export_config, &*progress, report)
+}
+
+/// Validates and writes the generated glTF to the given output.
+///
+/// The JSON of standard glTF output is pretty printed unless `minimal` is set,
+/// and its buffer is split into one file per node if `split_buffers` is set.
+/// Nothing is written if `strict` is set and warnings were reported.
+fn write_gltf(
+    document: Document,
+    data: Vec<u8>,
+    output: Output,
+    &ExportConfig {
+        validate,
+        no_overwrite,
+        minimal,
+        split_buffers,
+        strict,
+        ..
+    }: &ExportConfig,
+    progress: &dyn ProgressSink,
+    mut report: ExportReport,
+) -> Result<ExportReport, Error> {
+    if strict && report.warnings > 0 {
+        return Err(Error::Warnings(report.warnings));
+    }
+    let pretty = !minimal;
+    if validate {
+        report_validation(
+            "generated glTF",
+            crate::validate::validate(&document.root, &[&data]),
+        );
+    }
+
+    report.record_output(&document.root, &data, &output);
+    let paths = write_file(
+        document,
+        data,
+        &output,
+        no_overwrite,
+        pretty,
+        split_buffers,
+        progress,
+    )?;
+    report.record_file_sizes(&paths);
+
+    if validate {
+        validate_written(&report.output);
+    }
+    Ok(report)
+}
+
+/// Merges existing glTF files into a single file written to `output`.
+///
+/// Objects from all files are placed into a single scene, and identical
+/// materials and textures are stored only once. Animations are combined as
+/// specified by `animations`.
+pub fn export_merged(
+    paths: &[PathBuf],
     output: PathBuf,
-    time_step: f32,
-    insert_vanishing_frames: bool,
-    quiet: bool,
-) {
-    let (root, data, output) = build_gltf_parts(
-        morphed_meshes,
-        textures,
-        materials,
-        output,
-        time_step,
-        insert_vanishing_frames,
-        quiet,
-    );
-    write_file(root, data, output, quiet);
+    animations: AnimationMerge,
+    asset: AssetInfo,
+    validate: bool,
+    progress: &dyn ProgressSink,
+) -> Result<ExportReport, Error> {
+    let mut report = ExportReport::default();
+    let mut warnings = Vec::new();
+    let (mut root, data) = merge_documents(paths, animations, &mut warnings)?;
+    root.asset = asset.build(None, &mut warnings)?;
+    report.warnings += report_warnings(progress, warnings);
+
+    let output = Output::from_ext(output);
+    root.buffers.push(build_buffer(data.len(), &output)?);
+
+    if validate {
+        report_validation("merged glTF", crate::validate::validate(&root, &[&data]));
+    }
+
+    report.record_output(&root, &data, &output);
+    let paths = write_file(root.into(), data, &output, false, true, false, progress)?;
+    report.record_file_sizes(&paths);
+
+    if validate {
+        validate_written(&report.output);
+    }
+    Ok(report)
+}
+
+/// Validates the file written to `path`.
+fn validate_written(path: &Path) {
+    match crate::validate::validate_file(path) {
+        Ok(errors) => report_validation(&path.display().to_string(), errors),
+        Err(err) => log::error!("Failed to read {} for validation: {}", path.display(), err),
+    }
+}
+
+fn report_validation(what: &str, errors: Vec<(usize, String)>) {
+    if errors.is_empty() {
+        print_info(vec![(1, format!("Validation of {} passed", what))]);
+    } else {
+        log::error!("Validation of {} failed:", what);
+        print_errors(errors);
+    }
 }
 
 pub(crate) fn build_nonempty_buffer_vec3(
@@ -514,6 +1735,7 @@ pub(crate) fn build_nonempty_buffer_vec3(
 
     let byte_length = mem::size_of_val(vec);
 
+    align_data(data);
     let view = json::buffer::View::new(byte_length, data.len())
         .with_stride(mem::size_of::<[f32; 3]>())
         .with_target(json::buffer::Target::ArrayBuffer);
@@ -564,145 +1786,265 @@ pub(crate) fn build_buffer_vec3(
     }
 }
 
-fn build_gltf_parts(
-    morphed_meshes: Vec<Node>,
-    mut textures: Vec<TextureInfo>,
-    materials: Vec<MaterialInfo>,
-    output: PathBuf,
-    time_step: f32,
-    insert_vanishing_frames: bool,
-    quiet: bool,
-) -> (json::Root, Vec<u8>, Output) {
-    let count: u64 = morphed_meshes.iter().map(|m| m.morphs.len() as u64).sum();
-    let pb = new_progress_bar(quiet, count as usize);
-    pb.set_message("Constructing glTF");
+pub(crate) fn build_buffer_vec4(
+    vec: &[[f32; 4]],
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+    name: &str,
+) -> Option<json::Index<json::Accessor>> {
+    if vec.is_empty() {
+        return None;
+    }
 
-    // Keep track of the messages and warnings to be displayed after construction is complete.
-    let mut msgs = Vec::new();
-    let mut warnings = Vec::new();
+    align_data(data);
+    let view = json::buffer::View::new(mem::size_of_val(vec), data.len())
+        .with_stride(mem::size_of::<[f32; 4]>())
+        .with_target(json::buffer::Target::ArrayBuffer);
+    let view_index = buffer_views.len();
+    buffer_views.push(view);
 
-    // First populate materials
-    // Doing this first allows us to attach a default material if one is needed.
-    let mut materials: Vec<_> = materials.into_iter().map(Into::into).collect();
+    for x in vec.iter() {
+        for &coord in x.iter() {
+            data.write_f32::<LE>(coord).unwrap();
+        }
+    }
 
-    let mut accessors = Vec::new();
-    let mut buffer_views = Vec::new();
-    let mut meshes = Vec::new();
-    let mut nodes = Vec::new();
-    let mut animation_channels = Vec::new();
-    let mut animation_samplers = Vec::new();
-    let mut data = Vec::<u8>::new();
+    let acc = json::Accessor::new(vec.len(), GltfComponentType::F32)
+        .with_name(name.to_string())
+        .with_buffer_view(view_index)
+        .with_type(GltfType::Vec4);
 
-    for Node {
-        name,
-        first_frame,
-        mesh,
-        attrib_transfer,
-        morphs,
-    } in morphed_meshes.into_iter()
-    {
-        let (vertex_positions, indices) = mesh.build_topology(
-            &attrib_transfer,
-            &mut data,
-            &mut buffer_views,
-            &mut accessors,
-        );
+    let acc_index = accessors.len() as u32;
+    accessors.push(acc);
+    Some(json::Index::new(acc_index))
+}
 
-        // Push positions to data buffer.
-        let pos_acc_index = build_nonempty_buffer_vec3(
-            vertex_positions,
-            &mut accessors,
-            &mut buffer_views,
-            &mut data,
-            POSITION_ATTRIB_NAME,
-        );
+/// Buffer contents of a single primitive built by [`NodeBuffers::build`].
+struct PrimitiveBuffers {
+    attrib_transfer: AttribTransfer,
+    mode: json::validation::Checked<json::mesh::Mode>,
+    pos_acc_index: u32,
+    nml_acc_index: Option<json::Index<json::Accessor>>,
+    tng_acc_index: Option<json::Index<json::Accessor>>,
+    attrib_acc_indices: Vec<u32>,
+    color_attrib_acc_indices: Vec<u32>,
+    tex_attrib_acc_indices: Vec<u32>,
+    indices: Option<Vec<json::Index<json::Accessor>>>,
+    /// Morph targets shared by all primitives built from these buffers.
+    targets: Vec<json::mesh::MorphTarget>,
+    /// Accessors of the color deltas of each morph target.
+    target_colors: Vec<Option<json::Index<json::Accessor>>>,
+}
 
-        // Push normals and tangents to data buffer if any.
-        let nml_acc_index = build_buffer_vec3(
-            &attrib_transfer.normal_attrib,
-            &mut accessors,
-            &mut buffer_views,
-            &mut data,
-            NORMAL_ATTRIB_NAME,
-        );
-        let tng_acc_index = build_buffer_vec3(
-            &attrib_transfer.tangent_attrib,
-            &mut accessors,
-            &mut buffer_views,
-            &mut data,
-            TANGENT_ATTRIB_NAME,
-        );
+impl PrimitiveBuffers {
+    /// Offsets all accessor indices by `offset`.
+    fn shift_accessors(&mut self, offset: u32) {
+        let shift_opt = |index: &mut Option<json::Index<json::Accessor>>| {
+            if let Some(index) = index.as_mut() {
+                shift(index, offset);
+            }
+        };
+        self.pos_acc_index += offset;
+        shift_opt(&mut self.nml_acc_index);
+        shift_opt(&mut self.tng_acc_index);
+        for index in self
+            .attrib_acc_indices
+            .iter_mut()
+            .chain(self.color_attrib_acc_indices.iter_mut())
+            .chain(self.tex_attrib_acc_indices.iter_mut())
+        {
+            *index += offset;
+        }
+        for index in self.indices.iter_mut().flatten() {
+            shift(index, offset);
+        }
+        for target in self.targets.iter_mut() {
+            shift_opt(&mut target.positions);
+            shift_opt(&mut target.normals);
+            shift_opt(&mut target.tangents);
+        }
+        for index in self.target_colors.iter_mut() {
+            shift_opt(index);
+        }
+    }
+}
 
-        // Push color vertex attribute
-        let color_attrib_acc_indices: Vec<_> = attrib_transfer
-            .color_attribs_to_keep
+/// Buffer contents of a single node built independently of other nodes.
+///
+/// Accessor and buffer view indices as well as byte offsets are local to the
+/// node and are offset when the node is appended to the document.
+struct NodeBuffers {
+    name: String,
+    /// Frame of each morph target.
+    morph_frames: Vec<u32>,
+    primitives: Vec<PrimitiveBuffers>,
+    accessors: Vec<json::Accessor>,
+    buffer_views: Vec<json::buffer::View>,
+    data: Vec<u8>,
+    animation: Option<(json::animation::Channel, json::animation::Sampler)>,
+    segment: Option<Segment>,
+    /// Animated fields baked into images.
+    fields: Vec<BakedField>,
+    warnings: Vec<(usize, String)>,
+    skipped_attributes: Vec<String>,
+}
+
+impl NodeBuffers {
+    fn build(
+        node_index: usize,
+        mut node: Node,
+        max_primitive_vertices: usize,
+        time_step: f32,
+        insert_vanishing_frames: bool,
+        stage: &Stage,
+    ) -> Result<NodeBuffers, Error> {
+        let name = node.name.clone();
+        let segment = node.segment;
+        let morph_frames = node.morphs.iter().map(|morph| morph.frame).collect();
+        let mut accessors = Vec::new();
+        let mut buffer_views = Vec::new();
+        let mut data = Vec::<u8>::new();
+        let mut animation = None;
+        let mut warnings = Vec::new();
+        let mut skipped_attributes = Vec::new();
+        let mut primitives = Vec::new();
+
+        // Fields are baked before splitting so that all primitives of the mesh share one texture.
+        let num_vertices = node.mesh.vertex_positions().len();
+        let fields = mem::take(&mut node.fields)
             .iter()
-            .filter_map(|attrib| {
-                let num_bytes = match attrib.type_ {
-                    Type::Vec3(ComponentType::U8) => mem::size_of::<[u8; 3]>(),
-                    Type::Vec3(ComponentType::U16) => mem::size_of::<[u16; 3]>(),
-                    Type::Vec3(ComponentType::F32) => mem::size_of::<[f32; 3]>(),
-                    Type::Vec4(ComponentType::U8) => mem::size_of::<[u8; 4]>(),
-                    Type::Vec4(ComponentType::U16) => mem::size_of::<[u16; 4]>(),
-                    Type::Vec4(ComponentType::F32) => mem::size_of::<[f32; 4]>(),
-                    t => {
-                        log!(warnings;
-                            "Invalid color attribute type detected: {:?}. Skipping...",
-                            t
-                        );
-                        return None;
-                    }
-                };
-                let byte_length = attrib.attribute.len() * num_bytes;
+            .map(|series| bake_field(series, num_vertices))
+            .collect::<Result<Vec<_>, Error>>()?;
+        if !fields.is_empty() {
+            let texels = field_texel_attribute(num_vertices);
+            node.attrib_transfer.attribs_to_keep.push(texels);
+        }
 
-                let attrib_view = json::buffer::View::new(byte_length, data.len())
-                    .with_stride(num_bytes)
-                    .with_target(json::buffer::Target::ArrayBuffer);
+        // Large meshes are split into multiple primitives of the same mesh.
+        for (
+            part_index,
+            Node {
+                first_frame,
+                mesh,
+                attrib_transfer,
+                morphs,
+                holds,
+                ..
+            },
+        ) in node
+            .split_by_vertex_count(max_primitive_vertices)
+            .into_iter()
+            .enumerate()
+        {
+            let (vertex_positions, indices) = mesh.build_topology(
+                &attrib_transfer,
+                &mut data,
+                &mut buffer_views,
+                &mut accessors,
+            )?;
+
+            // Push positions to data buffer.
+            let pos_acc_index = build_nonempty_buffer_vec3(
+                vertex_positions,
+                &mut accessors,
+                &mut buffer_views,
+                &mut data,
+                POSITION_ATTRIB_NAME,
+            );
+
+            // Push normals and tangents to data buffer if any.
+            let nml_acc_index = build_buffer_vec3(
+                &attrib_transfer.normal_attrib,
+                &mut accessors,
+                &mut buffer_views,
+                &mut data,
+                NORMAL_ATTRIB_NAME,
+            );
+            let tng_acc_index = build_buffer_vec3(
+                &attrib_transfer.tangent_attrib,
+                &mut accessors,
+                &mut buffer_views,
+                &mut data,
+                TANGENT_ATTRIB_NAME,
+            );
+
+            // Push color vertex attribute
+            let color_attrib_acc_indices: Vec<_> = attrib_transfer
+                .color_attribs_to_keep
+                .iter()
+                .filter_map(|attrib| {
+                    let num_bytes = match attrib.type_ {
+                        Type::Vec3(ComponentType::U8) => mem::size_of::<[u8; 3]>(),
+                        Type::Vec3(ComponentType::U16) => mem::size_of::<[u16; 3]>(),
+                        Type::Vec3(ComponentType::F32) => mem::size_of::<[f32; 3]>(),
+                        Type::Vec4(ComponentType::U8) => mem::size_of::<[u8; 4]>(),
+                        Type::Vec4(ComponentType::U16) => mem::size_of::<[u16; 4]>(),
+                        Type::Vec4(ComponentType::F32) => mem::size_of::<[f32; 4]>(),
+                        t => {
+                            log!(warnings;
+                                "Invalid color attribute type detected: {:?}. Skipping...",
+                                t
+                            );
+                            skipped_attributes.push(format!(
+                                "{}: invalid color attribute type {:?}",
+                                attrib.name, t
+                            ));
+                            return None;
+                        }
+                    };
+                    let byte_length = attrib.attribute.len() * num_bytes;
 
-                let attrib_view_index = buffer_views.len();
-                buffer_views.push(attrib_view);
+                    align_data(&mut data);
+                    let attrib_view = json::buffer::View::new(byte_length, data.len())
+                        .with_stride(num_bytes)
+                        .with_target(json::buffer::Target::ArrayBuffer);
 
-                match attrib.type_ {
-                    Type::Vec3(ComponentType::U8) => {
-                        write_color_attribute_data::<[u8; 3]>(&mut data, attrib)
-                    }
-                    Type::Vec3(ComponentType::U16) => {
-                        write_color_attribute_data::<[u16; 3]>(&mut data, attrib)
-                    }
-                    Type::Vec3(ComponentType::F32) => {
-                        write_color_attribute_data::<[f32; 3]>(&mut data, attrib)
-                    }
-                    Type::Vec4(ComponentType::U8) => {
-                        write_color_attribute_data::<[u8; 4]>(&mut data, attrib)
-                    }
-                    Type::Vec4(ComponentType::U16) => {
-                        write_color_attribute_data::<[u16; 4]>(&mut data, attrib)
-                    }
-                    Type::Vec4(ComponentType::F32) => {
-                        write_color_attribute_data::<[f32; 4]>(&mut data, attrib)
+                    let attrib_view_index = buffer_views.len();
+                    buffer_views.push(attrib_view);
+
+                    match attrib.type_ {
+                        Type::Vec3(ComponentType::U8) => {
+                            write_color_attribute_data::<[u8; 3]>(&mut data, attrib)
+                        }
+                        Type::Vec3(ComponentType::U16) => {
+                            write_color_attribute_data::<[u16; 3]>(&mut data, attrib)
+                        }
+                        Type::Vec3(ComponentType::F32) => {
+                            write_color_attribute_data::<[f32; 3]>(&mut data, attrib)
+                        }
+                        Type::Vec4(ComponentType::U8) => {
+                            write_color_attribute_data::<[u8; 4]>(&mut data, attrib)
+                        }
+                        Type::Vec4(ComponentType::U16) => {
+                            write_color_attribute_data::<[u16; 4]>(&mut data, attrib)
+                        }
+                        Type::Vec4(ComponentType::F32) => {
+                            write_color_attribute_data::<[f32; 4]>(&mut data, attrib)
+                        }
+                        // This must have been checked above.
+                        _ => unreachable!(),
                     }
-                    // This must have been checked above.
-                    _ => unreachable!(),
-                }
 
-                let (type_, component_type) = attrib.type_.into();
-                let attrib_acc = json::Accessor::new(attrib.attribute.len(), component_type)
-                    .with_name(attrib.name.clone())
-                    .with_buffer_view(attrib_view_index)
-                    .with_type(type_);
+                    let (type_, component_type) = attrib.type_.into();
+                    let attrib_acc = json::Accessor::new(attrib.attribute.len(), component_type)
+                        .with_name(attrib.name.clone())
+                        .with_buffer_view(attrib_view_index)
+                        .with_type(type_);
 
-                let attrib_acc_index = accessors.len() as u32;
-                accessors.push(attrib_acc);
-                Some(attrib_acc_index)
-            })
-            .collect();
+                    let attrib_acc_index = accessors.len() as u32;
+                    accessors.push(attrib_acc);
+                    Some(attrib_acc_index)
+                })
+                .collect();
 
-        // Push custom vertex attributes to data buffer.
-        let attrib_acc_indices: Vec<_> = attrib_transfer
+            // Push custom vertex attributes to data buffer.
+            let attrib_acc_indices: Vec<_> = attrib_transfer
             .attribs_to_keep
             .iter()
             .map(|attrib| {
                 let byte_length = attrib.attribute.data.direct_data().unwrap().byte_len();
+                align_data(&mut data);
                 let attrib_view = json::buffer::View::new(byte_length, data.len())
                     .with_stride(call_typed_fn!(attrib.type_ => mem::size_of :: <_>()))
                     .with_target(json::buffer::Target::ArrayBuffer);
@@ -710,7 +2052,7 @@ fn build_gltf_parts(
                 let attrib_view_index = buffer_views.len();
                 buffer_views.push(attrib_view);
 
-                call_typed_fn!(attrib.type_ => self::write_attribute_data::<_>(&mut data, attrib));
+                call_typed_fn!(attrib.type_ => self::write_attribute_data::<_>(&mut data, attrib))?;
 
                 let (type_, component_type) = attrib.type_.into();
                 let attrib_acc = json::Accessor::new(attrib.attribute.len(), component_type)
@@ -720,12 +2062,12 @@ fn build_gltf_parts(
 
                 let attrib_acc_index = accessors.len() as u32;
                 accessors.push(attrib_acc);
-                attrib_acc_index
+                Ok(attrib_acc_index)
             })
-            .collect();
+            .collect::<Result<_, Error>>()?;
 
-        // Push texture coordinate attributes to data buffer.
-        let tex_attrib_acc_indices: Vec<_> = attrib_transfer
+            // Push texture coordinate attributes to data buffer.
+            let tex_attrib_acc_indices: Vec<_> = attrib_transfer
             .tex_attribs_to_keep
             .iter()
             .filter_map(|attrib| {
@@ -739,9 +2081,14 @@ fn build_gltf_parts(
                             "Invalid texture coordinate attribute type detected: {:?}. Skipping...",
                             t
                         );
+                        skipped_attributes.push(format!(
+                            "{}: invalid texture coordinate attribute type {:?}",
+                            attrib.name, t
+                        ));
                         return None;
                     }
                 };
+                align_data(&mut data);
                 let orig_data_len = data.len();
 
                 // First let's try to write the data to flush out any problems before appending the
@@ -776,214 +2123,1563 @@ fn build_gltf_parts(
             })
             .collect();
 
-        // If colors or textures were specified but not materials, add a default material.
-        if (!attrib_transfer.color_attribs_to_keep.is_empty()
-            || !attrib_transfer.tex_attribs_to_keep.is_empty())
-            && materials.is_empty()
-        {
-            materials.push(MaterialInfo::default().into());
+            let targets = if part_index == 0 {
+                build_animation(
+                    first_frame,
+                    &morphs,
+                    node_index,
+                    &mut accessors,
+                    &mut buffer_views,
+                    &mut data,
+                    time_step,
+                    // Only the first segment of a split animation starts with a vanishing frame.
+                    insert_vanishing_frames
+                        && first_frame != 0
+                        && segment.map_or(true, |segment| segment.index == 0),
+                    &holds,
+                    stage,
+                )
+                .map(|(channel, sampler, targets)| {
+                    animation = Some((channel, sampler));
+                    targets.into_iter().unzip()
+                })
+            } else if !morphs.is_empty() {
+                // Remaining parts share the animation channel built for the first part.
+                Some(
+                    morphs
+                        .iter()
+                        .map(|morph| {
+                            build_morph_target(morph, &mut accessors, &mut buffer_views, &mut data)
+                        })
+                        .unzip(),
+                )
+            } else {
+                None
+            };
+            let (targets, target_colors) = targets.unwrap_or_default();
+
+            let mode = Valid(if indices.is_some() {
+                json::mesh::Mode::Triangles
+            } else {
+                json::mesh::Mode::Points
+            });
+
+            primitives.push(PrimitiveBuffers {
+                mode,
+                pos_acc_index,
+                nml_acc_index,
+                tng_acc_index,
+                attrib_acc_indices,
+                color_attrib_acc_indices,
+                tex_attrib_acc_indices,
+                indices,
+                targets,
+                target_colors,
+                attrib_transfer,
+            });
         }
 
-        let targets = build_animation(
-            first_frame,
-            &morphs,
-            nodes.len(),
-            &mut accessors,
-            &mut buffer_views,
-            &mut data,
-            time_step,
-            insert_vanishing_frames && first_frame != 0,
-            &pb,
-        )
-        .map(|(mut channel, sampler, targets)| {
-            // Override the sampler index to correspond to the index within the animation_samplers Vec.
-            channel.sampler = json::Index::new(animation_samplers.len() as u32);
-            animation_channels.push(channel);
-            animation_samplers.push(sampler);
-            targets
-        });
+        Ok(NodeBuffers {
+            name,
+            morph_frames,
+            primitives,
+            accessors,
+            buffer_views,
+            data,
+            animation,
+            segment,
+            fields,
+            warnings,
+            skipped_attributes,
+        })
+    }
+}
 
-        let mode = Valid(if indices.is_some() {
-            json::mesh::Mode::Triangles
-        } else {
-            json::mesh::Mode::Points
-        });
+fn build_gltf_parts(
+    morphed_meshes: Vec<Node>,
+    mut textures: Vec<TextureInfo>,
+    materials: Vec<MaterialInfo>,
+    export_config: &ExportConfig,
+    progress: &dyn ProgressSink,
+    report: &mut ExportReport,
+) -> Result<(Document, Vec<u8>, Output), Error> {
+    let &ExportConfig {
+        time_step,
+        insert_vanishing_frames,
+        max_primitive_vertices,
+        studio_lighting,
+        frame_camera,
+        dedup_buffers,
+        default_material,
+        split_materials,
+        texture_atlas,
+        point_size,
+        points_as,
+        dcc,
+        collision_proxy,
+        animate_proxy,
+        ref output,
+        ref pointer_animations,
+        ref scene,
+        ref layout,
+        ref attach,
+        ref extras,
+        ref asset,
+        ref attribute_names,
+        ref material_id_map,
+        ref node_materials,
+        ..
+    } = export_config;
+    let mut lights = export_config.lights.clone();
+    // Content creation tools import each node as a separate action with a limited number of
+    // shape keys.
+    let animation_groups = match (dcc, &export_config.animation_groups) {
+        (Some(Dcc::Blender), AnimationGrouping::Single) => AnimationGrouping::ByName,
+        (_, animation_groups) => animation_groups.clone(),
+    };
 
-        let primitives = build_primitives(
-            mode,
-            pos_acc_index,
-            nml_acc_index,
-            tng_acc_index,
-            &attrib_transfer,
-            &attrib_acc_indices,
-            &color_attrib_acc_indices,
-            &tex_attrib_acc_indices,
-            indices,
-            targets,
-            materials.len(),
-            &mut msgs,
-        );
+    let count: u64 = morphed_meshes.iter().map(|m| m.morphs.len() as u64).sum();
+    let stage = Stage::start(progress, "Constructing glTF", count, ProgressUnit::Items);
 
-        nodes.push(json::Node {
-            camera: None,
-            children: None,
-            extensions: Default::default(),
-            extras: Default::default(),
-            matrix: None,
-            mesh: Some(json::Index::new(meshes.len() as u32)),
-            name: Some(name),
-            rotation: None,
-            scale: None,
-            translation: None,
-            skin: None,
-            weights: None,
-        });
+    // Keep track of the messages and warnings to be displayed after construction is complete.
+    let mut msgs = Vec::new();
+    let mut warnings = Vec::new();
 
-        meshes.push(json::Mesh {
+    // Point clouds replaced by instanced meshes are built after all other nodes.
+    let (instanced_nodes, morphed_meshes): (Vec<_>, Vec<_>) = morphed_meshes
+        .into_iter()
+        .partition(|node| points_as.is_some() && matches!(node.mesh, Mesh::PointCloud(_)));
+    let radius_attrib = attribute_names
+        .0
+        .iter()
+        .find(|(_, semantic)| semantic.as_str() == POINT_RADIUS_SEMANTIC)
+        .map(|(name, _)| name.clone());
+    let mut semantics = AttributeSemantics::new(attribute_names.clone());
+    let mut fallback_material = None;
+
+    // First populate materials
+    // Doing this first allows us to attach a default material if one is needed.
+    let mut materials: Vec<_> = materials.into_iter().map(Into::into).collect();
+
+    let mut accessors = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
+    let mut animation_channels = Vec::new();
+    let mut animation_samplers = Vec::new();
+    // Name of the node animated by each channel.
+    let mut animation_targets = Vec::new();
+    // Baked fields with the index of their mesh, added once other textures are in place.
+    let mut baked_fields = Vec::new();
+    let mut data = Vec::<u8>::new();
+
+    // Vertex indices are written as 32 bit unsigned integers, so no primitive can reference more
+    // vertices than that.
+    let max_primitive_vertices = max_primitive_vertices
+        .map_or(usize::MAX, |n| n as usize)
+        .min((u32::MAX as usize).saturating_add(1));
+
+    // Proxies are computed from the positions of every frame before nodes are consumed.
+    let proxies: Vec<Option<Proxy>> = morphed_meshes
+        .iter()
+        .map(|node| {
+            let positions = node.mesh.vertex_positions();
+            let frames = std::iter::once((node.first_frame, positions.to_vec())).chain(
+                node.morphs.iter().map(|morph| {
+                    let displaced = positions
+                        .iter()
+                        .zip(morph.position_disp.iter())
+                        .map(|(p, d)| [p[0] + d[0], p[1] + d[1], p[2] + d[2]])
+                        .collect();
+                    (morph.frame, displaced)
+                }),
+            );
+            let mut proxy = Proxy::new(collision_proxy?, animate_proxy, frames)?;
+            if animate_proxy {
+                proxy.hold(&node.holds);
+            }
+            Some(proxy)
+        })
+        .collect();
+    // Bounds of the mesh nodes and instanced point clouds over all frames, in the order of their nodes.
+    let node_bounds: Vec<_> = if studio_lighting || frame_camera {
+        morphed_meshes
+            .iter()
+            .chain(instanced_nodes.iter())
+            .map(Node::bounds)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // Proxies of mesh nodes with the index of their node, added once the hierarchy is in place.
+    let mut proxy_nodes = Vec::new();
+    // Mesh nodes whose materials are moved into child nodes.
+    let mut material_nodes = Vec::new();
+
+    // Buffers of each node are built in parallel and then appended in order.
+    #[cfg(feature = "parallel")]
+    let morphed_meshes = morphed_meshes.into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let morphed_meshes = morphed_meshes.into_iter();
+    let node_buffers = morphed_meshes
+        .enumerate()
+        .map(|(node_index, node)| {
+            NodeBuffers::build(
+                node_index,
+                node,
+                max_primitive_vertices,
+                time_step,
+                insert_vanishing_frames,
+                &stage,
+            )
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for (node, proxy) in node_buffers.into_iter().zip(proxies) {
+        let accessor_offset = accessors.len() as u32;
+        let buffer_view_offset = buffer_views.len() as u32;
+        align_data(&mut data);
+        let byte_offset = data.len() as u64;
+
+        for mut view in node.buffer_views {
+            view.byte_offset = Some(USize64(
+                view.byte_offset.map_or(0, |USize64(o)| o) + byte_offset,
+            ));
+            buffer_views.push(view);
+        }
+        for mut acc in node.accessors {
+            if let Some(view) = acc.buffer_view.as_mut() {
+                shift(view, buffer_view_offset);
+            }
+            if let Some(sparse) = acc.sparse.as_mut() {
+                shift(&mut sparse.indices.buffer_view, buffer_view_offset);
+                shift(&mut sparse.values.buffer_view, buffer_view_offset);
+            }
+            accessors.push(acc);
+        }
+        if data.is_empty() {
+            data = node.data;
+        } else {
+            data.extend_from_slice(&node.data);
+        }
+        warnings.extend(node.warnings);
+        report.skipped_attributes.extend(node.skipped_attributes);
+
+        if let Some((mut channel, mut sampler)) = node.animation {
+            // Override the sampler index to correspond to the index within the animation_samplers Vec.
+            channel.sampler = json::Index::new(animation_samplers.len() as u32);
+            shift(&mut sampler.input, accessor_offset);
+            shift(&mut sampler.output, accessor_offset);
+            if dcc.is_some() {
+                // Show each frame as is instead of blending between shape keys.
+                sampler.interpolation = Valid(json::animation::Interpolation::Step);
+            }
+            animation_channels.push(channel);
+            animation_samplers.push(sampler);
+            animation_targets.push(node.name.clone());
+        }
+
+        // Segments of a split animation are swapped by scaling them to and from zero.
+        if let Some(segment) = node.segment {
+            let time = |frame: u32| frame as f32 * time_step;
+            let mut keys = Vec::with_capacity(3);
+            if segment.index > 0 {
+                keys.push((0.0, 0.0));
+            }
+            keys.push((time(segment.show), 1.0));
+            if let Some(hide) = segment.hide {
+                keys.push((time(hide), 0.0));
+            }
+            let sampler = build_scale_sampler(&keys, &mut accessors, &mut buffer_views, &mut data);
+            animation_channels.push(json::animation::Channel {
+                sampler: json::Index::new(animation_samplers.len() as u32),
+                target: json::animation::Target {
+                    path: Valid(json::animation::Property::Scale),
+                    node: json::Index::new(nodes.len() as u32),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                },
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+            animation_samplers.push(sampler);
+            animation_targets.push(node.name.clone());
+        }
+
+        let mut primitives = Vec::new();
+        let mut material_ids: Vec<MaterialIdBinding> = Vec::new();
+        for mut primitive in node.primitives {
+            primitive.shift_accessors(accessor_offset);
+
+            let custom_semantics: Vec<_> = primitive
+                .attrib_transfer
+                .attribs_to_keep
+                .iter()
+                .map(|attrib| semantics.get(&attrib.name, &mut warnings))
+                .collect();
+
+            // If colors or textures were specified but not materials, add a default material.
+            if (!primitive.attrib_transfer.color_attribs_to_keep.is_empty()
+                || !primitive.attrib_transfer.tex_attribs_to_keep.is_empty())
+                && materials.is_empty()
+            {
+                materials.push(MaterialInfo::default().into());
+            }
+
+            let ids: Vec<u32> = match &primitive.attrib_transfer.material_ids {
+                Some(MaterialIds::Global { map }) => map.keys().copied().collect(),
+                _ => Vec::new(),
+            };
+            let mut binder = MaterialBinder {
+                remap: material_id_map,
+                num_materials: fallback_material.map_or(materials.len(), |i| i as usize),
+                fallback: fallback_material,
+            };
+            if default_material
+                && fallback_material.is_none()
+                && ids.iter().any(|&id| binder.bind(id).is_none())
+            {
+                let index = materials.len() as u32;
+                materials.push(MaterialInfo::default().into());
+                fallback_material = Some(index);
+                binder.fallback = fallback_material;
+            }
+            for &id in ids.iter() {
+                if !material_ids.iter().any(|binding| binding.id == id) {
+                    let material = binder.bind(id);
+                    if material.is_none() && binder.num_materials > 0 {
+                        log!(warnings; "Material ID {} of '{}' does not refer to any of the {} specified materials. Use '--material-id-map' to remap it or '--default-material' to bind a default material", id, node.name, binder.num_materials);
+                    }
+                    material_ids.push(MaterialIdBinding { id, material });
+                }
+            }
+
+            let first_primitive = primitives.len();
+            primitives.extend(build_primitives(
+                primitive.mode,
+                primitive.pos_acc_index,
+                primitive.nml_acc_index,
+                primitive.tng_acc_index,
+                &primitive.attrib_transfer,
+                &primitive.attrib_acc_indices,
+                &primitive.color_attrib_acc_indices,
+                &primitive.tex_attrib_acc_indices,
+                primitive.indices,
+                &primitive.targets,
+                &custom_semantics,
+                &binder,
+                &mut msgs,
+            ));
+            let polygon_ids = primitive_polygon_ids(&primitive.attrib_transfer);
+            for (i, prim) in primitives[first_primitive..].iter_mut().enumerate() {
+                prim.extras = target_colors_extras(&primitive.target_colors)?;
+                if let Some(ids) = polygon_ids.get(i) {
+                    prim.extras = with_polygon_ids(prim.extras.take(), ids)?;
+                }
+            }
+        }
+        if let Some(&material) = node_materials.get(&node.name) {
+            for prim in primitives.iter_mut() {
+                prim.material = Some(json::Index::new(material));
+            }
+        }
+        if !material_ids.is_empty() {
+            report.material_ids.push((node.name.clone(), material_ids));
+        }
+
+        nodes.push(json::Node {
+            camera: None,
+            children: None,
             extensions: Default::default(),
             extras: Default::default(),
+            matrix: None,
+            mesh: Some(json::Index::new(meshes.len() as u32)),
+            name: Some(node.name),
+            rotation: None,
+            scale: None,
+            translation: None,
+            skin: None,
+            weights: None,
+        });
+        if let Some(proxy) = proxy {
+            proxy_nodes.push((nodes.len() - 1, proxy));
+        }
+        if split_materials {
+            material_nodes.push(nodes.len() - 1);
+        }
+
+        let has_points = primitives
+            .iter()
+            .any(|prim| prim.mode == Valid(json::mesh::Mode::Points));
+        let mut mesh_extras = serde_json::Map::new();
+        if let Some(size) = point_size.filter(|_| has_points) {
+            mesh_extras.insert("pointSize".to_string(), size.into());
+        }
+        if dcc.is_some() && !node.morph_frames.is_empty() {
+            let names = node
+                .morph_frames
+                .iter()
+                .map(|frame| format!("frame_{}", frame));
+            mesh_extras.insert("targetNames".to_string(), names.collect());
+        }
+        baked_fields.extend(node.fields.into_iter().map(|field| (meshes.len(), field)));
+        meshes.push(json::Mesh {
+            extensions: Default::default(),
+            extras: if mesh_extras.is_empty() {
+                None
+            } else {
+                to_extras(&serde_json::Value::Object(mesh_extras))?
+            },
             name: None,
             primitives,
             weights: None,
         });
     }
 
-    let animations = if !animation_channels.is_empty() {
-        vec![json::Animation {
-            extensions: Default::default(),
-            extras: Default::default(),
-            name: None,
-            channels: animation_channels,
-            samplers: animation_samplers,
-        }]
-    } else {
-        vec![]
-    };
+    // Animated point clouds are split into one instanced node per frame, which are added as
+    // children once all top level nodes are in place.
+    let mut instanced_frames = Vec::new();
+    if let Some(points_as) = points_as {
+        if !instanced_nodes.is_empty() && materials.is_empty() {
+            materials.push(MaterialInfo::default().into());
+        }
+        for node in instanced_nodes {
+            let InstancedPoints {
+                mut mesh,
+                mut frames,
+            } = build_instanced_points(
+                &node,
+                points_as,
+                radius_attrib.as_deref(),
+                &mut accessors,
+                &mut buffer_views,
+                &mut data,
+                &mut warnings,
+            );
+            for primitive in &mut mesh.primitives {
+                primitive.material = Some(json::Index::new(0));
+            }
+            let mesh_index = json::Index::new(meshes.len() as u32);
+            meshes.push(mesh);
+            let mut json_node = json::Node {
+                name: Some(node.name),
+                ..Default::default()
+            };
+            if frames.len() == 1 {
+                let (_, extension) = frames.remove(0);
+                json_node.mesh = Some(mesh_index);
+                json_node.extensions = Some(extension);
+            } else {
+                instanced_frames.push((nodes.len(), mesh_index, frames));
+            }
+            nodes.push(json_node);
+        }
+    }
 
-    let output = Output::from_ext(output);
+    let output = Output::from_ext(output.clone());
 
     // Convert auto texture images to embedded or uri based on selected output.
     process_auto_textures(&mut textures, &output);
 
     let TextureData {
-        samplers,
+        mut samplers,
+        mut images,
+        mut textures,
+        dropped,
+    } = build_texture_data(textures, &mut data, &mut buffer_views, &mut warnings);
+    report.dropped_textures.extend(dropped);
+
+    // Baked fields share a single sampler reading exact texel values.
+    if !baked_fields.is_empty() {
+        let sampler_index = json::Index::new(samplers.len() as u32);
+        samplers.push(field_sampler());
+        for (mesh_index, field) in baked_fields {
+            let image = build_field_image(&field, &mut data, &mut buffer_views);
+            textures.push(json::texture::Texture {
+                source: json::Index::new(images.len() as u32),
+                sampler: Some(sampler_index),
+                name: Some(field.name.clone()),
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+            images.push(image);
+
+            let mesh = &mut meshes[mesh_index];
+            let mut mesh_extras = mesh
+                .extras
+                .as_ref()
+                .and_then(|extras| serde_json::from_str(extras.get()).ok())
+                .unwrap_or_else(|| serde_json::json!({}));
+            let entry = field_extras(&field, textures.len() - 1);
+            match mesh_extras[BAKED_FIELDS_KEY].as_array_mut() {
+                Some(fields) => fields.push(entry),
+                None => mesh_extras[BAKED_FIELDS_KEY] = serde_json::json!([entry]),
+            }
+            mesh.extras = to_extras(&mesh_extras)?;
+        }
+    }
+
+    layout.apply(&mut nodes);
+
+    let scene_bounds = union_bounds(node_bounds.iter().zip(nodes.iter()));
+    if studio_lighting {
+        match scene_bounds {
+            Some((min, max)) => lights.extend(studio_lights(min, max)),
+            None => log!(warnings; "No vertices found to place studio lights around. Skipping..."),
+        }
+    }
+
+    // Lights are attached to their own nodes following the mesh nodes.
+    let mut extensions_used = Vec::new();
+    let extensions = if lights.is_empty() {
+        None
+    } else {
+        for (light_index, light) in lights.iter().enumerate() {
+            nodes.push(build_light_node(light, light_index));
+        }
+        extensions_used.push("KHR_lights_punctual".to_string());
+        Some(json::extensions::Root {
+            khr_lights_punctual: Some(json::extensions::root::KhrLightsPunctual {
+                lights: lights.iter().map(Into::into).collect(),
+            }),
+            ..Default::default()
+        })
+    };
+
+    // The framing camera is attached to its own node following the lights.
+    let mut cameras = Vec::new();
+    if frame_camera {
+        match scene_bounds {
+            Some((min, max)) => {
+                let (camera, node) = build_framing_camera(min, max, cameras.len());
+                cameras.push(camera);
+                nodes.push(node);
+            }
+            None => log!(warnings; "No vertices found to frame with a camera. Skipping..."),
+        }
+    }
+
+    // Arrange generated nodes into the requested hierarchy.
+    let scene_nodes = scene.build_hierarchy(&mut nodes, &mut warnings);
+
+    for (parent, mesh, frames) in instanced_frames {
+        let times: Vec<_> = frames
+            .iter()
+            .map(|&(frame, _)| frame as f32 * time_step)
+            .collect();
+        let mut children = Vec::new();
+        for (index, (frame, extension)) in frames.into_iter().enumerate() {
+            let child = json::Index::new(nodes.len() as u32);
+            nodes.push(json::Node {
+                mesh: Some(mesh),
+                name: nodes[parent]
+                    .name
+                    .as_ref()
+                    .map(|name| format!("{}_{}", name, frame)),
+                extensions: Some(extension),
+                ..Default::default()
+            });
+            children.push(child);
+            let sampler = build_visibility_sampler(
+                &times,
+                index,
+                &mut accessors,
+                &mut buffer_views,
+                &mut data,
+            );
+            animation_channels.push(json::animation::Channel {
+                sampler: json::Index::new(animation_samplers.len() as u32),
+                target: json::animation::Target {
+                    path: Valid(json::animation::Property::Scale),
+                    node: child,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                },
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+            animation_samplers.push(sampler);
+            animation_targets.push(nodes[parent].name.clone().unwrap_or_default());
+        }
+        nodes[parent].children = Some(children);
+    }
+
+    // Collision proxies are children of their mesh nodes, so they follow any layout or hierarchy.
+    for (parent, proxy) in proxy_nodes {
+        let name = nodes[parent].name.clone().unwrap_or_default();
+        meshes.push(build_proxy_mesh(
+            &proxy,
+            &mut accessors,
+            &mut buffer_views,
+            &mut data,
+        ));
+        let mesh = json::Index::new(meshes.len() as u32 - 1);
+        let child = json::Index::new(nodes.len() as u32);
+        nodes.push(build_proxy_node(&name, &proxy, mesh)?);
+        nodes[parent]
+            .children
+            .get_or_insert_with(Vec::new)
+            .push(child);
+        for (path, sampler) in build_proxy_samplers(
+            &proxy,
+            time_step,
+            &mut accessors,
+            &mut buffer_views,
+            &mut data,
+        ) {
+            animation_channels.push(json::animation::Channel {
+                sampler: json::Index::new(animation_samplers.len() as u32),
+                target: json::animation::Target {
+                    path: Valid(path),
+                    node: child,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                },
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+            animation_samplers.push(sampler);
+            animation_targets.push(name.clone());
+        }
+    }
+
+    // Material groups become children after the hierarchy is built, so that they follow their mesh node.
+    for parent in material_nodes {
+        split_material_nodes(
+            parent,
+            &mut nodes,
+            &mut meshes,
+            &materials,
+            &mut animation_channels,
+            &mut animation_samplers,
+            &mut animation_targets,
+        );
+    }
+
+    // Material and light properties are animated through JSON pointers.
+    let mut pointer_samplers = Vec::new();
+    for info in pointer_animations.iter() {
+        let pointer = match info.resolve(&nodes, &materials, &lights) {
+            Ok(pointer) => pointer,
+            Err(name) => {
+                log!(warnings; "No object named '{}' found for the animated property '{}'. Skipping...", name, info.pointer);
+                continue;
+            }
+        };
+        match build_pointer_sampler(
+            info,
+            time_step,
+            &mut accessors,
+            &mut buffer_views,
+            &mut data,
+        ) {
+            Ok(sampler) => pointer_samplers.push(PointerSampler {
+                pointer,
+                group: info.group.clone(),
+                sampler,
+            }),
+            Err(err) => {
+                log!(warnings; "Failed to animate '{}': {}. Skipping...", info.pointer, err);
+            }
+        }
+    }
+    if !pointer_samplers.is_empty() {
+        extensions_used.push(KHR_ANIMATION_POINTER.to_string());
+    }
+
+    if nodes.iter().any(|node| {
+        node.extensions
+            .as_ref()
+            .is_some_and(|ext| ext.others.contains_key(EXT_MESH_GPU_INSTANCING))
+    }) {
+        extensions_used.push(EXT_MESH_GPU_INSTANCING.to_string());
+    }
+
+    let (animations, pointer_channels) = group_animations(
+        animation_channels,
+        animation_samplers,
+        &animation_targets,
+        &pointer_samplers,
+        &animation_groups,
+    );
+
+    let mut asset = asset.build(extras.asset.as_ref(), &mut warnings)?;
+    if let Some((min, max)) = scene_bounds.filter(|_| frame_camera) {
+        asset.extras = with_scene_bounds(asset.extras.take(), min, max)?;
+    }
+
+    let mut root = json::Root {
+        asset,
+        animations,
+        accessors,
+        buffer_views,
+        cameras,
+        meshes,
+        nodes,
         images,
+        samplers,
         textures,
-    } = build_texture_data(textures, &mut data, &mut buffer_views, &mut warnings);
+        materials,
+        extensions,
+        extensions_used,
+        ..Default::default()
+    };
+
+    // Merge static documents into the scene alongside the generated nodes.
+    let mut attached = Vec::new();
+    for path in attach.iter() {
+        match append_document(&mut root, &mut data, path, &mut warnings) {
+            Ok(roots) => attached.extend(roots),
+            Err(err) => log!(warnings; "Failed to attach {:?}: {}. Skipping...", path, err),
+        }
+    }
+
+    let (scenes, default_scene) =
+        scene.build_scenes(&root.nodes, scene_nodes, &attached, &mut warnings);
+    let scene_extras = match &extras.scene {
+        Some(value) => to_extras(value)?,
+        None => None,
+    };
+    root.scenes = scenes
+        .into_iter()
+        .map(|(name, nodes)| json::Scene {
+            extensions: Default::default(),
+            extras: scene_extras.clone(),
+            name,
+            nodes,
+        })
+        .collect();
+    root.scene = default_scene.map(|i| json::Index::new(i as u32));
 
-    pb.finish_with_message("Done constructing glTF");
+    for name in extras.apply(&mut root.nodes, &mut root.materials)? {
+        log!(warnings; "No node or material named '{}' found to attach extras to. Skipping...", name);
+    }
+
+    #[cfg(feature = "textures")]
+    if let Some(max_size) = texture_atlas {
+        pack_atlas(&mut root, &mut data, max_size, &mut warnings)?;
+    }
+    #[cfg(not(feature = "textures"))]
+    if texture_atlas.is_some() {
+        log!(warnings; "Texture atlases require the 'textures' feature. Keeping separate textures...");
+    }
+
+    if dedup_buffers {
+        dedup_buffer_views(&mut root, &mut data)?;
+    }
+
+    stage.finish("Done constructing glTF");
+
+    // Every writer pads the buffer before starting a new view, so this can only fail due to a bug.
+    debug_assert!(
+        crate::validate::misaligned_accessors(&root).is_empty(),
+        "Accessors {:?} are not aligned to their component size",
+        crate::validate::misaligned_accessors(&root)
+    );
+    // Parts of a mesh are built with one morph target per frame of the shared animation.
+    debug_assert!(
+        crate::validate::mismatched_morph_targets(&root).is_empty(),
+        "Meshes {:?} have inconsistent morph targets or weights",
+        crate::validate::mismatched_morph_targets(&root)
+    );
 
     // Print all accumulated warnings and messages.
     print_info(msgs);
-    print_warnings(warnings);
+    report.warnings += report_warnings(progress, warnings);
+
+    root.buffers.push(build_buffer(data.len(), &output)?);
 
-    let buffer = json::Buffer {
-        byte_length: data.len().into(),
+    // Return the json structure and binary blob.
+    let target_colors = take_target_colors(&mut root)?;
+    let document = Document {
+        root,
+        pointer_channels,
+        target_colors,
+    };
+    Ok((document, data, output))
+}
+
+/// Union of the bounding boxes of nodes, each offset by the translation of its node.
+fn union_bounds<'a>(
+    bounds: impl IntoIterator<Item = (&'a Option<([f32; 3], [f32; 3])>, &'a json::Node)>,
+) -> Option<([f32; 3], [f32; 3])> {
+    bounds
+        .into_iter()
+        .filter_map(|(bounds, node)| {
+            let (min, max) = (*bounds)?;
+            let t = node.translation.unwrap_or([0.0; 3]);
+            Some((
+                std::array::from_fn(|i| min[i] + t[i]),
+                std::array::from_fn(|i| max[i] + t[i]),
+            ))
+        })
+        .reduce(|(min_a, max_a), (min_b, max_b)| {
+            (
+                std::array::from_fn(|i| f32::min(min_a[i], min_b[i])),
+                std::array::from_fn(|i| f32::max(max_a[i], max_b[i])),
+            )
+        })
+}
+
+/// Builds the single buffer description for the given output type.
+fn build_buffer(byte_length: usize, output: &Output) -> Result<json::Buffer, Error> {
+    Ok(json::Buffer {
+        byte_length: byte_length.into(),
         extensions: Default::default(),
         extras: Default::default(),
         name: None,
-        uri: match &output {
+        uri: match output {
             Output::Binary { .. } => None,
-            Output::Standard { binary_path, .. } => Some(format!(
-                "./{}",
-                binary_path
-                    .file_name()
-                    .unwrap_or_else(|| panic!(
-                        "ERROR: Invalid binary path: {}",
-                        binary_path.display()
-                    ))
-                    .to_str()
-                    .expect("ERROR: Path is not valid UTF-8")
-            )),
+            Output::Standard { binary_path, .. } => {
+                Some(relative_uri(binary_path.file_name().ok_or_else(|| {
+                    Error::InvalidOutputPath(binary_path.clone())
+                })?))
+            }
         },
-    };
+    })
+}
 
-    let num_nodes = nodes.len();
+/// Number of temporary files created by [`write_atomic`] in this process, used
+/// to give concurrent writes distinct temporary names.
+static TMP_FILE_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
 
-    // Return the json structure and binary blob.
-    (
-        json::Root {
-            asset: json::Asset {
-                generator: Some(format!("gltfgen v{}", clap::crate_version!())),
-                ..Default::default()
-            },
-            animations,
-            accessors,
-            buffers: vec![buffer],
-            buffer_views,
-            meshes,
-            nodes,
-            scenes: vec![json::Scene {
-                extensions: Default::default(),
-                extras: Default::default(),
-                name: None,
-                nodes: (0..num_nodes).map(|i| json::Index::new(i as u32)).collect(),
-            }],
-            images,
-            samplers,
-            textures,
-            materials,
-            ..Default::default()
-        },
-        data,
-        output,
+/// Writes `path` by first writing to a temporary file in the same directory
+/// and then moving it into place, so that a failed or interrupted write never
+/// leaves a truncated file behind.
+///
+/// Unless `replace` is set, the temporary file is published with a hard link,
+/// which fails with [`std::io::ErrorKind::AlreadyExists`] instead of replacing
+/// a file created at `path` in the meantime.
+pub(crate) fn write_atomic(
+    path: &Path,
+    replace: bool,
+    write: impl FnOnce(&mut std::io::BufWriter<std::fs::File>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::sync::atomic::Ordering;
+    // The temporary name is built from the OS string to support file names that aren't UTF-8.
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TMP_FILE_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+    let result = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+        .and_then(|file| {
+            let mut writer = std::io::BufWriter::new(file);
+            write(&mut writer)?;
+            writer.flush()
+        })
+        .and_then(|_| {
+            if replace {
+                std::fs::rename(&tmp_path, path)
+            } else {
+                std::fs::hard_link(&tmp_path, path)
+            }
+        });
+    // After a successful hard link the temporary file is a second name for the output.
+    if result.is_err() || !replace {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Serializes the glTF document and its binary buffer into a GLB file in memory.
+pub fn to_glb(document: &Document, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut glb = Vec::with_capacity(data.len() + 1024);
+    write_glb(&mut glb, document, &data).map_err(Error::Write)?;
+    Ok(glb)
+}
+
+/// Counts the bytes written to it.
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the glTF document and its binary buffer to `writer` in GLB format.
+///
+/// The JSON is measured in a first pass and then serialized straight into the
+/// writer, so neither the JSON string nor a padded copy of the buffer is built.
+fn write_glb(
+    mut writer: impl std::io::Write,
+    document: &Document,
+    data: &[u8],
+) -> std::io::Result<()> {
+    const HEADER_SIZE: usize = 12;
+    const CHUNK_HEADER_SIZE: usize = 8;
+
+    let mut counter = ByteCounter::default();
+    write_root(&mut counter, document, false)?;
+    let json_len = counter.0;
+    let json_chunk_len = json_len.next_multiple_of(4);
+    let bin_chunk_len = data.len().next_multiple_of(4);
+    let length = u32::try_from(
+        HEADER_SIZE + CHUNK_HEADER_SIZE + json_chunk_len + CHUNK_HEADER_SIZE + bin_chunk_len,
     )
+    .map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "output exceeds the 4 GiB size limit of GLB files",
+        )
+    })?;
+
+    writer.write_all(b"glTF")?;
+    writer.write_u32::<LE>(2)?;
+    writer.write_u32::<LE>(length)?;
+
+    writer.write_u32::<LE>(json_chunk_len as u32)?;
+    writer.write_all(b"JSON")?;
+    write_root(&mut writer, document, false)?;
+    writer.write_all(&b"   "[..json_chunk_len - json_len])?;
+
+    writer.write_u32::<LE>(bin_chunk_len as u32)?;
+    writer.write_all(b"BIN\0")?;
+    writer.write_all(data)?;
+    writer.write_all(&[0; 3][..bin_chunk_len - data.len()])?;
+    Ok(())
 }
 
-fn write_file(root: json::Root, data: Vec<u8>, output: Output, quiet: bool) {
-    let pb = new_progress_bar_file(quiet, 0);
-    pb.set_message("Writing glTF to File");
+/// Converts an error from [`write_atomic`] for `path` into an [`Error`].
+fn write_error(err: std::io::Error, path: &Path, no_overwrite: bool) -> Error {
+    if no_overwrite && err.kind() == std::io::ErrorKind::AlreadyExists {
+        Error::OutputExists(path.to_path_buf())
+    } else {
+        Error::Write(err)
+    }
+}
+
+/// Writes the glTF document and its buffer to the given output.
+///
+/// Returns the paths of all written files.
+fn write_file(
+    mut document: Document,
+    data: Vec<u8>,
+    output: &Output,
+    no_overwrite: bool,
+    pretty: bool,
+    split_buffers: bool,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<PathBuf>, Error> {
+    // Buffers of standard output along with the files they are written to.
+    let (data, buffers) = match output {
+        Output::Binary { .. } => {
+            if split_buffers {
+                log::warn!("Buffers are only split into separate files for .gltf output");
+            }
+            (data, Vec::new())
+        }
+        Output::Standard { binary_path, .. } if split_buffers => {
+            let buffers = split::split_buffers(&mut document.root, &data, binary_path)?;
+            (Vec::new(), buffers)
+        }
+        Output::Standard { binary_path, .. } => (Vec::new(), vec![(binary_path.clone(), data)]),
+    };
+    let paths: Vec<PathBuf> = match output {
+        Output::Binary { glb_path } => vec![glb_path.clone()],
+        Output::Standard { gltf_path, .. } => std::iter::once(gltf_path.clone())
+            .chain(buffers.iter().map(|(path, _)| path.clone()))
+            .collect(),
+    };
+
+    // Existing files are checked up front to fail before anything is written,
+    // and again when each file is published in case it was created since.
+    if no_overwrite {
+        if let Some(path) = paths.iter().find(|p| p.exists()) {
+            return Err(Error::OutputExists(path.clone()));
+        }
+    }
+
+    let stage = Stage::start(progress, "Writing glTF to File", 0, ProgressUnit::Bytes);
 
     match output {
         Output::Binary { glb_path } => {
             // Output in binary format.
-            let json_string =
-                json::serialize::to_string(&root).expect("ERROR: Failed to serialize glTF json");
-            let json_offset = align_to_multiple_of_four(json_string.len() as u32);
-
-            let glb = gltf::binary::Glb {
-                header: gltf::binary::Header {
-                    magic: *b"glTF",
-                    version: 2,
-                    length: json_offset + align_to_multiple_of_four(data.len() as u32),
-                },
-                bin: Some(Cow::Owned(to_padded_byte_vector(data))),
-                json: Cow::Owned(json_string.into_bytes()),
-            };
-
-            // This is an approximation of the total size.
-            pb.set_length((glb.header.length + 28) as u64);
+            // This is an approximation of the total size, since the JSON is small in comparison.
+            stage.set_length(data.len() as u64);
 
-            let writer =
-                std::fs::File::create(glb_path).expect("ERROR: Failed to create output .glb file");
-            glb.to_writer(pb.wrap_write(writer))
-                .expect("ERROR: Failed to output glTF binary data");
+            write_atomic(glb_path, !no_overwrite, |writer| {
+                write_glb(stage.wrap_write(writer), &document, &data)
+            })
+            .map_err(|err| write_error(err, glb_path, no_overwrite))?;
         }
-        Output::Standard {
-            binary_path,
-            gltf_path,
-        } => {
+        Output::Standard { gltf_path, .. } => {
             // Output in standard format.
-            // Two files will be produced: a .bin containing binary data and a json file containing
+            // The .bin files containing binary data are written along with a json file containing
             // the json string (named as specified by the user). The base filename will be the one
             // matching the filename in the output path given.
+            // Buffers are written first so that the json never refers to a missing buffer.
             use std::io::Write;
-            let writer = std::fs::File::create(gltf_path)
-                .expect("ERROR: Failed to create output .gltf file");
-            json::serialize::to_writer_pretty(writer, &root)
-                .expect("ERROR: Failed to serialize glTF json");
+            stage.set_length(buffers.iter().map(|(_, data)| data.len() as u64).sum());
+
+            for (path, data) in buffers.iter() {
+                write_atomic(path, !no_overwrite, |writer| {
+                    let mut writer = stage.wrap_write(writer);
+                    writer.write_all(data)?;
+                    // Pad to a multiple of four bytes.
+                    writer.write_all(&[0; 3][..data.len().next_multiple_of(4) - data.len()])
+                })
+                .map_err(|err| write_error(err, path, no_overwrite))?;
+            }
 
-            let bin = to_padded_byte_vector(data);
+            write_atomic(gltf_path, !no_overwrite, |writer| {
+                write_root(writer, &document, pretty)
+            })
+            .map_err(|err| write_error(err, gltf_path, no_overwrite))?;
+        }
+    }
 
-            pb.set_length(bin.len() as u64);
+    stage.finish("Success!");
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::load_single_box;
+    use crate::{load_and_clean_meshes, load_mesh, proxy, validate, LoadConfig};
+    use gltf::Gltf;
+
+    #[test]
+    fn split_large_primitives() {
+        let mesh_meta: Vec<_> = (1..=3)
+            .map(|frame| {
+                let path = format!("./assets/box_rotate_{}.vtk", frame);
+                (String::from("box_rotate"), frame, PathBuf::from(path))
+            })
+            .collect();
+
+        let attributes = "{\"pressure\": f32}".parse().unwrap();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+
+        let load_config = LoadConfig::default();
+
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
 
-            let writer = std::fs::File::create(binary_path)
-                .expect("ERROR: Failed to create output .bin file");
-            pb.wrap_write(writer)
-                .write_all(&bin)
-                .expect("ERROR: Failed to output glTF binary data");
+        let meshes = load_and_clean_meshes(mesh_meta, load_config, attrib_config);
+
+        let artifact = "./tests/artifacts/split_large_primitives.glb";
+
+        export_clean_meshes(
+            meshes,
+            ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .max_primitive_vertices(Some(4))
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
+
+        let gltf = Gltf::open(artifact).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        assert!(mesh.primitives().count() > 1);
+        for prim in mesh.primitives() {
+            let positions = prim.get(&gltf::Semantic::Positions).unwrap();
+            assert!(positions.count() <= 4);
+            assert_eq!(prim.morph_targets().count(), 2);
+            let indices = prim.indices().unwrap();
+            assert!(indices.max().unwrap()[0].as_u64().unwrap() < positions.count() as u64);
         }
     }
 
-    pb.finish_with_message("Success!");
+    #[test]
+    fn attach_static_scene() {
+        let meshes = load_single_box();
+
+        let artifact = "./tests/artifacts/attach_static_scene.glb";
+        let context = "./assets/box_triangulated_expected.glb";
+
+        export_clean_meshes(
+            meshes,
+            ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .attach(vec![context.into()])
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
+
+        // Importing validates all indices and buffer ranges.
+        let (doc, buffers, _) = gltf::import(artifact).unwrap();
+        let (ctx_doc, ctx_buffers, _) = gltf::import(context).unwrap();
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(doc.nodes().count(), 1 + ctx_doc.nodes().count());
+        assert_eq!(doc.meshes().count(), 1 + ctx_doc.meshes().count());
+        let scene = doc.scenes().next().unwrap();
+        assert_eq!(
+            scene.nodes().count(),
+            1 + ctx_doc.scenes().next().unwrap().nodes().count()
+        );
+
+        // The attached positions must be intact.
+        let read_positions = |mesh: gltf::Mesh, buffers: &[gltf::buffer::Data]| -> Vec<[f32; 3]> {
+            let prim = mesh.primitives().next().unwrap();
+            prim.reader(|b| Some(&buffers[b.index()]))
+                .read_positions()
+                .unwrap()
+                .collect()
+        };
+        assert_eq!(
+            read_positions(doc.meshes().last().unwrap(), &buffers),
+            read_positions(ctx_doc.meshes().next().unwrap(), &ctx_buffers)
+        );
+    }
+
+    #[test]
+    fn build_gltf_in_memory() {
+        let mesh_meta = (1..=3)
+            .map(|frame| {
+                let path = format!("./assets/box_rotate_{}.vtk", frame);
+                (String::from("box_rotate"), frame, PathBuf::from(path))
+            })
+            .collect();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+
+        let meshes = load_and_clean_meshes(mesh_meta, LoadConfig::default(), attrib_config);
+
+        let artifact = "./tests/artifacts/in_memory_test.glb";
+        let config = ExportConfig::builder().output(artifact).quiet(true).build();
+        let (document, data) = build_gltf(meshes, config).unwrap();
+        let root = document.root;
+
+        assert_eq!(root.meshes.len(), 1);
+        assert_eq!(root.animations.len(), 1);
+        assert_eq!(root.buffers.len(), 1);
+        assert_eq!(root.buffers[0].byte_length.0 as usize, data.len());
+        assert!(root.buffers[0].uri.is_none());
+        assert!(!Path::new(artifact).exists());
+    }
+
+    #[test]
+    fn export_report() {
+        let mesh_meta = (1..=3)
+            .map(|frame| {
+                let path = format!("./assets/box_rotate_{}.vtk", frame);
+                (String::from("box_rotate"), frame, PathBuf::from(path))
+            })
+            .collect();
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let meshes = load_and_clean_meshes(mesh_meta, LoadConfig::default(), attrib_config);
+
+        let artifact = "./tests/artifacts/export_report_test.glb";
+        let config = ExportConfig::builder()
+            .output(artifact)
+            .textures(vec![TextureInfo {
+                image: ImageInfo::Embed(String::from("./assets/missing.png")),
+                ..Default::default()
+            }])
+            .quiet(true)
+            .build();
+        let report = export_clean_meshes(meshes, config).unwrap();
+
+        assert_eq!(report.output, PathBuf::from(artifact));
+        assert_eq!(report.dropped_textures, vec!["./assets/missing.png"]);
+        assert!(report.skipped_attributes.is_empty());
+        assert_eq!(report.nodes, 1);
+        assert_eq!(report.primitives, 1);
+        assert!(report.accessors > 0);
+        assert_eq!(
+            report.file_bytes,
+            std::fs::metadata(artifact).unwrap().len()
+        );
+        assert!(report.file_bytes > report.buffer_bytes as u64);
+    }
+
+    #[test]
+    fn max_morph_targets() {
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .max_morph_targets(Some(4))
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=10 {
+            let mesh = Mesh::from(meshx::TriMesh::new(
+                vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            ));
+            sequence.add_frame("tri", frame, mesh).unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        // Nine morph targets are split into segments sharing the frame at which they are swapped.
+        let num_targets: Vec<_> = root
+            .meshes
+            .iter()
+            .map(|mesh| mesh.primitives[0].targets.as_ref().unwrap().len())
+            .collect();
+        assert_eq!(num_targets, vec![4, 4, 3]);
+        assert!(root
+            .nodes
+            .iter()
+            .all(|node| node.name.as_deref() == Some("tri")));
+
+        let scale_keys = |node: u32| {
+            let animation = &root.animations[0];
+            let channel = animation
+                .channels
+                .iter()
+                .find(|channel| {
+                    channel.target.node.value() == node as usize
+                        && channel.target.path
+                            == gltf::json::validation::Checked::Valid(
+                                gltf::json::animation::Property::Scale,
+                            )
+                })
+                .unwrap();
+            let sampler = &animation.samplers[channel.sampler.value()];
+            let input = &root.accessors[sampler.input.value()];
+            let time = |value: &Option<serde_json::Value>| value.as_ref().unwrap()[0].as_f64();
+            (time(&input.min), time(&input.max), input.count.0)
+        };
+        let time = |frame: u32| Some((frame as f32 * (1.0f32 / 24.0)) as f64);
+        assert_eq!(scale_keys(0), (time(1), time(5), 2));
+        assert_eq!(scale_keys(1), (time(0), time(8), 3));
+        assert_eq!(scale_keys(2), (time(0), time(8), 2));
+    }
+
+    #[test]
+    fn shared_morph_targets() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::FaceIndex;
+
+        // A unit cube with two materials, which grows over three frames.
+        let (positions, tris) = proxy::box_mesh([0.0; 3], [1.0; 3]);
+        let tris: Vec<[usize; 3]> = tris.iter().map(|t| t.map(|v| v as usize)).collect();
+        let mtl_ids: Vec<u32> = (0..tris.len())
+            .map(|t| (2 * t / tris.len()) as u32)
+            .collect();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder().quiet(true).build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=3 {
+            let scaled = positions
+                .iter()
+                .map(|p| p.map(|x| x * frame as f32))
+                .collect();
+            let mut trimesh = meshx::TriMesh::new(scaled, tris.clone());
+            trimesh
+                .insert_attrib_data::<u32, FaceIndex>("mtl_id", mtl_ids.clone())
+                .unwrap();
+            sequence
+                .add_frame("cube", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let mut root = sequence.build().unwrap().0.root;
+
+        // Both primitives reference the same morph target accessors.
+        let mesh = &root.meshes[0];
+        assert_eq!(mesh.primitives.len(), 2);
+        let target_positions: Vec<_> = mesh
+            .primitives
+            .iter()
+            .map(|prim| {
+                let targets = prim.targets.as_ref().unwrap();
+                targets.iter().map(|t| t.positions).collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(target_positions[0].len(), 2);
+        assert_eq!(target_positions[0], target_positions[1]);
+        assert!(validate::mismatched_morph_targets(&root).is_empty());
+
+        root.meshes[0].primitives[1].targets.as_mut().unwrap().pop();
+        assert_eq!(validate::mismatched_morph_targets(&root), vec![0]);
+    }
+
+    #[test]
+    fn buffer_views_are_aligned() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::VertexIndex;
+
+        let attributes: AttributeInfo = r#"{"flag": U8}"#.parse().unwrap();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder().quiet(true).build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=2 {
+            // An odd number of single byte values leaves the buffer unaligned.
+            let mut trimesh = meshx::TriMesh::new(
+                vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            );
+            trimesh
+                .insert_attrib_data::<u8, VertexIndex>("flag", vec![1, 2, 3])
+                .unwrap();
+            sequence
+                .add_frame("tri", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+        assert!(root
+            .accessors
+            .iter()
+            .any(|acc| acc.name.as_deref() == Some("flag")));
+        assert!(validate::misaligned_accessors(&root).is_empty());
+        assert!(root
+            .buffer_views
+            .iter()
+            .all(|view| view.byte_offset.unwrap().0 % 4 == 0));
+    }
+
+    #[test]
+    fn recomputed_normals() {
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .recompute_normals(true)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        // The triangle tilts from the XY plane into the XZ plane.
+        for (frame, tip) in [(1, [0.0, 1.0, 0.0]), (2, [0.0, 0.0, 1.0])] {
+            let mesh = Mesh::from(meshx::TriMesh::new(
+                vec![[0.0; 3], [1.0, 0.0, 0.0], tip],
+                vec![[0, 1, 2]],
+            ));
+            sequence.add_frame("tri", frame, mesh).unwrap();
+        }
+        let (document, data) = sequence.build().unwrap();
+        let root = document.root;
+
+        let read_vec3 = |acc: &gltf::json::Index<gltf::json::Accessor>| {
+            let acc = &root.accessors[acc.value()];
+            let view = &root.buffer_views[acc.buffer_view.unwrap().value()];
+            let offset = view.byte_offset.map_or(0, |o| o.0 as usize);
+            data[offset..offset + acc.count.0 as usize * 12]
+                .chunks(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        let prim = &root.meshes[0].primitives[0];
+        let normals = &prim.attributes
+            [&gltf::json::validation::Checked::Valid(gltf::json::mesh::Semantic::Normals)];
+        assert_eq!(read_vec3(normals), [0.0f32, 0.0, 1.0].repeat(3));
+        let target = &prim.targets.as_ref().unwrap()[0];
+        assert_eq!(
+            read_vec3(target.normals.as_ref().unwrap()),
+            [0.0f32, -1.0, -1.0].repeat(3)
+        );
+    }
+
+    #[test]
+    fn recomputed_tangents() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::VertexIndex;
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords: TextureAttributeInfo = "{\"uv\": f32}".parse().unwrap();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .recompute_normals(true)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        // The triangle rotates by 90 degrees about the Z axis.
+        for (frame, u, v) in [
+            (1, [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            (2, [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+        ] {
+            let mut trimesh = meshx::TriMesh::new(vec![[0.0; 3], u, v], vec![[0, 1, 2]]);
+            trimesh
+                .insert_attrib_data::<[f32; 2], VertexIndex>(
+                    "uv",
+                    vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+                )
+                .unwrap();
+            sequence
+                .add_frame("tri", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let (document, data) = sequence.build().unwrap();
+        let root = document.root;
+
+        let read_vec3 = |acc: &gltf::json::Index<gltf::json::Accessor>| {
+            let acc = &root.accessors[acc.value()];
+            let view = &root.buffer_views[acc.buffer_view.unwrap().value()];
+            let offset = view.byte_offset.map_or(0, |o| o.0 as usize);
+            data[offset..offset + acc.count.0 as usize * 12]
+                .chunks(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        let prim = &root.meshes[0].primitives[0];
+        let tangents = &prim.attributes
+            [&gltf::json::validation::Checked::Valid(gltf::json::mesh::Semantic::Tangents)];
+        // Tangents follow the U direction of the texture coordinates.
+        assert_eq!(read_vec3(tangents), [1.0f32, 0.0, 0.0].repeat(3));
+        let target = &prim.targets.as_ref().unwrap()[0];
+        assert_eq!(
+            read_vec3(target.tangents.as_ref().unwrap()),
+            [-1.0f32, 1.0, 0.0].repeat(3)
+        );
+    }
+
+    #[test]
+    fn frame_gap_policy() {
+        use crate::discover::FrameGap;
+
+        assert!(find_frame_gaps(&[1, 2, 3]).is_empty());
+        assert_eq!(
+            find_frame_gaps(&[0, 10, 20, 40]),
+            vec![FrameGap {
+                last: 20,
+                next: 40,
+                spacing: 10
+            }]
+        );
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let sequence = |policy| {
+            let config = ExportConfig::builder()
+                .output("./tests/artifacts/frame_gap_policy.glb")
+                .gap_policy(policy)
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            // Frame 3 is missing.
+            for frame in [1, 2, 4] {
+                let mesh = Mesh::from(meshx::TriMesh::new(
+                    vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                    vec![[0, 1, 2]],
+                ));
+                sequence.add_frame("tri", frame, mesh).unwrap();
+            }
+            sequence
+        };
+        let build = |policy| sequence(policy).build();
+        let num_keyframes = |root: &gltf::json::Root| {
+            let input = root.animations[0].samplers[0].input;
+            root.accessors[input.value()].count.0
+        };
+
+        assert!(matches!(
+            build(GapPolicy::Error),
+            Err(Error::FrameGap(_, 2, 4))
+        ));
+        let (root, _) = build(GapPolicy::Interpolate).unwrap();
+        assert_eq!(num_keyframes(&root), 3);
+        let (root, _) = build(GapPolicy::Hold).unwrap();
+        assert_eq!(num_keyframes(&root), 4);
+        // Holding frames repeats weights without adding morph targets.
+        assert_eq!(
+            root.meshes[0].primitives[0].targets.as_ref().unwrap().len(),
+            2
+        );
+
+        // Warnings are counted for each export separately.
+        assert_eq!(sequence(GapPolicy::Warn).finish().unwrap().warnings, 1);
+        assert_eq!(
+            sequence(GapPolicy::Interpolate).finish().unwrap().warnings,
+            0
+        );
+    }
+
+    #[test]
+    fn write_atomic_no_replace() {
+        use std::io::Write;
+
+        let dir = PathBuf::from("./tests/artifacts/write_atomic");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.bin");
+        std::fs::write(&path, b"existing").unwrap();
+
+        let err = write_atomic(&path, false, |writer| writer.write_all(b"new")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read(&path).unwrap(), b"existing");
+
+        write_atomic(&path, true, |writer| writer.write_all(b"new")).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+
+        let other = dir.join("other.bin");
+        write_atomic(&other, false, |writer| writer.write_all(b"other")).unwrap();
+        assert_eq!(std::fs::read(&other).unwrap(), b"other");
+
+        // No temporary files are left behind.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    }
 }
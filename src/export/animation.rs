@@ -1,26 +1,35 @@
+use crate::config::AnimationGrouping;
 use crate::config::TIME_ATTRIB_NAME;
 use crate::config::WEIGHTS_ATTRIB_NAME;
 use crate::config::{
-    NORMAL_DISPLACEMENT_ATTRIB_NAME, POSITION_DISPLACEMENT_ATTRIB_NAME,
-    TANGENT_DISPLACEMENT_ATTRIB_NAME,
+    COLOR_DISPLACEMENT_ATTRIB_NAME, NORMAL_DISPLACEMENT_ATTRIB_NAME,
+    POSITION_DISPLACEMENT_ATTRIB_NAME, TANGENT_DISPLACEMENT_ATTRIB_NAME,
 };
 
-use super::build_buffer_vec3;
+use super::animation_pointer::PointerSampler;
 use super::builders::*;
-use super::Morph;
+use super::{build_buffer_vec3, build_buffer_vec4};
+use super::{Morph, PointerChannel};
+use crate::progress::Stage;
 use byteorder::{WriteBytesExt, LE};
 use gltf::json;
-use indicatif::ProgressBar;
+use indexmap::IndexMap;
 use json::accessor::ComponentType as GltfComponentType;
 use json::validation::Checked::Valid;
 use std::mem;
 
+/// A morph target along with the accessor of its color deltas, if any.
+///
+/// Color deltas can't be represented by [`json::mesh::MorphTarget`], so they are
+/// attached to the primitive separately (see [`super::target_colors`]).
+pub(crate) type Target = (json::mesh::MorphTarget, Option<json::Index<json::Accessor>>);
+
 pub(crate) fn build_morph_target(
     morph: &Morph,
     accessors: &mut Vec<json::Accessor>,
     buffer_views: &mut Vec<json::buffer::View>,
     data: &mut Vec<u8>,
-) -> json::mesh::MorphTarget {
+) -> Target {
     let disp_acc_index = build_buffer_vec3(
         &morph.position_disp,
         accessors,
@@ -43,11 +52,20 @@ pub(crate) fn build_morph_target(
         TANGENT_DISPLACEMENT_ATTRIB_NAME,
     );
 
-    json::mesh::MorphTarget {
+    let color_disp_acc_index = build_buffer_vec4(
+        &morph.color_disp,
+        accessors,
+        buffer_views,
+        data,
+        COLOR_DISPLACEMENT_ATTRIB_NAME,
+    );
+
+    let target = json::mesh::MorphTarget {
         positions: disp_acc_index,
         normals: normal_disp_acc_index,
         tangents: tangent_disp_acc_index,
-    }
+    };
+    (target, color_disp_acc_index)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -60,11 +78,12 @@ pub(crate) fn build_animation(
     data: &mut Vec<u8>,
     time_step: f32,
     insert_vanishing_frames: bool,
-    pb: &ProgressBar,
+    holds: &[u32],
+    stage: &Stage,
 ) -> Option<(
     json::animation::Channel,
     json::animation::Sampler,
-    Vec<json::mesh::MorphTarget>,
+    Vec<Target>,
 )> {
     if morphs.is_empty() {
         return None;
@@ -72,36 +91,57 @@ pub(crate) fn build_animation(
 
     let mut targets = Vec::new();
 
-    // Initialize animation frames
-    let num_animation_frames = morphs.len() + 1;
-
-    // Sparse weight indices
-    let byte_length = morphs.len() * mem::size_of::<u32>();
-    let weight_indices_view = json::buffer::View::new(byte_length, data.len());
-
+    // Keyframes given by their frame and the morph target with a non-zero weight.
+    let mut keyframes: Vec<(u32, Option<usize>)> = Vec::with_capacity(morphs.len() + 1);
     let mut first_morph = 0;
     if insert_vanishing_frames {
         // First frame is vanishing, second is the actual first frame of the animation.
         // We need to order the weights so the frames are in order.
-        data.write_u32::<LE>(0u32).unwrap();
+        keyframes.push((morphs[0].frame, Some(0)));
         first_morph = 1;
     }
     // Note: first frame is all zeros
-    for i in first_morph..morphs.len() {
-        // all frames but first have a non-zero weight
-        let index = morphs.len() * (i + 1) + i;
-        data.write_u32::<LE>(index as u32).unwrap();
+    keyframes.push((first_frame, None));
+    keyframes.extend((first_morph..morphs.len()).map(|i| (morphs[i].frame, Some(i))));
+
+    // Held frames repeat the weights of the preceding keyframe.
+    for &hold in holds {
+        let pos = keyframes.partition_point(|&(frame, _)| frame <= hold);
+        if pos > 0 && keyframes[pos - 1].0 != hold {
+            keyframes.insert(pos, (hold, keyframes[pos - 1].1));
+        }
+    }
+
+    // Initialize animation frames
+    let num_animation_frames = keyframes.len();
+    let num_weights = keyframes
+        .iter()
+        .filter(|(_, morph)| morph.is_some())
+        .count();
+
+    // Sparse weight indices
+    let byte_length = num_weights * mem::size_of::<u32>();
+    align_data(data);
+    let weight_indices_view = json::buffer::View::new(byte_length, data.len());
+
+    for (key, &(_, morph)) in keyframes.iter().enumerate() {
+        if let Some(i) = morph {
+            // all frames but first have a non-zero weight
+            let index = morphs.len() * key + i;
+            data.write_u32::<LE>(index as u32).unwrap();
+        }
     }
     let weight_indices_view_index = buffer_views.len();
     buffer_views.push(weight_indices_view);
 
     // Output animation frames as weights
-    let weight_view = json::buffer::View::new(morphs.len() * mem::size_of::<f32>(), data.len());
+    align_data(data);
+    let weight_view = json::buffer::View::new(num_weights * mem::size_of::<f32>(), data.len());
 
     let weight_view_index = buffer_views.len();
     buffer_views.push(weight_view);
 
-    for _ in 0..morphs.len() {
+    for _ in 0..num_weights {
         data.write_f32::<LE>(1.0).unwrap();
     }
 
@@ -110,31 +150,22 @@ pub(crate) fn build_animation(
         json::Accessor::new(num_animation_frames * morphs.len(), GltfComponentType::F32)
             .with_name(WEIGHTS_ATTRIB_NAME.to_string())
             .with_min_max(&[0.0][..], &[1.0][..])
-            .with_sparse(morphs.len(), weight_indices_view_index, weight_view_index);
+            .with_sparse(num_weights, weight_indices_view_index, weight_view_index);
 
     let weights_acc_index = accessors.len() as u32;
     accessors.push(weights_acc);
 
     // Animation keyframe times
     let byte_length = num_animation_frames * mem::size_of::<f32>();
+    align_data(data);
     let time_view = json::buffer::View::new(byte_length, data.len());
 
     let mut min_time = first_frame as f32 * time_step;
     let mut max_time = first_frame as f32 * time_step;
-    if insert_vanishing_frames {
-        data.write_f32::<LE>(morphs[0].frame as f32 * time_step)
-            .unwrap();
-    }
-    data.write_f32::<LE>(first_frame as f32 * time_step)
-        .unwrap();
-    for Morph { frame, .. } in morphs.iter() {
-        let time = *frame as f32 * time_step;
+    for &(frame, _) in keyframes.iter() {
+        let time = frame as f32 * time_step;
         min_time = min_time.min(time);
         max_time = max_time.max(time);
-        if insert_vanishing_frames && frame == &morphs[0].frame {
-            // Skip the first vanishing frame frame, since it was already inserted above.
-            continue;
-        }
         data.write_f32::<LE>(time).unwrap();
     }
     let time_view_index = buffer_views.len();
@@ -149,7 +180,7 @@ pub(crate) fn build_animation(
     accessors.push(time_acc);
 
     for morph in morphs.iter() {
-        pb.tick();
+        stage.inc(1);
         targets.push(build_morph_target(morph, accessors, buffer_views, data));
     }
 
@@ -176,3 +207,154 @@ pub(crate) fn build_animation(
 
     Some((channel, sampler, targets))
 }
+
+/// Splits animation channels into animations according to `grouping`.
+///
+/// Each channel is expected to use the sampler at the same index, and
+/// `targets` holds the name of the node animated by each channel. Animations
+/// are ordered by the first node they animate. Pointer samplers are added to
+/// the animation of their group, and their channels are returned separately.
+pub(crate) fn group_animations(
+    channels: Vec<json::animation::Channel>,
+    samplers: Vec<json::animation::Sampler>,
+    targets: &[String],
+    pointer_samplers: &[PointerSampler],
+    grouping: &AnimationGrouping,
+) -> (Vec<json::Animation>, Vec<PointerChannel>) {
+    let new_animation = |name: Option<&str>| json::Animation {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: name.filter(|name| !name.is_empty()).map(String::from),
+        channels: Vec::new(),
+        samplers: Vec::new(),
+    };
+    let mut groups: IndexMap<Option<&str>, json::Animation> = IndexMap::new();
+    for ((mut channel, sampler), target) in channels.into_iter().zip(samplers).zip(targets) {
+        let name = grouping.group(target);
+        let animation = groups.entry(name).or_insert_with(|| new_animation(name));
+        channel.sampler = json::Index::new(animation.samplers.len() as u32);
+        animation.channels.push(channel);
+        animation.samplers.push(sampler);
+    }
+    let mut pointer_channels = Vec::with_capacity(pointer_samplers.len());
+    for PointerSampler {
+        pointer,
+        group,
+        sampler,
+    } in pointer_samplers
+    {
+        let name = grouping.group(group);
+        let entry = groups.entry(name);
+        let animation_index = entry.index();
+        let animation = entry.or_insert_with(|| new_animation(name));
+        pointer_channels.push(PointerChannel {
+            animation: animation_index,
+            sampler: animation.samplers.len(),
+            pointer: pointer.clone(),
+        });
+        animation.samplers.push(sampler.clone());
+    }
+    (groups.into_values().collect(), pointer_channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Dcc;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn animation_groups() {
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let build = |grouping: &str| {
+            let config = ExportConfig::builder()
+                .animation_groups(grouping.parse().unwrap())
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            for name in ["cloth", "debris", "fluid"] {
+                for frame in 1..=2 {
+                    let mesh = Mesh::from(meshx::TriMesh::new(
+                        vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                        vec![[0, 1, 2]],
+                    ));
+                    sequence.add_frame(name, frame, mesh).unwrap();
+                }
+            }
+            let root = sequence.build().unwrap().0.root;
+            root.animations
+                .iter()
+                .map(|anim| (anim.name.clone(), anim.channels.len()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(AnimationGrouping::default(), AnimationGrouping::Single);
+        assert_eq!(build("single"), vec![(None, 3)]);
+        assert_eq!(
+            build("by_name"),
+            vec![
+                (Some("cloth".to_string()), 1),
+                (Some("debris".to_string()), 1),
+                (Some("fluid".to_string()), 1),
+            ]
+        );
+        assert_eq!(
+            build(r#"groups({"cloth": "sim", "fluid": "sim"})"#),
+            vec![
+                (Some("sim".to_string()), 2),
+                (Some("debris".to_string()), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn dcc_blender() {
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .dcc(Some(Dcc::Blender))
+            .dcc_max_targets(3)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=10 {
+            let mesh = Mesh::from(meshx::TriMesh::new(
+                vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            ));
+            sequence.add_frame("tri", frame, mesh).unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        let mesh = &root.meshes[0];
+        assert_eq!(mesh.primitives[0].targets.as_ref().unwrap().len(), 3);
+        let extras: serde_json::Value =
+            serde_json::from_str(mesh.extras.as_ref().unwrap().get()).unwrap();
+        assert_eq!(
+            extras["targetNames"],
+            serde_json::json!(["frame_2", "frame_6", "frame_10"])
+        );
+        assert_eq!(root.animations[0].name.as_deref(), Some("tri"));
+        assert_eq!(
+            root.animations[0].samplers[0].interpolation,
+            gltf::json::validation::Checked::Valid(gltf::json::animation::Interpolation::Step)
+        );
+    }
+}
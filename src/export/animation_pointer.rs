@@ -0,0 +1,253 @@
+//! Animation of material and light properties with KHR_animation_pointer.
+//!
+//! [`json::animation::Target`] can only refer to node properties, so channels
+//! animating other properties are kept alongside the root in
+//! [`super::Document::pointer_channels`].
+
+use std::mem;
+
+use byteorder::{WriteBytesExt, LE};
+use gltf::json;
+use json::accessor::ComponentType as GltfComponentType;
+use json::accessor::Type as GltfType;
+use json::validation::Checked::Valid;
+
+use super::builders::*;
+use crate::config::TIME_ATTRIB_NAME;
+use crate::PointerAnimationInfo;
+
+/// Name of the extension used to animate arbitrary properties.
+pub const KHR_ANIMATION_POINTER: &str = "KHR_animation_pointer";
+
+/// A sampler animating the property at `pointer` in the animation of `group`.
+pub(crate) struct PointerSampler {
+    pub pointer: String,
+    pub group: String,
+    pub sampler: json::animation::Sampler,
+}
+
+/// Builds the sampler interpolating the values of the given animated property.
+///
+/// Returns an error message if the property has no values or if values
+/// differ in their number of components.
+pub(crate) fn build_pointer_sampler(
+    info: &PointerAnimationInfo,
+    time_step: f32,
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+) -> Result<json::animation::Sampler, String> {
+    let num_components = info
+        .values
+        .values()
+        .next()
+        .map(|value| value.components().len())
+        .ok_or_else(|| "no values given".to_string())?;
+    let type_ = match num_components {
+        1 => GltfType::Scalar,
+        2 => GltfType::Vec2,
+        3 => GltfType::Vec3,
+        4 => GltfType::Vec4,
+        n => return Err(format!("values with {} components are not supported", n)),
+    };
+    if let Some((frame, _)) = info
+        .values
+        .iter()
+        .find(|(_, value)| value.components().len() != num_components)
+    {
+        return Err(format!(
+            "value at frame {} does not have {} components",
+            frame, num_components
+        ));
+    }
+
+    let times: Vec<f32> = info
+        .values
+        .keys()
+        .map(|&frame| frame as f32 * time_step)
+        .collect();
+    align_data(data);
+    let time_view = json::buffer::View::new(mem::size_of_val(times.as_slice()), data.len());
+    let time_view_index = buffer_views.len();
+    buffer_views.push(time_view);
+    for &time in times.iter() {
+        data.write_f32::<LE>(time).unwrap();
+    }
+    let time_acc = json::Accessor::new(times.len(), GltfComponentType::F32)
+        .with_name(TIME_ATTRIB_NAME.to_string())
+        .with_buffer_view(time_view_index)
+        .with_min_max(&times[..1], &times[times.len() - 1..]);
+    let time_acc_index = json::Index::new(accessors.len() as u32);
+    accessors.push(time_acc);
+
+    align_data(data);
+    let value_view = json::buffer::View::new(
+        times.len() * num_components * mem::size_of::<f32>(),
+        data.len(),
+    );
+    let value_view_index = buffer_views.len();
+    buffer_views.push(value_view);
+    for value in info.values.values() {
+        for &x in value.components() {
+            data.write_f32::<LE>(x).unwrap();
+        }
+    }
+    let value_acc = json::Accessor::new(times.len(), GltfComponentType::F32)
+        .with_name(info.pointer.clone())
+        .with_buffer_view(value_view_index)
+        .with_type(type_);
+    let value_acc_index = json::Index::new(accessors.len() as u32);
+    accessors.push(value_acc);
+
+    let interpolation = if info.step {
+        json::animation::Interpolation::Step
+    } else {
+        json::animation::Interpolation::Linear
+    };
+    Ok(json::animation::Sampler {
+        input: time_acc_index,
+        interpolation: Valid(interpolation),
+        output: value_acc_index,
+        extensions: Default::default(),
+        extras: Default::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{build_gltf, to_glb, ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{
+        load_and_clean_meshes, AttribConfig, AttributeInfo, LoadConfig, TextureAttributeInfo,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn pointer_animations() {
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let pointer_animations = vec![
+            "(pointer:\"/materials/lava/emissiveFactor\", values:{1:[0.0, 0.0, 0.0], 3:[1.0, 0.4, 0.0]})"
+                .parse()
+                .unwrap(),
+            "(pointer:\"/materials/0/pbrMetallicRoughness/metallicFactor\", values:{1:0.0, 3:1}, step:true)"
+                .parse()
+                .unwrap(),
+            "(pointer:\"/materials/missing/emissiveFactor\", values:{1:[0.0, 0.0, 0.0]})"
+                .parse()
+                .unwrap(),
+        ];
+        let config = ExportConfig::builder()
+            .materials(vec!["(name:\"lava\")".parse().unwrap()])
+            .pointer_animations(pointer_animations)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=3 {
+            let mesh = Mesh::from(meshx::TriMesh::new(
+                vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            ));
+            sequence.add_frame("tri", frame, mesh).unwrap();
+        }
+        let (document, data) = sequence.build().unwrap();
+        assert!(document
+            .root
+            .extensions_used
+            .iter()
+            .any(|ext| ext == KHR_ANIMATION_POINTER));
+        // Only the morph target weights channel targets a node.
+        assert_eq!(document.root.animations[0].channels.len(), 1);
+        assert_eq!(document.root.animations[0].samplers.len(), 3);
+        assert_eq!(document.pointer_channels.len(), 2);
+
+        // Pointer channels are written with KHR_animation_pointer targets.
+        let glb = to_glb(&document, data).unwrap();
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        let channels = json["animations"][0]["channels"].as_array().unwrap();
+        // The morph target weights channel and two resolved pointers; the missing material is skipped.
+        assert_eq!(channels.len(), 3);
+        let pointers: Vec<_> = channels[1..]
+            .iter()
+            .map(|channel| {
+                assert_eq!(channel["target"]["path"], "pointer");
+                assert!(channel.get("extras").is_none());
+                channel["target"]["extensions"]["KHR_animation_pointer"]["pointer"].clone()
+            })
+            .collect();
+        assert_eq!(
+            pointers,
+            vec![
+                serde_json::json!("/materials/0/emissiveFactor"),
+                serde_json::json!("/materials/0/pbrMetallicRoughness/metallicFactor"),
+            ]
+        );
+        let samplers = &json["animations"][0]["samplers"];
+        let output = |channel: &serde_json::Value| {
+            let sampler = &samplers[channel["sampler"].as_u64().unwrap() as usize];
+            &json["accessors"][sampler["output"].as_u64().unwrap() as usize]["type"]
+        };
+        assert_eq!(output(&channels[1]), "VEC3");
+        assert_eq!(output(&channels[2]), "SCALAR");
+        assert_eq!(
+            samplers[channels[2]["sampler"].as_u64().unwrap() as usize]["interpolation"],
+            "STEP"
+        );
+    }
+
+    #[test]
+    fn pointer_channels_target_no_nodes() {
+        let mesh_meta = (1..=3)
+            .map(|frame| {
+                let path = format!("./assets/box_rotate_{}.vtk", frame);
+                (String::from("box_rotate"), frame, PathBuf::from(path))
+            })
+            .collect();
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let meshes = load_and_clean_meshes(mesh_meta, LoadConfig::default(), attrib_config);
+        let config = ExportConfig::builder()
+            .materials(vec!["(name:\"lava\")".parse().unwrap()])
+            .pointer_animations(vec![
+                "(pointer:\"/materials/lava/emissiveFactor\", values:{1:[0.0, 0.0, 0.0], 3:[1.0, 0.4, 0.0]})"
+                    .parse()
+                    .unwrap(),
+            ])
+            .quiet(true)
+            .build();
+        let (document, data) = build_gltf(meshes, config).unwrap();
+
+        let translation = Valid(json::animation::Property::Translation);
+        assert!(document
+            .root
+            .animations
+            .iter()
+            .flat_map(|animation| animation.channels.iter())
+            .all(|channel| channel.target.path != translation));
+        assert_eq!(document.pointer_channels.len(), 1);
+        let channel = &document.pointer_channels[0];
+        assert_eq!(channel.pointer, "/materials/0/emissiveFactor");
+        let sampler = &document.root.animations[channel.animation].samplers[channel.sampler];
+        assert_eq!(
+            document.root.accessors[sampler.output.value()].type_,
+            Valid(GltfType::Vec3)
+        );
+        assert!(crate::validate::validate(&document.root, &[&data]).is_empty());
+    }
+}
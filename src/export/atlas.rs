@@ -0,0 +1,521 @@
+//! Packing of base color textures into a single texture atlas.
+//!
+//! Meshes assembled from many small parts, like OBJ sequences with a texture
+//! per part, produce a material and an image per part, each costing a draw
+//! call in real-time engines. Embedded PNG images of base color textures are
+//! packed into one atlas here, texture coordinates of the primitives sampling
+//! them are moved into their region of the atlas, and materials that become
+//! identical apart from their names are merged.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use byteorder::{ByteOrder, LE};
+use gltf::json;
+use json::accessor::ComponentType as GltfComponentType;
+use json::accessor::Type as GltfType;
+use json::validation::{Checked::Valid, USize64};
+
+use super::builders::*;
+use super::KHR_ANIMATION_POINTER;
+use crate::merge::{dedup_by_key, remap, remap_buffer_views, to_json_key};
+use crate::Error;
+
+/// Pixels of edge color repeated around each image so that filtering doesn't
+/// blend in neighbouring images.
+const PADDING: u32 = 2;
+
+/// Texture coordinates this far outside the unit square are considered to
+/// repeat the texture, which can't be done within an atlas.
+const UV_TOLERANCE: f32 = 1e-4;
+
+/// Region of an image in the atlas in pixels, excluding padding.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Decoded RGBA image.
+struct Pixels {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+/// Bytes of the given buffer view.
+fn view_bytes<'a>(root: &json::Root, data: &'a [u8], view: usize) -> Option<&'a [u8]> {
+    let view = &root.buffer_views[view];
+    if view.buffer.value() != 0 {
+        return None;
+    }
+    let start = view.byte_offset.map_or(0, |USize64(o)| o) as usize;
+    data.get(start..start + view.byte_length.0 as usize)
+}
+
+/// Decodes a PNG image into 8 bit RGBA pixels.
+fn decode_png(bytes: &[u8]) -> Option<Pixels> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    let channels = reader.output_color_type().0.samples();
+    let mut buf = vec![0; width as usize * height as usize * channels];
+    reader.next_frame(&mut buf).ok()?;
+    let rgba = buf
+        .chunks_exact(channels)
+        .flat_map(|px| match *px {
+            [l] => [l, l, l, 255],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => [0, 0, 0, 255],
+        })
+        .collect();
+    Some(Pixels {
+        width,
+        height,
+        rgba,
+    })
+}
+
+/// Reads the texture coordinates of the given accessor.
+///
+/// Returns `None` unless the accessor holds plain 2D float vectors.
+fn read_uvs(root: &json::Root, data: &[u8], accessor: usize) -> Option<Vec<[f32; 2]>> {
+    let acc = &root.accessors[accessor];
+    let is_f32 =
+        acc.component_type == Valid(json::accessor::GenericComponentType(GltfComponentType::F32));
+    if !is_f32 || acc.type_ != Valid(GltfType::Vec2) || acc.sparse.is_some() {
+        return None;
+    }
+    let view = acc.buffer_view?.value();
+    let stride = root.buffer_views[view]
+        .byte_stride
+        .map_or(8, |stride| stride.0);
+    let bytes = view_bytes(root, data, view)?;
+    let offset = acc.byte_offset.map_or(0, |USize64(o)| o) as usize;
+    (0..acc.count.0 as usize)
+        .map(|i| {
+            let uv = bytes.get(offset + i * stride..offset + i * stride + 8)?;
+            Some([LE::read_f32(&uv[..4]), LE::read_f32(&uv[4..])])
+        })
+        .collect()
+}
+
+/// Packs the given image sizes into rows of an atlas at most `max_size` pixels wide and tall.
+///
+/// Returns the width and height of the atlas with the region of each image,
+/// or `None` for images that didn't fit.
+fn pack(sizes: &[(u32, u32)], max_size: u32) -> (u32, u32, Vec<Option<Rect>>) {
+    let padded = |size: u32| size + 2 * PADDING;
+    let area: u64 = sizes
+        .iter()
+        .map(|&(w, h)| u64::from(padded(w)) * u64::from(padded(h)))
+        .sum();
+    let widest = sizes.iter().map(|&(w, _)| padded(w)).max().unwrap_or(1);
+    let width = ((area as f64).sqrt().ceil() as u32)
+        .next_power_of_two()
+        .max(widest)
+        .min(max_size);
+
+    // Place the tallest images first, filling rows left to right.
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+    let mut rects = vec![None; sizes.len()];
+    let (mut x, mut y, mut row_height, mut height) = (0, 0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if padded(w) > width {
+            continue;
+        }
+        if x + padded(w) > width {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+        if y + padded(h) > max_size {
+            continue;
+        }
+        rects[i] = Some(Rect {
+            x: x + PADDING,
+            y: y + PADDING,
+            width: w,
+            height: h,
+        });
+        x += padded(w);
+        row_height = row_height.max(padded(h));
+        height = height.max(y + row_height);
+    }
+    (width, height, rects)
+}
+
+/// Packs embedded PNG base color textures into a single atlas no larger than
+/// `max_size` pixels along each side.
+///
+/// Textures are only packed if they are sampled with the same sampler, are not
+/// used for anything other than base colors, and all texture coordinates
+/// sampling them lie within the unit square. Returns the number of packed images.
+pub(crate) fn pack_atlas(
+    root: &mut json::Root,
+    data: &mut Vec<u8>,
+    max_size: u32,
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<usize, Error> {
+    // Textures used as base colors, excluding those also used in other ways.
+    let mut candidates = vec![false; root.textures.len()];
+    let mut excluded = vec![false; root.textures.len()];
+    for mtl in root.materials.iter() {
+        let pbr = &mtl.pbr_metallic_roughness;
+        if let Some(info) = &pbr.base_color_texture {
+            candidates[info.index.value()] = true;
+        }
+        let others = [
+            pbr.metallic_roughness_texture
+                .as_ref()
+                .map(|info| info.index),
+            mtl.normal_texture.as_ref().map(|info| info.index),
+            mtl.occlusion_texture.as_ref().map(|info| info.index),
+            mtl.emissive_texture.as_ref().map(|info| info.index),
+        ];
+        for texture in others.into_iter().flatten() {
+            excluded[texture.value()] = true;
+        }
+    }
+    let first = (0..root.textures.len()).find(|&t| candidates[t] && !excluded[t]);
+    let Some(sampler) = first.map(|t| root.textures[t].sampler) else {
+        return Ok(0);
+    };
+    for (t, texture) in root.textures.iter().enumerate() {
+        if texture.sampler != sampler {
+            excluded[t] = true;
+        }
+    }
+
+    // Decode the images of the remaining textures.
+    let mut images: HashMap<usize, Pixels> = HashMap::new();
+    for t in 0..root.textures.len() {
+        if !candidates[t] || excluded[t] {
+            continue;
+        }
+        let source = root.textures[t].source.value();
+        if images.contains_key(&source) {
+            continue;
+        }
+        let image = &root.images[source];
+        let pixels = image
+            .buffer_view
+            .filter(|_| image.mime_type.as_ref().is_some_and(|m| m.0 == "image/png"))
+            .and_then(|view| view_bytes(root, data, view.value()))
+            .and_then(decode_png);
+        match pixels {
+            Some(pixels) => {
+                images.insert(source, pixels);
+            }
+            None => excluded[t] = true,
+        }
+    }
+
+    // Texture coordinate accessors sampling each texture through base colors.
+    let mut uses = Vec::new();
+    for (m, mesh) in root.meshes.iter().enumerate() {
+        for (p, prim) in mesh.primitives.iter().enumerate() {
+            let Some(info) = prim.material.and_then(|mtl| {
+                root.materials[mtl.value()]
+                    .pbr_metallic_roughness
+                    .base_color_texture
+                    .as_ref()
+            }) else {
+                continue;
+            };
+            let t = info.index.value();
+            if !candidates[t] || excluded[t] {
+                continue;
+            }
+            let semantic = Valid(json::mesh::Semantic::TexCoords(info.tex_coord));
+            if let Some(&accessor) = prim.attributes.get(&semantic) {
+                uses.push((m, p, semantic, accessor.value(), t));
+            }
+        }
+    }
+    let mut uvs: HashMap<usize, Vec<[f32; 2]>> = HashMap::new();
+    for &(_, _, _, accessor, t) in uses.iter() {
+        let values = uvs
+            .entry(accessor)
+            .or_insert_with(|| read_uvs(root, data, accessor).unwrap_or_default());
+        let in_range = values
+            .iter()
+            .flatten()
+            .all(|&x| (-UV_TOLERANCE..=1.0 + UV_TOLERANCE).contains(&x));
+        if !in_range || (values.is_empty() && root.accessors[accessor].count.0 > 0) {
+            log::debug!(
+                "Texture {} is sampled outside of its bounds and is kept out of the atlas",
+                t
+            );
+            excluded[t] = true;
+        }
+    }
+
+    // Images are removed once packed, so they can't be shared with textures left out.
+    let texture_sources: Vec<usize> = root.textures.iter().map(|t| t.source.value()).collect();
+    for t in 0..root.textures.len() {
+        if !candidates[t] || excluded[t] {
+            for u in 0..root.textures.len() {
+                if texture_sources[u] == texture_sources[t] {
+                    excluded[u] = true;
+                }
+            }
+        }
+    }
+
+    // Pack the images of textures that can still be atlased.
+    let packed: Vec<usize> = (0..root.textures.len())
+        .filter(|&t| candidates[t] && !excluded[t])
+        .collect();
+    let mut sources: Vec<usize> = packed
+        .iter()
+        .map(|&t| root.textures[t].source.value())
+        .collect();
+    sources.sort_unstable();
+    sources.dedup();
+    if sources.len() < 2 {
+        return Ok(0);
+    }
+    let sizes: Vec<_> = sources
+        .iter()
+        .map(|s| (images[s].width, images[s].height))
+        .collect();
+    let (width, height, rects) = pack(&sizes, max_size);
+    let mut regions = HashMap::new();
+    for (&source, rect) in sources.iter().zip(rects) {
+        match rect {
+            Some(rect) => {
+                regions.insert(source, rect);
+            }
+            None => {
+                log!(warnings; "Image {} doesn't fit into a {}x{} texture atlas. Keeping it separate...", source, max_size, max_size);
+            }
+        }
+    }
+    let packed: Vec<usize> = packed
+        .into_iter()
+        .filter(|&t| regions.contains_key(&root.textures[t].source.value()))
+        .collect();
+    if regions.len() < 2 {
+        return Ok(0);
+    }
+
+    // Copy the images into the atlas, extending their edges into the padding.
+    let mut rgba = vec![0; width as usize * height as usize * 4];
+    for (source, rect) in regions.iter() {
+        let image = &images[source];
+        for y in 0..rect.height + 2 * PADDING {
+            let src_y = y.saturating_sub(PADDING).min(image.height - 1);
+            for x in 0..rect.width + 2 * PADDING {
+                let src_x = x.saturating_sub(PADDING).min(image.width - 1);
+                let src = 4 * (src_y * image.width + src_x) as usize;
+                let dst = 4 * ((rect.y - PADDING + y) * width + rect.x - PADDING + x) as usize;
+                rgba[dst..dst + 4].copy_from_slice(&image.rgba[src..src + 4]);
+            }
+        }
+    }
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let encode = |encoder: png::Encoder<&mut Vec<u8>>| -> Result<(), png::EncodingError> {
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&rgba)?;
+        writer.finish()
+    };
+    if let Err(err) = encode(encoder) {
+        log!(warnings; "Failed to encode the texture atlas: {}. Keeping separate textures...", err);
+        return Ok(0);
+    }
+    align_data(data);
+    root.buffer_views
+        .push(json::buffer::View::new(png.len(), data.len()));
+    data.extend_from_slice(&png);
+    root.images.push(json::image::Image {
+        name: Some("atlas".to_string()),
+        buffer_view: Some(json::Index::new(root.buffer_views.len() as u32 - 1)),
+        mime_type: Some(json::image::MimeType("image/png".to_string())),
+        uri: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+    let atlas_image = root.images.len() - 1;
+
+    // Move texture coordinates into the regions of their images. Accessors
+    // also read by other primitives or textures are copied before rewriting.
+    let mut num_reads: HashMap<usize, usize> = HashMap::new();
+    for prim in root.meshes.iter().flat_map(|mesh| mesh.primitives.iter()) {
+        for accessor in prim.attributes.values() {
+            *num_reads.entry(accessor.value()).or_default() += 1;
+        }
+    }
+    let region_of = |t: usize| regions[&texture_sources[t]];
+    let mut rewritten: HashMap<(usize, usize), json::Index<json::Accessor>> = HashMap::new();
+    for &(m, p, ref semantic, accessor, t) in uses.iter() {
+        if !packed.contains(&t) {
+            continue;
+        }
+        let rect = region_of(t);
+        let key = (accessor, texture_sources[t]);
+        if let Some(&index) = rewritten.get(&key) {
+            root.meshes[m].primitives[p]
+                .attributes
+                .insert(semantic.clone(), index);
+            continue;
+        }
+        let in_place = uses
+            .iter()
+            .filter(|&&(_, _, _, a, u)| {
+                a == accessor && packed.contains(&u) && region_of(u) == rect
+            })
+            .count()
+            == num_reads[&accessor];
+        let moved: Vec<[f32; 2]> = uvs[&accessor]
+            .iter()
+            .map(|&[u, v]| {
+                [
+                    (rect.x as f32 + u * rect.width as f32) / width as f32,
+                    (rect.y as f32 + v * rect.height as f32) / height as f32,
+                ]
+            })
+            .collect();
+        align_data(data);
+        let view = json::buffer::View::new(8 * moved.len(), data.len());
+        for x in moved.iter().flatten() {
+            data.extend_from_slice(&x.to_le_bytes());
+        }
+        root.buffer_views.push(view);
+        let view = root.buffer_views.len() - 1;
+        let index = if in_place {
+            let acc = &mut root.accessors[accessor];
+            acc.buffer_view = Some(json::Index::new(view as u32));
+            acc.byte_offset = Some(0_u64.into());
+            json::Index::new(accessor as u32)
+        } else {
+            let mut acc = root.accessors[accessor].clone();
+            acc.buffer_view = Some(json::Index::new(view as u32));
+            acc.byte_offset = Some(0_u64.into());
+            root.accessors.push(acc);
+            json::Index::new(root.accessors.len() as u32 - 1)
+        };
+        if root.accessors[index.value()].min.is_some() {
+            let (min, max) = moved.iter().fold(
+                ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]),
+                |(min, max), uv| {
+                    (
+                        [min[0].min(uv[0]), min[1].min(uv[1])],
+                        [max[0].max(uv[0]), max[1].max(uv[1])],
+                    )
+                },
+            );
+            let acc = &mut root.accessors[index.value()];
+            acc.min = Some(json::Value::from(&min[..]));
+            acc.max = Some(json::Value::from(&max[..]));
+        }
+        rewritten.insert(key, index);
+        root.meshes[m].primitives[p]
+            .attributes
+            .insert(semantic.clone(), index);
+    }
+
+    // Point all packed textures at the atlas and materials at the first of them.
+    let atlas_texture = json::Index::new(packed[0] as u32);
+    for &t in packed.iter() {
+        root.textures[t].source = json::Index::new(atlas_image as u32);
+    }
+    for mtl in root.materials.iter_mut() {
+        if let Some(info) = mtl.pbr_metallic_roughness.base_color_texture.as_mut() {
+            if packed.contains(&info.index.value()) {
+                info.index = atlas_texture;
+            }
+        }
+    }
+
+    // Drop the packed images, and the data no longer referenced after rewriting.
+    let mut image_map = Vec::with_capacity(root.images.len());
+    let mut kept_images = Vec::new();
+    for (i, image) in root.images.drain(..).enumerate() {
+        image_map.push(kept_images.len() as u32);
+        if !regions.contains_key(&i) {
+            kept_images.push(image);
+        }
+    }
+    root.images = kept_images;
+    for texture in root.textures.iter_mut() {
+        remap(&mut texture.source, &image_map);
+    }
+    let mut referenced = vec![false; root.buffer_views.len()];
+    for acc in root.accessors.iter() {
+        let sparse = acc
+            .sparse
+            .iter()
+            .flat_map(|sparse| [sparse.indices.buffer_view, sparse.values.buffer_view]);
+        for view in acc.buffer_view.into_iter().chain(sparse) {
+            referenced[view.value()] = true;
+        }
+    }
+    for view in root.images.iter().filter_map(|image| image.buffer_view) {
+        referenced[view.value()] = true;
+    }
+    let mut view_map = Vec::with_capacity(root.buffer_views.len());
+    let mut kept_views = Vec::new();
+    for (view, referenced) in root.buffer_views.drain(..).zip(referenced) {
+        view_map.push(kept_views.len() as u32);
+        if referenced {
+            kept_views.push(view);
+        }
+    }
+    root.buffer_views = kept_views;
+    remap_buffer_views(root, data, &view_map);
+
+    // Merge materials that now only differ by name, unless they are animated by index.
+    if !root
+        .extensions_used
+        .iter()
+        .any(|ext| ext == KHR_ANIMATION_POINTER)
+    {
+        let material_index = Cell::new(0);
+        let material_map = dedup_by_key(&mut root.materials, |mtl| {
+            let i = material_index.get();
+            material_index.set(i + 1);
+            let atlased = mtl
+                .pbr_metallic_roughness
+                .base_color_texture
+                .as_ref()
+                .is_some_and(|info| info.index == atlas_texture);
+            // Atlased materials are keyed by their contents, all others are kept apart.
+            Ok(if atlased {
+                Err(to_json_key(&json::Material {
+                    name: None,
+                    ..mtl.clone()
+                })?)
+            } else {
+                Ok(i)
+            })
+        })?;
+        for prim in root
+            .meshes
+            .iter_mut()
+            .flat_map(|mesh| mesh.primitives.iter_mut())
+        {
+            if let Some(mtl) = prim.material.as_mut() {
+                remap(mtl, &material_map);
+            }
+        }
+    }
+
+    log::info!(
+        "Packed {} images into a {}x{} texture atlas",
+        regions.len(),
+        width,
+        height
+    );
+    Ok(regions.len())
+}
@@ -4,13 +4,25 @@
 //! Some of these may be obsolete when the gltf crate is updated.
 
 use crate::attrib::*;
+use crate::Error;
 use gltf::json;
 use json::accessor::ComponentType as GltfComponentType;
 use json::accessor::Type as GltfType;
 
-use byteorder::{WriteBytesExt, LE};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 use json::validation::Checked::Valid;
 
+/// Alignment in bytes of the start of every buffer view.
+///
+/// This is the size of the largest component type, so accessors starting at
+/// the beginning of a view are always aligned to their component size.
+pub(crate) const VIEW_ALIGNMENT: usize = 4;
+
+/// Pads `data` with zeros so that the next buffer view starts at an aligned offset.
+pub(crate) fn align_data(data: &mut Vec<u8>) {
+    data.resize(data.len().next_multiple_of(VIEW_ALIGNMENT), 0);
+}
+
 pub(crate) trait BufferViewBuilder {
     fn new(byte_length: usize, byte_offset: usize) -> Self;
     fn with_target(self, target: json::buffer::Target) -> Self;
@@ -44,8 +56,10 @@ pub trait AccessorBuilder {
     fn new(count: usize, generic_comp: GltfComponentType) -> Self;
     fn with_name(self, name: String) -> Self;
     fn with_buffer_view(self, buffer_view: usize) -> Self;
+    #[allow(dead_code)]
     fn with_byte_offset(self, byte_offset: usize) -> Self;
     fn with_type(self, type_: GltfType) -> Self;
+    #[allow(dead_code)]
     fn with_component_type(self, component_type: json::accessor::GenericComponentType) -> Self;
     fn with_min_max<'a, T>(self, min: &'a [T], max: &'a [T]) -> Self
     where
@@ -187,17 +201,56 @@ macro_rules! impl_write_bytes_for_arr {
 }
 impl_write_bytes_for_arr![2 3 4];
 
+/// Generic interface to byteorder for reading values written with [`WriteBytes`].
+pub(crate) trait ReadBytes: Sized {
+    fn read_bytes(data: &mut &[u8]) -> std::io::Result<Self>;
+}
+
+macro_rules! impl_read_bytes {
+    ($($t:ty => $read:ident $(::<$e:ident>)?),* $(,)?) => {
+        $(
+            impl ReadBytes for $t {
+                #[inline]
+                fn read_bytes(data: &mut &[u8]) -> std::io::Result<Self> {
+                    data.$read$(::<$e>)?()
+                }
+            }
+        )*
+    };
+}
+impl_read_bytes!(
+    u8 => read_u8,
+    i8 => read_i8,
+    i16 => read_i16::<LE>,
+    u16 => read_u16::<LE>,
+    u32 => read_u32::<LE>,
+    f32 => read_f32::<LE>,
+);
+
+macro_rules! impl_read_bytes_for_arr {
+    [$($n:expr)+] => {
+        $(
+            impl<T: ReadBytes + Copy + Default> ReadBytes for [T; $n] {
+                #[inline]
+                fn read_bytes(data: &mut &[u8]) -> std::io::Result<Self> {
+                    let mut out = [T::default(); $n];
+                    for x in out.iter_mut() { *x = T::read_bytes(data)?; }
+                    Ok(out)
+                }
+            }
+        )*
+    };
+}
+impl_read_bytes_for_arr![2 3 4];
+
 pub(crate) fn write_attribute_data<T: WriteBytes + 'static>(
     data: &mut Vec<u8>,
     attrib: &Attribute,
-) {
-    let iter = VertexAttribute::iter::<T>(&attrib.attribute).unwrap_or_else(|_| {
-        panic!(
-            "Unsupported attribute: \"{:?}\"",
-            (attrib.name.as_str(), attrib.type_)
-        )
-    });
+) -> Result<(), Error> {
+    let iter = VertexAttribute::iter::<T>(&attrib.attribute)
+        .map_err(|_| Error::UnsupportedAttribute(attrib.name.clone(), attrib.type_))?;
     iter.for_each(|x| x.write_bytes(data));
+    Ok(())
 }
 
 pub(crate) fn write_tex_attribute_data<T: Copy + WriteBytes + 'static>(
@@ -0,0 +1,145 @@
+//! Camera framing the animated scene.
+//!
+//! The bounding box of all frames is recorded in the asset extras under
+//! [`SCENE_BOUNDS_KEY`], which lets review tools check the unit scale of a
+//! model, and a perspective camera is placed in front of the scene so that
+//! its bounding sphere fills the view.
+
+use gltf::json;
+use json::validation::Checked::Valid;
+use serde_json::Value;
+
+use crate::extras::to_extras;
+use crate::light::{look_rotation, normalized};
+use crate::Error;
+
+/// Key of the asset extras holding the bounding box of the scene over all frames.
+pub const SCENE_BOUNDS_KEY: &str = "sceneBounds";
+
+/// Vertical field of view of the framing camera in radians.
+const FRAMING_YFOV: f32 = 0.8;
+
+/// Adds the given bounding box of the scene to asset extras.
+pub(crate) fn with_scene_bounds(
+    extras: json::Extras,
+    min: [f32; 3],
+    max: [f32; 3],
+) -> Result<json::Extras, Error> {
+    let mut value = extras
+        .and_then(|extras| serde_json::from_str::<Value>(extras.get()).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Value::Object(map) = &mut value {
+        map.insert(
+            SCENE_BOUNDS_KEY.to_string(),
+            serde_json::json!({ "min": min, "max": max }),
+        );
+    }
+    to_extras(&value)
+}
+
+/// Builds a perspective camera and its node viewing the given bounding box from the front.
+///
+/// The camera looks down at the center of the box from slightly above, far
+/// enough for the sphere enclosing the box to fit the vertical field of view.
+pub(crate) fn build_framing_camera(
+    min: [f32; 3],
+    max: [f32; 3],
+    camera_index: usize,
+) -> (json::Camera, json::Node) {
+    let center: [f32; 3] = std::array::from_fn(|i| 0.5 * (min[i] + max[i]));
+    let radius = (0..3)
+        .map(|i| 0.5 * (max[i] - min[i]))
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt()
+        .max(f32::EPSILON);
+    let distance = radius / (0.5 * FRAMING_YFOV).sin();
+    let direction = normalized([0.0, 0.5, 1.0]);
+
+    let camera = json::Camera {
+        name: Some("camera".to_string()),
+        orthographic: None,
+        perspective: Some(json::camera::Perspective {
+            aspect_ratio: None,
+            yfov: FRAMING_YFOV,
+            zfar: Some(2.0 * (distance + radius)),
+            znear: 0.01 * distance,
+            extensions: Default::default(),
+            extras: Default::default(),
+        }),
+        type_: Valid(json::camera::Type::Perspective),
+        extensions: Default::default(),
+        extras: Default::default(),
+    };
+    let node = json::Node {
+        camera: Some(json::Index::new(camera_index as u32)),
+        name: Some("camera".to_string()),
+        rotation: Some(json::scene::UnitQuaternion(look_rotation(
+            direction.map(|x| -x),
+        ))),
+        translation: Some(std::array::from_fn(|i| center[i] + distance * direction[i])),
+        ..Default::default()
+    };
+    (camera, node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{proxy, AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn frame_camera() {
+        // A unit cube, which grows in the second frame.
+        let (positions, tris) = proxy::box_mesh([0.0; 3], [1.0; 3]);
+        let tris: Vec<[usize; 3]> = tris.iter().map(|t| t.map(|v| v as usize)).collect();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .frame_camera(true)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=2 {
+            let scaled = positions
+                .iter()
+                .map(|p| p.map(|x| x * frame as f32))
+                .collect();
+            let trimesh = meshx::TriMesh::new(scaled, tris.clone());
+            sequence
+                .add_frame("cube", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        let extras: serde_json::Value =
+            serde_json::from_str(root.asset.extras.as_ref().unwrap().get()).unwrap();
+        assert_eq!(
+            extras[SCENE_BOUNDS_KEY],
+            serde_json::json!({ "min": [0.0, 0.0, 0.0], "max": [2.0, 2.0, 2.0] })
+        );
+
+        // The sphere enclosing both frames fits into the view of the camera.
+        assert_eq!(root.cameras.len(), 1);
+        let yfov = root.cameras[0].perspective.as_ref().unwrap().yfov;
+        let node = root
+            .nodes
+            .iter()
+            .find(|node| node.camera.is_some())
+            .unwrap();
+        let t = node.translation.unwrap();
+        let distance = t.iter().map(|x| (x - 1.0) * (x - 1.0)).sum::<f32>().sqrt();
+        assert!(distance * (0.5 * yfov).sin() >= 3.0_f32.sqrt() - 1e-5);
+        assert!(t[2] > 2.0);
+    }
+}
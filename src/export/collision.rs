@@ -0,0 +1,241 @@
+//! Collision proxies attached to mesh nodes.
+//!
+//! Each proxy is a child node of the mesh node it encloses, marked by
+//! [`COLLISION_PROXY_KEY`] in its extras. Animated proxies are moved and
+//! scaled with translation and scale channels instead of morph targets.
+
+use std::mem;
+
+use byteorder::{WriteBytesExt, LE};
+use gltf::json;
+use json::accessor::ComponentType as GltfComponentType;
+use json::accessor::Type as GltfType;
+use json::validation::Checked::Valid;
+
+use super::build_nonempty_buffer_vec3;
+use super::builders::*;
+use crate::config::{CollisionProxy, INDEX_ATTRIB_NAME, POSITION_ATTRIB_NAME, TIME_ATTRIB_NAME};
+use crate::extras::to_extras;
+use crate::proxy::Proxy;
+use crate::Error;
+
+/// Key of the node extras marking collision proxies, holding the shape of the proxy.
+pub const COLLISION_PROXY_KEY: &str = "collisionProxy";
+
+/// Builds the node of a collision proxy for the mesh node named `name`.
+pub(crate) fn build_proxy_node(
+    name: &str,
+    proxy: &Proxy,
+    mesh: json::Index<json::Mesh>,
+) -> Result<json::Node, Error> {
+    let shape = match proxy.shape {
+        CollisionProxy::Box => "box",
+        CollisionProxy::Hull => "hull",
+    };
+    Ok(json::Node {
+        mesh: Some(mesh),
+        name: Some(format!("{}_proxy", name)),
+        translation: Some(proxy.translation),
+        extras: to_extras(&serde_json::json!({ COLLISION_PROXY_KEY: shape }))?,
+        ..Default::default()
+    })
+}
+
+/// Builds the mesh of a collision proxy.
+pub(crate) fn build_proxy_mesh(
+    proxy: &Proxy,
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+) -> json::Mesh {
+    let pos_acc_index = build_nonempty_buffer_vec3(
+        &proxy.positions,
+        accessors,
+        buffer_views,
+        data,
+        POSITION_ATTRIB_NAME,
+    );
+
+    align_data(data);
+    let indices_view =
+        json::buffer::View::new(mem::size_of_val(proxy.indices.as_slice()), data.len())
+            .with_target(json::buffer::Target::ElementArrayBuffer);
+    let indices_view_index = buffer_views.len();
+    buffer_views.push(indices_view);
+    for &index in proxy.indices.iter().flatten() {
+        data.write_u32::<LE>(index).unwrap();
+    }
+    let indices_acc = json::Accessor::new(3 * proxy.indices.len(), GltfComponentType::U32)
+        .with_name(INDEX_ATTRIB_NAME.to_string())
+        .with_buffer_view(indices_view_index);
+    accessors.push(indices_acc);
+    let indices_acc_index = json::Index::new(accessors.len() as u32 - 1);
+
+    let mut attributes = std::collections::BTreeMap::new();
+    attributes.insert(
+        Valid(json::mesh::Semantic::Positions),
+        json::Index::new(pos_acc_index),
+    );
+    json::Mesh {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: None,
+        primitives: vec![json::mesh::Primitive {
+            attributes,
+            extensions: Default::default(),
+            extras: Default::default(),
+            indices: Some(indices_acc_index),
+            material: None,
+            mode: Valid(json::mesh::Mode::Triangles),
+            targets: None,
+        }],
+        weights: None,
+    }
+}
+
+/// Builds samplers of the translation and scale of an animated proxy.
+///
+/// Returns an empty `Vec` if the proxy has a single key.
+pub(crate) fn build_proxy_samplers(
+    proxy: &Proxy,
+    time_step: f32,
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+) -> Vec<(json::animation::Property, json::animation::Sampler)> {
+    if proxy.keys.len() < 2 {
+        return Vec::new();
+    }
+    align_data(data);
+    let times: Vec<f32> = proxy
+        .keys
+        .iter()
+        .map(|key| key.frame as f32 * time_step)
+        .collect();
+    let time_view = json::buffer::View::new(mem::size_of_val(times.as_slice()), data.len());
+    let time_view_index = buffer_views.len();
+    buffer_views.push(time_view);
+    for &time in times.iter() {
+        data.write_f32::<LE>(time).unwrap();
+    }
+    let time_acc = json::Accessor::new(times.len(), GltfComponentType::F32)
+        .with_name(TIME_ATTRIB_NAME.to_string())
+        .with_buffer_view(time_view_index)
+        .with_min_max(&[times[0]][..], &[times[times.len() - 1]][..]);
+    accessors.push(time_acc);
+    let time_acc_index = json::Index::new(accessors.len() as u32 - 1);
+
+    let mut vec3_sampler = |values: Vec<[f32; 3]>, name: &str| {
+        align_data(data);
+        let view = json::buffer::View::new(mem::size_of_val(values.as_slice()), data.len());
+        let view_index = buffer_views.len();
+        buffer_views.push(view);
+        for &x in values.iter().flatten() {
+            data.write_f32::<LE>(x).unwrap();
+        }
+        let acc = json::Accessor::new(values.len(), GltfComponentType::F32)
+            .with_name(name.to_string())
+            .with_buffer_view(view_index)
+            .with_type(GltfType::Vec3);
+        accessors.push(acc);
+        json::animation::Sampler {
+            input: time_acc_index,
+            interpolation: Valid(json::animation::Interpolation::Linear),
+            output: json::Index::new(accessors.len() as u32 - 1),
+            extensions: Default::default(),
+            extras: Default::default(),
+        }
+    };
+    let translations = proxy.keys.iter().map(|key| key.translation).collect();
+    let scales = proxy.keys.iter().map(|key| key.scale).collect();
+    vec![
+        (
+            json::animation::Property::Translation,
+            vec3_sampler(translations, "proxy_translation"),
+        ),
+        (
+            json::animation::Property::Scale,
+            vec3_sampler(scales, "proxy_scale"),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{proxy, AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn collision_proxy() {
+        use gltf::json::animation::Property;
+
+        // A unit cube, which grows in the second frame.
+        let (positions, tris) = proxy::box_mesh([0.0; 3], [1.0; 3]);
+        let tris: Vec<[usize; 3]> = tris.iter().map(|t| t.map(|v| v as usize)).collect();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .collision_proxy(Some(CollisionProxy::Box))
+            .animate_proxy(true)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=2 {
+            let scaled = positions
+                .iter()
+                .map(|p| p.map(|x| x * frame as f32))
+                .collect();
+            let trimesh = meshx::TriMesh::new(scaled, tris.clone());
+            sequence
+                .add_frame("cube", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        let proxy = root
+            .nodes
+            .iter()
+            .position(|node| node.name.as_deref() == Some("cube_proxy"))
+            .unwrap();
+        let cube = root
+            .nodes
+            .iter()
+            .find(|node| node.name.as_deref() == Some("cube"))
+            .unwrap();
+        assert_eq!(cube.children.as_ref().unwrap()[0].value(), proxy);
+        let extras: serde_json::Value =
+            serde_json::from_str(root.nodes[proxy].extras.as_ref().unwrap().get()).unwrap();
+        assert_eq!(extras[COLLISION_PROXY_KEY], "box");
+        assert_eq!(root.nodes[proxy].translation, Some([0.5; 3]));
+
+        // The proxy follows the growing cube with translation and scale.
+        let paths: Vec<_> = root.animations[0]
+            .channels
+            .iter()
+            .filter(|channel| channel.target.node.value() == proxy)
+            .map(|channel| channel.target.path.clone())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![Valid(Property::Translation), Valid(Property::Scale)]
+        );
+
+        // Interior points are not part of the hull.
+        let mut points = positions.clone();
+        points.push([0.5; 3]);
+        let (hull_positions, hull_tris) = proxy::convex_hull(&points).unwrap();
+        assert_eq!(hull_positions.len(), 8);
+        assert_eq!(hull_tris.len(), 12);
+        assert!(proxy::convex_hull(&[[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]).is_none());
+    }
+}
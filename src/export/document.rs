@@ -0,0 +1,116 @@
+//! Generated glTF documents along with the properties [`json::Root`] can't represent.
+
+use std::io::Write;
+
+use gltf::json;
+use serde_json::Value;
+
+use super::animation_pointer::KHR_ANIMATION_POINTER;
+use super::target_colors::insert_target_colors;
+
+/// A channel animating a material or light property with KHR_animation_pointer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PointerChannel {
+    /// Index of the animation in [`Document::root`] holding the channel.
+    pub animation: usize,
+    /// Index of the sampler of the channel within its animation.
+    pub sampler: usize,
+    /// JSON pointer to the animated property.
+    pub pointer: String,
+}
+
+/// Color deltas of the morph targets of a primitive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TargetColors {
+    /// Index of the mesh in [`Document::root`].
+    pub mesh: usize,
+    /// Index of the primitive within the mesh.
+    pub primitive: usize,
+    /// Accessor holding the `COLOR_0` deltas of each morph target, if any.
+    pub colors: Vec<Option<json::Index<json::Accessor>>>,
+}
+
+/// A glTF document built by `gltfgen`.
+///
+/// [`json::animation::Target`] can only refer to node properties, so channels
+/// animating other properties are kept in `pointer_channels`. Their samplers
+/// are part of the animations in `root`, and an animation whose channels all
+/// animate pointers has no channels in `root`. Likewise,
+/// [`json::mesh::MorphTarget`] has no field for `COLOR_0`, so color deltas are
+/// kept in `target_colors`. Both are added to the JSON produced by
+/// [`Document::to_json`], which is what gets written.
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    pub root: json::Root,
+    pub pointer_channels: Vec<PointerChannel>,
+    pub target_colors: Vec<TargetColors>,
+}
+
+impl From<json::Root> for Document {
+    fn from(root: json::Root) -> Self {
+        Document {
+            root,
+            pointer_channels: Vec::new(),
+            target_colors: Vec::new(),
+        }
+    }
+}
+
+impl Document {
+    /// Whether the document can be serialized directly from `root`.
+    fn is_typed(&self) -> bool {
+        self.pointer_channels.is_empty() && self.target_colors.is_empty()
+    }
+
+    /// Builds the JSON of the complete document.
+    ///
+    /// Pointer channels are appended to the channels of their animations with
+    /// KHR_animation_pointer targets, and color deltas are added to the morph
+    /// targets of their primitives. Entries referring to objects that don't
+    /// exist (e.g. removed by export hooks) are skipped.
+    pub fn to_json(&self) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(&self.root)?;
+        insert_target_colors(&mut value, &self.target_colors);
+        for channel in self.pointer_channels.iter() {
+            let Some(channels) = value
+                .get_mut("animations")
+                .and_then(|animations| animations.get_mut(channel.animation))
+                .and_then(|animation| animation.get_mut("channels"))
+                .and_then(Value::as_array_mut)
+            else {
+                continue;
+            };
+            channels.push(serde_json::json!({
+                "sampler": channel.sampler,
+                "target": {
+                    "path": "pointer",
+                    "extensions": { KHR_ANIMATION_POINTER: { "pointer": channel.pointer } },
+                },
+            }));
+        }
+        Ok(value)
+    }
+}
+
+/// Serializes the glTF document as JSON into `writer`.
+pub(crate) fn write_root(
+    writer: impl Write,
+    document: &Document,
+    pretty: bool,
+) -> std::io::Result<()> {
+    if document.is_typed() {
+        return if pretty {
+            json::serialize::to_writer_pretty(writer, &document.root)
+        } else {
+            json::serialize::to_writer(writer, &document.root)
+        }
+        .map_err(std::io::Error::from);
+    }
+    let value = document.to_json()?;
+    if pretty {
+        serde_json::to_writer_pretty(writer, &value)?;
+    } else {
+        serde_json::to_writer(writer, &value)?;
+    }
+    Ok(())
+}
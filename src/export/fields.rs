@@ -0,0 +1,278 @@
+//! Baking of animated scalar vertex fields into textures.
+//!
+//! glTF can't animate vertex attributes, so the values of a scalar attribute
+//! at every frame are stored in a texture instead. Each frame occupies
+//! `rowsPerFrame` consecutive rows of texels, with the value of vertex `v` at
+//! column `v % width` and row `v / width` within the frame. Values are
+//! normalized to the range of the field and packed into 16 bits, with the high
+//! byte in the red channel and the low byte in the green channel of an 8 bit
+//! RGB PNG, since web browsers decode images to 8 bits per channel.
+//!
+//! Meshes with baked fields get a [`FIELD_TEXEL_ATTRIB_NAME`] vertex attribute
+//! holding the column and row offset of each vertex, which stays valid when
+//! meshes are split into multiple primitives.
+
+use gltf::json;
+use json::validation::Checked::Valid;
+
+use super::builders::*;
+use crate::attrib::{Attribute, ComponentType, Type, VertexAttribute};
+use crate::Error;
+
+/// Name of the vertex attribute holding the texel coordinates of each vertex in baked fields.
+pub const FIELD_TEXEL_ATTRIB_NAME: &str = "field_texel";
+
+/// Key of the mesh extras listing the baked fields of a mesh.
+pub const BAKED_FIELDS_KEY: &str = "bakedFields";
+
+/// Maximum width of baked field textures supported by most WebGL implementations.
+const MAX_WIDTH: usize = 4096;
+
+/// Values of a scalar vertex attribute at every frame of a node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldSeries {
+    pub name: String,
+    /// Frame of each row of values.
+    pub frames: Vec<u32>,
+    /// Values at each vertex for each frame.
+    pub values: Vec<Vec<f32>>,
+}
+
+impl FieldSeries {
+    /// Appends the values of the field at the given frame.
+    ///
+    /// Values of the previous frame are repeated if the given values don't
+    /// match the number of vertices.
+    pub(crate) fn push(&mut self, frame: u32, values: Option<Vec<f32>>) {
+        let values = match (values, self.values.last()) {
+            (Some(values), Some(last)) if values.len() != last.len() => last.clone(),
+            (Some(values), _) => values,
+            (None, Some(last)) => last.clone(),
+            (None, None) => return,
+        };
+        self.frames.push(frame);
+        self.values.push(values);
+    }
+}
+
+/// A field baked into a PNG image.
+pub(crate) struct BakedField {
+    pub name: String,
+    pub frames: Vec<u32>,
+    pub min: f32,
+    pub max: f32,
+    pub rows_per_frame: usize,
+    pub png: Vec<u8>,
+}
+
+/// Width of the texture holding fields of meshes with the given number of vertices.
+fn texture_width(num_vertices: usize) -> usize {
+    num_vertices.clamp(1, MAX_WIDTH)
+}
+
+/// Builds the attribute holding the texel column and row offset of each vertex.
+pub(crate) fn field_texel_attribute(num_vertices: usize) -> Attribute {
+    let width = texture_width(num_vertices);
+    let texels: Vec<[f32; 2]> = (0..num_vertices)
+        .map(|v| [(v % width) as f32, (v / width) as f32])
+        .collect();
+    Attribute {
+        name: FIELD_TEXEL_ATTRIB_NAME.to_string(),
+        type_: Type::Vec2(ComponentType::F32),
+        attribute: VertexAttribute::direct_from_vec(texels),
+    }
+}
+
+/// Bakes the given field of a mesh with `num_vertices` vertices into a PNG image.
+pub(crate) fn bake_field(series: &FieldSeries, num_vertices: usize) -> Result<BakedField, Error> {
+    let width = texture_width(num_vertices);
+    let rows_per_frame = num_vertices.div_ceil(width).max(1);
+    let height = rows_per_frame * series.frames.len();
+
+    let finite = || {
+        series
+            .values
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|x| x.is_finite())
+    };
+    let min = finite().fold(f32::INFINITY, f32::min);
+    let max = finite().fold(f32::NEG_INFINITY, f32::max);
+    let (min, max) = if min <= max { (min, max) } else { (0.0, 0.0) };
+    let range = max - min;
+
+    let mut pixels = vec![0u8; width * height * 3];
+    for (frame_index, values) in series.values.iter().enumerate() {
+        for (v, &x) in values.iter().take(num_vertices).enumerate() {
+            let t = if range > 0.0 && x.is_finite() {
+                (x - min) / range
+            } else {
+                0.0
+            };
+            let packed = (t.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+            let row = frame_index * rows_per_frame + v / width;
+            let offset = (row * width + v % width) * 3;
+            pixels[offset..offset + 2].copy_from_slice(&packed.to_be_bytes());
+        }
+    }
+
+    Ok(BakedField {
+        name: series.name.clone(),
+        frames: series.frames.clone(),
+        min,
+        max,
+        rows_per_frame,
+        png: encode_png(&series.name, &pixels, width, height)?,
+    })
+}
+
+/// Encodes the 8 bit RGB pixels of the named field as a PNG image.
+#[cfg(feature = "textures")]
+fn encode_png(name: &str, pixels: &[u8], width: usize, height: usize) -> Result<Vec<u8>, Error> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let encode = |encoder: png::Encoder<&mut Vec<u8>>| -> Result<(), png::EncodingError> {
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(pixels)?;
+        writer.finish()
+    };
+    encode(encoder).map_err(|err| Error::FieldBake(name.to_string(), err.to_string()))?;
+    Ok(png)
+}
+
+#[cfg(not(feature = "textures"))]
+fn encode_png(_: &str, _: &[u8], _: usize, _: usize) -> Result<Vec<u8>, Error> {
+    Err(Error::FeatureDisabled("textures"))
+}
+
+/// Appends the image of a baked field to the buffer and returns its image.
+pub(crate) fn build_field_image(
+    field: &BakedField,
+    data: &mut Vec<u8>,
+    buffer_views: &mut Vec<json::buffer::View>,
+) -> json::image::Image {
+    align_data(data);
+    let view = json::buffer::View::new(field.png.len(), data.len());
+    data.extend_from_slice(&field.png);
+    let view_index = buffer_views.len();
+    buffer_views.push(view);
+    json::image::Image {
+        name: Some(field.name.clone()),
+        buffer_view: json::Index::new(view_index as u32).into(),
+        mime_type: json::image::MimeType("image/png".to_string()).into(),
+        uri: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    }
+}
+
+/// Builds the sampler reading exact texel values from baked field textures.
+pub(crate) fn field_sampler() -> json::texture::Sampler {
+    json::texture::Sampler {
+        mag_filter: Some(Valid(json::texture::MagFilter::Nearest)),
+        min_filter: Some(Valid(json::texture::MinFilter::Nearest)),
+        wrap_s: Valid(json::texture::WrappingMode::ClampToEdge),
+        wrap_t: Valid(json::texture::WrappingMode::ClampToEdge),
+        name: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    }
+}
+
+/// Describes a baked field stored in the texture at `texture` for the mesh extras.
+pub(crate) fn field_extras(field: &BakedField, texture: usize) -> serde_json::Value {
+    serde_json::json!({
+        "name": field.name,
+        "texture": texture,
+        "min": field.min,
+        "max": field.max,
+        "frames": field.frames,
+        "rowsPerFrame": field.rows_per_frame,
+    })
+}
+
+#[cfg(all(test, feature = "textures"))]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn baked_fields() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::VertexIndex;
+
+        let attributes: AttributeInfo = r#"{"temperature": f32}"#.parse().unwrap();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .bake_fields(vec!["temperature".to_string()])
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for (frame, z, temperature) in [(1, 0.0, [0.0, 1.0, 2.0]), (2, 1.0, [2.0; 3])] {
+            let mut trimesh = meshx::TriMesh::new(
+                vec![[0.0, 0.0, z], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            );
+            trimesh
+                .insert_attrib_data::<f32, VertexIndex>("temperature", temperature.to_vec())
+                .unwrap();
+            sequence
+                .add_frame("tri", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let (document, data) = sequence.build().unwrap();
+        let root = document.root;
+
+        let mesh = &root.meshes[0];
+        let semantic = |name: &str| {
+            gltf::json::validation::Checked::Valid(gltf::json::mesh::Semantic::Extras(
+                name.to_string(),
+            ))
+        };
+        assert!(mesh.primitives[0]
+            .attributes
+            .contains_key(&semantic("FIELD_TEXEL")));
+        assert!(!mesh.primitives[0]
+            .attributes
+            .contains_key(&semantic("TEMPERATURE")));
+
+        let extras: serde_json::Value =
+            serde_json::from_str(mesh.extras.as_ref().unwrap().get()).unwrap();
+        let field = &extras[BAKED_FIELDS_KEY][0];
+        assert_eq!(field["name"], "temperature");
+        assert_eq!(field["min"], 0.0);
+        assert_eq!(field["max"], 2.0);
+        assert_eq!(field["frames"], serde_json::json!([1, 2]));
+        assert_eq!(field["rowsPerFrame"], 1);
+
+        let texture = &root.textures[field["texture"].as_u64().unwrap() as usize];
+        let image = &root.images[texture.source.value()];
+        assert_eq!(image.mime_type.as_ref().unwrap().0, "image/png");
+        let view = &root.buffer_views[image.buffer_view.unwrap().value()];
+        let offset = view.byte_offset.map_or(0, |o| o.0 as usize);
+        let png = &data[offset..offset + view.byte_length.0 as usize];
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (3, 2));
+        let mut pixels = vec![0; 3 * 2 * 3];
+        reader.next_frame(&mut pixels).unwrap();
+        let values: Vec<u16> = pixels
+            .chunks(3)
+            .map(|rgb| u16::from_be_bytes([rgb[0], rgb[1]]))
+            .collect();
+        assert_eq!(values, [0, 32768, 65535, 65535, 65535, 65535]);
+    }
+}
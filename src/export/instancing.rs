@@ -0,0 +1,591 @@
+//! Point clouds rendered as instanced meshes using `EXT_mesh_gpu_instancing`.
+//!
+//! Many engines don't render point primitives at all, so each point is
+//! replaced by a small mesh instanced at its position. Instance attributes
+//! can't be animated, so animated point clouds are split into one instanced
+//! node per frame, and each node is shown only for the duration of its frame
+//! by stepping its scale between zero and one.
+
+use std::collections::HashMap;
+use std::mem;
+
+use byteorder::{WriteBytesExt, LE};
+use gltf::json;
+use json::accessor::ComponentType as GltfComponentType;
+use json::accessor::Type as GltfType;
+use json::validation::Checked::Valid;
+use meshx::mesh::vertex_positions::VertexPositions;
+
+use super::builders::*;
+use super::{build_buffer_vec3, build_nonempty_buffer_vec3, Node};
+use crate::attrib::Attribute;
+use crate::config::{
+    PointsAs, INDEX_ATTRIB_NAME, NORMAL_ATTRIB_NAME, POSITION_ATTRIB_NAME, TIME_ATTRIB_NAME,
+};
+
+pub(crate) const EXT_MESH_GPU_INSTANCING: &str = "EXT_mesh_gpu_instancing";
+
+/// Fraction of the bounding box diagonal of a point cloud used as the radius
+/// of billboards and cubes without a radius attribute.
+const AUTO_RADIUS_FRACTION: f32 = 0.005;
+
+/// Triangulated shape with unit radius centered at the origin.
+struct Shape {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl Shape {
+    fn new(points_as: PointsAs) -> Shape {
+        match points_as {
+            PointsAs::Billboard => Shape::billboard(),
+            PointsAs::Sphere(_) => Shape::sphere(),
+            PointsAs::Cube => Shape::cube(),
+        }
+    }
+
+    /// Appends a square with the given normal spanned by the two other axes.
+    ///
+    /// `offset` is the distance of the square from the origin along its normal.
+    fn push_square(&mut self, axis: usize, sign: f32, offset: f32) {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut normal = [0.0; 3];
+        normal[axis] = sign;
+        let first = self.positions.len() as u32;
+        for (cu, cv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let mut pos = [0.0; 3];
+            pos[axis] = sign * offset;
+            pos[u] = cu;
+            pos[v] = cv;
+            self.positions.push(pos);
+            self.normals.push(normal);
+        }
+        // Counter-clockwise when viewed from the side the normal points to.
+        let [a, b, c, d] = [first, first + 1, first + 2, first + 3];
+        if sign > 0.0 {
+            self.triangles.extend([[a, b, c], [a, c, d]]);
+        } else {
+            self.triangles.extend([[a, c, b], [a, d, c]]);
+        }
+    }
+
+    /// Three perpendicular double-sided squares.
+    fn billboard() -> Shape {
+        let mut shape = Shape::empty();
+        for axis in 0..3 {
+            shape.push_square(axis, 1.0, 0.0);
+            shape.push_square(axis, -1.0, 0.0);
+        }
+        shape
+    }
+
+    fn cube() -> Shape {
+        let mut shape = Shape::empty();
+        for axis in 0..3 {
+            shape.push_square(axis, 1.0, 1.0);
+            shape.push_square(axis, -1.0, 1.0);
+        }
+        shape
+    }
+
+    /// Icosahedron subdivided once and projected onto the unit sphere.
+    fn sphere() -> Shape {
+        let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let normalize = |p: [f32; 3]| {
+            let len = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+            [p[0] / len, p[1] / len, p[2] / len]
+        };
+        let mut positions: Vec<_> = [
+            [-1.0, t, 0.0],
+            [1.0, t, 0.0],
+            [-1.0, -t, 0.0],
+            [1.0, -t, 0.0],
+            [0.0, -1.0, t],
+            [0.0, 1.0, t],
+            [0.0, -1.0, -t],
+            [0.0, 1.0, -t],
+            [t, 0.0, -1.0],
+            [t, 0.0, 1.0],
+            [-t, 0.0, -1.0],
+            [-t, 0.0, 1.0],
+        ]
+        .into_iter()
+        .map(normalize)
+        .collect();
+        let icosahedron: [[u32; 3]; 20] = [
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (pa, pb) = (positions[a as usize], positions[b as usize]);
+                positions.push(normalize([pa[0] + pb[0], pa[1] + pb[1], pa[2] + pb[2]]));
+                positions.len() as u32 - 1
+            })
+        };
+        let mut triangles = Vec::with_capacity(4 * icosahedron.len());
+        for [a, b, c] in icosahedron {
+            let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+            triangles.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+
+        Shape {
+            normals: positions.clone(),
+            positions,
+            triangles,
+        }
+    }
+
+    fn empty() -> Shape {
+        Shape {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            triangles: Vec::new(),
+        }
+    }
+}
+
+/// Writes a buffer view and accessor for the given vectors without a target or stride,
+/// as required for instance attributes.
+fn build_instance_vec3(
+    values: &[[f32; 3]],
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+    name: &str,
+) -> json::Index<json::Accessor> {
+    align_data(data);
+    let view = json::buffer::View::new(mem::size_of_val(values), data.len());
+    let view_index = buffer_views.len();
+    buffer_views.push(view);
+    for value in values.iter() {
+        for &x in value.iter() {
+            data.write_f32::<LE>(x).unwrap();
+        }
+    }
+    let acc = json::Accessor::new(values.len(), GltfComponentType::F32)
+        .with_name(name.to_string())
+        .with_buffer_view(view_index)
+        .with_type(GltfType::Vec3);
+    accessors.push(acc);
+    json::Index::new(accessors.len() as u32 - 1)
+}
+
+/// Builds the mesh instanced at every point, scaled by `radius`.
+fn build_shape_mesh(
+    points_as: PointsAs,
+    radius: f32,
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+) -> json::Mesh {
+    let shape = Shape::new(points_as);
+    let positions: Vec<_> = shape
+        .positions
+        .iter()
+        .map(|p| [p[0] * radius, p[1] * radius, p[2] * radius])
+        .collect();
+    let pos_acc_index = build_nonempty_buffer_vec3(
+        &positions,
+        accessors,
+        buffer_views,
+        data,
+        POSITION_ATTRIB_NAME,
+    );
+    let nml_acc_index = build_buffer_vec3(
+        &shape.normals,
+        accessors,
+        buffer_views,
+        data,
+        NORMAL_ATTRIB_NAME,
+    );
+
+    align_data(data);
+    let indices_view =
+        json::buffer::View::new(mem::size_of_val(shape.triangles.as_slice()), data.len())
+            .with_target(json::buffer::Target::ElementArrayBuffer);
+    let indices_view_index = buffer_views.len();
+    buffer_views.push(indices_view);
+    for &index in shape.triangles.iter().flatten() {
+        data.write_u32::<LE>(index).unwrap();
+    }
+    let indices_acc = json::Accessor::new(3 * shape.triangles.len(), GltfComponentType::U32)
+        .with_name(INDEX_ATTRIB_NAME.to_string())
+        .with_buffer_view(indices_view_index);
+    accessors.push(indices_acc);
+    let indices_acc_index = json::Index::new(accessors.len() as u32 - 1);
+
+    let mut attributes = std::collections::BTreeMap::new();
+    attributes.insert(
+        Valid(json::mesh::Semantic::Positions),
+        json::Index::new(pos_acc_index),
+    );
+    if let Some(index) = nml_acc_index {
+        attributes.insert(Valid(json::mesh::Semantic::Normals), index);
+    }
+    json::Mesh {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: None,
+        primitives: vec![json::mesh::Primitive {
+            attributes,
+            extensions: Default::default(),
+            extras: Default::default(),
+            indices: Some(indices_acc_index),
+            material: None,
+            mode: Valid(json::mesh::Mode::Triangles),
+            targets: None,
+        }],
+        weights: None,
+    }
+}
+
+/// Instanced mesh replacing a point cloud along with the instancing extension of each frame.
+pub(crate) struct InstancedPoints {
+    pub mesh: json::Mesh,
+    /// Frame number and node extension of each keyframe in increasing order of frames.
+    pub frames: Vec<(u32, json::extensions::scene::Node)>,
+}
+
+/// Builds instance attributes for every keyframe of a point cloud node.
+///
+/// Instances are scaled by the scalar `f32` attribute named `radius_attrib`
+/// if the node has one. The instanced mesh is built without a material.
+pub(crate) fn build_instanced_points(
+    node: &Node,
+    points_as: PointsAs,
+    radius_attrib: Option<&str>,
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+    warnings: &mut Vec<(usize, String)>,
+) -> InstancedPoints {
+    let base = node.mesh.vertex_positions();
+    let transfer = &node.attrib_transfer;
+
+    let radii = radius_attrib.and_then(|name| {
+        let attrib = transfer
+            .attribs_to_keep
+            .iter()
+            .find(|attrib| attrib.name == name)?;
+        let radii = attrib.attribute.as_slice::<f32>().ok();
+        if radii.is_none() {
+            log!(warnings; "Radius attribute '{}' of '{}' is not a 32-bit float scalar. Skipping...", name, node.name);
+        }
+        radii
+    });
+
+    // Without a radius attribute the radius is baked into the instanced mesh.
+    let radius = match (radii, points_as) {
+        (Some(_), _) => 1.0,
+        (None, PointsAs::Sphere(radius)) => radius,
+        (None, _) => {
+            let mut min = [f32::INFINITY; 3];
+            let mut max = [f32::NEG_INFINITY; 3];
+            for p in base.iter() {
+                for i in 0..3 {
+                    min[i] = min[i].min(p[i]);
+                    max[i] = max[i].max(p[i]);
+                }
+            }
+            let diag = (0..3)
+                .map(|i| (max[i] - min[i]).powi(2))
+                .sum::<f32>()
+                .sqrt();
+            if diag > 0.0 && diag.is_finite() {
+                AUTO_RADIUS_FRACTION * diag
+            } else {
+                1.0
+            }
+        }
+    };
+    let mesh = build_shape_mesh(points_as, radius, accessors, buffer_views, data);
+
+    let scale_acc_index = radii.map(|radii| {
+        let scales: Vec<_> = radii.iter().map(|&r| [r; 3]).collect();
+        build_instance_vec3(&scales, accessors, buffer_views, data, "scale")
+    });
+
+    let base_colors = transfer
+        .color_attribs_to_keep
+        .first()
+        .and_then(Attribute::rgba_colors);
+    let rgb = |colors: &[[f32; 4]]| -> Vec<[f32; 3]> {
+        colors.iter().map(|c| [c[0], c[1], c[2]]).collect()
+    };
+    let base_color_acc_index = base_colors
+        .as_ref()
+        .map(|colors| build_instance_vec3(&rgb(colors), accessors, buffer_views, data, "Cd"));
+
+    // The first frame is given by the base mesh and others by morph targets.
+    let mut keyframes: Vec<(u32, Option<usize>)> = vec![(node.first_frame, None)];
+    keyframes.extend(
+        node.morphs
+            .iter()
+            .enumerate()
+            .map(|(i, morph)| (morph.frame, Some(i))),
+    );
+    keyframes.sort_by_key(|&(frame, _)| frame);
+
+    let frames = keyframes
+        .into_iter()
+        .map(|(frame, morph)| {
+            let morph = morph.map(|i| &node.morphs[i]);
+            let translations: Vec<_> = match morph {
+                Some(morph) if morph.position_disp.len() == base.len() => base
+                    .iter()
+                    .zip(morph.position_disp.iter())
+                    .map(|(p, d)| [p[0] + d[0], p[1] + d[1], p[2] + d[2]])
+                    .collect(),
+                _ => base.to_vec(),
+            };
+            let mut attributes = serde_json::Map::new();
+            let translation_acc_index = build_instance_vec3(
+                &translations,
+                accessors,
+                buffer_views,
+                data,
+                POSITION_ATTRIB_NAME,
+            );
+            attributes.insert("TRANSLATION".into(), translation_acc_index.value().into());
+            if let Some(index) = scale_acc_index {
+                attributes.insert("SCALE".into(), index.value().into());
+            }
+            let color_acc_index = match (morph, &base_colors) {
+                (Some(morph), Some(colors)) if morph.color_disp.len() == colors.len() => {
+                    let colors: Vec<_> = colors
+                        .iter()
+                        .zip(morph.color_disp.iter())
+                        .map(|(c, d)| [c[0] + d[0], c[1] + d[1], c[2] + d[2], c[3] + d[3]])
+                        .collect();
+                    Some(build_instance_vec3(
+                        &rgb(&colors),
+                        accessors,
+                        buffer_views,
+                        data,
+                        "Cd",
+                    ))
+                }
+                _ => base_color_acc_index,
+            };
+            if let Some(index) = color_acc_index {
+                attributes.insert("_COLOR_0".into(), index.value().into());
+            }
+            let mut extension = json::extensions::scene::Node::default();
+            extension.others.insert(
+                EXT_MESH_GPU_INSTANCING.to_string(),
+                serde_json::json!({ "attributes": attributes }),
+            );
+            (frame, extension)
+        })
+        .collect();
+
+    InstancedPoints { mesh, frames }
+}
+
+/// Builds a sampler showing a node only from the keyframe at `index` until the next keyframe.
+///
+/// Nodes are shown and hidden by stepping their scale between one and zero.
+pub(crate) fn build_visibility_sampler(
+    times: &[f32],
+    index: usize,
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+) -> json::animation::Sampler {
+    let mut keys = Vec::with_capacity(3);
+    if index > 0 {
+        keys.push((times[0], 0.0));
+    }
+    keys.push((times[index], 1.0));
+    if let Some(&next) = times.get(index + 1) {
+        keys.push((next, 0.0));
+    }
+    build_scale_sampler(&keys, accessors, buffer_views, data)
+}
+
+/// Builds a sampler stepping through the given times and uniform scales.
+pub(crate) fn build_scale_sampler(
+    keys: &[(f32, f32)],
+    accessors: &mut Vec<json::Accessor>,
+    buffer_views: &mut Vec<json::buffer::View>,
+    data: &mut Vec<u8>,
+) -> json::animation::Sampler {
+    align_data(data);
+    let time_view = json::buffer::View::new(keys.len() * mem::size_of::<f32>(), data.len());
+    let time_view_index = buffer_views.len();
+    buffer_views.push(time_view);
+    for &(time, _) in keys.iter() {
+        data.write_f32::<LE>(time).unwrap();
+    }
+    let min_time = keys[0].0;
+    let max_time = keys[keys.len() - 1].0;
+    let time_acc = json::Accessor::new(keys.len(), GltfComponentType::F32)
+        .with_name(TIME_ATTRIB_NAME.to_string())
+        .with_buffer_view(time_view_index)
+        .with_min_max(&[min_time][..], &[max_time][..]);
+    accessors.push(time_acc);
+    let time_acc_index = json::Index::new(accessors.len() as u32 - 1);
+
+    let scales: Vec<_> = keys.iter().map(|&(_, scale)| [scale; 3]).collect();
+    let scale_acc_index = build_instance_vec3(&scales, accessors, buffer_views, data, "scale");
+
+    json::animation::Sampler {
+        input: time_acc_index,
+        interpolation: Valid(json::animation::Interpolation::Step),
+        output: scale_acc_index,
+        extensions: Default::default(),
+        extras: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::POINT_RADIUS_SEMANTIC;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{validate, AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn point_size_and_radius() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::VertexIndex;
+
+        let attributes: AttributeInfo = r#"{"pscale": F32}"#.parse().unwrap();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .point_size(Some(4.0))
+            .attribute_names(
+                format!(r#"{{"pscale": "{}"}}"#, POINT_RADIUS_SEMANTIC)
+                    .parse()
+                    .unwrap(),
+            )
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        let mut points = meshx::PointCloud::new(vec![[0.0_f32, 0.0, 0.0], [1.0, 0.0, 0.0]]);
+        points
+            .insert_attrib_data::<f32, VertexIndex>("pscale", vec![0.1, 0.2])
+            .unwrap();
+        sequence
+            .add_frame("particles", 1, Mesh::from(points))
+            .unwrap();
+        let root = sequence.build().unwrap().0.root;
+
+        let mesh = &root.meshes[0];
+        let extras: serde_json::Value =
+            serde_json::from_str(mesh.extras.as_ref().unwrap().get()).unwrap();
+        assert_eq!(extras["pointSize"], 4.0);
+        let radius = gltf::json::validation::Checked::Valid(gltf::json::mesh::Semantic::Extras(
+            "RADIUS".to_string(),
+        ));
+        assert!(mesh.primitives[0].attributes.contains_key(&radius));
+    }
+
+    #[test]
+    fn points_as_instances() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::VertexIndex;
+
+        let attributes: AttributeInfo = r#"{"pscale": F32}"#.parse().unwrap();
+        let colors: AttributeInfo = r#"{"Cd": Vec3(F32)}"#.parse().unwrap();
+        let texcoords = TextureAttributeInfo::default();
+        let build = |num_frames: u32| {
+            let attrib_config = AttribConfig {
+                attributes: &attributes,
+                colors: &colors,
+                texcoords: &texcoords,
+                material_attribute: "mtl_id",
+            };
+            let config = ExportConfig::builder()
+                .points_as(Some("sphere(0.1)".parse().unwrap()))
+                .attribute_names(r#"{"pscale": "_RADIUS"}"#.parse().unwrap())
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            for frame in 1..=num_frames {
+                let mut points =
+                    meshx::PointCloud::new(vec![[0.0_f32, frame as f32, 0.0], [1.0, 0.0, 0.0]]);
+                points
+                    .insert_attrib_data::<f32, VertexIndex>("pscale", vec![0.1, 0.2])
+                    .unwrap();
+                points
+                    .insert_attrib_data::<[f32; 3], VertexIndex>("Cd", vec![[1.0, 0.0, 0.0]; 2])
+                    .unwrap();
+                sequence
+                    .add_frame("particles", frame, Mesh::from(points))
+                    .unwrap();
+            }
+            let (document, data) = sequence.build().unwrap();
+            (document.root, data)
+        };
+        let instancing = |node: &gltf::json::Node| {
+            node.extensions
+                .as_ref()
+                .and_then(|ext| ext.others.get("EXT_mesh_gpu_instancing"))
+                .map(|ext| ext["attributes"].clone())
+        };
+
+        // A single frame is instanced directly on the generated node.
+        let (root, data) = build(1);
+        assert_eq!(root.extensions_used, vec!["EXT_mesh_gpu_instancing"]);
+        assert_eq!(root.nodes.len(), 1);
+        let attributes = instancing(&root.nodes[0]).unwrap();
+        let translation = &root.accessors[attributes["TRANSLATION"].as_u64().unwrap() as usize];
+        assert_eq!(translation.count.0, 2);
+        assert!(attributes.get("SCALE").is_some());
+        assert!(attributes.get("_COLOR_0").is_some());
+        let prim = &root.meshes[0].primitives[0];
+        assert_eq!(
+            prim.mode,
+            gltf::json::validation::Checked::Valid(gltf::json::mesh::Mode::Triangles)
+        );
+        assert!(validate::validate(&root, &[&data]).is_empty());
+
+        // Animated point clouds are shown one frame at a time.
+        let (root, data) = build(3);
+        let children = root.nodes[0].children.clone().unwrap();
+        assert_eq!(children.len(), 3);
+        assert!(root.nodes[0].mesh.is_none());
+        for child in children.iter() {
+            assert!(instancing(&root.nodes[child.value()]).is_some());
+        }
+        let animation = &root.animations[0];
+        assert_eq!(animation.channels.len(), 3);
+        assert_eq!(animation.channels[0].target.node, children[0]);
+        assert_eq!(
+            animation.samplers[1].interpolation,
+            gltf::json::validation::Checked::Valid(gltf::json::animation::Interpolation::Step)
+        );
+        assert!(validate::validate(&root, &[&data]).is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+//! Materials of meshes moved into separate child nodes.
+//!
+//! Viewers toggle visibility per node rather than per primitive, so giving
+//! each material of a mesh its own node lets reviewers hide some materials
+//! independently of others. The child meshes keep referencing the vertex and
+//! morph target accessors of the original mesh, so no data is duplicated, and
+//! each child is animated by a copy of the weights channel of its parent.
+
+use gltf::json;
+use indexmap::IndexMap;
+use json::validation::Checked::Valid;
+
+/// Moves each material group of the mesh of node `parent` into a child node.
+///
+/// The parent keeps its name, transform and any other children, but no
+/// longer references a mesh. Nodes whose primitives all share a material are
+/// left as they are.
+pub(crate) fn split_material_nodes(
+    parent: usize,
+    nodes: &mut Vec<json::Node>,
+    meshes: &mut Vec<json::Mesh>,
+    materials: &[json::Material],
+    channels: &mut Vec<json::animation::Channel>,
+    samplers: &mut Vec<json::animation::Sampler>,
+    targets: &mut Vec<String>,
+) {
+    let Some(mesh_index) = nodes[parent].mesh else {
+        return;
+    };
+    let mesh = &mut meshes[mesh_index.value()];
+    let mut groups: IndexMap<Option<usize>, Vec<json::mesh::Primitive>> = IndexMap::new();
+    for prim in mesh.primitives.drain(..) {
+        let material = prim.material.map(|material| material.value());
+        groups.entry(material).or_default().push(prim);
+    }
+    if groups.len() < 2 {
+        mesh.primitives = groups.into_values().flatten().collect();
+        return;
+    }
+    let mesh_extras = mesh.extras.clone();
+    let name = nodes[parent].name.clone().unwrap_or_default();
+
+    // Weights are animated per node, so every child needs its own channel.
+    let weight_channels: Vec<usize> = channels
+        .iter()
+        .enumerate()
+        .filter(|(_, channel)| {
+            channel.target.node.value() == parent
+                && channel.target.path == Valid(json::animation::Property::MorphTargetWeights)
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut children = Vec::with_capacity(groups.len());
+    for (group, (material, primitives)) in groups.into_iter().enumerate() {
+        // The first group keeps the original mesh, which may be referenced elsewhere.
+        let mesh = if group == 0 {
+            meshes[mesh_index.value()].primitives = primitives;
+            mesh_index
+        } else {
+            meshes.push(json::Mesh {
+                extensions: Default::default(),
+                extras: mesh_extras.clone(),
+                name: None,
+                primitives,
+                weights: None,
+            });
+            json::Index::new(meshes.len() as u32 - 1)
+        };
+        let material_name = match material {
+            Some(index) => materials
+                .get(index)
+                .and_then(|material| material.name.clone())
+                .unwrap_or_else(|| format!("material{}", index)),
+            None => "default".to_string(),
+        };
+        let child = json::Index::new(nodes.len() as u32);
+        nodes.push(json::Node {
+            mesh: Some(mesh),
+            name: Some(format!("{}_{}", name, material_name)),
+            ..Default::default()
+        });
+        children.push(child);
+
+        for &c in weight_channels.iter() {
+            if group == 0 {
+                channels[c].target.node = child;
+                continue;
+            }
+            let sampler = samplers[channels[c].sampler.value()].clone();
+            let mut channel = channels[c].clone();
+            channel.target.node = child;
+            channel.sampler = json::Index::new(samplers.len() as u32);
+            samplers.push(sampler);
+            channels.push(channel);
+            targets.push(targets[c].clone());
+        }
+    }
+
+    nodes[parent].mesh = None;
+    nodes[parent]
+        .children
+        .get_or_insert_with(Vec::new)
+        .extend(children);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{proxy, validate, AttribConfig, AttributeInfo, MaterialInfo, TextureAttributeInfo};
+
+    #[test]
+    fn split_materials() {
+        use gltf::json::animation::Property;
+        use meshx::attrib::Attrib;
+        use meshx::topology::FaceIndex;
+
+        // A unit cube with an interior and an exterior material, which grows over two frames.
+        let (positions, tris) = proxy::box_mesh([0.0; 3], [1.0; 3]);
+        let tris: Vec<[usize; 3]> = tris.iter().map(|t| t.map(|v| v as usize)).collect();
+        let mtl_ids: Vec<u32> = (0..tris.len())
+            .map(|t| (2 * t / tris.len()) as u32)
+            .collect();
+        let materials: Vec<MaterialInfo> =
+            ron::de::from_str("[(name:\"interior\"), (name:\"exterior\")]").unwrap();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .materials(materials)
+            .split_materials(true)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=2 {
+            let scaled = positions
+                .iter()
+                .map(|p| p.map(|x| x * frame as f32))
+                .collect();
+            let mut trimesh = meshx::TriMesh::new(scaled, tris.clone());
+            trimesh
+                .insert_attrib_data::<u32, FaceIndex>("mtl_id", mtl_ids.clone())
+                .unwrap();
+            sequence
+                .add_frame("cube", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        let cube = root
+            .nodes
+            .iter()
+            .find(|node| node.name.as_deref() == Some("cube"))
+            .unwrap();
+        assert!(cube.mesh.is_none());
+        let children: Vec<usize> = cube
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|child| child.value())
+            .collect();
+        let names: Vec<_> = children
+            .iter()
+            .map(|&child| root.nodes[child].name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["cube_interior", "cube_exterior"]);
+
+        // Each child has a single primitive and its own weights channel.
+        for &child in children.iter() {
+            let mesh = &root.meshes[root.nodes[child].mesh.unwrap().value()];
+            assert_eq!(mesh.primitives.len(), 1);
+            assert!(root.animations[0].channels.iter().any(|channel| {
+                channel.target.node.value() == child
+                    && channel.target.path == Valid(Property::MorphTargetWeights)
+            }));
+        }
+        assert!(validate::mismatched_morph_targets(&root).is_empty());
+    }
+}
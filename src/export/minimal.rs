@@ -0,0 +1,49 @@
+//! Removal of optional names and metadata for minimal output.
+//!
+//! Generated documents name accessors, buffer views, meshes and nodes and
+//! attach metadata in extras to make them easier to inspect. None of this is
+//! needed to display the output, so it can be dropped to save space.
+
+use gltf::json;
+
+/// Removes the names of accessors, buffer views, meshes and nodes.
+///
+/// Names of animations, materials, textures and images are kept, since
+/// viewers show them to users.
+pub(crate) fn strip_names(root: &mut json::Root) {
+    for acc in root.accessors.iter_mut() {
+        acc.name = None;
+    }
+    for view in root.buffer_views.iter_mut() {
+        view.name = None;
+    }
+    for mesh in root.meshes.iter_mut() {
+        mesh.name = None;
+    }
+    for node in root.nodes.iter_mut() {
+        node.name = None;
+    }
+}
+
+/// Removes the extras of all objects in the document.
+pub(crate) fn strip_extras(root: &mut json::Root) {
+    root.asset.extras = None;
+    for scene in root.scenes.iter_mut() {
+        scene.extras = None;
+    }
+    for node in root.nodes.iter_mut() {
+        node.extras = None;
+    }
+    for mesh in root.meshes.iter_mut() {
+        mesh.extras = None;
+        for prim in mesh.primitives.iter_mut() {
+            prim.extras = None;
+        }
+    }
+    for material in root.materials.iter_mut() {
+        material.extras = None;
+    }
+    for animation in root.animations.iter_mut() {
+        animation.extras = None;
+    }
+}
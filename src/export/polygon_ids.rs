@@ -0,0 +1,90 @@
+//! Source polygons of exported triangles.
+//!
+//! Quads and n-gons are split into triangles when meshes are loaded. When the
+//! source polygon of each triangle is known, it is recorded in the extras of
+//! each primitive under [`POLYGON_IDS_KEY`], one polygon index per triangle in
+//! the order of the primitive indices. This lets viewers draw the edges of the
+//! original polygons instead of the triangulated wireframe.
+
+use gltf::json;
+use serde_json::Value;
+
+use crate::attrib::{AttribTransfer, MaterialIds};
+use crate::extras::to_extras;
+use crate::Error;
+
+/// Key of the primitive extras holding the source polygon of each triangle.
+pub const POLYGON_IDS_KEY: &str = "polygonIds";
+
+/// Source polygons of the triangles of each primitive built from `attrib_transfer`.
+///
+/// Primitives are split by material like their indices, and the result is
+/// empty if no polygons were recorded.
+pub(crate) fn primitive_polygon_ids(attrib_transfer: &AttribTransfer) -> Vec<Vec<u32>> {
+    let ids = &attrib_transfer.polygon_ids;
+    if ids.is_empty() {
+        return Vec::new();
+    }
+    match &attrib_transfer.material_ids {
+        Some(MaterialIds::Global { map }) => map
+            .values()
+            .map(|faces| faces.iter().map(|&face| ids[face]).collect())
+            .collect(),
+        _ => vec![ids.clone()],
+    }
+}
+
+/// Adds the given source polygons of triangles to primitive extras.
+pub(crate) fn with_polygon_ids(extras: json::Extras, ids: &[u32]) -> Result<json::Extras, Error> {
+    let mut value = extras
+        .and_then(|extras| serde_json::from_str::<Value>(extras.get()).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Value::Object(map) = &mut value {
+        map.insert(POLYGON_IDS_KEY.to_string(), serde_json::json!(ids));
+    }
+    to_extras(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{triangulate, AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn polygon_ids() {
+        // A quad followed by a triangle sharing one of its edges.
+        let polymesh = meshx::PolyMesh::new(
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [2.0, 0.0, 0.0],
+            ],
+            &[4, 0, 1, 2, 3, 3, 1, 4, 2],
+        );
+        let polygons = triangulate::polygons(&polymesh);
+        let mut trimesh = meshx::TriMesh::<f32>::from(polymesh);
+        triangulate::insert_polygon_ids(&mut trimesh, 0, &polygons);
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder().quiet(true).build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        sequence.add_frame("quad", 1, Mesh::from(trimesh)).unwrap();
+        let root = sequence.build().unwrap().0.root;
+
+        let extras = root.meshes[0].primitives[0].extras.as_ref().unwrap();
+        let extras: serde_json::Value = serde_json::from_str(extras.get()).unwrap();
+        assert_eq!(extras[POLYGON_IDS_KEY], serde_json::json!([0, 0, 1]));
+    }
+}
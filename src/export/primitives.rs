@@ -1,5 +1,6 @@
 use crate::AttribTransfer;
 use crate::Attribute;
+use crate::MaterialIdRemap;
 use crate::MaterialIds;
 use crate::TextureAttribute;
 
@@ -7,6 +8,34 @@ use gltf::json;
 use gltf::json::validation::Checked;
 use json::validation::Checked::Valid;
 
+/// Resolves material ids found in meshes to indices of output materials.
+pub(crate) struct MaterialBinder<'a> {
+    pub remap: &'a MaterialIdRemap,
+    /// Number of materials that material ids can refer to.
+    pub num_materials: usize,
+    /// Material bound to ids that do not refer to any material.
+    pub fallback: Option<u32>,
+}
+
+impl MaterialBinder<'_> {
+    /// Returns the index of the material bound to the given material id.
+    pub(crate) fn bind(&self, id: u32) -> Option<u32> {
+        let index = self.remap.get(id);
+        if (index as usize) < self.num_materials {
+            Some(index)
+        } else {
+            self.fallback
+        }
+    }
+}
+
+/// Builds a primitive for each material found on the mesh.
+///
+/// All primitives reference the same vertex attribute and morph target
+/// accessors, so only the indices are written per material, and every
+/// primitive of the mesh has the same number of morph targets as required for
+/// its weights to be animated together. An empty `targets` slice produces
+/// primitives without morph targets.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn build_primitives(
     mode: Checked<json::mesh::Mode>,
@@ -18,11 +47,12 @@ pub(crate) fn build_primitives(
     color_attrib_acc_indices: &[u32],
     tex_attrib_acc_indices: &[u32],
     indices: Option<Vec<json::Index<json::Accessor>>>,
-    targets: Option<Vec<json::mesh::MorphTarget>>,
-    num_materials: usize,
+    targets: &[json::mesh::MorphTarget],
+    custom_semantics: &[String],
+    materials: &MaterialBinder,
     msgs: &mut Vec<(usize, String)>,
 ) -> Vec<json::mesh::Primitive> {
-    // TODO: Split the mesh into multiple primitives, one for each material that appears on the mesh.
+    let targets = (!targets.is_empty()).then(|| targets.to_vec());
     let build_attributes = || {
         let mut map = std::collections::BTreeMap::new();
         map.insert(
@@ -59,15 +89,10 @@ pub(crate) fn build_primitives(
             );
         }
         // Custom attributes
-        for (Attribute { name, .. }, &attrib_acc_index) in attrib_transfer
-            .attribs_to_keep
-            .iter()
-            .zip(attrib_acc_indices.iter())
+        for (semantic, &attrib_acc_index) in custom_semantics.iter().zip(attrib_acc_indices.iter())
         {
-            use heck::ToShoutySnakeCase;
-
             map.insert(
-                Valid(json::mesh::Semantic::Extras(name.to_shouty_snake_case())),
+                Valid(json::mesh::Semantic::Extras(semantic.clone())),
                 json::Index::new(attrib_acc_index),
             );
         }
@@ -85,12 +110,11 @@ pub(crate) fn build_primitives(
                     extras: Default::default(),
                     indices: Some(indices),
                     material: {
-                        if mtl_id < num_materials as u32 {
-                            Some(json::Index::new(mtl_id))
-                        } else {
+                        let material = materials.bind(mtl_id);
+                        if material.is_none() && materials.num_materials == 0 {
                             log!(msgs; "Material ID was found but no materials were specified.");
-                            None
                         }
+                        material.map(json::Index::new)
                     },
                     mode,
                     targets: targets.clone(),
@@ -111,7 +135,7 @@ pub(crate) fn build_primitives(
                         material: {
                             // Assign the material index only if there are materials there to prevent producing
                             // an invalid gltf.
-                            if num_materials > 0 {
+                            if materials.num_materials > 0 {
                                 Some(json::Index::new(0))
                             } else {
                                 None
@@ -134,11 +158,7 @@ pub(crate) fn build_primitives(
                 // an invalid gltf.
                 if let Some(MaterialIds::Global { map }) = &attrib_transfer.material_ids {
                     let mtl_id = map.keys().next().unwrap_or(&0);
-                    if *mtl_id < num_materials as u32 {
-                        Some(json::Index::new(*mtl_id))
-                    } else {
-                        None
-                    }
+                    materials.bind(*mtl_id).map(json::Index::new)
                 } else {
                     None
                 }
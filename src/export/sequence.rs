@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+
+use super::{build_gltf_from_nodes, export_nodes, Document, ExportConfig, ExportReport, Node};
+use crate::attrib::clean_mesh;
+use crate::config::NonFinitePolicy;
+use crate::finite::{find_non_finite, repair_non_finite};
+use crate::mesh::Mesh;
+use crate::utils::report_warnings;
+use crate::{AttribConfig, AttribTransfer, Error};
+
+/// Incrementally builds an animated glTF from frames pushed one at a time.
+///
+/// This is useful for exporting the output of a running simulation: each frame
+/// is converted into morph target displacements as soon as it is added, so full
+/// meshes are only kept for the first frame of each topology. The displacements
+/// of all frames are kept in memory until the document is built.
+///
+/// Frames of different objects may be interleaved, but frames of the same
+/// object must be added in increasing order.
+pub struct SequenceBuilder<'a> {
+    attrib_config: AttribConfig<'a>,
+    export_config: ExportConfig,
+    /// Nodes for each object name, kept sorted by name.
+    nodes: BTreeMap<String, Vec<Node>>,
+    last_frames: BTreeMap<String, u32>,
+    /// Number of warnings reported while adding frames.
+    warnings: usize,
+}
+
+impl<'a> SequenceBuilder<'a> {
+    pub fn new(attrib_config: AttribConfig<'a>, export_config: ExportConfig) -> Self {
+        SequenceBuilder {
+            attrib_config,
+            export_config,
+            nodes: BTreeMap::new(),
+            last_frames: BTreeMap::new(),
+            warnings: 0,
+        }
+    }
+
+    /// Adds the mesh for the given object name and frame.
+    pub fn add_frame(
+        &mut self,
+        name: impl Into<String>,
+        frame: u32,
+        mut mesh: Mesh,
+    ) -> Result<(), Error> {
+        let attrib_transfer = clean_mesh(&mut mesh, self.attrib_config, |e| {
+            log::warn!("{}, Skipping...", e)
+        });
+        self.add_clean_frame(name, frame, mesh, attrib_transfer)
+    }
+
+    /// Adds a mesh that was already cleaned with [`clean_mesh`] or loaded with
+    /// [`crate::load_and_clean_mesh`].
+    pub fn add_clean_frame(
+        &mut self,
+        name: impl Into<String>,
+        frame: u32,
+        mut mesh: Mesh,
+        mut attrib_transfer: AttribTransfer,
+    ) -> Result<(), Error> {
+        let name = name.into();
+        if let Some(&last) = self.last_frames.get(&name) {
+            if frame <= last {
+                return Err(Error::FrameOutOfOrder(name, frame));
+            }
+        }
+
+        if let Some(found) = find_non_finite(&mesh, &attrib_transfer) {
+            match self.export_config.non_finite {
+                NonFinitePolicy::Error => return Err(Error::NonFinite(name, frame, found)),
+                NonFinitePolicy::Drop => return Err(Error::NonFiniteDropped(name, frame, found)),
+                NonFinitePolicy::Previous => {
+                    let previous = self.nodes.get(&name).and_then(|nodes| nodes.last());
+                    if !previous.is_some_and(|previous| {
+                        repair_non_finite(&mut mesh, &mut attrib_transfer, previous)
+                    }) {
+                        return Err(Error::NonFiniteDropped(name, frame, found));
+                    }
+                    log::warn!(
+                        "Replaced non-finite values in frame {} of '{}' with the previous frame: {}",
+                        frame,
+                        name,
+                        found
+                    );
+                }
+            }
+        }
+        self.last_frames.insert(name.clone(), frame);
+
+        let nodes = self.nodes.entry(name.clone()).or_default();
+        let mut warnings = Vec::new();
+        self.export_config.morph_options().push_frame(
+            nodes,
+            name,
+            frame,
+            mesh,
+            attrib_transfer,
+            &mut warnings,
+        );
+        if !warnings.is_empty() {
+            self.warnings += report_warnings(&*self.export_config.progress_sink(), warnings);
+        }
+        Ok(())
+    }
+
+    /// Attaches metadata to the given frame of the named object.
+    ///
+    /// The metadata is stored in the extras of the object's nodes, keyed by
+    /// frame number (see [`crate::ExtrasInfo::add_frame`]).
+    pub fn add_frame_extras(&mut self, name: &str, frame: u32, value: serde_json::Value) {
+        self.export_config.extras.add_frame(name, frame, value);
+    }
+
+    /// Number of warnings reported while adding frames so far, counting repeats.
+    pub fn warning_count(&self) -> usize {
+        self.warnings
+    }
+
+    /// Number of frames added so far across all objects.
+    pub fn num_frames(&self) -> usize {
+        self.nodes
+            .values()
+            .flatten()
+            .map(|node| node.morphs.len() + 1)
+            .sum()
+    }
+
+    /// Builds the glTF document and its binary buffer in memory.
+    pub fn build(self) -> Result<(Document, Vec<u8>), Error> {
+        let progress = self.export_config.progress_sink();
+        let nodes = self.nodes.into_values().flatten().collect();
+        build_gltf_from_nodes(
+            nodes,
+            &self.export_config,
+            &*progress,
+            &mut ExportReport::default(),
+        )
+        .map(|(document, data, _)| (document, data))
+    }
+
+    /// Writes the glTF to the output specified in the export configuration.
+    pub fn finish(self) -> Result<ExportReport, Error> {
+        let nodes = self.nodes.into_values().flatten().collect();
+        let mut report = export_nodes(nodes, self.export_config)?;
+        report.warnings += self.warnings;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::build_gltf;
+    use crate::{
+        load_and_clean_meshes, load_mesh, AttributeInfo, LoadConfig, TextureAttributeInfo,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn sequence_builder() {
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .output("./tests/artifacts/sequence_test.glb")
+            .quiet(true)
+            .build();
+
+        let mesh_meta: Vec<_> = (1..=4)
+            .flat_map(|frame: u32| {
+                [
+                    ("box_rotate", format!("./assets/box_rotate_{}.vtk", frame)),
+                    ("tet", format!("./assets/tet_{}.vtk", frame.div_ceil(2))),
+                ]
+                .map(|(name, path)| (String::from(name), frame, PathBuf::from(path)))
+            })
+            .collect();
+
+        // Frames of both objects are interleaved.
+        let mut builder = SequenceBuilder::new(attrib_config, config.clone());
+        for (name, frame, path) in mesh_meta.iter() {
+            let mesh = load_mesh(path, LoadConfig::default()).unwrap();
+            builder.add_frame(name.as_str(), *frame, mesh).unwrap();
+        }
+        assert_eq!(builder.num_frames(), 8);
+        let mesh = load_mesh(&mesh_meta[0].2, LoadConfig::default()).unwrap();
+        assert!(matches!(
+            builder.add_frame("tet", 4, mesh),
+            Err(Error::FrameOutOfOrder(_, 4))
+        ));
+        let (document, data) = builder.build().unwrap();
+        let root = document.root;
+
+        let meshes = load_and_clean_meshes(mesh_meta, LoadConfig::default(), attrib_config);
+        let (expected, expected_data) = build_gltf(meshes, config).unwrap();
+        let expected_root = expected.root;
+
+        assert_eq!(data, expected_data);
+        assert_eq!(root.nodes.len(), expected_root.nodes.len());
+        assert_eq!(root.accessors.len(), expected_root.accessors.len());
+    }
+}
@@ -0,0 +1,183 @@
+//! Splitting the buffer of standard glTF output into one file per node.
+//!
+//! This lets clients fetch the data of each animated node only when it is
+//! needed, instead of downloading a single buffer holding every sequence.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use gltf::json;
+use json::validation::USize64;
+
+use super::align_data;
+use crate::uri::relative_uri;
+use crate::Error;
+
+/// Node owning the data of a buffer view.
+#[derive(Copy, Clone, PartialEq)]
+enum Owner {
+    Unused,
+    Node(usize),
+    Shared,
+}
+
+/// Buffer views holding the data of the given accessor, including sparse storage.
+fn accessor_views(acc: &json::Accessor) -> impl Iterator<Item = usize> + '_ {
+    let sparse = acc
+        .sparse
+        .iter()
+        .flat_map(|sparse| [sparse.indices.buffer_view, sparse.values.buffer_view]);
+    acc.buffer_view
+        .into_iter()
+        .chain(sparse)
+        .map(|view| view.value())
+}
+
+/// Determines which node owns each buffer view of `root`.
+///
+/// A view is owned by a node if it only holds vertex data, morph targets or
+/// animation samplers of that node. Samplers of pointer channels don't target
+/// a node, so their data stays in the shared buffer.
+fn view_owners(root: &json::Root) -> Vec<Owner> {
+    let mut owners = vec![Owner::Unused; root.buffer_views.len()];
+    let mut claim = |acc: json::Index<json::Accessor>, node: usize| {
+        let Some(acc) = root.accessors.get(acc.value()) else {
+            return;
+        };
+        for view in accessor_views(acc) {
+            if let Some(owner) = owners.get_mut(view) {
+                *owner = match *owner {
+                    Owner::Unused => Owner::Node(node),
+                    Owner::Node(n) if n == node => Owner::Node(node),
+                    _ => Owner::Shared,
+                };
+            }
+        }
+    };
+
+    for (node_index, node) in root.nodes.iter().enumerate() {
+        let Some(mesh) = node.mesh.and_then(|mesh| root.meshes.get(mesh.value())) else {
+            continue;
+        };
+        for prim in mesh.primitives.iter() {
+            for &acc in prim.attributes.values() {
+                claim(acc, node_index);
+            }
+            if let Some(indices) = prim.indices {
+                claim(indices, node_index);
+            }
+            for target in prim.targets.iter().flatten() {
+                for acc in [target.positions, target.normals, target.tangents]
+                    .into_iter()
+                    .flatten()
+                {
+                    claim(acc, node_index);
+                }
+            }
+        }
+    }
+
+    for anim in root.animations.iter() {
+        for channel in anim.channels.iter() {
+            let Some(sampler) = anim.samplers.get(channel.sampler.value()) else {
+                continue;
+            };
+            claim(sampler.input, channel.target.node.value());
+            claim(sampler.output, channel.target.node.value());
+        }
+    }
+    owners
+}
+
+/// Replaces characters that are not safe to use in file names.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Moves the data owned by each node into a separate buffer.
+///
+/// Vertex data, morph targets and animation samplers used by a single node
+/// are written to a file named after the node next to `binary_path`. Data
+/// shared between nodes or not used by any node (e.g. images) stays in the
+/// buffer at `binary_path`. The buffers of `root` are replaced accordingly.
+///
+/// Returns the path and contents of each buffer.
+pub(crate) fn split_buffers(
+    root: &mut json::Root,
+    data: &[u8],
+    binary_path: &Path,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    let owners = view_owners(root);
+    let stem = binary_path
+        .file_stem()
+        .ok_or_else(|| Error::InvalidOutputPath(binary_path.to_path_buf()))?;
+
+    let mut buffer_nodes: Vec<Option<usize>> = Vec::new();
+    let mut buffer_data: Vec<Vec<u8>> = Vec::new();
+    let mut buffer_index = HashMap::new();
+    for (view, owner) in root.buffer_views.iter_mut().zip(owners) {
+        let node = match owner {
+            Owner::Node(node) => Some(node),
+            Owner::Unused | Owner::Shared => None,
+        };
+        let index = *buffer_index.entry(node).or_insert_with(|| {
+            buffer_nodes.push(node);
+            buffer_data.push(Vec::new());
+            buffer_nodes.len() - 1
+        });
+        let start = view.byte_offset.map_or(0, |USize64(o)| o) as usize;
+        let end = start + view.byte_length.0 as usize;
+        let buffer = &mut buffer_data[index];
+        align_data(buffer);
+        view.byte_offset = Some(USize64(buffer.len() as u64));
+        view.buffer = json::Index::new(index as u32);
+        buffer.extend_from_slice(&data[start..end]);
+    }
+
+    // File names are built as OS strings, since the stem need not be valid UTF-8.
+    let file_name = |suffix: &str| {
+        let mut name = stem.to_os_string();
+        name.push(suffix);
+        name
+    };
+    let mut used_names = HashSet::from([file_name(".bin")]);
+    let mut buffers = Vec::new();
+    let mut files = Vec::new();
+    for (node, data) in buffer_nodes.into_iter().zip(buffer_data) {
+        let name = node.and_then(|node| root.nodes[node].name.clone());
+        let path = match node {
+            None => binary_path.to_path_buf(),
+            Some(node) => {
+                let base = match name.as_deref().map(sanitize) {
+                    Some(name) if !name.is_empty() => format!("_{}", name),
+                    _ => format!("_{}", node),
+                };
+                let mut name = file_name(&format!("{}.bin", base));
+                let mut n = 1;
+                while !used_names.insert(name.clone()) {
+                    name = file_name(&format!("{}_{}.bin", base, n));
+                    n += 1;
+                }
+                binary_path.with_file_name(name)
+            }
+        };
+        buffers.push(json::Buffer {
+            byte_length: data.len().into(),
+            extensions: Default::default(),
+            extras: Default::default(),
+            name,
+            uri: path.file_name().map(relative_uri),
+        });
+        files.push((path, data));
+    }
+    root.buffers = buffers;
+    Ok(files)
+}
@@ -0,0 +1,160 @@
+//! Color deltas of morph targets.
+//!
+//! glTF allows morph targets to displace `COLOR_0`, but
+//! [`json::mesh::MorphTarget`] has no field for it. While the document is
+//! built, the accessors of color deltas are kept in the extras of each
+//! primitive under [`TARGET_COLORS_KEY`], one entry (or `null`) per morph
+//! target, so that they follow the primitive when meshes are split. They are
+//! moved into [`super::Document::target_colors`] once the document is built.
+
+use gltf::json;
+use serde_json::Value;
+
+use super::TargetColors;
+use crate::extras::to_extras;
+use crate::Error;
+
+/// Key of the primitive extras holding the color delta accessors of its morph targets.
+const TARGET_COLORS_KEY: &str = "targetColors";
+
+/// Builds primitive extras referring to the given color delta accessors of each morph target.
+///
+/// Returns `None` if no morph target has color deltas.
+pub(crate) fn target_colors_extras(
+    colors: &[Option<json::Index<json::Accessor>>],
+) -> Result<json::Extras, Error> {
+    if colors.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    to_extras(&serde_json::json!({ TARGET_COLORS_KEY: colors }))
+}
+
+/// Removes the color delta accessors recorded in primitive extras.
+pub(crate) fn take_target_colors(root: &mut json::Root) -> Result<Vec<TargetColors>, Error> {
+    let mut target_colors = Vec::new();
+    for (mesh, mesh_json) in root.meshes.iter_mut().enumerate() {
+        for (primitive, prim) in mesh_json.primitives.iter_mut().enumerate() {
+            let Some(extras) = prim.extras.as_ref() else {
+                continue;
+            };
+            let mut extras: serde_json::Map<String, Value> =
+                serde_json::from_str(extras.get()).map_err(Error::GltfSerialize)?;
+            let Some(colors) = extras.remove(TARGET_COLORS_KEY) else {
+                continue;
+            };
+            target_colors.push(TargetColors {
+                mesh,
+                primitive,
+                colors: serde_json::from_value(colors).map_err(Error::GltfSerialize)?,
+            });
+            prim.extras = if extras.is_empty() {
+                None
+            } else {
+                to_extras(&Value::Object(extras))?
+            };
+        }
+    }
+    Ok(target_colors)
+}
+
+/// Adds the color delta accessors to `COLOR_0` of the corresponding morph targets.
+pub(crate) fn insert_target_colors(root: &mut Value, target_colors: &[TargetColors]) {
+    for TargetColors {
+        mesh,
+        primitive,
+        colors,
+    } in target_colors
+    {
+        let Some(targets) = root
+            .get_mut("meshes")
+            .and_then(|meshes| meshes.get_mut(mesh))
+            .and_then(|mesh| mesh.get_mut("primitives"))
+            .and_then(|prims| prims.get_mut(primitive))
+            .and_then(|prim| prim.get_mut("targets"))
+            .and_then(Value::as_array_mut)
+        else {
+            continue;
+        };
+        for (target, color) in targets.iter_mut().zip(colors) {
+            if let (Some(target), Some(color)) = (target.as_object_mut(), color) {
+                target.insert("COLOR_0".to_string(), color.value().into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{to_glb, ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::{AttribConfig, AttributeInfo, TextureAttributeInfo};
+
+    #[test]
+    fn animated_colors() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::VertexIndex;
+
+        let attributes = AttributeInfo::default();
+        let colors: AttributeInfo = r#"{"Cd": Vec3(F32)}"#.parse().unwrap();
+        let texcoords = TextureAttributeInfo::default();
+        let build = |animate_colors: bool| {
+            let attrib_config = AttribConfig {
+                attributes: &attributes,
+                colors: &colors,
+                texcoords: &texcoords,
+                material_attribute: "mtl_id",
+            };
+            let config = ExportConfig::builder()
+                .animate_colors(animate_colors)
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            for frame in 1..=3 {
+                let mut trimesh = meshx::TriMesh::new(
+                    vec![[0.0, 0.0, frame as f32], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                    vec![[0, 1, 2]],
+                );
+                // Colors change only in the last frame.
+                let red = if frame == 3 { 1.0 } else { 0.5 };
+                trimesh
+                    .insert_attrib_data::<[f32; 3], VertexIndex>("Cd", vec![[red, 0.0, 0.0]; 3])
+                    .unwrap();
+                sequence
+                    .add_frame("tri", frame, Mesh::from(trimesh))
+                    .unwrap();
+            }
+            sequence.build().unwrap()
+        };
+
+        // Colors are not animated by default.
+        let (document, _) = build(false);
+        assert!(document.target_colors.is_empty());
+
+        // Color deltas are recorded next to the root, which keeps no trace of them.
+        let (document, data) = build(true);
+        let root = &document.root;
+        assert!(root.meshes[0].primitives[0].extras.is_none());
+        assert_eq!(document.target_colors.len(), 1);
+        let TargetColors {
+            mesh,
+            primitive,
+            colors,
+        } = &document.target_colors[0];
+        assert_eq!((*mesh, *primitive), (0, 0));
+        assert_eq!(colors.len(), 2);
+        assert!(colors[0].is_none());
+        let color_acc = colors[1].unwrap().value();
+        assert_eq!(root.accessors[color_acc].count.0, 3);
+
+        // Color deltas are moved into the morph targets when serialized.
+        let glb = to_glb(&document, data).unwrap();
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        let prim = &json["meshes"][0]["primitives"][0];
+        assert!(prim.get("extras").is_none());
+        assert!(prim["targets"][0].get("COLOR_0").is_none());
+        assert_eq!(prim["targets"][1]["COLOR_0"], color_acc);
+        assert!(gltf::Gltf::from_slice(&glb).is_ok());
+    }
+}
@@ -0,0 +1,337 @@
+//! Reconstruction of mesh sequences from animated glTF files.
+
+use std::path::{Path, PathBuf};
+
+use gltf::json;
+use json::validation::Checked::Valid;
+use meshx::mesh::{PointCloud, TriMesh};
+
+use crate::config::NORMAL_ATTRIB_NAME;
+use crate::mesh::{insert_vertex_attrib, Mesh};
+use crate::validate::{read_accessor, read_indices};
+use crate::Error;
+
+/// Name of the texture coordinate attribute recognized by mesh writers.
+const UV_ATTRIB_NAME: &str = "uv";
+
+/// Position and normal displacements of a morph target.
+type TargetData = (Vec<[f32; 3]>, Vec<[f32; 3]>);
+
+/// Geometry of a single primitive along with its morph targets.
+#[derive(Default)]
+struct PrimitiveData {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<[usize; 3]>,
+    targets: Vec<TargetData>,
+}
+
+impl PrimitiveData {
+    /// Computes vertex positions and normals for the given morph target weights.
+    fn evaluate(&self, weights: &[f32]) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+        let mut positions = self.positions.clone();
+        let mut normals = self.normals.clone();
+        for (&w, (pos_disp, nml_disp)) in weights.iter().zip(self.targets.iter()) {
+            if w == 0.0 {
+                continue;
+            }
+            for (p, d) in positions.iter_mut().zip(pos_disp.iter()) {
+                for i in 0..3 {
+                    p[i] += w * d[i];
+                }
+            }
+            for (n, d) in normals.iter_mut().zip(nml_disp.iter()) {
+                for i in 0..3 {
+                    n[i] += w * d[i];
+                }
+            }
+        }
+        (positions, normals)
+    }
+}
+
+fn read_vecs<const N: usize>(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    acc: Option<json::Index<json::Accessor>>,
+) -> Vec<[f32; N]> {
+    acc.and_then(|acc| read_accessor(root, buffers, acc.value()))
+        .map(|values| {
+            values
+                .chunks_exact(N)
+                .map(|c| std::array::from_fn(|i| c[i] as f32))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the triangles of an index accessor.
+///
+/// Returns `None` if the indices can't be read or refer to vertices beyond `num_vertices`.
+fn read_triangles(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    acc: json::Index<json::Accessor>,
+    num_vertices: usize,
+) -> Option<Vec<[usize; 3]>> {
+    let indices = read_indices(root, buffers, acc.value())?;
+    if indices.iter().any(|&i| i as usize >= num_vertices) {
+        return None;
+    }
+    Some(
+        indices
+            .chunks_exact(3)
+            .map(|tri| [tri[0] as usize, tri[1] as usize, tri[2] as usize])
+            .collect(),
+    )
+}
+
+/// Reconstructs the mesh sequence of each animated node in the given document.
+///
+/// Meshes are evaluated at each keyframe of their morph target weights
+/// animation. Frame numbers are computed from keyframe times using
+/// `time_step`. Nodes without animation produce a single mesh at frame 0.
+///
+/// Only triangle and point primitives are supported. Vertex positions, normals
+/// and the first set of texture coordinates are extracted in the local
+/// coordinate frame of each node.
+///
+/// Returns the name, frame number and mesh of each extracted frame.
+pub fn extract_frames(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    time_step: f32,
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<Vec<(String, u32, Mesh)>, Error> {
+    use json::mesh::{Mode, Semantic};
+
+    let mut frames = Vec::new();
+    for (node_index, node) in root.nodes.iter().enumerate() {
+        let Some(mesh_index) = node.mesh else {
+            continue;
+        };
+        let mesh = &root.meshes[mesh_index.value()];
+        let name = node
+            .name
+            .clone()
+            .or_else(|| mesh.name.clone())
+            .unwrap_or_else(|| format!("node{}", node_index));
+
+        let mut triangles = Vec::new();
+        let mut points = Vec::new();
+        for prim in mesh.primitives.iter() {
+            let attrib = |semantic| prim.attributes.get(&Valid(semantic)).copied();
+            let mut data = PrimitiveData {
+                positions: read_vecs(root, buffers, attrib(Semantic::Positions)),
+                normals: read_vecs(root, buffers, attrib(Semantic::Normals)),
+                uvs: read_vecs(root, buffers, attrib(Semantic::TexCoords(0))),
+                ..Default::default()
+            };
+            for target in prim.targets.iter().flatten() {
+                data.targets.push((
+                    read_vecs(root, buffers, target.positions),
+                    read_vecs(root, buffers, target.normals),
+                ));
+            }
+            match prim.mode {
+                Valid(Mode::Triangles) => {
+                    let num_vertices = data.positions.len();
+                    data.indices = match prim.indices {
+                        Some(acc) => match read_triangles(root, buffers, acc, num_vertices) {
+                            Some(indices) => indices,
+                            None => {
+                                log!(warnings;
+                                    "Failed to read valid indices of a primitive of '{}'. Skipping primitive...",
+                                    name
+                                );
+                                continue;
+                            }
+                        },
+                        None => (0..num_vertices / 3)
+                            .map(|t| [3 * t, 3 * t + 1, 3 * t + 2])
+                            .collect(),
+                    };
+                    triangles.push(data);
+                }
+                Valid(Mode::Points) => points.push(data),
+                _ => {
+                    log!(warnings;
+                        "Only triangle and point primitives can be extracted. Skipping a primitive of '{}'...",
+                        name
+                    );
+                }
+            }
+        }
+        if !triangles.is_empty() && !points.is_empty() {
+            log!(warnings;
+                "Mesh '{}' has both triangle and point primitives. Skipping point primitives...",
+                name
+            );
+            points.clear();
+        }
+
+        // All primitives of a mesh should have the same number of morph targets.
+        let target_counts: Vec<usize> = mesh
+            .primitives
+            .iter()
+            .map(|p| p.targets.as_ref().map_or(0, Vec::len))
+            .collect();
+        let num_targets = target_counts.iter().copied().max().unwrap_or(0);
+        if target_counts.iter().any(|&n| n != num_targets) {
+            log!(warnings;
+                "Primitives of mesh '{}' have different numbers of morph targets. Missing targets are treated as zero displacements...",
+                name
+            );
+        }
+        for (time, weights) in keyframe_weights(root, buffers, node_index, num_targets, warnings) {
+            let frame = (time / time_step).round().max(0.0) as u32;
+            let mesh = if !triangles.is_empty() {
+                Mesh::TriMesh(Box::new(build_trimesh(&triangles, &weights)?))
+            } else {
+                let positions = points.iter().flat_map(|p| p.evaluate(&weights).0).collect();
+                Mesh::PointCloud(PointCloud::new(positions))
+            };
+            frames.push((name.clone(), frame, mesh));
+        }
+    }
+    Ok(frames)
+}
+
+/// Keyframe times and morph target weights animating the given node.
+///
+/// If the node is not animated, a single keyframe at time zero with zero
+/// weights is returned.
+fn keyframe_weights(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    node_index: usize,
+    num_targets: usize,
+    warnings: &mut Vec<(usize, String)>,
+) -> Vec<(f32, Vec<f32>)> {
+    use json::animation::{Interpolation, Property};
+
+    let static_frame = vec![(0.0, vec![0.0; num_targets])];
+    let sampler = root.animations.iter().find_map(|anim| {
+        anim.channels
+            .iter()
+            .find(|c| {
+                c.target.node.value() == node_index
+                    && c.target.path == Valid(Property::MorphTargetWeights)
+            })
+            .map(|c| &anim.samplers[c.sampler.value()])
+    });
+    let Some(sampler) = sampler else {
+        return static_frame;
+    };
+    if num_targets == 0 {
+        return static_frame;
+    }
+    let (Some(times), Some(weights)) = (
+        read_accessor(root, buffers, sampler.input.value()),
+        read_accessor(root, buffers, sampler.output.value()),
+    ) else {
+        log!(warnings; "Failed to read animation of node {}. Skipping animation...", node_index);
+        return static_frame;
+    };
+
+    // Cubic spline samplers store an in-tangent, value and out-tangent for each keyframe.
+    let (stride, value_offset) = match sampler.interpolation {
+        Valid(Interpolation::CubicSpline) => (3 * num_targets, num_targets),
+        _ => (num_targets, 0),
+    };
+    times
+        .iter()
+        .enumerate()
+        .filter_map(|(k, &t)| {
+            let start = k * stride + value_offset;
+            let w = weights.get(start..start + num_targets)?;
+            Some((t as f32, w.iter().map(|&w| w as f32).collect()))
+        })
+        .collect()
+}
+
+/// Combines all triangle primitives of a mesh into a single triangle mesh.
+fn build_trimesh(primitives: &[PrimitiveData], weights: &[f32]) -> Result<TriMesh<f32>, Error> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    let has_normals = primitives
+        .iter()
+        .all(|p| p.normals.len() == p.positions.len());
+    let has_uvs = primitives.iter().all(|p| p.uvs.len() == p.positions.len());
+    for prim in primitives.iter() {
+        let offset = positions.len();
+        let (pos, nml) = prim.evaluate(weights);
+        indices.extend(prim.indices.iter().map(|tri| tri.map(|i| i + offset)));
+        positions.extend(pos);
+        normals.extend(nml);
+        uvs.extend_from_slice(&prim.uvs);
+    }
+    let mut trimesh = TriMesh::new(positions, indices);
+    if has_normals {
+        insert_vertex_attrib(&mut trimesh, NORMAL_ATTRIB_NAME, normals)?;
+    }
+    if has_uvs {
+        insert_vertex_attrib(&mut trimesh, UV_ATTRIB_NAME, uvs)?;
+    }
+    Ok(trimesh)
+}
+
+/// Builds the path of an extracted frame from the given output pattern.
+///
+/// The `#` character in the file name is replaced by the frame number and
+/// `{}` is replaced by the mesh name. If the file name has no `#`, the frame
+/// number is appended to the file stem. If there are multiple meshes and the
+/// pattern has no `{}`, the mesh name is prepended to the file name.
+pub fn frame_path(pattern: &Path, name: &str, frame: u32, multiple_meshes: bool) -> PathBuf {
+    let file_name = pattern
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut file_name = if file_name.contains('#') {
+        file_name
+    } else {
+        match file_name.rsplit_once('.') {
+            Some((stem, ext)) => format!("{}_#.{}", stem, ext),
+            None => format!("{}_#", file_name),
+        }
+    };
+    if multiple_meshes && !file_name.contains("{}") {
+        file_name = format!("{{}}_{}", file_name);
+    }
+    let file_name = file_name
+        .replace('#', &frame.to_string())
+        .replace("{}", name);
+    pattern.with_file_name(file_name)
+}
+
+/// Extracts the mesh sequences from the glTF file at `path` and writes each
+/// frame to a file named according to `output` (see [`frame_path`]).
+///
+/// The mesh format is determined by the extension of `output`.
+///
+/// Returns the number of files written.
+pub fn extract_file(
+    path: &Path,
+    output: &Path,
+    time_step: f32,
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<usize, Error> {
+    let (document, buffers, _) = gltf::import(path)?;
+    let buffers: Vec<&[u8]> = buffers.iter().map(|b| b.0.as_slice()).collect();
+    let frames = extract_frames(document.as_json(), &buffers, time_step, warnings)?;
+    let multiple_meshes = frames.iter().any(|(name, _, _)| name != &frames[0].0);
+    for (name, frame, mesh) in frames.iter() {
+        let path = frame_path(output, name, *frame, multiple_meshes);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(Error::OutputDirectory)?;
+        }
+        match mesh {
+            Mesh::TriMesh(trimesh) => meshx::io::save_trimesh(trimesh, &path)?,
+            Mesh::PointCloud(ptcloud) => meshx::io::save_pointcloud(ptcloud, &path)?,
+        }
+    }
+    Ok(frames.len())
+}
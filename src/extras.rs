@@ -0,0 +1,175 @@
+use gltf::json;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::path::{Path, PathBuf};
+
+/// Key under which per-frame metadata is stored in node extras.
+pub const FRAME_EXTRAS_KEY: &str = "frames";
+
+/*
+ * Parsing extras info from command line
+ */
+
+/// Arbitrary JSON metadata attached to the `extras` field of glTF objects.
+///
+/// Nodes and materials are matched by name.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtrasInfo {
+    #[serde(default)]
+    pub asset: Option<Value>,
+    #[serde(default)]
+    pub scene: Option<Value>,
+    #[serde(default)]
+    pub nodes: IndexMap<String, Value>,
+    #[serde(default)]
+    pub materials: IndexMap<String, Value>,
+}
+
+impl std::str::FromStr for ExtrasInfo {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<ExtrasInfo, Self::Err> {
+        ron::de::from_str::<ExtrasInfo>(input).map_err(Self::Err::from)
+    }
+}
+
+impl ExtrasInfo {
+    /// Records metadata for a single frame of the named node.
+    ///
+    /// The value is stored in the node extras under [`FRAME_EXTRAS_KEY`],
+    /// keyed by the frame number. Existing extras of the node are kept if
+    /// they form a JSON object and replaced otherwise.
+    pub fn add_frame(&mut self, name: &str, frame: u32, value: Value) {
+        let extras = self
+            .nodes
+            .entry(name.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        if !extras.is_object() {
+            log::warn!(
+                "Extras of node '{}' are not a JSON object. Replacing with frame metadata...",
+                name
+            );
+            *extras = Value::Object(Default::default());
+        }
+        let frames = extras
+            .as_object_mut()
+            .unwrap()
+            .entry(FRAME_EXTRAS_KEY)
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Value::Object(frames) = frames {
+            frames.insert(frame.to_string(), value);
+        }
+    }
+
+    /// Attaches extras to named nodes and materials.
+    ///
+    /// Returns the names of nodes and materials for which no matching object was found.
+    pub(crate) fn apply(
+        &self,
+        nodes: &mut [json::Node],
+        materials: &mut [json::Material],
+    ) -> Result<Vec<String>, crate::Error> {
+        let mut unmatched = Vec::new();
+        for (name, value) in self.nodes.iter() {
+            let mut found = false;
+            for node in nodes
+                .iter_mut()
+                .filter(|n| n.name.as_deref() == Some(name.as_str()))
+            {
+                node.extras = to_extras(value)?;
+                found = true;
+            }
+            if !found {
+                unmatched.push(name.clone());
+            }
+        }
+        for (name, value) in self.materials.iter() {
+            let mut found = false;
+            for mtl in materials
+                .iter_mut()
+                .filter(|m| m.name.as_deref() == Some(name.as_str()))
+            {
+                mtl.extras = to_extras(value)?;
+                found = true;
+            }
+            if !found {
+                unmatched.push(name.clone());
+            }
+        }
+        Ok(unmatched)
+    }
+}
+
+/// Converts a JSON value into the raw representation stored in glTF objects.
+pub(crate) fn to_extras(value: &Value) -> Result<json::Extras, crate::Error> {
+    serde_json::value::to_raw_value(value)
+        .map(Some)
+        .map_err(crate::Error::GltfSerialize)
+}
+
+/// Path of the JSON sidecar file accompanying the given mesh file.
+///
+/// The sidecar has the same stem as the mesh with a `.json` extension.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    path.with_extension("json")
+}
+
+/// Reads the JSON sidecar of the given mesh file if one exists.
+pub fn load_sidecar(path: &Path) -> Result<Option<Value>, crate::Error> {
+    let path = sidecar_path(path);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let read = || -> Result<Value, String> {
+        let file = std::fs::File::open(&path).map_err(|err| err.to_string())?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|err| err.to_string())
+    };
+    read()
+        .map(Some)
+        .map_err(|err| crate::Error::Sidecar(path.clone(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{export_clean_meshes, ExportConfig};
+    use crate::tests::load_single_box;
+    use gltf::Gltf;
+
+    #[test]
+    fn custom_extras() {
+        let meshes = load_single_box();
+
+        let artifact = "./tests/artifacts/custom_extras.glb";
+
+        let extras: ExtrasInfo = "(asset:Some({\"shot\":\"sh010\"}), scene:Some({\"units\":\"m\"}), nodes:{\"box_rotate\":{\"solver\":\"v2.1\"}})"
+            .parse()
+            .unwrap();
+
+        export_clean_meshes(
+            meshes,
+            ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .extras(extras)
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
+
+        let gltf = Gltf::open(artifact).unwrap();
+        assert_eq!(
+            gltf.document.as_json().asset.extras.as_ref().unwrap().get(),
+            "{\"shot\":\"sh010\"}"
+        );
+        let scene = gltf.scenes().next().unwrap();
+        assert_eq!(scene.extras().as_ref().unwrap().get(), "{\"units\":\"m\"}");
+        let node = scene.nodes().next().unwrap();
+        assert_eq!(
+            node.extras().as_ref().unwrap().get(),
+            "{\"solver\":\"v2.1\"}"
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! C interface for embedding `gltfgen` in other applications.
+//!
+//! A context collects frames given as raw arrays and exports them either to a
+//! file or to a buffer in memory. All functions returning `int` return zero on
+//! success and a negative value on failure, in which case
+//! [`gltfgen_last_error`] describes the problem.
+//!
+//! The matching C header is in `include/gltfgen.h` and can be regenerated
+//! with `cbindgen --config cbindgen.toml --output include/gltfgen.h`. The
+//! crate is built as an `rlib` by default, so the shared library is built with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use meshx::mesh::vertex_positions::VertexPositions;
+
+use crate::bindings::Options;
+use crate::export::{build_gltf, export_clean_meshes, to_glb};
+use crate::mesh::{Mesh, RawAttribute};
+
+/// Frames to be exported along with the options used to export them.
+pub struct GltfgenContext {
+    options: Options,
+    meshes: Vec<(String, u32, Mesh)>,
+    last_error: Option<CString>,
+}
+
+impl GltfgenContext {
+    /// Runs `f` recording any error or panic as the last error.
+    fn run(&mut self, f: impl FnOnce(&mut Self) -> Result<(), String>) -> c_int {
+        let result = catch_unwind(AssertUnwindSafe(|| f(self)))
+            .unwrap_or_else(|_| Err("Unexpected panic in gltfgen".to_string()));
+        match result {
+            Ok(()) => {
+                self.last_error = None;
+                0
+            }
+            Err(msg) => {
+                self.last_error = CString::new(msg.replace('\0', " ")).ok();
+                -1
+            }
+        }
+    }
+}
+
+/// Creates a new context with default options.
+///
+/// The context must be freed with [`gltfgen_context_free`].
+#[no_mangle]
+pub extern "C" fn gltfgen_context_new() -> *mut GltfgenContext {
+    Box::into_raw(Box::new(GltfgenContext {
+        options: Options::default(),
+        meshes: Vec::new(),
+        last_error: None,
+    }))
+}
+
+/// Frees a context created with [`gltfgen_context_new`].
+///
+/// # Safety
+///
+/// `ctx` must be null or a pointer returned by [`gltfgen_context_new`] that
+/// has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_context_free(ctx: *mut GltfgenContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// Returns the message of the last error, or null if the last call succeeded.
+///
+/// The message is owned by the context and valid until the next call using it.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_last_error(ctx: *const GltfgenContext) -> *const c_char {
+    match ctx.as_ref().and_then(|ctx| ctx.last_error.as_ref()) {
+        Some(msg) => msg.as_ptr(),
+        None => std::ptr::null(),
+    }
+}
+
+/// Sets the export options from a JSON string.
+///
+/// Field names and defaults match those of the configuration file, e.g.
+/// `{"fps": 30, "attributes": {"pressure": "f32"}}`.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context and `json` a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_set_config(
+    ctx: *mut GltfgenContext,
+    json: *const c_char,
+) -> c_int {
+    let Some(ctx) = ctx.as_mut() else {
+        return -1;
+    };
+    ctx.run(|ctx| {
+        let json = to_str(json, "config")?;
+        ctx.options =
+            serde_json::from_str(json).map_err(|e| format!("Invalid configuration: {}", e))?;
+        Ok(())
+    })
+}
+
+/// Adds a frame of the object `name` from raw arrays.
+///
+/// `positions` holds `3 * num_vertices` coordinates. `indices` holds
+/// `3 * num_triangles` vertex indices, or is null to add a point cloud.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context, `name` a null terminated string, and the
+/// arrays must hold the given number of elements.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_add_frame(
+    ctx: *mut GltfgenContext,
+    name: *const c_char,
+    frame: u32,
+    positions: *const f32,
+    num_vertices: usize,
+    indices: *const u32,
+    num_triangles: usize,
+) -> c_int {
+    let Some(ctx) = ctx.as_mut() else {
+        return -1;
+    };
+    ctx.run(|ctx| {
+        let name = to_str(name, "name")?;
+        let positions: Vec<[f32; 3]> = chunks(positions, num_vertices, "positions")?;
+        let mesh = if indices.is_null() {
+            Mesh::from_raw_points(positions, Vec::new())
+        } else {
+            let indices = chunks(indices, num_triangles, "indices")?;
+            Mesh::from_raw(positions, indices, Vec::new())
+        }
+        .map_err(|e| e.to_string())?;
+        ctx.meshes.push((name.to_string(), frame, mesh));
+        Ok(())
+    })
+}
+
+/// Adds a vertex attribute to the most recently added frame.
+///
+/// `data` holds `num_components` values for each vertex of the frame, where
+/// `num_components` is between 1 and 4.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context, `name` a null terminated string, and `data`
+/// must hold `num_components` values per vertex.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_add_attribute(
+    ctx: *mut GltfgenContext,
+    name: *const c_char,
+    data: *const f32,
+    num_components: usize,
+) -> c_int {
+    let Some(ctx) = ctx.as_mut() else {
+        return -1;
+    };
+    ctx.run(|ctx| {
+        let name = to_str(name, "name")?;
+        let Some((_, _, mesh)) = ctx.meshes.last_mut() else {
+            return Err("No frame to add the attribute to".to_string());
+        };
+        let n = mesh.vertex_positions().len();
+        let values = match num_components {
+            1 => RawAttribute::F32(chunks::<f32, 1>(data, n, "data")?.concat()),
+            2 => RawAttribute::from(chunks::<f32, 2>(data, n, "data")?),
+            3 => RawAttribute::from(chunks::<f32, 3>(data, n, "data")?),
+            4 => RawAttribute::from(chunks::<f32, 4>(data, n, "data")?),
+            _ => {
+                return Err(format!(
+                    "Attribute '{}' must have between 1 and 4 components",
+                    name
+                ))
+            }
+        };
+        mesh.insert_raw_attribute(name, values)
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// Writes all added frames to a glTF file at `path`.
+///
+/// The format is determined by the extension as on the command line. The
+/// frames are removed from the context even if the export fails, so it can be
+/// reused for another export.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context and `path` a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_export_to_path(
+    ctx: *mut GltfgenContext,
+    path: *const c_char,
+) -> c_int {
+    let Some(ctx) = ctx.as_mut() else {
+        return -1;
+    };
+    ctx.run(|ctx| {
+        let path = PathBuf::from(to_str(path, "path")?);
+        let options = ctx.options.clone();
+        let meshes = std::mem::take(&mut ctx.meshes);
+        let (meshes, export_config) = options.prepare(meshes, Some(path));
+        export_clean_meshes(meshes, export_config).map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+/// Builds a GLB file from all added frames in memory.
+///
+/// The frames are removed from the context even if the export fails. On
+/// success `data` and `len` are set to the contents, which must be freed with
+/// [`gltfgen_buffer_free`].
+///
+/// # Safety
+///
+/// `ctx` must be a valid context, and `data` and `len` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_export_to_buffer(
+    ctx: *mut GltfgenContext,
+    data: *mut *mut u8,
+    len: *mut usize,
+) -> c_int {
+    let Some(ctx) = ctx.as_mut() else {
+        return -1;
+    };
+    ctx.run(|ctx| {
+        if data.is_null() || len.is_null() {
+            return Err("Output pointers must not be null".to_string());
+        }
+        let options = ctx.options.clone();
+        let meshes = std::mem::take(&mut ctx.meshes);
+        let (meshes, export_config) = options.prepare(meshes, None);
+        let (document, bin) = build_gltf(meshes, export_config).map_err(|e| e.to_string())?;
+        let glb = to_glb(&document, bin).map_err(|e| e.to_string())?;
+        let glb = Box::into_raw(glb.into_boxed_slice());
+        *len = glb.len();
+        *data = glb as *mut u8;
+        Ok(())
+    })
+}
+
+/// Frees a buffer returned by [`gltfgen_export_to_buffer`].
+///
+/// # Safety
+///
+/// `data` and `len` must be null or exactly as returned by
+/// [`gltfgen_export_to_buffer`].
+#[no_mangle]
+pub unsafe extern "C" fn gltfgen_buffer_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char, what: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("The {} must not be null", what));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| format!("The {} is not valid UTF-8", what))
+}
+
+/// Copies `n` groups of `N` values from a raw array.
+///
+/// Fails if the array would be larger than any allocation can be.
+unsafe fn chunks<T: Copy + Default, const N: usize>(
+    data: *const T,
+    n: usize,
+    what: &str,
+) -> Result<Vec<[T; N]>, String> {
+    if data.is_null() {
+        return Err(format!("The {} must not be null", what));
+    }
+    let len = n
+        .checked_mul(N)
+        .filter(|len| {
+            len.checked_mul(std::mem::size_of::<T>())
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        })
+        .ok_or_else(|| format!("The {} array is too large", what))?;
+    let data = std::slice::from_raw_parts(data, len);
+    Ok(data
+        .chunks_exact(N)
+        .map(|c| {
+            let mut out = [T::default(); N];
+            out.copy_from_slice(c);
+            out
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi_export() {
+        let positions: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let moved: [f32; 9] = [0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0];
+        let indices: [u32; 3] = [0, 1, 2];
+        let pressure: [f32; 3] = [1.0, 2.0, 3.0];
+        unsafe {
+            let ctx = gltfgen_context_new();
+            assert_eq!(
+                gltfgen_set_config(ctx, c"{\"attributes\": {\"pressure\": f32}}".as_ptr()),
+                -1
+            );
+            assert!(!gltfgen_last_error(ctx).is_null());
+            let config = c"{\"fps\": 30, \"attributes\": {\"pressure\": \"f32\"}}";
+            assert_eq!(gltfgen_set_config(ctx, config.as_ptr()), 0);
+            assert!(gltfgen_last_error(ctx).is_null());
+
+            // Attributes need a frame to be added to.
+            let name = c"pressure";
+            assert_eq!(
+                gltfgen_add_attribute(ctx, name.as_ptr(), pressure.as_ptr(), 1),
+                -1
+            );
+
+            for (frame, positions) in [positions, moved].iter().enumerate() {
+                let result = gltfgen_add_frame(
+                    ctx,
+                    c"tri".as_ptr(),
+                    frame as u32,
+                    positions.as_ptr(),
+                    3,
+                    indices.as_ptr(),
+                    1,
+                );
+                assert_eq!(result, 0);
+                assert_eq!(
+                    gltfgen_add_attribute(ctx, name.as_ptr(), pressure.as_ptr(), 1),
+                    0
+                );
+            }
+
+            let bad_indices: [u32; 3] = [0, 1, 3];
+            let result = gltfgen_add_frame(
+                ctx,
+                c"tri".as_ptr(),
+                2,
+                positions.as_ptr(),
+                3,
+                bad_indices.as_ptr(),
+                1,
+            );
+            assert_eq!(result, -1);
+            let msg = CStr::from_ptr(gltfgen_last_error(ctx));
+            assert!(msg.to_str().unwrap().contains("out of bounds"));
+
+            // Array sizes overflowing `usize` are rejected before reading.
+            let result = gltfgen_add_frame(
+                ctx,
+                c"tri".as_ptr(),
+                2,
+                positions.as_ptr(),
+                usize::MAX / 2,
+                indices.as_ptr(),
+                1,
+            );
+            assert_eq!(result, -1);
+            let msg = CStr::from_ptr(gltfgen_last_error(ctx));
+            assert!(msg.to_str().unwrap().contains("too large"));
+
+            let mut data = std::ptr::null_mut();
+            let mut len = 0;
+            assert_eq!(gltfgen_export_to_buffer(ctx, &mut data, &mut len), 0);
+            let glb = std::slice::from_raw_parts(data, len);
+            let gltf = gltf::Gltf::from_slice(glb).unwrap();
+            assert_eq!(gltf.meshes().count(), 1);
+            assert_eq!(gltf.animations().count(), 1);
+            gltfgen_buffer_free(data, len);
+            gltfgen_context_free(ctx);
+        }
+    }
+}
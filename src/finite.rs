@@ -0,0 +1,266 @@
+//! Detection and repair of non-finite (NaN or infinite) values in loaded frames.
+//!
+//! Simulations that blow up often write NaN positions, which glTF viewers fail
+//! to render. Frames are checked before they are converted into morph targets
+//! so that such values never reach the output.
+
+use meshx::mesh::vertex_positions::VertexPositions;
+
+use crate::attrib::*;
+use crate::export::Node;
+use crate::mesh::Mesh;
+
+/// Values that are checked for being finite.
+trait Finite: Copy {
+    fn is_finite(&self) -> bool;
+}
+
+macro_rules! impl_finite_for_int {
+    ($($t:ty)*) => {
+        $(
+            impl Finite for $t {
+                #[inline]
+                fn is_finite(&self) -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+impl_finite_for_int!(i8 u8 i16 u16 u32);
+
+impl Finite for f32 {
+    #[inline]
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+
+impl<T: Finite, const N: usize> Finite for [T; N] {
+    #[inline]
+    fn is_finite(&self) -> bool {
+        self.iter().all(Finite::is_finite)
+    }
+}
+
+fn count_non_finite<T: Finite + 'static>(attrib: &VertexAttribute) -> usize {
+    attrib
+        .as_slice::<T>()
+        .map_or(0, |values| values.iter().filter(|v| !v.is_finite()).count())
+}
+
+/// Replaces non-finite values in `values` with the corresponding values in `previous`.
+///
+/// Returns `false` if some value could not be replaced.
+fn replace_non_finite<T: Finite>(values: &mut [T], previous: &[T]) -> bool {
+    if values.len() != previous.len() {
+        return values.iter().all(Finite::is_finite);
+    }
+    let mut repaired = true;
+    for (value, prev) in values.iter_mut().zip(previous) {
+        if !value.is_finite() {
+            if prev.is_finite() {
+                *value = *prev;
+            } else {
+                repaired = false;
+            }
+        }
+    }
+    repaired
+}
+
+fn replace_non_finite_attrib<T: Finite + 'static>(
+    attrib: &mut VertexAttribute,
+    previous: Option<&VertexAttribute>,
+) -> bool {
+    let Ok(values) = attrib.as_mut_slice::<T>() else {
+        return true;
+    };
+    match previous.and_then(|prev| prev.as_slice::<T>().ok()) {
+        Some(prev) => replace_non_finite(values, prev),
+        None => values.iter().all(Finite::is_finite),
+    }
+}
+
+fn count_non_finite_tex(attrib: &TextureAttribute) -> usize {
+    match attrib.component_type {
+        ComponentType::F32 => {
+            count_non_finite::<[f32; 2]>(&attrib.attribute)
+                + count_non_finite::<[f32; 3]>(&attrib.attribute)
+        }
+        _ => 0,
+    }
+}
+
+/// Describes the non-finite values in the given frame, or returns `None` if all values are finite.
+///
+/// Positions, normals, tangents as well as all attributes that are exported are checked.
+pub fn find_non_finite(mesh: &Mesh, attrib_transfer: &AttribTransfer) -> Option<String> {
+    let count_vec3 = |values: &[[f32; 3]]| values.iter().filter(|v| !Finite::is_finite(*v)).count();
+    let mut found = Vec::new();
+    let mut push = |count: usize, what: String| {
+        if count > 0 {
+            found.push(format!("{} {}", count, what));
+        }
+    };
+    push(count_vec3(mesh.vertex_positions()), "position(s)".into());
+    push(
+        count_vec3(&attrib_transfer.normal_attrib),
+        "normal(s)".into(),
+    );
+    push(
+        count_vec3(&attrib_transfer.tangent_attrib),
+        "tangent(s)".into(),
+    );
+    for attrib in attrib_transfer
+        .attribs_to_keep
+        .iter()
+        .chain(attrib_transfer.color_attribs_to_keep.iter())
+    {
+        let count = call_typed_fn!(attrib.type_ => self::count_non_finite::<_>(&attrib.attribute));
+        push(count, format!("value(s) of '{}'", attrib.name));
+    }
+    for attrib in attrib_transfer.tex_attribs_to_keep.iter() {
+        push(
+            count_non_finite_tex(attrib),
+            format!("value(s) of '{}'", attrib.name),
+        );
+    }
+    if found.is_empty() {
+        None
+    } else {
+        Some(found.join(", "))
+    }
+}
+
+/// Replaces non-finite values in a frame with the values of the last frame in `previous`.
+///
+/// Positions, normals and tangents of the last frame are reconstructed from
+/// the morph targets of `previous`. Other attributes are taken from the first
+/// frame of `previous`, since only those are exported. Returns `false` if some
+/// values could not be replaced, for instance because the vertex count changed.
+pub(crate) fn repair_non_finite(
+    mesh: &mut Mesh,
+    attrib_transfer: &mut AttribTransfer,
+    previous: &Node,
+) -> bool {
+    // Displacements of the latest frame, ignoring the vanishing frame inserted before the first.
+    let last_morph = previous
+        .morphs
+        .iter()
+        .rev()
+        .find(|morph| morph.frame >= previous.first_frame);
+    let displaced = |base: &[[f32; 3]], disp: Option<&Vec<[f32; 3]>>| -> Vec<[f32; 3]> {
+        match disp.filter(|disp| disp.len() == base.len()) {
+            Some(disp) => base
+                .iter()
+                .zip(disp)
+                .map(|(b, d)| [b[0] + d[0], b[1] + d[1], b[2] + d[2]])
+                .collect(),
+            None => base.to_vec(),
+        }
+    };
+
+    let prev_positions = displaced(
+        previous.mesh.vertex_positions(),
+        last_morph.map(|m| &m.position_disp),
+    );
+    let prev_normals = displaced(
+        &previous.attrib_transfer.normal_attrib,
+        last_morph.map(|m| &m.normal_disp),
+    );
+    let prev_tangents = displaced(
+        &previous.attrib_transfer.tangent_attrib,
+        last_morph.map(|m| &m.tangent_disp),
+    );
+
+    let mut repaired = replace_non_finite(mesh.vertex_positions_mut(), &prev_positions);
+    repaired &= replace_non_finite(&mut attrib_transfer.normal_attrib, &prev_normals);
+    repaired &= replace_non_finite(&mut attrib_transfer.tangent_attrib, &prev_tangents);
+
+    let prev_transfer = &previous.attrib_transfer;
+    for (attribs, prev_attribs) in [
+        (
+            &mut attrib_transfer.attribs_to_keep,
+            &prev_transfer.attribs_to_keep,
+        ),
+        (
+            &mut attrib_transfer.color_attribs_to_keep,
+            &prev_transfer.color_attribs_to_keep,
+        ),
+    ] {
+        for attrib in attribs.iter_mut() {
+            let prev = prev_attribs
+                .iter()
+                .find(|prev| prev.name == attrib.name && prev.type_ == attrib.type_)
+                .map(|prev| &prev.attribute);
+            repaired &= call_typed_fn!(attrib.type_ => self::replace_non_finite_attrib::<_>(&mut attrib.attribute, prev));
+        }
+    }
+    for attrib in attrib_transfer.tex_attribs_to_keep.iter_mut() {
+        if attrib.component_type != ComponentType::F32 {
+            continue;
+        }
+        let prev = prev_transfer
+            .tex_attribs_to_keep
+            .iter()
+            .find(|prev| prev.name == attrib.name)
+            .map(|prev| &prev.attribute);
+        repaired &= replace_non_finite_attrib::<[f32; 2]>(&mut attrib.attribute, prev)
+            && replace_non_finite_attrib::<[f32; 3]>(&mut attrib.attribute, prev);
+    }
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::{AttribConfig, Error};
+
+    #[test]
+    fn non_finite_policy() {
+        use crate::config::NonFinitePolicy;
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let triangle = |z: f32| {
+            Mesh::from(meshx::TriMesh::new(
+                vec![[0.0, 0.0, z], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            ))
+        };
+
+        let add_frames = |policy| {
+            let config = ExportConfig::builder()
+                .non_finite(policy)
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            sequence.add_frame("tri", 1, triangle(0.5)).unwrap();
+            let result = sequence.add_frame("tri", 2, triangle(f32::NAN));
+            (sequence, result)
+        };
+
+        let (_, result) = add_frames(NonFinitePolicy::Error);
+        assert!(matches!(result, Err(Error::NonFinite(_, 2, _))));
+        let (sequence, result) = add_frames(NonFinitePolicy::Drop);
+        assert!(matches!(result, Err(Error::NonFiniteDropped(_, 2, _))));
+        assert_eq!(sequence.num_frames(), 1);
+
+        let (sequence, result) = add_frames(NonFinitePolicy::Previous);
+        assert!(result.is_ok());
+        assert_eq!(sequence.num_frames(), 2);
+        let (_, data) = sequence.build().unwrap();
+        assert!(data
+            .chunks_exact(4)
+            .all(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]).is_finite()));
+    }
+}
@@ -0,0 +1,161 @@
+//! Callbacks for customizing the generated glTF document before it is written.
+
+use std::sync::Arc;
+
+use gltf::json;
+use indexmap::IndexMap;
+
+type Hook<T> = Arc<dyn Fn(&mut T) + Send + Sync>;
+
+/// Callbacks invoked on the finished glTF document just before serialization.
+///
+/// These can be used to attach vendor extensions or extras that `gltfgen`
+/// doesn't know about. Node and material hooks are matched by name and are run
+/// before the hooks on the whole document. Channels animating material and
+/// light properties and color deltas of morph targets are kept outside of the
+/// root (see [`crate::export::Document`]), so they are not visible to the hooks.
+#[derive(Clone, Default)]
+pub struct ExportHooks {
+    root: Vec<Hook<json::Root>>,
+    nodes: IndexMap<String, Vec<Hook<json::Node>>>,
+    materials: IndexMap<String, Vec<Hook<json::Material>>>,
+}
+
+impl std::fmt::Debug for ExportHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportHooks")
+            .field("root", &self.root.len())
+            .field("nodes", &self.nodes.keys().collect::<Vec<_>>())
+            .field("materials", &self.materials.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ExportHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a callback on the whole document.
+    pub fn root(mut self, hook: impl Fn(&mut json::Root) + Send + Sync + 'static) -> Self {
+        self.root.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a callback on every node with the given name.
+    pub fn node(
+        mut self,
+        name: impl Into<String>,
+        hook: impl Fn(&mut json::Node) + Send + Sync + 'static,
+    ) -> Self {
+        self.nodes
+            .entry(name.into())
+            .or_default()
+            .push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a callback on every material with the given name.
+    pub fn material(
+        mut self,
+        name: impl Into<String>,
+        hook: impl Fn(&mut json::Material) + Send + Sync + 'static,
+    ) -> Self {
+        self.materials
+            .entry(name.into())
+            .or_default()
+            .push(Arc::new(hook));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty() && self.nodes.is_empty() && self.materials.is_empty()
+    }
+
+    /// Runs all hooks on the given document.
+    ///
+    /// Returns the names of nodes and materials for which no matching object was found.
+    pub(crate) fn apply(&self, root: &mut json::Root) -> Vec<String> {
+        let mut unmatched = Vec::new();
+        for (name, hooks) in self.nodes.iter() {
+            let mut found = false;
+            for node in root
+                .nodes
+                .iter_mut()
+                .filter(|n| n.name.as_deref() == Some(name.as_str()))
+            {
+                hooks.iter().for_each(|hook| hook(node));
+                found = true;
+            }
+            if !found {
+                unmatched.push(name.clone());
+            }
+        }
+        for (name, hooks) in self.materials.iter() {
+            let mut found = false;
+            for mtl in root
+                .materials
+                .iter_mut()
+                .filter(|m| m.name.as_deref() == Some(name.as_str()))
+            {
+                hooks.iter().for_each(|hook| hook(mtl));
+                found = true;
+            }
+            if !found {
+                unmatched.push(name.clone());
+            }
+        }
+        for hook in self.root.iter() {
+            hook(root);
+        }
+        unmatched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{build_gltf, ExportConfig};
+    use crate::extras::to_extras;
+    use crate::{
+        load_and_clean_meshes, AttribConfig, AttributeInfo, LoadConfig, TextureAttributeInfo,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn export_hooks() {
+        let mesh_meta = (1..=2)
+            .map(|frame| {
+                let path = format!("./assets/box_rotate_{}.vtk", frame);
+                (String::from("box_rotate"), frame, PathBuf::from(path))
+            })
+            .collect();
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let meshes = load_and_clean_meshes(mesh_meta, LoadConfig::default(), attrib_config);
+
+        let hooks = ExportHooks::new()
+            .node("box_rotate", |node| {
+                node.extras = to_extras(&serde_json::json!({ "id": 7 })).unwrap();
+            })
+            .material("missing", |_| panic!("no such material"))
+            .root(|root| root.extensions_used.push(String::from("VENDOR_custom")));
+        let config = ExportConfig::builder().quiet(true).hooks(hooks).build();
+        let root = build_gltf(meshes, config).unwrap().0.root;
+
+        assert_eq!(root.extensions_used, vec![String::from("VENDOR_custom")]);
+        let node = root
+            .nodes
+            .iter()
+            .find(|n| n.name.as_deref() == Some("box_rotate"))
+            .unwrap();
+        assert_eq!(node.extras.as_ref().unwrap().get(), r#"{"id":7}"#);
+    }
+}
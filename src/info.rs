@@ -0,0 +1,243 @@
+//! Summaries of existing glTF files used by the `info` subcommand.
+
+use std::fmt;
+use std::path::Path;
+
+use gltf::json;
+use indexmap::IndexMap;
+use json::validation::Checked::Valid;
+
+/// Structured summary of a glTF document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GltfSummary {
+    pub generator: Option<String>,
+    pub num_scenes: usize,
+    pub num_nodes: usize,
+    pub num_meshes: usize,
+    pub num_primitives: usize,
+    pub num_morph_targets: usize,
+    pub num_materials: usize,
+    pub num_textures: usize,
+    pub num_images: usize,
+    pub num_accessors: usize,
+    /// Total number of bytes referenced by accessors.
+    pub accessor_bytes: u64,
+    /// Name and duration in seconds of each animation.
+    pub animations: Vec<(Option<String>, f32)>,
+    /// Number of bytes in each buffer.
+    pub buffers: Vec<u64>,
+    /// Number of bytes stored in buffer views for each category of data.
+    pub buffer_breakdown: IndexMap<&'static str, u64>,
+    /// Number of bytes of morph target displacements for each node.
+    pub morph_breakdown: IndexMap<String, u64>,
+}
+
+impl GltfSummary {
+    /// Reads and summarizes the glTF file at `path`.
+    ///
+    /// Only the JSON part is read, so external buffers need not be present.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<GltfSummary, gltf::Error> {
+        let gltf = gltf::Gltf::open(path)?;
+        Ok(GltfSummary::new(gltf.document.as_json()))
+    }
+
+    pub fn new(root: &json::Root) -> GltfSummary {
+        let mut view_category = vec!["other"; root.buffer_views.len()];
+        let mut set_category = |acc: json::Index<json::Accessor>, category: &'static str| {
+            for view in accessor_views(&root.accessors[acc.value()]) {
+                view_category[view.value()] = category;
+            }
+        };
+
+        let mut num_primitives = 0;
+        let mut num_morph_targets = 0;
+        for mesh in root.meshes.iter() {
+            // Primitives of a mesh share its morph targets.
+            num_morph_targets += mesh
+                .primitives
+                .first()
+                .and_then(|prim| prim.targets.as_ref())
+                .map_or(0, Vec::len);
+            for prim in mesh.primitives.iter() {
+                num_primitives += 1;
+                for (semantic, &acc) in prim.attributes.iter() {
+                    set_category(acc, semantic_category(semantic));
+                }
+                if let Some(acc) = prim.indices {
+                    set_category(acc, "indices");
+                }
+                for target in prim.targets.iter().flatten() {
+                    for (acc, category) in [
+                        (target.positions, "morph positions"),
+                        (target.normals, "morph normals"),
+                        (target.tangents, "morph tangents"),
+                    ] {
+                        if let Some(acc) = acc {
+                            set_category(acc, category);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut animations = Vec::new();
+        for anim in root.animations.iter() {
+            let mut duration = 0.0_f32;
+            for sampler in anim.samplers.iter() {
+                set_category(sampler.input, "animation");
+                set_category(sampler.output, "animation");
+                let input = &root.accessors[sampler.input.value()];
+                if let Some(max) = input
+                    .max
+                    .as_ref()
+                    .and_then(|m| m.get(0))
+                    .and_then(|m| m.as_f64())
+                {
+                    duration = duration.max(max as f32);
+                }
+            }
+            animations.push((anim.name.clone(), duration));
+        }
+
+        for image in root.images.iter() {
+            if let Some(view) = image.buffer_view {
+                view_category[view.value()] = "images";
+            }
+        }
+
+        let mut buffer_breakdown = IndexMap::new();
+        for (view, category) in root.buffer_views.iter().zip(view_category) {
+            *buffer_breakdown.entry(category).or_insert(0) += view.byte_length.0;
+        }
+        let referenced: u64 = buffer_breakdown.values().sum();
+        let buffer_bytes: u64 = root.buffers.iter().map(|b| b.byte_length.0).sum();
+        if buffer_bytes > referenced {
+            // Alignment padding and data not referenced by any view.
+            buffer_breakdown.insert("padding", buffer_bytes - referenced);
+        }
+        buffer_breakdown.sort_by(|_, a, _, b| b.cmp(a));
+
+        let mut morph_breakdown = IndexMap::new();
+        for (i, node) in root.nodes.iter().enumerate() {
+            let Some(mesh) = node.mesh else {
+                continue;
+            };
+            let mesh = &root.meshes[mesh.value()];
+            let mut views: Vec<_> = mesh
+                .primitives
+                .iter()
+                .flat_map(|prim| prim.targets.iter().flatten())
+                .flat_map(|target| [target.positions, target.normals, target.tangents])
+                .flatten()
+                .flat_map(|acc| accessor_views(&root.accessors[acc.value()]))
+                .map(|view| view.value())
+                .collect();
+            if views.is_empty() {
+                continue;
+            }
+            views.sort_unstable();
+            views.dedup();
+            let name = [&node.name, &mesh.name]
+                .into_iter()
+                .flatten()
+                .find(|name| !name.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("#{}", i));
+            let bytes = views
+                .into_iter()
+                .map(|view| root.buffer_views[view].byte_length.0)
+                .sum::<u64>();
+            *morph_breakdown.entry(name).or_insert(0) += bytes;
+        }
+        morph_breakdown.sort_by(|_, a, _, b| b.cmp(a));
+
+        let accessor_bytes = root
+            .accessors
+            .iter()
+            .map(|acc| match (&acc.component_type, &acc.type_) {
+                (Valid(ct), Valid(t)) => acc.count.0 * (ct.0.size() * t.multiplicity()) as u64,
+                _ => 0,
+            })
+            .sum();
+
+        GltfSummary {
+            generator: root.asset.generator.clone(),
+            num_scenes: root.scenes.len(),
+            num_nodes: root.nodes.len(),
+            num_meshes: root.meshes.len(),
+            num_primitives,
+            num_morph_targets,
+            num_materials: root.materials.len(),
+            num_textures: root.textures.len(),
+            num_images: root.images.len(),
+            num_accessors: root.accessors.len(),
+            accessor_bytes,
+            animations,
+            buffers: root.buffers.iter().map(|b| b.byte_length.0).collect(),
+            buffer_breakdown,
+            morph_breakdown,
+        }
+    }
+}
+
+/// Buffer views holding the data of the given accessor, including sparse storage.
+fn accessor_views(acc: &json::Accessor) -> impl Iterator<Item = json::Index<json::buffer::View>> {
+    let sparse = acc
+        .sparse
+        .as_ref()
+        .map(|sparse| [sparse.indices.buffer_view, sparse.values.buffer_view]);
+    acc.buffer_view
+        .into_iter()
+        .chain(sparse.into_iter().flatten())
+}
+
+fn semantic_category(semantic: &json::validation::Checked<json::mesh::Semantic>) -> &'static str {
+    use json::mesh::Semantic;
+    match semantic {
+        Valid(Semantic::Positions) => "positions",
+        Valid(Semantic::Normals) => "normals",
+        Valid(Semantic::Tangents) => "tangents",
+        Valid(Semantic::Colors(_)) => "colors",
+        Valid(Semantic::TexCoords(_)) => "texture coordinates",
+        Valid(Semantic::Joints(_)) | Valid(Semantic::Weights(_)) => "skinning",
+        _ => "custom attributes",
+    }
+}
+
+impl fmt::Display for GltfSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::utils::HumanBytes;
+        if let Some(generator) = &self.generator {
+            writeln!(f, "generator: {}", generator)?;
+        }
+        writeln!(f, "scenes: {}", self.num_scenes)?;
+        writeln!(f, "nodes: {}", self.num_nodes)?;
+        writeln!(
+            f,
+            "meshes: {} ({} primitives, {} morph targets)",
+            self.num_meshes, self.num_primitives, self.num_morph_targets
+        )?;
+        writeln!(
+            f,
+            "materials: {}, textures: {}, images: {}",
+            self.num_materials, self.num_textures, self.num_images
+        )?;
+        writeln!(
+            f,
+            "accessors: {} ({})",
+            self.num_accessors,
+            HumanBytes(self.accessor_bytes)
+        )?;
+        writeln!(f, "animations: {}", self.animations.len())?;
+        for (i, (name, duration)) in self.animations.iter().enumerate() {
+            let name = name.clone().unwrap_or_else(|| format!("#{}", i));
+            writeln!(f, "  {}: {:.3}s", name, duration)?;
+        }
+        let total: u64 = self.buffers.iter().sum();
+        writeln!(f, "buffers: {} ({})", self.buffers.len(), HumanBytes(total))?;
+        for (category, bytes) in self.buffer_breakdown.iter() {
+            writeln!(f, "  {:<20} {}", category, HumanBytes(*bytes))?;
+        }
+        Ok(())
+    }
+}
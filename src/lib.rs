@@ -1,32 +1,185 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use meshx::algo::Merge;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
 #[macro_use]
 pub mod utils;
 #[macro_use]
 pub mod attrib;
+#[cfg(any(feature = "wasm", feature = "ffi"))]
+mod bindings;
+pub mod cache;
 pub mod config;
+pub mod diff;
+pub mod discover;
 pub mod error;
 pub mod export;
+pub mod extract;
+pub mod extras;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod finite;
+pub mod hooks;
+pub mod info;
+pub mod light;
+pub mod lint;
+#[cfg(feature = "manifest")]
+pub mod manifest;
 pub mod material;
+pub mod merge;
 pub mod mesh;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+pub mod pattern;
+pub mod pointer;
+pub mod preview;
+pub mod probe;
+pub mod progress;
+pub mod proxy;
+pub mod report;
+pub mod scene;
+pub mod schema;
+#[cfg(feature = "cli")]
+pub mod serve;
 pub mod texture;
+pub mod time;
+pub mod triangulate;
+pub mod uri;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod winding;
 
 pub use attrib::*;
+pub use discover::{discover_frames, DiscoverOptions, FrameMeta};
 pub use error::*;
+pub use extras::*;
+pub use hooks::ExportHooks;
+pub use light::*;
 pub use material::*;
+pub use pointer::*;
+pub use scene::*;
 pub use texture::*;
 pub use utils::*;
 
-use mesh::{trimesh_f64_to_f32, Mesh};
+use mesh::{trimesh_f64_to_f32, Mesh, MeshFormat};
 
 /// Configuration for loading meshes.
-#[derive(Clone, Copy, Debug)]
+///
+/// Use [`LoadConfig::builder`] to construct it.
+#[derive(Clone, Copy, Debug, Default)]
+#[non_exhaustive]
 pub struct LoadConfig {
     pub reverse: bool,
+    /// How triangles with inconsistent winding are flipped.
+    pub fix_winding: config::WindingFix,
     pub invert_tets: bool,
+    /// Format of the files to load, or `None` to probe each file.
+    pub format: Option<MeshFormat>,
+    /// How polygons with more than three vertices are split into triangles.
+    pub triangulation: config::Triangulation,
+    /// Record the source polygon of each triangle.
+    pub polygon_ids: bool,
+}
+
+impl LoadConfig {
+    /// Starts building a load configuration with all options disabled.
+    pub fn builder() -> LoadConfigBuilder {
+        LoadConfigBuilder::default()
+    }
+}
+
+/// Builder for [`LoadConfig`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadConfigBuilder {
+    config: LoadConfig,
+}
+
+impl LoadConfigBuilder {
+    /// Reverse polygon orientation.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.config.reverse = reverse;
+        self
+    }
+    /// Flip triangles wound inconsistently with their neighbours.
+    pub fn fix_winding(mut self, fix_winding: config::WindingFix) -> Self {
+        self.config.fix_winding = fix_winding;
+        self
+    }
+    /// Invert tetrahedra before extracting their surface.
+    pub fn invert_tets(mut self, invert_tets: bool) -> Self {
+        self.config.invert_tets = invert_tets;
+        self
+    }
+    /// Load all files with the given format instead of probing each file.
+    pub fn format(mut self, format: Option<MeshFormat>) -> Self {
+        self.config.format = format;
+        self
+    }
+    /// Split polygons into triangles with the given strategy.
+    pub fn triangulation(mut self, triangulation: config::Triangulation) -> Self {
+        self.config.triangulation = triangulation;
+        self
+    }
+    /// Record the polygon each triangle came from in a face attribute.
+    pub fn polygon_ids(mut self, polygon_ids: bool) -> Self {
+        self.config.polygon_ids = polygon_ids;
+        self
+    }
+    pub fn build(self) -> LoadConfig {
+        self.config
+    }
+}
+
+/// Formats of mesh sequences, probed once for each sequence.
+///
+/// Frames of the same animation with the same extension are assumed to share
+/// a format, so only the first one loaded needs to be probed. This matters for
+/// files without a recognized extension whose format is detected from their
+/// contents.
+#[derive(Debug, Default)]
+pub struct FormatCache {
+    formats: Mutex<HashMap<(String, String), Option<MeshFormat>>>,
+}
+
+impl FormatCache {
+    /// Returns the configuration for loading a frame of the animation `name`
+    /// stored at `path`.
+    pub fn load_config(&self, name: &str, path: &Path, config: LoadConfig) -> LoadConfig {
+        if config.format.is_some() {
+            return config;
+        }
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let key = (name.to_string(), ext);
+        let cached = self.formats.lock().unwrap().get(&key).copied();
+        let format = cached.unwrap_or_else(|| {
+            let format = MeshFormat::probe(path);
+            self.formats.lock().unwrap().insert(key, format);
+            format
+        });
+        LoadConfig { format, ..config }
+    }
+
+    /// Probes the format of the first of the given frames of each animation.
+    ///
+    /// Frames loaded in parallel would otherwise take their format from
+    /// whichever frame happens to be loaded first.
+    pub fn probe_first<'a>(
+        &self,
+        frames: impl IntoIterator<Item = (&'a str, &'a Path)>,
+        config: LoadConfig,
+    ) {
+        for (name, path) in frames {
+            self.load_config(name, path, config);
+        }
+    }
 }
 
 /// Configuration for locating attributes within loaded meshes.
@@ -38,6 +191,20 @@ pub struct AttribConfig<'a> {
     pub material_attribute: &'a str,
 }
 
+impl AttribConfig<'_> {
+    /// Returns `true` if the attribute with the given name is used by this configuration.
+    ///
+    /// Normals and tangents are always considered requested.
+    pub fn requests(&self, name: &str) -> bool {
+        name == config::NORMAL_ATTRIB_NAME
+            || name == config::TANGENT_ATTRIB_NAME
+            || name == self.material_attribute
+            || self.attributes.0.contains_key(name)
+            || self.colors.0.contains_key(name)
+            || self.texcoords.0.contains_key(name)
+    }
+}
+
 /// Convenience routine for loading and meshes extracting the required
 /// attributes and removing all extraneous attributes.
 pub fn load_and_clean_meshes(
@@ -46,9 +213,20 @@ pub fn load_and_clean_meshes(
     attrib_config: AttribConfig,
 ) -> Vec<(String, u32, Mesh, AttribTransfer)> {
     let process_attrib_error = |e| log::warn!("{}, Skipping...", e);
+    let formats = FormatCache::default();
+    formats.probe_first(
+        mesh_meta
+            .iter()
+            .map(|(name, _, path)| (name.as_str(), path.as_path())),
+        load_config,
+    );
+    #[cfg(feature = "parallel")]
+    let mesh_meta = mesh_meta.into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let mesh_meta = mesh_meta.into_iter();
     mesh_meta
-        .into_par_iter()
         .filter_map(|(name, frame, path)| {
+            let load_config = formats.load_config(&name, &path, load_config);
             load_and_clean_mesh(&path, load_config, attrib_config, process_attrib_error)
                 .map(|(mesh, attrib_transfer)| (name, frame, mesh, attrib_transfer))
         })
@@ -64,12 +242,23 @@ pub fn clean_named_meshes(
     meshes: Vec<(String, Mesh)>,
     attrib_config: AttribConfig,
 ) -> Vec<(String, u32, Mesh, AttribTransfer)> {
-    let process_attrib_error = |e| log::warn!("{}, Skipping...", e);
+    clean_named_meshes_with(meshes, attrib_config, |e| log::warn!("{}, Skipping...", e))
+}
+
+/// Same as `clean_named_meshes` but with a custom attribute error handler.
+pub(crate) fn clean_named_meshes_with(
+    meshes: Vec<(String, Mesh)>,
+    attrib_config: AttribConfig,
+    process_attrib_error: impl Fn(attrib::AttribError) + Sync,
+) -> Vec<(String, u32, Mesh, AttribTransfer)> {
+    #[cfg(feature = "parallel")]
+    let meshes = meshes.into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let meshes = meshes.into_iter();
     meshes
-        .into_par_iter()
         .enumerate()
         .map(|(frame, (name, mut mesh))| {
-            let attrib_transfer = clean_mesh(&mut mesh, attrib_config, process_attrib_error);
+            let attrib_transfer = clean_mesh(&mut mesh, attrib_config, &process_attrib_error);
             (name, frame as u32, mesh, attrib_transfer)
         })
         .collect()
@@ -81,7 +270,12 @@ pub fn load_and_clean_mesh(
     attrib_config: AttribConfig,
     process_attrib_error: impl FnMut(attrib::AttribError),
 ) -> Option<(Mesh, AttribTransfer)> {
-    let mut mesh = load_mesh(path, load_config)?;
+    // Attributes that are not requested are dropped before meshes are
+    // extracted, since they would be removed by `clean_mesh` anyway.
+    let mut file = mesh::MeshFile::open(path, load_config.format).ok()?;
+    file.retain_attributes(|name| attrib_config.requests(name));
+    let mut mesh = load_mesh_from_file(&file, load_config)?;
+    drop(file);
     let attrib_transfer = clean_mesh(&mut mesh, attrib_config, process_attrib_error);
     Some((mesh, attrib_transfer))
 }
@@ -90,20 +284,40 @@ pub fn load_mesh(path: impl AsRef<Path>, config: LoadConfig) -> Option<Mesh> {
     load_mesh_impl(path.as_ref(), config)
 }
 
+/// Loads a mesh from the contents of a file with the given extension (e.g. "obj" or "vtk").
+///
+/// This doesn't touch the file system, which makes it suitable for meshes
+/// received over the network or in the browser.
+pub fn load_mesh_from_bytes(bytes: &[u8], extension: &str, config: LoadConfig) -> Option<Mesh> {
+    let file = mesh::MeshFile::from_bytes(bytes, extension).ok()?;
+    load_mesh_from_file(&file, config)
+}
+
 fn load_mesh_impl(path: &Path, config: LoadConfig) -> Option<Mesh> {
-    let polymesh_tris = if let Ok(polymesh) = meshx::io::load_polymesh::<f64, _>(path) {
-        trimesh_f64_to_f32(meshx::TriMesh::from(polymesh))
-    } else if let Ok(polymesh) = meshx::io::load_polymesh::<f32, _>(path) {
-        meshx::TriMesh::<f32>::from(polymesh)
+    let file = mesh::MeshFile::open(path, config.format).ok()?;
+    load_mesh_from_file(&file, config)
+}
+
+fn load_mesh_from_file(file: &mesh::MeshFile, config: LoadConfig) -> Option<Mesh> {
+    let (polymesh_tris, polygons) = if let Ok(polymesh) = file.load_polymesh::<f64>() {
+        let polygons = triangulate::polygons(&polymesh);
+        let trimesh = trimesh_f64_to_f32(meshx::TriMesh::from(polymesh));
+        let trimesh = triangulate::retriangulate(trimesh, &polygons, config.triangulation);
+        (trimesh, polygons)
+    } else if let Ok(polymesh) = file.load_polymesh::<f32>() {
+        let polygons = triangulate::polygons(&polymesh);
+        let trimesh = meshx::TriMesh::<f32>::from(polymesh);
+        let trimesh = triangulate::retriangulate(trimesh, &polygons, config.triangulation);
+        (trimesh, polygons)
     } else {
-        meshx::TriMesh::default()
+        (meshx::TriMesh::default(), Vec::new())
     };
 
     let polymesh_tris = mesh::remove_orphaned_vertices(polymesh_tris);
 
-    let mut tetmesh_tris = if let Ok(tetmesh) = meshx::io::load_tetmesh::<f64, _>(path) {
+    let mut tetmesh_tris = if let Ok(tetmesh) = file.load_tetmesh::<f64>() {
         trimesh_f64_to_f32(tetmesh.surface_trimesh())
-    } else if let Ok(tetmesh) = meshx::io::load_tetmesh::<f32, _>(path) {
+    } else if let Ok(tetmesh) = file.load_tetmesh::<f32>() {
         tetmesh.surface_trimesh()
     } else {
         meshx::TriMesh::default()
@@ -115,13 +329,18 @@ fn load_mesh_impl(path: &Path, config: LoadConfig) -> Option<Mesh> {
         tetmesh_tris.reverse();
     }
 
+    let num_tetmesh_tris = tetmesh_tris.indices.len();
     tetmesh_tris.merge(polymesh_tris);
+    if config.polygon_ids {
+        triangulate::insert_polygon_ids(&mut tetmesh_tris, num_tetmesh_tris, &polygons);
+    }
+    winding::fix_winding(&mut tetmesh_tris, config.fix_winding);
     let mut mesh = Mesh::from(tetmesh_tris);
 
     if mesh.is_empty() {
-        mesh = if let Ok(ptcloud) = meshx::io::load_pointcloud::<f64, _>(path) {
+        mesh = if let Ok(ptcloud) = file.load_pointcloud::<f64>() {
             ptcloud.into()
-        } else if let Ok(ptcloud) = meshx::io::load_pointcloud::<f32, _>(path) {
+        } else if let Ok(ptcloud) = file.load_pointcloud::<f32>() {
             ptcloud.into()
         } else {
             return None;
@@ -153,10 +372,7 @@ mod tests {
         let texcoords: TextureAttributeInfo = "{\"uv\": f32}".parse().unwrap();
         let material_attribute = "mtl_id";
 
-        let load_config = LoadConfig {
-            reverse: false,
-            invert_tets: false,
-        };
+        let load_config = LoadConfig::default();
 
         let attrib_config = AttribConfig {
             attributes: &attributes,
@@ -184,10 +400,7 @@ mod tests {
         let texcoords = TextureAttributeInfo::default();
         let material_attribute = "mtl_id";
 
-        let load_config = LoadConfig {
-            reverse: false,
-            invert_tets: false,
-        };
+        let load_config = LoadConfig::default();
 
         let attrib_config = AttribConfig {
             attributes: &attributes,
@@ -223,10 +436,7 @@ mod tests {
         let texcoords = TextureAttributeInfo::default();
         let material_attribute = "mtl_id";
 
-        let load_config = LoadConfig {
-            reverse: true,
-            invert_tets: false,
-        };
+        let load_config = LoadConfig::builder().reverse(true).build();
 
         let attrib_config = AttribConfig {
             attributes: &attributes,
@@ -245,17 +455,15 @@ mod tests {
 
         export::export_clean_meshes(
             meshes,
-            export::ExportConfig {
-                textures: Vec::new(),
-                materials: Vec::new(),
-                output: artifact.into(),
-                time_step: dt,
-                insert_vanishing_frames: false,
-                animate_normals: false,
-                animate_tangents: false,
-                quiet: true,
-            },
-        );
+            export::ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .time_step(dt)
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
 
         let actual = Gltf::open(artifact).unwrap().blob;
 
@@ -265,10 +473,7 @@ mod tests {
     // Alternative way to export loaded meshes.
     #[test]
     fn multi_alt() {
-        let load_config = LoadConfig {
-            reverse: true,
-            invert_tets: false,
-        };
+        let load_config = LoadConfig::builder().reverse(true).build();
 
         // Meshes can be loaded without any kind of attribute processing.
         let meshes = vec![(
@@ -293,20 +498,162 @@ mod tests {
         export::export_named_meshes(
             meshes,
             attrib_config,
-            export::ExportConfig {
-                textures: Vec::new(),
-                materials: Vec::new(),
-                output: artifact.into(),
-                time_step: dt,
-                insert_vanishing_frames: false,
-                animate_normals: false,
-                animate_tangents: false,
-                quiet: true,
-            },
-        );
+            export::ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .time_step(dt)
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
 
         let actual = Gltf::open(artifact).unwrap().blob;
 
         dbg!(&actual);
     }
+
+    /// Loads the first frame of the rotating box without any extra attributes.
+    pub(crate) fn load_single_box() -> Vec<(String, u32, Mesh, AttribTransfer)> {
+        let mesh_meta = vec![(
+            String::from("box_rotate"),
+            1,
+            PathBuf::from("./assets/box_rotate_1.vtk"),
+        )];
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+
+        let load_config = LoadConfig::default();
+
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+
+        load_and_clean_meshes(mesh_meta, load_config, attrib_config)
+    }
+
+    #[test]
+    fn config_builders() {
+        let config = export::ExportConfig::builder()
+            .output("./tests/artifacts/builder.glb")
+            .time_step(0.5)
+            .quiet(true)
+            .build();
+        assert_eq!(
+            config.output,
+            PathBuf::from("./tests/artifacts/builder.glb")
+        );
+        assert_eq!(config.time_step, 0.5);
+        assert!(config.quiet);
+        assert!(config.animate_normals);
+        assert!(!config.no_overwrite);
+
+        let config = LoadConfig::builder().reverse(true).build();
+        assert!(config.reverse);
+        assert!(!config.invert_tets);
+    }
+
+    #[test]
+    fn load_from_bytes_to_glb() {
+        use meshx::mesh::vertex_positions::VertexPositions;
+        for ext in ["obj", "vtk"] {
+            let path = format!("./assets/box_rotate_1.{}", ext);
+            let bytes = std::fs::read(&path).unwrap();
+            let from_bytes = load_mesh_from_bytes(&bytes, ext, LoadConfig::default()).unwrap();
+            let from_path = load_mesh(&path, LoadConfig::default()).unwrap();
+            assert!(from_bytes.eq_topo(&from_path), "{}", ext);
+            assert_eq!(from_bytes.vertex_positions(), from_path.vertex_positions());
+        }
+        assert!(load_mesh_from_bytes(b"", "ply", LoadConfig::default()).is_none());
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let meshes = (1..=2)
+            .map(|frame| {
+                let bytes = std::fs::read(format!("./assets/box_rotate_{}.obj", frame)).unwrap();
+                let mut mesh = load_mesh_from_bytes(&bytes, "obj", LoadConfig::default()).unwrap();
+                let attrib_transfer = clean_mesh(&mut mesh, attrib_config, |_| {});
+                (String::from("box_rotate"), frame, mesh, attrib_transfer)
+            })
+            .collect();
+        let config = export::ExportConfig::builder().quiet(true).build();
+        let (document, data) = export::build_gltf(meshes, config).unwrap();
+        let glb = export::to_glb(&document, data).unwrap();
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(gltf.meshes().count(), 1);
+        assert!(gltf.blob.is_some());
+    }
+
+    #[test]
+    fn probe_mesh_format() {
+        use std::fs;
+
+        assert_eq!(MeshFormat::from_extension("VTU"), Some(MeshFormat::XmlVtk));
+        assert_eq!(
+            MeshFormat::from_header(b"# vtk DataFile Version 2.0\n"),
+            Some(MeshFormat::LegacyVtk)
+        );
+        assert_eq!(
+            MeshFormat::from_header(b"# comment\nv 0 0 0\n"),
+            Some(MeshFormat::Obj)
+        );
+        assert_eq!(MeshFormat::from_header(b"\x00\x01binary"), None);
+
+        // Files without an extension are probed from their contents.
+        let dir = std::env::temp_dir().join("gltfgen_probe_mesh_format");
+        fs::create_dir_all(&dir).unwrap();
+        for (src, format) in [
+            ("assets/box_rotate_1.vtk", MeshFormat::LegacyVtk),
+            ("assets/box_rotate_1.obj", MeshFormat::Obj),
+        ] {
+            let path = dir.join("frame_1");
+            fs::copy(src, &path).unwrap();
+            assert_eq!(MeshFormat::probe(&path), Some(format));
+            let formats = FormatCache::default();
+            let config = formats.load_config("frame", &path, LoadConfig::default());
+            assert_eq!(config.format, Some(format));
+            assert!(load_mesh(&path, config).is_some());
+        }
+    }
+
+    #[test]
+    fn skip_unrequested_attributes() {
+        use meshx::attrib::Attrib;
+        use meshx::topology::{FaceIndex, VertexIndex};
+
+        let attributes = "{\"pressure\": f32}".parse().unwrap();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        assert!(attrib_config.requests("pressure"));
+        assert!(attrib_config.requests("N"));
+        assert!(!attrib_config.requests("Cd"));
+
+        let mut file = mesh::MeshFile::open(Path::new("assets/box_rotate_1.vtk"), None).unwrap();
+        file.retain_attributes(|name| attrib_config.requests(name));
+        let Some(Mesh::TriMesh(mesh)) = load_mesh_from_file(&file, LoadConfig::default()) else {
+            panic!("expected a triangle mesh");
+        };
+        assert!(mesh.attrib_exists::<VertexIndex>("pressure"));
+        assert!(mesh.attrib_exists::<VertexIndex>("N"));
+        assert!(mesh.attrib_exists::<FaceIndex>("mtl_id"));
+        assert!(!mesh.attrib_exists::<VertexIndex>("Cd"));
+    }
 }
@@ -0,0 +1,328 @@
+use gltf::json;
+use json::extensions::scene::khr_lights_punctual;
+use json::validation::Checked::Valid;
+use serde::{Deserialize, Serialize};
+
+/*
+ * Parsing light info from command line
+ */
+
+/// Type of a punctual light.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum LightType {
+    /// Light emitted in the direction of the local -z axis.
+    #[serde(alias = "directional")]
+    #[default]
+    Directional,
+    /// Light emitted in all directions from the position of the light.
+    #[serde(alias = "point")]
+    Point,
+    /// Light emitted in a cone in the direction of the local -z axis.
+    #[serde(alias = "spot")]
+    Spot,
+}
+
+fn default_outer_cone_angle() -> f32 {
+    std::f32::consts::FRAC_PI_4
+}
+
+fn default_light_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_rotation() -> [f32; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub type_: LightType,
+    #[serde(default = "default_light_color")]
+    pub color: [f32; 3],
+    #[serde(default = "default_intensity")]
+    pub intensity: f32,
+    /// Distance cutoff for point and spot lights. Infinite if not specified.
+    #[serde(default)]
+    pub range: Option<f32>,
+    /// Angle in radians from the center of the cone where spot light falloff begins.
+    #[serde(default)]
+    pub inner_cone_angle: f32,
+    /// Angle in radians from the center of the cone where spot light falloff ends.
+    #[serde(default = "default_outer_cone_angle")]
+    pub outer_cone_angle: f32,
+    #[serde(default)]
+    pub translation: [f32; 3],
+    /// Rotation of the light node given as a unit quaternion `[x, y, z, w]`.
+    #[serde(default = "default_rotation")]
+    pub rotation: [f32; 4],
+}
+
+impl Default for LightInfo {
+    fn default() -> Self {
+        LightInfo {
+            name: String::new(),
+            type_: LightType::default(),
+            color: default_light_color(),
+            intensity: default_intensity(),
+            range: None,
+            inner_cone_angle: 0.0,
+            outer_cone_angle: default_outer_cone_angle(),
+            translation: [0.0; 3],
+            rotation: default_rotation(),
+        }
+    }
+}
+
+impl std::str::FromStr for LightInfo {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<LightInfo, Self::Err> {
+        ron::de::from_str::<LightInfo>(input).map_err(Self::Err::from)
+    }
+}
+
+impl From<&LightInfo> for khr_lights_punctual::Light {
+    fn from(li: &LightInfo) -> khr_lights_punctual::Light {
+        let (type_, spot) = match li.type_ {
+            LightType::Directional => (khr_lights_punctual::Type::Directional, None),
+            LightType::Point => (khr_lights_punctual::Type::Point, None),
+            LightType::Spot => (
+                khr_lights_punctual::Type::Spot,
+                Some(khr_lights_punctual::Spot {
+                    inner_cone_angle: li.inner_cone_angle,
+                    outer_cone_angle: li.outer_cone_angle,
+                }),
+            ),
+        };
+        khr_lights_punctual::Light {
+            color: li.color,
+            extensions: None,
+            extras: Default::default(),
+            intensity: li.intensity,
+            name: if li.name.is_empty() {
+                None
+            } else {
+                Some(li.name.clone())
+            },
+            range: li.range,
+            spot,
+            type_: Valid(type_),
+        }
+    }
+}
+
+/// Distance of studio lights from the center of the scene relative to its radius.
+const STUDIO_DISTANCE: f32 = 2.5;
+
+/// Builds a three-point rig of spot lights aimed at the center of the given bounding box.
+///
+/// Viewers look down the -Z axis by default, so the key and fill lights are
+/// in front of the scene on either side, and the rim light is behind it. The
+/// intensity of each light is scaled with its squared distance to keep the
+/// illuminance at the center independent of the size of the scene.
+pub fn studio_lights(min: [f32; 3], max: [f32; 3]) -> Vec<LightInfo> {
+    let center: [f32; 3] = std::array::from_fn(|i| 0.5 * (min[i] + max[i]));
+    let radius = (0..3)
+        .map(|i| 0.5 * (max[i] - min[i]))
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt()
+        .max(f32::EPSILON);
+    let distance = STUDIO_DISTANCE * radius;
+    // Name, direction from the center and illuminance at the center in lux.
+    let rig = [
+        ("key_light", [1.0, 1.0, 1.0], 3.0),
+        ("fill_light", [-1.0, 0.5, 1.0], 1.0),
+        ("rim_light", [0.0, 1.0, -1.0], 2.0),
+    ];
+    rig.into_iter()
+        .map(|(name, direction, illuminance)| {
+            let direction = normalized(direction);
+            LightInfo {
+                name: name.to_string(),
+                type_: LightType::Spot,
+                intensity: illuminance * distance * distance,
+                // The scene spans about 22 degrees from each light.
+                inner_cone_angle: 0.3,
+                outer_cone_angle: 0.6,
+                translation: std::array::from_fn(|i| center[i] + distance * direction[i]),
+                rotation: look_rotation(direction.map(|x| -x)),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn normalized(v: [f32; 3]) -> [f32; 3] {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    v.map(|x| x / norm)
+}
+
+/// Rotation taking the -Z axis, along which lights and cameras point, to the unit vector `dir`.
+pub(crate) fn look_rotation(dir: [f32; 3]) -> [f32; 4] {
+    // Half way between -Z and `dir`, from the cross and dot products of the two.
+    let [x, y, z] = dir;
+    if z > 1.0 - 1e-6 {
+        return [0.0, 1.0, 0.0, 0.0];
+    }
+    let q = [y, -x, 0.0, 1.0 - z];
+    let norm = q.iter().map(|x| x * x).sum::<f32>().sqrt();
+    q.map(|x| x / norm)
+}
+
+/// Build a node referencing the light at `light_index` positioned according to `li`.
+pub(crate) fn build_light_node(li: &LightInfo, light_index: usize) -> json::Node {
+    json::Node {
+        camera: None,
+        children: None,
+        extensions: Some(json::extensions::scene::Node {
+            khr_lights_punctual: Some(khr_lights_punctual::KhrLightsPunctual {
+                light: json::Index::new(light_index as u32),
+            }),
+            ..Default::default()
+        }),
+        extras: Default::default(),
+        matrix: None,
+        mesh: None,
+        name: if li.name.is_empty() {
+            None
+        } else {
+            Some(li.name.clone())
+        },
+        rotation: Some(json::scene::UnitQuaternion(li.rotation)),
+        scale: None,
+        translation: Some(li.translation),
+        skin: None,
+        weights: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::tests::load_single_box;
+    use crate::{proxy, AttribConfig, AttributeInfo, TextureAttributeInfo};
+    use gltf::Gltf;
+
+    #[test]
+    fn punctual_lights() {
+        let meshes = load_single_box();
+
+        let artifact = "./tests/artifacts/punctual_lights.glb";
+
+        let lights = vec![
+            "(name:\"sun\", type:Directional, intensity:3.0)"
+                .parse()
+                .unwrap(),
+            "(type:Spot, outer_cone_angle:0.5, translation:(0.0, 2.0, 0.0))"
+                .parse()
+                .unwrap(),
+        ];
+
+        export::export_clean_meshes(
+            meshes,
+            ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .lights(lights)
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
+
+        let gltf = Gltf::open(artifact).unwrap();
+        assert!(gltf
+            .extensions_used()
+            .any(|ext| ext == "KHR_lights_punctual"));
+        let lights: Vec<_> = gltf.lights().unwrap().collect();
+        assert_eq!(lights.len(), 2);
+        assert_eq!(lights[0].name(), Some("sun"));
+        assert_eq!(lights[0].intensity(), 3.0);
+
+        // Both lights must be reachable from the scene.
+        let scene = gltf.scenes().next().unwrap();
+        let light_nodes: Vec<_> = scene.nodes().filter_map(|n| n.light()).collect();
+        assert_eq!(light_nodes.len(), 2);
+        match light_nodes[1].kind() {
+            gltf::khr_lights_punctual::Kind::Spot {
+                outer_cone_angle, ..
+            } => assert_eq!(outer_cone_angle, 0.5),
+            _ => panic!("expected a spot light"),
+        }
+    }
+
+    #[test]
+    fn studio_lighting() {
+        // A unit cube, which moves along x in the second frame.
+        let (positions, tris) = proxy::box_mesh([0.0; 3], [1.0; 3]);
+        let tris: Vec<[usize; 3]> = tris.iter().map(|t| t.map(|v| v as usize)).collect();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder()
+            .studio_lighting(true)
+            .quiet(true)
+            .build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for frame in 1..=2 {
+            let moved = positions
+                .iter()
+                .map(|&[x, y, z]| [x + (frame - 1) as f32, y, z])
+                .collect();
+            let trimesh = meshx::TriMesh::new(moved, tris.clone());
+            sequence
+                .add_frame("cube", frame, Mesh::from(trimesh))
+                .unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        let lights = &root.extensions.as_ref().unwrap().khr_lights_punctual;
+        let names: Vec<_> = lights
+            .as_ref()
+            .unwrap()
+            .lights
+            .iter()
+            .map(|light| light.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["key_light", "fill_light", "rim_light"]);
+
+        // Every light points at the center of the box enclosing both frames.
+        let center = [1.0, 0.5, 0.5];
+        for name in names {
+            let node = root
+                .nodes
+                .iter()
+                .find(|node| node.name.as_deref() == Some(name))
+                .unwrap();
+            let t = node.translation.unwrap();
+            let [x, y, z, w] = node.rotation.as_ref().unwrap().0;
+            // Rotate the -Z axis by the quaternion.
+            let dir = [
+                -2.0 * (x * z + w * y),
+                -2.0 * (y * z - w * x),
+                -1.0 + 2.0 * (x * x + y * y),
+            ];
+            let to_center: [f32; 3] = std::array::from_fn(|i| center[i] - t[i]);
+            let norm = to_center.iter().map(|x| x * x).sum::<f32>().sqrt();
+            for i in 0..3 {
+                assert!((dir[i] - to_center[i] / norm).abs() < 1e-5);
+            }
+        }
+    }
+}
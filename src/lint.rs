@@ -0,0 +1,315 @@
+//! Checks of configurations that catch mistakes before any meshes are loaded.
+//!
+//! Inline RON given on the command line is parsed with [`parse_ron`], which
+//! suggests the intended spelling of misspelled fields and enum variants.
+//! [`lint_config`] then checks values that parse fine but would produce an
+//! invalid or surprising glTF file, like material factors out of range or
+//! references to textures that were never specified.
+
+use serde::de::DeserializeOwned;
+
+use crate::config::Config;
+use crate::light::LightType;
+use crate::material::TextureRef;
+use crate::texture::ImageInfo;
+
+/// A problem found in a configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lint {
+    /// Location of the offending value, e.g. `materials[1].metallic`.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Error parsing inline RON, pointing at the offending part of the input.
+///
+/// The displayed error quotes the line of the input with the error
+/// underlined, followed by a suggestion for misspelled names and an example of
+/// valid input.
+#[derive(Debug)]
+pub struct ParseError {
+    pub error: ron::error::SpannedError,
+    /// Inline RON that failed to parse.
+    pub input: String,
+    /// Known name closest to a misspelled field or enum variant.
+    pub suggestion: Option<&'static str>,
+    /// Valid input of the expected type.
+    pub example: &'static str,
+}
+
+impl ParseError {
+    /// Byte range of the offending part of the input.
+    ///
+    /// This is the misspelled name if the error is about an unknown field or
+    /// variant, and the character at the position of the error otherwise.
+    pub fn span(&self) -> std::ops::Range<usize> {
+        let position = &self.error.position;
+        let line_start: usize = self
+            .input
+            .split_inclusive('\n')
+            .take(position.line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        // Columns count bytes rather than characters.
+        let mut offset = (line_start + position.col.saturating_sub(1)).min(self.input.len());
+        while !self.input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let found = match &self.error.code {
+            ron::Error::NoSuchEnumVariant { found, .. }
+            | ron::Error::NoSuchStructField { found, .. } => Some(found.as_str()),
+            _ => None,
+        };
+        // Names are reported once they have been read, so they end around the position.
+        let search_end = (offset + found.map_or(0, str::len)).min(self.input.len());
+        let name = found.and_then(|found| {
+            let start = self.input.get(..search_end)?.rfind(found)?;
+            Some(start..start + found.len())
+        });
+        name.unwrap_or_else(|| {
+            let len = self.input[offset..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+            offset..offset + len
+        })
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(suggestion) = self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+
+        let span = self.span();
+        let line_start = self.input[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line = self.input[line_start..].lines().next().unwrap_or("");
+        let indent = self.input[line_start..span.start].chars().count();
+        let width = self.input[span].chars().count().max(1);
+        writeln!(f)?;
+        writeln!(f, "  {}", line)?;
+        writeln!(f, "  {}{}", " ".repeat(indent), "^".repeat(width))?;
+        write!(f, "For example: {}", self.example)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses inline RON, suggesting known names for misspelled fields and enum variants.
+///
+/// Errors refer to `example` as valid input of the expected type.
+pub fn parse_ron<T: DeserializeOwned>(input: &str, example: &'static str) -> Result<T, ParseError> {
+    ron::de::from_str(input).map_err(|error| {
+        let suggestion = match &error.code {
+            ron::Error::NoSuchEnumVariant {
+                expected, found, ..
+            }
+            | ron::Error::NoSuchStructField {
+                expected, found, ..
+            } => suggest(found, expected),
+            _ => None,
+        };
+        ParseError {
+            error,
+            input: input.to_string(),
+            suggestion,
+            example,
+        }
+    })
+}
+
+/// Returns the known name closest to `found`, if any is close enough to be a likely misspelling.
+pub fn suggest(found: &str, known: &[&'static str]) -> Option<&'static str> {
+    let found = found.to_lowercase();
+    known
+        .iter()
+        .map(|&name| (edit_distance(&found, &name.to_lowercase()), name))
+        .filter(|&(distance, name)| distance <= name.len().div_ceil(3))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+/// Number of single character insertions, deletions, substitutions and
+/// transpositions of adjacent characters turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Distances between prefixes of `a` and `b`.
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Checks the values of the given configuration.
+///
+/// Returns the problems found, which is empty if the configuration is fine.
+pub fn lint_config(config: &Config) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut lint = |path: String, message: String| lints.push(Lint { path, message });
+
+    for (i, material) in config.materials.iter().enumerate() {
+        let path = |field: &str| format!("materials[{}].{}", i, field);
+        for (field, value) in [
+            ("metallic", material.metallic),
+            ("roughness", material.roughness),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                lint(
+                    path(field),
+                    format!("factor {} is outside of the range [0, 1]", value),
+                );
+            }
+        }
+        if material.base_color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            lint(
+                path("base_color"),
+                format!(
+                    "components {:?} are outside of the range [0, 1]",
+                    material.base_color
+                ),
+            );
+        }
+        if let TextureRef::Some { index, .. } = material.base_texture {
+            let num_textures = config.textures.len();
+            if index as usize >= num_textures {
+                lint(
+                    path("base_texture"),
+                    format!(
+                        "texture index {} is out of range for {} specified texture(s)",
+                        index, num_textures
+                    ),
+                );
+            }
+        }
+    }
+
+    for (i, texture) in config.textures.iter().enumerate() {
+        let (ImageInfo::Auto(image) | ImageInfo::Uri(image) | ImageInfo::Embed(image)) =
+            &texture.image;
+        let is_ktx2 = std::path::Path::new(image)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ktx2"));
+        if is_ktx2 {
+            lint(
+                format!("textures[{}].image", i),
+                "KTX2 images and mip chain generation are not supported, use a PNG or JPEG image"
+                    .to_string(),
+            );
+        }
+    }
+
+    for (i, light) in config.lights.iter().enumerate() {
+        let path = |field: &str| format!("lights[{}].{}", i, field);
+        if light.intensity < 0.0 {
+            lint(
+                path("intensity"),
+                format!("intensity {} is negative", light.intensity),
+            );
+        }
+        if light.range.is_some_and(|range| range <= 0.0) {
+            lint(path("range"), "range must be positive".to_string());
+        }
+        let max_angle = std::f32::consts::FRAC_PI_2;
+        if light.type_ == LightType::Spot
+            && !(0.0 <= light.inner_cone_angle
+                && light.inner_cone_angle < light.outer_cone_angle
+                && light.outer_cone_angle <= max_angle)
+        {
+            lint(
+                path("outer_cone_angle"),
+                format!(
+                    "cone angles must satisfy 0 <= inner ({}) < outer ({}) <= {}",
+                    light.inner_cone_angle, light.outer_cone_angle, max_angle
+                ),
+            );
+        }
+    }
+
+    if config.deterministic {
+        for (option, used) in [("cache", config.cache.is_some()), ("resume", config.resume)] {
+            if used {
+                lint(
+                    "deterministic".to_string(),
+                    format!("can't be combined with '{}'", option),
+                );
+            }
+        }
+    }
+
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttributeInfo, MaterialInfo, TextureInfo};
+
+    #[test]
+    fn lint_suggestions() {
+        assert_eq!(
+            suggest("metalic", &["name", "metallic", "roughness"]),
+            Some("metallic")
+        );
+        assert_eq!(
+            suggest("spto", &["Directional", "Point", "Spot"]),
+            Some("Spot")
+        );
+        assert_eq!(suggest("colour", &["name", "metallic"]), None);
+
+        let texture = |input: &str| input.parse::<TextureInfo>().unwrap_err().suggestion;
+        assert_eq!(
+            texture("(image: Embed(\"a.png\"), wrap_s: Repaet)"),
+            Some("Repeat")
+        );
+        assert_eq!(
+            texture("(image: Embed(\"a.png\"), wrap: Repeat)"),
+            Some("wrap_s")
+        );
+        let err = "{\"N\": Vec3(f23)}".parse::<AttributeInfo>().unwrap_err();
+        assert!(err
+            .suggestion
+            .is_some_and(|s| s.eq_ignore_ascii_case("f32")));
+    }
+
+    #[test]
+    fn parse_error_span() {
+        let err = "(name:\"red\", metalic:0.5)"
+            .parse::<MaterialInfo>()
+            .unwrap_err();
+        assert_eq!(&err.input[err.span()], "metalic");
+        let message = err.to_string();
+        assert!(message.contains("\n  (name:\"red\", metalic:0.5)\n               ^^^^^^^\n"));
+        assert!(message.contains("For example: (name:\"red\""));
+
+        // Errors other than misspelled names point at a single character.
+        let err = "(image: Embed(\"a.png\") wrap_s: Repeat)"
+            .parse::<TextureInfo>()
+            .unwrap_err();
+        assert_eq!(err.span().len(), 1);
+    }
+}
@@ -1,12 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use clap::Parser;
 use clap_verbosity_flag::Verbosity;
 use console::style;
-use env_logger;
-use gltfgen::config::Config;
+use gltfgen::cache::FrameCache;
+use gltfgen::config::{Config, GapPolicy};
+use gltfgen::progress::IndicatifProgress;
+use gltfgen::report::Report;
+use gltfgen::time::{self, FrameRate, Seconds};
 use indicatif::ParallelProgressIterator;
-use log;
 use rayon::prelude::*;
 
 use gltfgen::*;
@@ -63,15 +69,26 @@ struct Opt {
 
     /// A path to the configuration file specifying how glTF files should be built.
     ///
-    /// If unspecified, gltfgen will look for a 'gltfgen.ron' or a
-    /// 'gltfgen.json' configuration file in the current working directory, and
-    /// if none found, it will use default arguments or arguments specified
-    /// on the command line.
+    /// If unspecified, gltfgen will look for a 'gltfgen.ron', 'gltfgen.json',
+    /// 'gltfgen.toml' or 'gltfgen.yaml' configuration file in the current
+    /// working directory, and if none found, it will use default arguments or
+    /// arguments specified on the command line.
     ///
     /// If specified, any explicit command line configuration will override the config loaded.
     #[clap(name = "CONFIG", long = "config")]
     config_path: Option<PathBuf>,
 
+    /// Name of the profile to apply from the configuration file.
+    ///
+    /// Profiles are named sets of overrides defined in the 'profiles' section
+    /// of a configuration file. For example, a 'gltfgen.ron' containing
+    ///
+    /// (pattern: "./#.vtk", fps: 24, profiles: { "preview": (fps: 12, step: 2) })
+    ///
+    /// can be used with '--profile preview' to produce a lower frame rate output.
+    #[clap(value_name = "NAME", long)]
+    profile: Option<String>,
+
     /// Controls verobosity of printed output.
     #[clap(flatten)]
     verbose: Verbosity,
@@ -114,46 +131,324 @@ struct Opt {
     #[clap(long)]
     print_ron_config: bool,
 
+    /// Print the configuration in TOML format, but don't run the generator.
+    ///
+    /// This is useful for debugging or for generating a configuration file that
+    /// can later be reused. For example, run
+    ///
+    /// $ gltfgen -f 60 --print-toml-config > gltfgen.toml
+    ///
+    /// to create a TOML configuration file initialized with default parameters and 60 fps output.
+    /// This file can then later be used as
+    ///
+    /// $ gltfgen --config gltfgen.toml "<PATTERN>"
+    ///
+    /// without specifying the '-f' flag explicitly every time. (Replace
+    /// <PATTERN> with an actual sequence pattern).
+    ///
+    /// The --verbose and --quiet flags are ignored.
+    #[clap(long)]
+    print_toml_config: bool,
+
+    /// Print the configuration in YAML format, but don't run the generator.
+    ///
+    /// This is useful for debugging or for generating a configuration file that
+    /// can later be reused. For example, run
+    ///
+    /// $ gltfgen -f 60 --print-yaml-config > gltfgen.yaml
+    ///
+    /// to create a YAML configuration file initialized with default parameters and 60 fps output.
+    /// This file can then later be used as
+    ///
+    /// $ gltfgen --config gltfgen.yaml "<PATTERN>"
+    ///
+    /// without specifying the '-f' flag explicitly every time. (Replace
+    /// <PATTERN> with an actual sequence pattern).
+    ///
+    /// The --verbose and --quiet flags are ignored.
+    #[clap(long)]
+    print_yaml_config: bool,
+
     /// Print the configuration and required input argments, but don't run the generator.
     ///
     /// This is only useful for debugging. For printing the config using a
-    /// specific format like JSON or RON, use either of --print-json-config or
-    /// --print-ron-config options.
+    /// specific format like JSON, RON, TOML or YAML, use one of --print-json-config,
+    /// --print-ron-config, --print-toml-config or --print-yaml-config options.
     ///
     /// The --verbose and --quiet flags are ignored.
     #[clap(long)]
     print_full_config: bool,
+
+    /// Print a JSON Schema describing the JSON configuration format, but don't run the generator.
+    ///
+    /// Editors can use the schema to validate and autocomplete 'gltfgen.json'
+    /// configuration files.
+    #[clap(long)]
+    print_config_schema: bool,
+
+    /// Find and probe input files, but don't load all frames or write any output.
+    ///
+    /// This prints a report with the number of frames found for each
+    /// animation, the attributes present in the first frame of each animation
+    /// along with their types, and an estimate of the output size.
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Write a JSON report summarizing the run to the given file.
+    ///
+    /// The report lists the input files that were loaded or skipped along with
+    /// the reason, the attributes and materials of each generated node,
+    /// attributes that failed to transfer, output sizes by category and the
+    /// time spent in each stage.
+    #[clap(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Print a breakdown of the output size after exporting.
+    ///
+    /// Buffer bytes are attributed to indices, positions, normals, tangents,
+    /// colors, texture coordinates, custom attributes, morph target
+    /// displacements of each node, animation samplers and embedded images,
+    /// along with hints for options that would shrink the output.
+    #[clap(long)]
+    size_report: bool,
+
+    /// Serve the output over HTTP after exporting, along with a viewer page.
+    ///
+    /// The output along with the buffers and images it references is served
+    /// on all network interfaces at the given port (8000 by default), so
+    /// teammates can open the viewer page at the root of the server in a web
+    /// browser. No other files are served. Open pages
+    /// reload the model whenever the output file is rewritten, for instance
+    /// by another export to the same file. The server runs until interrupted.
+    #[clap(
+        long,
+        value_name = "PORT",
+        num_args = 0..=1,
+        default_missing_value = "8000"
+    )]
+    serve: Option<u16>,
+
+    /// Overwrite existing output files even if '--no-overwrite' is set in a configuration file.
+    #[clap(long)]
+    force: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// Additional tools for working with glTF files.
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Print a summary of existing glTF files.
+    ///
+    /// This includes node, mesh and primitive counts, accessor sizes,
+    /// animation durations, morph target counts and a breakdown of buffer
+    /// sizes by category.
+    Info {
+        /// glTF (.gltf or .glb) files to inspect.
+        #[clap(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Merge glTF files produced by separate runs into a single scene.
+    ///
+    /// Identical materials, textures and images are stored only once.
+    Merge {
+        /// glTF (.gltf or .glb) files to merge.
+        #[clap(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Output file for the merged glTF.
+        ///
+        /// As with generated output, the .glb extension produces a binary
+        /// glTF file and the .gltf extension produces a standard glTF file
+        /// with a separate .bin buffer.
+        #[clap(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// How animations from the given files are combined.
+        ///
+        /// 'Parallel' combines all animations into a single animation playing
+        /// simultaneously. 'Concatenate' plays the files one after another
+        /// in the order given, showing the nodes of each file only while its
+        /// animation is playing. 'Separate' keeps all animations as they are.
+        #[clap(long, value_name = "MODE", default_value = "Parallel")]
+        animations: merge::AnimationMerge,
+
+        /// Validate the merged glTF before and after writing it.
+        #[clap(long)]
+        validate: bool,
+    },
+    /// Add newly generated nodes and animations to an existing glTF file.
+    ///
+    /// Meshes matched by the given pattern are converted as usual and merged
+    /// into the existing file, which is rewritten in place. Identical
+    /// materials, textures and images are stored only once, so materials
+    /// already in the file are reused. The '--output' option is ignored.
+    Append {
+        /// Existing glTF (.gltf or .glb) file to append to.
+        #[clap(long, value_name = "FILE")]
+        into: PathBuf,
+
+        /// How the new animation is combined with existing animations.
+        ///
+        /// By default, the new animation is added alongside the existing ones.
+        /// See the 'merge' command for other options.
+        #[clap(long, value_name = "MODE", default_value = "Separate")]
+        animations: merge::AnimationMerge,
+
+        #[clap(flatten)]
+        config: Box<config::Config>,
+    },
+    /// Compare two glTF files structurally and numerically.
+    ///
+    /// Files match if they have the same structure, including topology,
+    /// materials and animations, and all numbers including accessor data
+    /// agree within the given tolerance. Differences in how data is laid out
+    /// in buffers and in asset information are ignored.
+    ///
+    /// Exits with a non-zero code if the files differ.
+    Diff {
+        /// First glTF (.gltf or .glb) file.
+        #[clap(value_name = "FILE")]
+        first: PathBuf,
+
+        /// Second glTF (.gltf or .glb) file.
+        #[clap(value_name = "FILE")]
+        second: PathBuf,
+
+        /// Relative tolerance used for comparing numbers.
+        ///
+        /// Numbers with magnitude less than one are compared with this absolute tolerance.
+        #[clap(short, long, value_name = "EPS", default_value = "1e-5")]
+        epsilon: f64,
+    },
+    /// Unpack an animated glTF file back into a sequence of mesh files.
+    ///
+    /// Each animated mesh is evaluated at every keyframe of its morph target
+    /// animation and written to a separate file. Vertex positions, normals and
+    /// texture coordinates are written in the local frame of each node.
+    Extract {
+        /// glTF (.gltf or .glb) file to extract meshes from.
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Output file pattern.
+        ///
+        /// The '#' character is replaced by the frame number and '{}' is
+        /// replaced by the mesh name. The mesh format (.obj, .vtk or .vtu) is
+        /// determined by the extension.
+        #[clap(short, long, value_name = "PATTERN")]
+        output: PathBuf,
+
+        /// Frames per second used to compute frame numbers from keyframe times.
+        #[clap(short, long, value_name = "FPS", default_value = "24")]
+        fps: FrameRate,
+
+        /// Time step between frames used to compute frame numbers.
+        ///
+        /// This overrides the time step computed from '--fps'.
+        #[clap(short, long, value_name = "TIME")]
+        time_step: Option<Seconds>,
+    },
+    /// Write a self-contained HTML page previewing a glTF file.
+    ///
+    /// The file is embedded into the page, which can be opened directly in a
+    /// web browser to check a conversion without installing a glTF viewer.
+    /// The viewer component is loaded from the web when the page is opened.
+    Preview {
+        /// glTF (.gltf or .glb) file to preview.
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Output HTML file.
+        ///
+        /// Defaults to the input file with the .html extension.
+        #[clap(long, value_name = "FILE")]
+        html: Option<PathBuf>,
+    },
+    /// List the attributes of a representative input frame.
+    ///
+    /// Loads the given mesh file, or the first frame of the first animation
+    /// matched by a pattern, and prints the name, element type and number of
+    /// elements of every vertex, face and face-vertex attribute it stores.
+    Probe {
+        /// Mesh file or input pattern as accepted by the main command.
+        #[clap(value_name = "FILE_OR_PATTERN")]
+        input: String,
+
+        /// Print the attributes as JSON.
+        #[clap(long)]
+        json: bool,
+    },
 }
 
 fn main() {
     if let Err(err) = try_main() {
         eprintln!("{}", err);
-        std::process::exit(1); // Non-zero value indicating that an error occurred.
+        std::process::exit(err.exit_code()); // Non-zero value indicating the kind of error.
     }
 }
 fn try_main() -> Result<(), Error> {
     let cli = clap::Command::new("gltfgen");
-    let cli = <Opt as clap::Args>::augment_args(cli);
+    let cli = <Opt as clap::Args>::augment_args(cli)
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true);
     let matches = cli.get_matches();
     let opt = <Opt as clap::FromArgMatches>::from_arg_matches(&matches).unwrap();
     env_logger::Builder::new()
         .filter_level(opt.verbose.log_level_filter())
         .init();
 
+    if let Some(command) = opt.command {
+        return run_command(command, opt.verbose.is_silent());
+    }
+
+    if opt.print_config_schema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema::config_schema())?
+        );
+        return Ok(());
+    }
+
     // Try to load the config file if specified.
-    let config = if let Some(path) = opt.config_path {
-        Config::load_with_override(path, &opt.config, &matches)?
+    let mut config = if let Some(path) = opt.config_path {
+        Config::load_profile_with_override(path, opt.profile.as_deref(), &opt.config, &matches)?
     } else {
-        // Check if there is a local configuration file with the name "gltfgen.ron" or "gltfgen.json" and try to load that.
-        if let Ok(local_config) = Config::load_with_override("./gltfgen.ron", &opt.config, &matches)
-        {
+        // Check if there is a local configuration file with the name "gltfgen.ron", "gltfgen.json",
+        // "gltfgen.toml" or "gltfgen.yaml" and try to load that.
+        if let Ok(local_config) = Config::load_profile_with_override(
+            "./gltfgen.ron",
+            opt.profile.as_deref(),
+            &opt.config,
+            &matches,
+        ) {
             print_info(vec![(1, "Using local ./gltfgen.ron config.".to_string())]);
             local_config
-        } else if let Ok(local_config) =
-            Config::load_with_override("./gltfgen.json", &opt.config, &matches)
-        {
+        } else if let Ok(local_config) = Config::load_profile_with_override(
+            "./gltfgen.json",
+            opt.profile.as_deref(),
+            &opt.config,
+            &matches,
+        ) {
             print_info(vec![(1, "Using local ./gltfgen.json config.".to_string())]);
             local_config
+        } else if let Ok(local_config) = Config::load_profile_with_override(
+            "./gltfgen.toml",
+            opt.profile.as_deref(),
+            &opt.config,
+            &matches,
+        ) {
+            print_info(vec![(1, "Using local ./gltfgen.toml config.".to_string())]);
+            local_config
+        } else if let Ok(local_config) = Config::load_profile_with_override(
+            "./gltfgen.yaml",
+            opt.profile.as_deref(),
+            &opt.config,
+            &matches,
+        ) {
+            print_info(vec![(1, "Using local ./gltfgen.yaml config.".to_string())]);
+            local_config
         } else {
             // Otherwise just use whatever was specified on the commandline.
             opt.config
@@ -172,125 +467,350 @@ fn try_main() -> Result<(), Error> {
     } else if opt.print_json_config {
         println!("{}", serde_json::to_string(&config)?);
         return Ok(());
+    } else if opt.print_toml_config {
+        print!("{}", toml::to_string(&config)?);
+        return Ok(());
+    } else if opt.print_yaml_config {
+        print!("{}", serde_yaml::to_string(&config)?);
+        return Ok(());
+    }
+
+    // Catch mistakes in the configuration before any meshes are loaded.
+    let lints = gltfgen::lint::lint_config(&config);
+    if !lints.is_empty() {
+        print_errors(lints.iter().map(|lint| (1, lint.to_string())).collect());
+        return Err(Error::ConfigLint(lints.len()));
+    }
+
+    if let Some(threads) = config.threads {
+        if let Err(err) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            log::warn!("Failed to configure the thread pool: {}", err);
+        }
+    }
+
+    if opt.force {
+        config.no_overwrite = false;
+    }
+
+    let output = config.output.clone();
+    let mut report = Report::default();
+    let result = generate(config, opt.dry_run, opt.verbose.is_silent(), &mut report);
+
+    if let (true, Ok(()), Some(output)) = (opt.size_report, &result, &report.output) {
+        print!("{}", output);
+    }
+
+    // Verbose runs end with a summary of what ended up in the output.
+    if result.is_ok() && opt.verbose.log_level_filter() > log::LevelFilter::Error {
+        print!("{}", report.summary());
     }
 
-    let pattern = if config.pattern.starts_with("./") {
-        &config.pattern[2..]
+    if let Some(path) = &opt.report {
+        if let Err(err) = &result {
+            report.error = Some(err.to_string());
+        }
+        report.write(path).map_err(Error::Write)?;
+    }
+
+    if let (Some(port), Ok(()), false) = (opt.serve, &result, opt.dry_run) {
+        let output = report.output.map_or(output, |output| output.path);
+        let server = gltfgen::serve::Server::bind(&output, port)?;
+        println!(
+            "Serving {} at http://localhost:{}/ (press Ctrl-C to stop)",
+            output.display(),
+            server.port()?
+        );
+        server.run();
+    }
+
+    result
+}
+
+/// Converts the mesh sequences matched by the configured pattern into a glTF file.
+///
+/// A summary of the run is recorded in `report`.
+fn generate(
+    mut config: Config,
+    dry_run: bool,
+    quiet: bool,
+    report: &mut Report,
+) -> Result<(), Error> {
+    let start = Instant::now();
+
+    // The manifest records the configuration as given, before it is adjusted below.
+    let manifest_config = if config.manifest {
+        Some(serde_json::to_value(&config)?)
     } else {
-        &config.pattern[..]
+        None
     };
 
-    let regex = glob_to_regex(pattern);
-    let pattern = remove_braces(
-        &pattern
-            .replace("*#*", "*")
-            .replace("*#", "*")
-            .replace("#*", "*")
-            .replace('#', "*"),
-    );
-    let glob_options = glob::MatchOptions {
-        case_sensitive: true,
-        require_literal_separator: true,
-        require_literal_leading_dot: false,
-    };
+    // Transfer the radius of points along with the other attributes.
+    if let Some(radius) = &config.point_radius {
+        config
+            .attributes
+            .0
+            .entry(radius.clone())
+            .or_insert(Type::F32);
+        config
+            .attribute_names
+            .0
+            .entry(radius.clone())
+            .or_insert_with(|| config::POINT_RADIUS_SEMANTIC.to_string());
+    }
 
-    let pb = utils::new_spinner(opt.verbose.is_silent());
+    let pb = utils::new_spinner(quiet);
 
     pb.set_prefix("Looking for files");
 
-    let entries = glob::glob_with(&pattern, glob_options)?;
+    let discover_options = discover::DiscoverOptions {
+        case_insensitive: config.case_insensitive,
+        frame_offset: config.frame_offset,
+        order: config.order,
+        substeps: config.substeps,
+        unparsable_frames: config.unparsable_frames,
+    };
+    let name_filter = discover::NameFilter::new(
+        config.include_names.as_deref(),
+        config.exclude_names.as_deref(),
+    )?;
+    // Warnings of this run, checked with '--strict'.
+    let mut num_warnings = 0;
+    let mut sequences = Vec::new();
+    for pattern in std::iter::once(&config.pattern).chain(config.concat.iter()) {
+        let mut warnings = Vec::new();
+        let discovered =
+            discover::discover_frames_with_skipped(pattern, discover_options, &mut warnings);
+        num_warnings += print_warnings(warnings);
+        let (frames, skipped) = discovered?;
+        for discover::SkippedFile { path, reason } in skipped {
+            report.skipped(path, None, reason);
+        }
+        let (frames, excluded): (Vec<_>, Vec<_>) = frames
+            .into_iter()
+            .partition(|meta| name_filter.matches(&meta.name));
+        for meta in excluded {
+            report.skipped(meta.path, Some((meta.name, meta.frame)), "excluded by name");
+        }
+        sequences.push(frames);
+    }
+    if let Some(&count) = report.skipped.get(discover::UNPARSABLE_FRAME) {
+        num_warnings += print_warnings(vec![(
+            1,
+            format!(
+                "{} file(s) skipped since their frame numbers could not be parsed",
+                count
+            ),
+        )]);
+    }
+    let (frames, transitions) = discover::concat_sequences(sequences, config.crossfade);
 
-    // First parse entries and retrieve the necessary data before building the meshes.
+    // Retrieve the necessary data before building the meshes.
     // This will allow us to prune skipped frames before actually building meshes.
-
-    let mut lowest_frame_num = None;
-
-    let mut warnings = Vec::new();
-
-    let mut mesh_meta: Vec<_> = entries
-        .filter_map(|entry| {
-            entry.ok().and_then(|path| {
-                pb.tick();
-                if let Some(f) = path.file_name() {
-                    pb.set_message(f.to_string_lossy().into_owned());
-                }
-                let path_str = path.to_string_lossy();
-                let caps = match regex.captures(&path_str) {
-                    Some(caps) => caps,
-                    None => {
-                        crate::log!(warnings;
-                            "Path '{}' skipped since regex '{}' did not match.",
-                            &path_str,
-                            regex.as_str(),
-                        );
-                        return None;
-                    }
-                };
-                let frame_cap = caps.name("frame");
-                let frame = frame_cap
-                    .map(|frame_match| {
-                        let frame = frame_match
-                            .as_str()
-                            .parse::<u32>()
-                            .expect("ERROR: Failed to parse frame number");
-                        lowest_frame_num =
-                            Some(lowest_frame_num.map_or(frame, |n: u32| n.min(frame)));
-                        frame
-                    })
-                    .unwrap_or(0);
-
-                // Find a unique name for this mesh in the filename.
-                let mut name = String::new();
-                for cap in caps
-                    .iter()
-                    .skip(1)
-                    .filter(|&cap| cap != frame_cap)
-                    .flatten()
-                {
-                    name.push_str(cap.as_str());
-                }
-                Some((name, frame, path))
-            })
-        })
+    let lowest_frame_num = frames.iter().map(|meta| meta.frame).min();
+    let mut mesh_meta: Vec<_> = frames
+        .into_iter()
+        .map(|meta| (meta.name, meta.frame, meta.path))
         .collect();
 
     log::warn!("Glob returned {} entries", mesh_meta.len());
 
     pb.finish_with_message(format!("Found {} files", mesh_meta.len()));
 
-    print_warnings(warnings);
+    report.time("discovery", start.elapsed());
+    let stage_start = Instant::now();
 
     // Prune mesh meta before building meshes
-    if config.step > 1 {
+    let step_of = |name: &str| {
+        config
+            .overrides
+            .get(name)
+            .and_then(|o| o.step)
+            .unwrap_or(config.step)
+            .max(1)
+    };
+    if mesh_meta.iter().any(|(name, _, _)| step_of(name) > 1) {
         if let Some(lowest_frame_num) = lowest_frame_num {
-            let pb = utils::new_progress_bar(opt.verbose.is_silent(), mesh_meta.len());
+            let pb = utils::new_progress_bar(quiet, mesh_meta.len());
             pb.set_message("Pruning frames");
 
-            mesh_meta = mesh_meta
+            let pruned: Vec<_>;
+            (mesh_meta, pruned) = mesh_meta
                 .into_par_iter()
                 .progress_with(pb.clone())
-                .filter_map(|(name, frame, path)| {
+                .partition(|(name, frame, _)| {
                     // Note frameless meshes are placed at frame zero, and they won't be skipped
                     // here.
-                    if (frame - lowest_frame_num) % config.step == 0 {
-                        Some((name, frame, path))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+                    (frame - lowest_frame_num) % step_of(name) == 0
+                });
+
+            for (name, frame, path) in pruned {
+                report.skipped(path, Some((name, frame)), "pruned by step");
+            }
 
             pb.finish_with_message(format!("{} frames remain after pruning", mesh_meta.len()));
         }
     }
 
-    let pb = utils::new_progress_bar(opt.verbose.is_silent(), mesh_meta.len());
-    pb.set_message("Building Meshes");
+    // Fail before loading any meshes if frames are known to be missing.
+    if config.gap_policy == GapPolicy::Error {
+        let mut frames = BTreeMap::<&str, Vec<u32>>::new();
+        for (name, frame, _) in mesh_meta.iter() {
+            frames.entry(name).or_default().push(*frame);
+        }
+        for (name, mut frames) in frames {
+            frames.sort_unstable();
+            if let Some(gap) = discover::find_frame_gaps(&frames)
+                .into_iter()
+                .find(|gap| !transitions.contains(&(name.to_string(), gap.last)))
+            {
+                return Err(Error::FrameGap(name.to_string(), gap.last, gap.next));
+            }
+        }
+    }
+
+    let load_config = LoadConfig::builder()
+        .reverse(config.reverse)
+        .fix_winding(config.fix_winding)
+        .invert_tets(config.invert_tets)
+        .triangulation(config.triangulation)
+        .polygon_ids(config.polygon_ids)
+        .build();
+
+    if dry_run {
+        print_dry_run_report(&mesh_meta, load_config);
+        return Ok(());
+    }
+
+    // Look for colors and texture coordinates under common names if none are configured.
+    let default_texcoords: TextureAttributeInfo = "{\"uv\":f32}".parse().unwrap();
+    let find_texcoords = config.texcoords == default_texcoords;
+    let find_colors = config.colors.0.is_empty();
+    if !config.no_auto_attributes && (find_texcoords || find_colors) {
+        let first_frame = mesh_meta
+            .first()
+            .and_then(|(_, _, path)| load_mesh(path, load_config));
+        if let Some(mesh) = first_frame {
+            let mut info = Vec::new();
+            let texcoords = find_texcoords.then(|| probe::find_texcoords(&mesh));
+            if let Some(name) = texcoords.flatten().filter(|name| name != "uv") {
+                info.push((
+                    1,
+                    format!(
+                        "No \"uv\" attribute found, using {:?} as texture coordinates",
+                        name
+                    ),
+                ));
+                config.texcoords = TextureAttributeInfo([(name, ComponentType::F32)].into());
+            }
+            if let Some((name, type_)) = probe::find_colors(&mesh).filter(|_| find_colors) {
+                info.push((1, format!("Using {:?} as colors", name)));
+                config.colors = AttributeInfo([(name, type_)].into());
+            }
+            print_info(info);
+        }
+    }
 
-    let load_config = LoadConfig {
-        reverse: config.reverse,
-        invert_tets: config.invert_tets,
+    report.time("pruning", stage_start.elapsed());
+    let stage_start = Instant::now();
+
+    let mut scene = if let Some(path) = &config.scene {
+        SceneInfo::load(path)?
+    } else {
+        SceneInfo::default()
     };
+    let mut node_materials = HashMap::new();
+    for (name, o) in config.overrides.iter() {
+        if o.translation.is_some() || o.rotation.is_some() || o.scale.is_some() {
+            scene.set_transform(name, o.translation, o.rotation, o.scale);
+        }
+        if let Some(material) = &o.material {
+            match config.materials.iter().position(|m| &m.name == material) {
+                Some(index) => {
+                    node_materials.insert(name.clone(), index as u32);
+                }
+                None => log::warn!(
+                    "Material '{}' overridden for '{}' is not one of the specified materials",
+                    material,
+                    name
+                ),
+            }
+        }
+    }
+
+    let frames = || mesh_meta.iter().map(|(_, frame, _)| *frame);
+    let frame_span = frames().max().unwrap_or(0) - frames().min().unwrap_or(0);
+    if config.duration.is_some() && frame_span == 0 {
+        log::warn!("Ignoring the duration of an animation with a single frame");
+    }
+    let dt = time::frame_time_step(
+        config.fps,
+        config.time_step,
+        config.duration,
+        frame_span,
+        config.substeps,
+    );
+
+    let output = config.output.clone();
+    let export_config = export::ExportConfig::builder()
+        .textures(config.textures)
+        .materials(config.materials)
+        .output(config.output)
+        .time_step(dt)
+        .insert_vanishing_frames(config.insert_vanishing_frames)
+        .animate_normals(!config.no_animated_normals)
+        .animate_tangents(!config.no_animated_tangents)
+        .animate_colors(config.animate_colors)
+        .recompute_normals(config.recompute_normals)
+        .crease_angle(config.crease_angle)
+        .bake_fields(config.bake_fields)
+        .max_primitive_vertices(config.max_primitive_vertices)
+        .point_size(config.point_size)
+        .points_as(config.points_as)
+        .animation_groups(config.animation_groups)
+        .dcc(config.dcc)
+        .dcc_max_targets(config.dcc_max_targets)
+        .collision_proxy(config.collision_proxy)
+        .animate_proxy(config.animate_proxy)
+        .max_morph_targets(config.max_morph_targets)
+        .lights(config.lights)
+        .studio_lighting(config.studio_lighting)
+        .frame_camera(config.frame_camera)
+        .pointer_animations(config.pointer_animations)
+        .scene(scene)
+        .layout(config.layout)
+        .attach(config.attach)
+        .extras(config.extras)
+        .asset(export::AssetInfo {
+            copyright: config.copyright,
+            generator_suffix: config.generator_suffix,
+            license: config.license,
+        })
+        .validate(config.validate)
+        .no_overwrite(config.no_overwrite)
+        .dedup_buffers(config.dedup_buffers)
+        .split_buffers(config.split_buffers)
+        .strip_names(config.strip_names)
+        .minimal(config.minimal)
+        .deterministic(config.deterministic)
+        .strict(config.strict)
+        .non_finite(config.non_finite)
+        .gap_policy(config.gap_policy)
+        .attribute_mismatch(config.attribute_mismatch)
+        .transitions(transitions)
+        .attribute_names(config.attribute_names)
+        .material_id_map(config.material_id_map)
+        .default_material(config.default_material)
+        .split_materials(config.split_materials)
+        .texture_atlas(config.texture_atlas)
+        .node_materials(node_materials)
+        .progress(Arc::new(IndicatifProgress::new(quiet)))
+        .build();
+
+    let pb = utils::new_progress_bar(quiet, mesh_meta.len());
+    pb.set_message("Building Meshes");
 
     let attrib_config = AttribConfig {
         attributes: &config.attributes,
@@ -299,45 +819,403 @@ fn try_main() -> Result<(), Error> {
         material_attribute: &config.material_attribute,
     };
 
-    let process_attrib_error = |e| {
+    let num_attrib_errors = AtomicUsize::new(0);
+    let dropped_attributes = Mutex::new(Vec::new());
+    let process_attrib_error = |name: &str, e: AttribError| {
+        num_attrib_errors.fetch_add(1, Ordering::Relaxed);
         pb.println(format!("{}: {}, Skipping...", style("WARNING").yellow(), e));
+        dropped_attributes
+            .lock()
+            .unwrap()
+            .push((name.to_string(), e.to_string()));
     };
 
-    // Load all meshes with the appropriate conversions and attribute transfers.
-    let meshes: Vec<_> = mesh_meta
-        .into_par_iter()
-        .progress_with(pb.clone())
-        .filter_map(|(name, frame, path)| {
-            load_and_clean_mesh(&path, load_config, attrib_config, process_attrib_error)
-                .map(|(mesh, attrib_transfer)| (name, frame, mesh, attrib_transfer))
+    let num_files = mesh_meta.len();
+
+    // Frames of a resumable export are checkpointed to the cache if one is given.
+    let checkpoint =
+        (config.resume && config.cache.is_none()).then(|| gltfgen::cache::checkpoint_dir(&output));
+    let cache = config
+        .cache
+        .as_ref()
+        .or(checkpoint.as_ref())
+        .and_then(|dir| {
+            FrameCache::open(dir, load_config, attrib_config)
+                .map_err(|err| log::warn!("Failed to open frame cache {}: {}", dir.display(), err))
+                .ok()
+        });
+    let num_cached = AtomicUsize::new(0);
+
+    // Animations with overridden attributes are loaded with their own
+    // attribute configuration and cached separately.
+    let attrib_overrides: HashMap<&str, AttribConfig> = config
+        .overrides
+        .iter()
+        .filter(|(_, o)| o.changes_attributes())
+        .map(|(name, o)| {
+            let attrib_config = AttribConfig {
+                attributes: o.attributes.as_ref().unwrap_or(&config.attributes),
+                colors: o.colors.as_ref().unwrap_or(&config.colors),
+                texcoords: o.texcoords.as_ref().unwrap_or(&config.texcoords),
+                material_attribute: &config.material_attribute,
+            };
+            (name.as_str(), attrib_config)
+        })
+        .collect();
+    let override_caches: HashMap<&str, FrameCache> = attrib_overrides
+        .iter()
+        .filter_map(|(&name, &attrib_config)| {
+            let cache = cache.as_ref()?.with_config(load_config, attrib_config);
+            Some((name, cache))
         })
         .collect();
 
+    // Frames are loaded in parallel a few at a time and converted into morph
+    // targets right away, so only a small window of full meshes is in memory.
+    mesh_meta.sort_by(|(name_a, frame_a, path_a), (name_b, frame_b, path_b)| {
+        name_a
+            .cmp(name_b)
+            .then(frame_a.cmp(frame_b))
+            .then(path_a.cmp(path_b))
+    });
+    let window = 2 * rayon::current_num_threads().max(1);
+    let formats = FormatCache::default();
+    formats.probe_first(
+        mesh_meta
+            .iter()
+            .map(|(name, _, path)| (name.as_str(), path.as_path())),
+        load_config,
+    );
+    let mut sequence = export::SequenceBuilder::new(attrib_config, export_config);
+    let mut num_loaded = 0;
+    let mut mesh_meta = std::collections::VecDeque::from(mesh_meta);
+    while !mesh_meta.is_empty() {
+        let window = window.min(mesh_meta.len());
+        let loaded: Vec<_> = mesh_meta
+            .drain(..window)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .progress_with(pb.clone())
+            .map(|(name, frame, path)| {
+                let attrib_config = attrib_overrides
+                    .get(name.as_str())
+                    .copied()
+                    .unwrap_or(attrib_config);
+                let cache = override_caches.get(name.as_str()).or(cache.as_ref());
+                if let Some(cached) = cache.and_then(|cache| cache.get(&path)) {
+                    num_cached.fetch_add(1, Ordering::Relaxed);
+                    return (name, frame, path, Some(cached));
+                }
+                let load_config = formats.load_config(&name, &path, load_config);
+                // Frames with dropped attributes are not cached so that the
+                // warnings are reported again on the next run.
+                let mut has_attrib_errors = false;
+                let mesh = load_and_clean_mesh(&path, load_config, attrib_config, |e| {
+                    has_attrib_errors = true;
+                    process_attrib_error(&name, e)
+                });
+                if let (Some(cache), Some((mesh, attrib_transfer))) = (cache, &mesh) {
+                    if !has_attrib_errors {
+                        if let Err(err) = cache.insert(&path, mesh, attrib_transfer) {
+                            log::warn!("Failed to cache {}: {}", path.display(), err);
+                        }
+                    }
+                }
+                (name, frame, path, mesh)
+            })
+            .map(|(name, frame, path, mesh)| {
+                let sidecar = if config.sidecar && mesh.is_some() {
+                    extras::load_sidecar(&path)
+                        .map_err(|err| log::warn!("{}. Skipping...", err))
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                (name, frame, path, mesh, sidecar)
+            })
+            .collect();
+
+        for (name, frame, path, mesh, sidecar) in loaded {
+            match mesh {
+                Some((mesh, attrib_transfer)) => {
+                    // Duplicate frames of the same animation are reported as out of order.
+                    match sequence.add_clean_frame(name.clone(), frame, mesh, attrib_transfer) {
+                        Ok(()) => {
+                            if let Some(sidecar) = sidecar {
+                                sequence.add_frame_extras(&name, frame, sidecar);
+                            }
+                            report.loaded(path, name, frame);
+                            num_loaded += 1;
+                        }
+                        Err(err @ Error::NonFinite(..)) => return Err(err),
+                        Err(err) => {
+                            log::warn!("{}. Skipping...", err);
+                            report.skipped(path, Some((name, frame)), err.to_string())
+                        }
+                    }
+                }
+                None => report.skipped(path, Some((name, frame)), "failed to load mesh"),
+            }
+        }
+    }
+
     pb.finish_with_message("Done building meshes");
 
-    if meshes.is_empty() {
+    if cache.is_some() {
+        log::info!(
+            "Reused {} cached frame(s)",
+            num_cached.load(Ordering::Relaxed)
+        );
+    }
+
+    for (name, error) in dropped_attributes.into_inner().unwrap() {
+        report.dropped_attribute(&name, error);
+    }
+
+    report.time("loading", stage_start.elapsed());
+    let stage_start = Instant::now();
+
+    if num_loaded == 0 {
         return Err(Error::NoMeshesFound);
     }
 
-    let dt = if let Some(dt) = config.time_step {
-        dt
-    } else {
-        1.0 / config.fps as f32
+    let num_skipped = num_files - num_loaded;
+    if num_skipped > 0 {
+        num_warnings += print_warnings(vec![(
+            1,
+            format!(
+                "{} frame(s) skipped since their meshes failed to load",
+                num_skipped
+            ),
+        )]);
+    }
+
+    if config.strict {
+        let num_attrib_errors = num_attrib_errors.into_inner();
+        if num_skipped > 0 {
+            return Err(Error::FramesSkipped(num_skipped));
+        } else if num_attrib_errors > 0 {
+            return Err(Error::AttributeErrors(num_attrib_errors));
+        } else if num_warnings + sequence.warning_count() > 0 {
+            return Err(Error::Warnings(num_warnings + sequence.warning_count()));
+        }
+    }
+
+    // In strict mode, warnings reported while building the glTF fail the
+    // export before anything is written.
+    let export_report = sequence.finish()?;
+
+    if let Some(dir) = checkpoint.filter(|dir| dir.exists()) {
+        if let Err(err) = std::fs::remove_dir_all(&dir) {
+            log::warn!("Failed to remove checkpoints in {}: {}", dir.display(), err);
+        }
+    }
+
+    report.time("export", stage_start.elapsed());
+    report.time("total", start.elapsed());
+    if let Err(err) = report.read_output(&output) {
+        log::warn!("Failed to read output for the report: {}", err);
+    }
+    for (name, material_ids) in export_report.material_ids {
+        report.material_ids(&name, material_ids);
+    }
+
+    if let Some(config) = manifest_config {
+        let inputs: Vec<_> = report
+            .files
+            .iter()
+            .filter(|file| file.loaded)
+            .map(|file| file.path.clone())
+            .collect();
+        let path = manifest::manifest_path(&export_report.output);
+        manifest::Manifest::new(config, &inputs, &export_report.files)
+            .and_then(|manifest| manifest.write(&path))
+            .map_err(Error::Write)?;
+    }
+
+    Ok(())
+}
+
+fn run_command(command: Command, quiet: bool) -> Result<(), Error> {
+    match command {
+        Command::Info { files } => {
+            for (i, path) in files.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                let summary = info::GltfSummary::from_path(path)?;
+                println!("{}:", path.display());
+                print!("{}", summary);
+            }
+        }
+        Command::Merge {
+            files,
+            output,
+            animations,
+            validate,
+        } => {
+            export::export_merged(
+                &files,
+                output,
+                animations,
+                Default::default(),
+                validate,
+                &IndicatifProgress::new(quiet),
+            )?;
+        }
+        Command::Append {
+            into,
+            animations,
+            config,
+        } => append(into, animations, *config, quiet)?,
+        Command::Diff {
+            first,
+            second,
+            epsilon,
+        } => {
+            let diffs = diff::diff_files(&first, &second, epsilon)?;
+            for d in diffs.iter() {
+                println!("{}", d);
+            }
+            if !diffs.is_empty() {
+                return Err(Error::GltfMismatch(diffs.len()));
+            }
+        }
+        Command::Extract {
+            file,
+            output,
+            fps,
+            time_step,
+        } => {
+            let time_step = time::frame_time_step(fps, time_step, None, 0, 1);
+            let mut warnings = Vec::new();
+            let count = extract::extract_file(&file, &output, time_step, &mut warnings)?;
+            print_warnings(warnings);
+            print_info(vec![(1, format!("Wrote {} mesh file(s)", count))]);
+        }
+        Command::Preview { file, html } => {
+            let html = preview::write_preview(&file, html)?;
+            print_info(vec![(1, format!("Wrote preview to {}", html.display()))]);
+        }
+        Command::Probe { input, json } => {
+            let report = probe::probe(&input, LoadConfig::default())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{}:", report.path.display());
+                println!(
+                    "  vertices: {}, triangles: {}",
+                    report.mesh.num_vertices, report.mesh.num_faces
+                );
+                for attrib in report.mesh.attributes.iter() {
+                    println!(
+                        "  {:<12} {:?}: {} x {}",
+                        attrib.location, attrib.name, attrib.type_name, attrib.len
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generates glTF from the meshes matched by `config` and merges it into the file at `into`.
+///
+/// The generated glTF is written next to `into` first and removed once merged.
+fn append(
+    into: PathBuf,
+    animations: merge::AnimationMerge,
+    mut config: Config,
+    quiet: bool,
+) -> Result<(), Error> {
+    if !into.is_file() {
+        return Err(Error::InvalidOutputPath(into));
+    }
+    let mut generated = into.clone();
+    generated.set_extension("append.glb");
+    config.output = generated.clone();
+    config.no_overwrite = false;
+    let asset = export::AssetInfo {
+        copyright: config.copyright.clone(),
+        generator_suffix: config.generator_suffix.clone(),
+        license: config.license.clone(),
     };
+    let validate = config.validate;
 
-    export::export_clean_meshes(
-        meshes,
-        export::ExportConfig {
-            textures: config.textures,
-            materials: config.materials,
-            output: config.output,
-            time_step: dt,
-            insert_vanishing_frames: config.insert_vanishing_frames,
-            animate_normals: !config.no_animated_normals,
-            animate_tangents: !config.no_animated_tangents,
-            quiet: opt.verbose.is_silent(),
-        },
+    let result = generate(config, false, quiet, &mut Report::default()).and_then(|()| {
+        export::export_merged(
+            &[into.clone(), generated.clone()],
+            into,
+            animations,
+            asset,
+            validate,
+            &IndicatifProgress::new(quiet),
+        )
+    });
+    if generated.exists() {
+        if let Err(err) = std::fs::remove_file(&generated) {
+            log::warn!("Failed to remove {}: {}", generated.display(), err);
+        }
+    }
+    result.map(|_| ())
+}
+
+/// Prints the frames found for each animation along with a summary of the first frame.
+fn print_dry_run_report(mesh_meta: &[(String, u32, PathBuf)], load_config: LoadConfig) {
+    use indicatif::HumanBytes;
+
+    // Collect frames for each animation sorted by name.
+    let mut animations = BTreeMap::<&str, Vec<(u32, &PathBuf)>>::new();
+    for (name, frame, path) in mesh_meta.iter() {
+        animations.entry(name).or_default().push((*frame, path));
+    }
+
+    println!(
+        "Found {} files in {} animation(s)",
+        mesh_meta.len(),
+        animations.len()
     );
 
-    Ok(())
+    let mut total_size = 0;
+    for (name, mut frames) in animations.into_iter() {
+        frames.sort_by_key(|&(frame, _)| frame);
+        let (first, first_path) = frames[0];
+        let last = frames[frames.len() - 1].0;
+        println!();
+        println!("{:?}: {} frame(s) ({}-{})", name, frames.len(), first, last);
+        println!("  first frame: {}", first_path.display());
+        let Some(mesh) = load_mesh(first_path, load_config) else {
+            println!(
+                "  {}: failed to load first frame",
+                style("WARNING").yellow()
+            );
+            continue;
+        };
+        let summary = probe::MeshSummary::new(&mesh);
+        println!(
+            "  vertices: {}, triangles: {}",
+            summary.num_vertices, summary.num_faces
+        );
+        if summary.attributes.is_empty() {
+            println!("  attributes: none");
+        } else {
+            println!("  attributes:");
+            for attrib in summary.attributes.iter() {
+                println!(
+                    "    {:<12} {:?}: {}",
+                    attrib.location, attrib.name, attrib.type_name
+                );
+            }
+        }
+        let size = summary.estimate_output_size(frames.len());
+        total_size += size;
+        println!("  estimated size: {}", HumanBytes(size as u64));
+    }
+
+    println!();
+    println!(
+        "Estimated total output size: {}",
+        HumanBytes(total_size as u64)
+    );
 }
@@ -0,0 +1,105 @@
+//! Manifests recording the inputs, outputs and configuration of an export.
+//!
+//! A manifest is written next to the output (see [`manifest_path`]) so that
+//! exports can be audited and reproduced later. Files are identified by their
+//! SHA-256 digest.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Size and digest of a single file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FileDigest {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Lowercase hexadecimal SHA-256 digest of the file contents.
+    pub sha256: String,
+}
+
+impl FileDigest {
+    /// Reads and hashes the file at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<FileDigest> {
+        let path = path.as_ref();
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 1 << 16];
+        let mut bytes = 0;
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            bytes += n as u64;
+        }
+        let sha256 = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok(FileDigest {
+            path: path.to_path_buf(),
+            bytes,
+            sha256,
+        })
+    }
+}
+
+/// Record of a single export.
+#[derive(Clone, Debug, Serialize)]
+pub struct Manifest {
+    /// Name of the tool that produced the output.
+    pub tool: String,
+    /// Version of the tool that produced the output.
+    pub version: String,
+    /// Configuration used for the export with all overrides applied.
+    pub config: serde_json::Value,
+    /// Files the output was generated from.
+    pub inputs: Vec<FileDigest>,
+    /// Written output files, including external buffers.
+    pub outputs: Vec<FileDigest>,
+}
+
+impl Manifest {
+    /// Builds a manifest by hashing the given input and output files.
+    pub fn new(
+        config: serde_json::Value,
+        inputs: &[PathBuf],
+        outputs: &[PathBuf],
+    ) -> std::io::Result<Manifest> {
+        Ok(Manifest {
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config,
+            inputs: digests(inputs)?,
+            outputs: digests(outputs)?,
+        })
+    }
+
+    /// Writes this manifest as JSON to `path`.
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Path of the manifest accompanying the given output file.
+///
+/// For an output at `out.glb`, the manifest is written to `out.manifest.json`.
+pub fn manifest_path(output: &Path) -> PathBuf {
+    output.with_extension("manifest.json")
+}
+
+fn digests(paths: &[PathBuf]) -> std::io::Result<Vec<FileDigest>> {
+    #[cfg(feature = "parallel")]
+    let paths = paths.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let paths = paths.iter();
+    paths.map(FileDigest::from_path).collect()
+}
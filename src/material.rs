@@ -1,4 +1,5 @@
 use gltf::json;
+use indexmap::IndexMap;
 use json::validation::Checked::Valid;
 use serde::{Deserialize, Serialize};
 
@@ -49,6 +50,7 @@ fn default_roughness() -> f32 {
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MaterialInfo {
     #[serde(default)]
     pub name: String,
@@ -75,9 +77,32 @@ impl Default for MaterialInfo {
 }
 
 impl std::str::FromStr for MaterialInfo {
-    type Err = ron::de::Error;
+    type Err = crate::lint::ParseError;
     fn from_str(input: &str) -> Result<MaterialInfo, Self::Err> {
-        ron::de::from_str::<MaterialInfo>(input).map_err(Self::Err::from)
+        crate::lint::parse_ron(
+            input,
+            "(name:\"red\", base_color:(1.0, 0.0, 0.0, 1.0), base_texture:(index:0, texcoord:0), metallic:0.0, roughness:0.5)",
+        )
+    }
+}
+
+/// Remapping of material ids found in meshes to indices of the specified materials.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaterialIdRemap(#[serde(with = "crate::config::integer_keys")] pub IndexMap<u32, u32>);
+
+impl MaterialIdRemap {
+    /// Returns the material index the given material id refers to.
+    pub fn get(&self, id: u32) -> u32 {
+        self.0.get(&id).copied().unwrap_or(id)
+    }
+}
+
+impl std::str::FromStr for MaterialIdRemap {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<MaterialIdRemap, Self::Err> {
+        ron::de::from_str::<IndexMap<u32, u32>>(input)
+            .map(MaterialIdRemap)
+            .map_err(Self::Err::from)
     }
 }
 
@@ -0,0 +1,715 @@
+//! Utilities for merging existing glTF documents into the generated output.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use byteorder::{WriteBytesExt, LE};
+use gltf::json;
+use json::accessor::ComponentType as GltfComponentType;
+use json::validation::{Checked::Valid, USize64};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::export::{align_data, build_nonempty_buffer_vec3, AccessorBuilder, BufferViewBuilder};
+use crate::uri::uri_to_path;
+
+/// How animations of merged documents are combined.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum AnimationMerge {
+    /// All animations are combined into a single animation playing simultaneously.
+    #[serde(alias = "parallel")]
+    #[default]
+    Parallel,
+    /// Animations are combined into a single animation playing one document
+    /// after another in the order given.
+    ///
+    /// Documents whose keyframes start before the previous document ends are
+    /// shifted in time to follow it. The nodes of each document are only
+    /// visible while its part of the animation is playing.
+    #[serde(alias = "concatenate")]
+    Concatenate,
+    /// Animations are kept as they are.
+    #[serde(alias = "separate")]
+    Separate,
+}
+
+impl std::str::FromStr for AnimationMerge {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<AnimationMerge, Self::Err> {
+        ron::de::from_str::<AnimationMerge>(input).map_err(Self::Err::from)
+    }
+}
+
+/// Number of objects of each type in a root before another document is appended.
+#[derive(Copy, Clone, Debug, Default)]
+struct Offsets {
+    accessors: u32,
+    buffer_views: u32,
+    cameras: u32,
+    images: u32,
+    materials: u32,
+    meshes: u32,
+    nodes: u32,
+    samplers: u32,
+    skins: u32,
+    textures: u32,
+    lights: u32,
+}
+
+impl Offsets {
+    fn of(root: &json::Root) -> Offsets {
+        Offsets {
+            accessors: root.accessors.len() as u32,
+            buffer_views: root.buffer_views.len() as u32,
+            cameras: root.cameras.len() as u32,
+            images: root.images.len() as u32,
+            materials: root.materials.len() as u32,
+            meshes: root.meshes.len() as u32,
+            nodes: root.nodes.len() as u32,
+            samplers: root.samplers.len() as u32,
+            skins: root.skins.len() as u32,
+            textures: root.textures.len() as u32,
+            lights: root
+                .extensions
+                .as_ref()
+                .and_then(|ext| ext.khr_lights_punctual.as_ref())
+                .map_or(0, |l| l.lights.len() as u32),
+        }
+    }
+}
+
+pub(crate) fn shift<T>(index: &mut json::Index<T>, offset: u32) {
+    *index = json::Index::new(index.value() as u32 + offset);
+}
+
+pub(crate) fn remap<T>(index: &mut json::Index<T>, map: &[u32]) {
+    *index = json::Index::new(map[index.value()]);
+}
+
+/// Texture references of the given material.
+fn material_textures_mut(
+    mtl: &mut json::Material,
+) -> impl Iterator<Item = &mut json::Index<json::Texture>> {
+    let json::Material {
+        pbr_metallic_roughness: pbr,
+        normal_texture,
+        occlusion_texture,
+        emissive_texture,
+        ..
+    } = mtl;
+    [
+        pbr.base_color_texture.as_mut().map(|info| &mut info.index),
+        pbr.metallic_roughness_texture
+            .as_mut()
+            .map(|info| &mut info.index),
+        normal_texture.as_mut().map(|info| &mut info.index),
+        occlusion_texture.as_mut().map(|info| &mut info.index),
+        emissive_texture.as_mut().map(|info| &mut info.index),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Appends the glTF document at `path` to `root`, copying its buffers into `data`.
+///
+/// All objects are appended after existing ones, so indices into `root` remain
+/// valid. Relative image URIs are resolved and embedded into `data` such that
+/// the output is self-contained.
+///
+/// Returns the root nodes of the default (or first) scene of the appended document.
+pub(crate) fn append_document(
+    root: &mut json::Root,
+    data: &mut Vec<u8>,
+    path: &Path,
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<Vec<json::Index<json::Node>>, gltf::Error> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::open(path)?;
+    let base = path.parent();
+    let buffers = gltf::import_buffers(&document, base, blob)?;
+    let mut src = document.into_json();
+
+    let offsets = Offsets::of(root);
+
+    // Copy all source buffers into our single buffer.
+    let mut buffer_offsets = Vec::with_capacity(buffers.len());
+    for buffer in buffers.iter() {
+        align_data(data);
+        buffer_offsets.push(data.len() as u64);
+        data.extend_from_slice(&buffer.0);
+    }
+
+    for view in src.buffer_views.iter_mut() {
+        let offset = buffer_offsets[view.buffer.value()];
+        view.byte_offset = Some(USize64(view.byte_offset.map_or(0, |USize64(o)| o) + offset));
+        view.buffer = json::Index::new(0);
+    }
+
+    for acc in src.accessors.iter_mut() {
+        if let Some(view) = acc.buffer_view.as_mut() {
+            shift(view, offsets.buffer_views);
+        }
+        if let Some(sparse) = acc.sparse.as_mut() {
+            shift(&mut sparse.indices.buffer_view, offsets.buffer_views);
+            shift(&mut sparse.values.buffer_view, offsets.buffer_views);
+        }
+    }
+
+    for image in src.images.iter_mut() {
+        if let Some(view) = image.buffer_view.as_mut() {
+            shift(view, offsets.buffer_views);
+        } else if let Some(uri) = image.uri.as_ref().filter(|uri| !uri.starts_with("data:")) {
+            let image_path =
+                base.map_or_else(|| uri_to_path(uri), |base| base.join(uri_to_path(uri)));
+            let mime_type = image_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| match ext.to_lowercase().as_str() {
+                    "jpeg" | "jpg" => Some("image/jpeg"),
+                    "png" => Some("image/png"),
+                    _ => None,
+                });
+            match (mime_type, std::fs::read(&image_path)) {
+                (Some(mime_type), Ok(bytes)) => {
+                    align_data(data);
+                    let view = json::buffer::View::new(bytes.len(), data.len());
+                    data.extend_from_slice(&bytes);
+                    image.buffer_view = Some(json::Index::new(
+                        offsets.buffer_views + src.buffer_views.len() as u32,
+                    ));
+                    image.mime_type = Some(json::image::MimeType(mime_type.to_string()));
+                    image.uri = None;
+                    src.buffer_views.push(view);
+                }
+                _ => {
+                    log!(warnings;
+                        "Failed to embed image: {:?}. Keeping the original URI...",
+                        &image_path
+                    );
+                }
+            }
+        }
+    }
+
+    for texture in src.textures.iter_mut() {
+        if let Some(sampler) = texture.sampler.as_mut() {
+            shift(sampler, offsets.samplers);
+        }
+        shift(&mut texture.source, offsets.images);
+    }
+
+    for mtl in src.materials.iter_mut() {
+        for texture in material_textures_mut(mtl) {
+            shift(texture, offsets.textures);
+        }
+    }
+
+    for mesh in src.meshes.iter_mut() {
+        for prim in mesh.primitives.iter_mut() {
+            for acc in prim.attributes.values_mut() {
+                shift(acc, offsets.accessors);
+            }
+            if let Some(acc) = prim.indices.as_mut() {
+                shift(acc, offsets.accessors);
+            }
+            if let Some(mtl) = prim.material.as_mut() {
+                shift(mtl, offsets.materials);
+            }
+            for target in prim.targets.iter_mut().flatten() {
+                for acc in [
+                    &mut target.positions,
+                    &mut target.normals,
+                    &mut target.tangents,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    shift(acc, offsets.accessors);
+                }
+            }
+        }
+    }
+
+    for node in src.nodes.iter_mut() {
+        if let Some(camera) = node.camera.as_mut() {
+            shift(camera, offsets.cameras);
+        }
+        for child in node.children.iter_mut().flatten() {
+            shift(child, offsets.nodes);
+        }
+        if let Some(mesh) = node.mesh.as_mut() {
+            shift(mesh, offsets.meshes);
+        }
+        if let Some(skin) = node.skin.as_mut() {
+            shift(skin, offsets.skins);
+        }
+        if let Some(light) = node
+            .extensions
+            .as_mut()
+            .and_then(|ext| ext.khr_lights_punctual.as_mut())
+        {
+            shift(&mut light.light, offsets.lights);
+        }
+    }
+
+    for skin in src.skins.iter_mut() {
+        if let Some(acc) = skin.inverse_bind_matrices.as_mut() {
+            shift(acc, offsets.accessors);
+        }
+        for joint in skin.joints.iter_mut() {
+            shift(joint, offsets.nodes);
+        }
+        if let Some(skeleton) = skin.skeleton.as_mut() {
+            shift(skeleton, offsets.nodes);
+        }
+    }
+
+    for anim in src.animations.iter_mut() {
+        for channel in anim.channels.iter_mut() {
+            shift(&mut channel.target.node, offsets.nodes);
+        }
+        for sampler in anim.samplers.iter_mut() {
+            shift(&mut sampler.input, offsets.accessors);
+            shift(&mut sampler.output, offsets.accessors);
+        }
+    }
+
+    let scene_roots = src
+        .scene
+        .map(|i| i.value())
+        .or_else(|| (!src.scenes.is_empty()).then_some(0))
+        .map_or_else(Vec::new, |i| {
+            src.scenes[i]
+                .nodes
+                .iter()
+                .map(|&n| json::Index::new(n.value() as u32 + offsets.nodes))
+                .collect()
+        });
+
+    if let Some(src_lights) = src
+        .extensions
+        .and_then(|ext| ext.khr_lights_punctual)
+        .filter(|l| !l.lights.is_empty())
+    {
+        let lights = &mut root
+            .extensions
+            .get_or_insert_with(Default::default)
+            .khr_lights_punctual
+            .get_or_insert_with(|| json::extensions::root::KhrLightsPunctual { lights: Vec::new() })
+            .lights;
+        lights.extend(src_lights.lights);
+    }
+
+    for ext in src.extensions_used {
+        if !root.extensions_used.contains(&ext) {
+            root.extensions_used.push(ext);
+        }
+    }
+    for ext in src.extensions_required {
+        if !root.extensions_required.contains(&ext) {
+            root.extensions_required.push(ext);
+        }
+    }
+
+    root.accessors.extend(src.accessors);
+    root.animations.extend(src.animations);
+    root.buffer_views.extend(src.buffer_views);
+    root.cameras.extend(src.cameras);
+    root.images.extend(src.images);
+    root.materials.extend(src.materials);
+    root.meshes.extend(src.meshes);
+    root.nodes.extend(src.nodes);
+    root.samplers.extend(src.samplers);
+    root.skins.extend(src.skins);
+    root.textures.extend(src.textures);
+
+    Ok(scene_roots)
+}
+
+/// Merges the glTF documents at the given paths into a single document with one scene.
+///
+/// Identical images, samplers, textures and materials are stored only once.
+/// Animations are combined according to `animations`.
+///
+/// Returns the merged document along with its binary buffer data. No buffer
+/// is pushed to the returned root, since that depends on the output format.
+pub(crate) fn merge_documents(
+    paths: &[PathBuf],
+    animations: AnimationMerge,
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<(json::Root, Vec<u8>), Error> {
+    let mut root = json::Root::default();
+    let mut data = Vec::new();
+    let mut scene_nodes = Vec::new();
+    // Animations and scene roots contributed by each document.
+    let mut parts = Vec::new();
+    for path in paths.iter() {
+        let first_animation = root.animations.len();
+        let roots = append_document(&mut root, &mut data, path, warnings)?;
+        parts.push((first_animation..root.animations.len(), roots.clone()));
+        scene_nodes.extend(roots);
+    }
+
+    root.scenes.push(json::Scene {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: None,
+        nodes: scene_nodes,
+    });
+
+    dedup_materials(&mut root, &data)?;
+    dedup_buffer_views(&mut root, &mut data)?;
+
+    match animations {
+        AnimationMerge::Parallel => combine_animations(&mut root),
+        AnimationMerge::Concatenate => {
+            concatenate_animations(&mut root, &mut data, &parts, warnings);
+            combine_animations(&mut root);
+        }
+        AnimationMerge::Separate => {}
+    }
+
+    Ok((root, data))
+}
+
+/// Removes duplicate items keeping the first occurrence.
+///
+/// Returns the new index of each original item, or the first error returned
+/// by `key`, in which case `items` is left unchanged.
+pub(crate) fn dedup_by_key<T, K: Eq + Hash>(
+    items: &mut Vec<T>,
+    key: impl Fn(&T) -> Result<K, Error>,
+) -> Result<Vec<u32>, Error> {
+    let keys = items.iter().map(key).collect::<Result<Vec<_>, _>>()?;
+    let mut seen = HashMap::new();
+    let mut map = Vec::with_capacity(items.len());
+    let mut kept = Vec::new();
+    for (item, key) in items.drain(..).zip(keys) {
+        let next = kept.len() as u32;
+        let index = *seen.entry(key).or_insert(next);
+        if index == next {
+            kept.push(item);
+        }
+        map.push(index);
+    }
+    *items = kept;
+    Ok(map)
+}
+
+pub(crate) fn to_json_key<T: Serialize>(item: &T) -> Result<String, Error> {
+    serde_json::to_string(item).map_err(Error::GltfSerialize)
+}
+
+/// Deduplicates images, samplers, textures and materials.
+///
+/// Images are compared by their contents, all other objects are compared by
+/// their JSON representation after references have been deduplicated.
+fn dedup_materials(root: &mut json::Root, data: &[u8]) -> Result<(), Error> {
+    let buffer_views = &root.buffer_views;
+    let image_map = dedup_by_key(&mut root.images, |image| {
+        let bytes = image.buffer_view.and_then(|view| {
+            let view = &buffer_views[view.value()];
+            let start = view.byte_offset.map_or(0, |USize64(o)| o) as usize;
+            data.get(start..start + view.byte_length.0 as usize)
+        });
+        Ok((
+            image.uri.clone(),
+            image.mime_type.as_ref().map(|m| m.0.clone()),
+            bytes,
+        ))
+    })?;
+    let sampler_map = dedup_by_key(&mut root.samplers, to_json_key)?;
+
+    for texture in root.textures.iter_mut() {
+        remap(&mut texture.source, &image_map);
+        if let Some(sampler) = texture.sampler.as_mut() {
+            remap(sampler, &sampler_map);
+        }
+    }
+    let texture_map = dedup_by_key(&mut root.textures, to_json_key)?;
+
+    for mtl in root.materials.iter_mut() {
+        for texture in material_textures_mut(mtl) {
+            remap(texture, &texture_map);
+        }
+    }
+    let material_map = dedup_by_key(&mut root.materials, to_json_key)?;
+
+    for mesh in root.meshes.iter_mut() {
+        for prim in mesh.primitives.iter_mut() {
+            if let Some(mtl) = prim.material.as_mut() {
+                remap(mtl, &material_map);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Makes buffer views with identical contents share a single view and drops
+/// the bytes of the removed views from `data`.
+///
+/// Views are only shared if they also have the same stride and target, so
+/// accessors referencing them are unaffected.
+pub(crate) fn dedup_buffer_views(root: &mut json::Root, data: &mut Vec<u8>) -> Result<(), Error> {
+    let num_views = root.buffer_views.len();
+    let bytes = |view: &json::buffer::View| {
+        let start = view.byte_offset.map_or(0, |USize64(o)| o) as usize;
+        data.get(start..start + view.byte_length.0 as usize)
+    };
+    let view_map = dedup_by_key(&mut root.buffer_views, |view| {
+        Ok((
+            view.buffer.value(),
+            view.byte_stride,
+            to_json_key(&view.target)?,
+            // Views outside the buffer are compared by their range.
+            bytes(view).ok_or((view.byte_offset, view.byte_length)),
+        ))
+    })?;
+    if root.buffer_views.len() == num_views {
+        return Ok(());
+    }
+    remap_buffer_views(root, data, &view_map);
+    Ok(())
+}
+
+/// Points accessors and images at the views given by `view_map`, and rewrites
+/// `data` to hold only the bytes of the views remaining in `root`.
+pub(crate) fn remap_buffer_views(root: &mut json::Root, data: &mut Vec<u8>, view_map: &[u32]) {
+    for acc in root.accessors.iter_mut() {
+        if let Some(view) = acc.buffer_view.as_mut() {
+            remap(view, view_map);
+        }
+        if let Some(sparse) = acc.sparse.as_mut() {
+            remap(&mut sparse.indices.buffer_view, view_map);
+            remap(&mut sparse.values.buffer_view, view_map);
+        }
+    }
+    for image in root.images.iter_mut() {
+        if let Some(view) = image.buffer_view.as_mut() {
+            remap(view, view_map);
+        }
+    }
+
+    // Copy only the remaining views into the new buffer.
+    let mut compacted = Vec::with_capacity(data.len());
+    for view in root.buffer_views.iter_mut() {
+        if view.buffer.value() != 0 {
+            continue;
+        }
+        let start = view.byte_offset.map_or(0, |USize64(o)| o) as usize;
+        let end = start + view.byte_length.0 as usize;
+        let Some(bytes) = data.get(start..end) else {
+            continue;
+        };
+        align_data(&mut compacted);
+        view.byte_offset = Some(USize64(compacted.len() as u64));
+        compacted.extend_from_slice(bytes);
+    }
+    *data = compacted;
+}
+
+/// Combines all animations into a single animation.
+///
+/// The combined animation takes the name of the first named animation.
+fn combine_animations(root: &mut json::Root) {
+    if root.animations.len() < 2 {
+        return;
+    }
+    let mut combined = json::Animation {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: None,
+        channels: Vec::new(),
+        samplers: Vec::new(),
+    };
+    for anim in root.animations.drain(..) {
+        let offset = combined.samplers.len() as u32;
+        combined
+            .channels
+            .extend(anim.channels.into_iter().map(|mut channel| {
+                shift(&mut channel.sampler, offset);
+                channel
+            }));
+        combined.samplers.extend(anim.samplers);
+        combined.name = combined.name.or(anim.name);
+    }
+    root.animations.push(combined);
+}
+
+/// Time range spanned by the keyframes of the given animations.
+fn time_range(root: &json::Root, animations: &[json::Animation]) -> Option<(f32, f32)> {
+    let bound = |value: &Option<json::Value>| {
+        value
+            .as_ref()
+            .and_then(|v| v.get(0))
+            .and_then(|v| v.as_f64())
+            .map(|v| v as f32)
+    };
+    let mut range: Option<(f32, f32)> = None;
+    for sampler in animations.iter().flat_map(|anim| anim.samplers.iter()) {
+        let input = &root.accessors[sampler.input.value()];
+        if let (Some(min), Some(max)) = (bound(&input.min), bound(&input.max)) {
+            range = Some(range.map_or((min, max), |(a, b)| (a.min(min), b.max(max))));
+        }
+    }
+    range
+}
+
+/// Appends keyframe times to the buffer returning the index of the new accessor.
+fn push_times(
+    root: &mut json::Root,
+    data: &mut Vec<u8>,
+    times: &[f32],
+) -> json::Index<json::Accessor> {
+    align_data(data);
+    let view = json::buffer::View::new(mem::size_of_val(times), data.len());
+    for &t in times.iter() {
+        data.write_f32::<LE>(t).unwrap();
+    }
+    let min = times.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = times.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let acc = json::Accessor::new(times.len(), GltfComponentType::F32)
+        .with_buffer_view(root.buffer_views.len())
+        .with_min_max(&[min][..], &[max][..]);
+    root.buffer_views.push(view);
+    root.accessors.push(acc);
+    json::Index::new(root.accessors.len() as u32 - 1)
+}
+
+/// Shifts animations of each document to follow the previous one, and
+/// animates the scale of each document's root nodes to hide them outside of
+/// their part of the animation.
+fn concatenate_animations(
+    root: &mut json::Root,
+    data: &mut Vec<u8>,
+    parts: &[(std::ops::Range<usize>, Vec<json::Index<json::Node>>)],
+    warnings: &mut Vec<(usize, String)>,
+) {
+    // Determine the time range of each document after shifting.
+    let mut ranges = Vec::with_capacity(parts.len());
+    let mut prev_end: Option<f32> = None;
+    for (animations, _) in parts.iter() {
+        let range = time_range(root, &root.animations[animations.clone()]);
+        let offset = match (range, prev_end) {
+            (Some((start, _)), Some(prev_end)) if start < prev_end => prev_end - start,
+            _ => 0.0,
+        };
+        let range = range.map(|(start, end)| (start + offset, end + offset));
+        if let Some((_, end)) = range {
+            prev_end = Some(end);
+        }
+        ranges.push((offset, range));
+    }
+
+    // Shift keyframe times, sharing shifted inputs between samplers.
+    for ((animations, _), &(offset, _)) in parts.iter().zip(ranges.iter()) {
+        if offset == 0.0 {
+            continue;
+        }
+        let mut shifted = HashMap::new();
+        for a in animations.clone() {
+            for s in 0..root.animations[a].samplers.len() {
+                let input = root.animations[a].samplers[s].input;
+                let new_input = match shifted.get(&input.value()) {
+                    Some(&new_input) => new_input,
+                    None => {
+                        let buffers = [data.as_slice()];
+                        let Some(times) =
+                            crate::validate::read_accessor(root, &buffers, input.value())
+                        else {
+                            log!(warnings;
+                                "Failed to read keyframe times from accessors[{}]. Skipping...",
+                                input.value()
+                            );
+                            continue;
+                        };
+                        let times: Vec<f32> =
+                            times.into_iter().map(|t| t as f32 + offset).collect();
+                        let new_input = push_times(root, data, &times);
+                        shifted.insert(input.value(), new_input);
+                        new_input
+                    }
+                };
+                root.animations[a].samplers[s].input = new_input;
+            }
+        }
+    }
+
+    if parts.len() < 2 {
+        return;
+    }
+
+    // Toggle visibility of document roots using stepped scale animation.
+    let mut channels = Vec::new();
+    let mut samplers = Vec::new();
+    let last = parts.len() - 1;
+    for (i, ((_, roots), &(_, range))) in parts.iter().zip(ranges.iter()).enumerate() {
+        let Some((start, end)) = range else {
+            continue;
+        };
+        for &node in roots.iter() {
+            let animates_scale = root
+                .animations
+                .iter()
+                .flat_map(|a| a.channels.iter())
+                .any(|c| {
+                    c.target.node == node
+                        && c.target.path == Valid(json::animation::Property::Scale)
+                });
+            if animates_scale {
+                log!(warnings;
+                    "Node {} already has a scale animation. Its visibility will not be animated...",
+                    node.value()
+                );
+                continue;
+            }
+            let scale = root.nodes[node.value()].scale.unwrap_or([1.0; 3]);
+            let mut keys = Vec::new();
+            if i > 0 && start > 0.0 {
+                keys.push((0.0, [0.0; 3]));
+            }
+            keys.push((start, scale));
+            if i < last && end > start {
+                keys.push((end, [0.0; 3]));
+            }
+            let times: Vec<f32> = keys.iter().map(|&(t, _)| t).collect();
+            let values: Vec<[f32; 3]> = keys.iter().map(|&(_, v)| v).collect();
+            let input = push_times(root, data, &times);
+            let output = build_nonempty_buffer_vec3(
+                &values,
+                &mut root.accessors,
+                &mut root.buffer_views,
+                data,
+                "",
+            );
+            channels.push(json::animation::Channel {
+                sampler: json::Index::new(samplers.len() as u32),
+                target: json::animation::Target {
+                    path: Valid(json::animation::Property::Scale),
+                    node,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                },
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+            samplers.push(json::animation::Sampler {
+                input,
+                interpolation: Valid(json::animation::Interpolation::Step),
+                output: json::Index::new(output),
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+        }
+    }
+    if !channels.is_empty() {
+        root.animations.push(json::Animation {
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            channels,
+            samplers,
+        });
+    }
+}
@@ -1,13 +1,48 @@
+use std::collections::HashMap;
+use std::path::Path;
+
 use gltf::json;
+use meshx::attrib::{Attrib, AttribIndex, AttributeValue};
+use meshx::io::obj::ObjData;
+use meshx::io::vtk::model;
+use meshx::io::{MeshExtractor, Vtk};
 use meshx::mesh::vertex_positions::VertexPositions;
 use meshx::mesh::{PointCloud, PolyMesh, TetMesh, TriMesh, TriMeshExt};
-use meshx::topology::NumVertices;
+use meshx::topology::{NumVertices, VertexIndex};
 
 use crate::config::INDEX_ATTRIB_NAME;
-use crate::{AttribTransfer, MaterialIds};
+use crate::{AttribTransfer, Error, MaterialIds};
+
+/// Vertices of a triangle mesh split along creases, found with [`Mesh::crease_split`].
+///
+/// The same split is applied to every frame of an animation so that they keep
+/// the same topology.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreaseSplit {
+    topo_hash: u64,
+    /// Original vertex of each vertex of the split mesh.
+    vertices: Vec<usize>,
+    /// Triangles of the split mesh.
+    indices: Vec<[usize; 3]>,
+}
+
+impl CreaseSplit {
+    /// Hash of the topology (see [`Mesh::topo_hash`]) of the mesh before splitting.
+    pub fn topo_hash(&self) -> u64 {
+        self.topo_hash
+    }
+    /// Original vertex of each vertex of the split mesh.
+    pub fn vertices(&self) -> &[usize] {
+        &self.vertices
+    }
+    /// Number of triangles, which is unchanged by the split.
+    pub fn num_faces(&self) -> usize {
+        self.indices.len()
+    }
+}
 
 /// Supported output mesh types.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Mesh {
     TriMesh(Box<TriMesh<f32>>),
     PointCloud(PointCloud<f32>),
@@ -50,14 +85,311 @@ impl Mesh {
         }
     }
 
+    /// Describes how the topology of `other` differs from that of `self`.
+    ///
+    /// Returns `None` if the two meshes have equivalent topology (see [`Mesh::eq_topo`]).
+    pub fn topo_difference(&self, other: &Mesh) -> Option<String> {
+        let count_change = |what: &str, before: usize, after: usize| {
+            (before != after).then(|| {
+                format!(
+                    "{} count changed by {:+} ({} to {})",
+                    what,
+                    after as i64 - before as i64,
+                    before,
+                    after
+                )
+            })
+        };
+        let vertex_change = count_change(
+            "vertex",
+            self.vertex_positions().len(),
+            other.vertex_positions().len(),
+        );
+        match (self, other) {
+            (Mesh::TriMesh(self_mesh), Mesh::TriMesh(other_mesh)) => {
+                let self_tris = self_mesh.indices.as_slice();
+                let other_tris = other_mesh.indices.as_slice();
+                let tri_change = count_change("triangle", self_tris.len(), other_tris.len());
+                let first_diff = self_tris
+                    .iter()
+                    .zip(other_tris)
+                    .position(|(a, b)| a != b)
+                    .map(|i| {
+                        format!(
+                            "triangle {} changed from {:?} to {:?}",
+                            i, self_tris[i], other_tris[i]
+                        )
+                    });
+                let changes: Vec<_> = [vertex_change, tri_change, first_diff]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (!changes.is_empty()).then(|| changes.join(", "))
+            }
+            (Mesh::PointCloud(_), Mesh::PointCloud(_)) => vertex_change,
+            (Mesh::TriMesh(_), Mesh::PointCloud(_)) => {
+                Some("triangle mesh changed into a point cloud".to_string())
+            }
+            (Mesh::PointCloud(_), Mesh::TriMesh(_)) => {
+                Some("point cloud changed into a triangle mesh".to_string())
+            }
+        }
+    }
+
+    /// Returns a hash of the topology of this mesh.
+    ///
+    /// Meshes with equivalent topology (see [`Mesh::eq_topo`]) have equal
+    /// hashes, so differing hashes rule out equivalent topology without
+    /// comparing all indices.
+    pub fn topo_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        match self {
+            Mesh::TriMesh(mesh) => {
+                0u8.hash(&mut hasher);
+                mesh.num_vertices().hash(&mut hasher);
+                mesh.indices.as_slice().hash(&mut hasher);
+            }
+            Mesh::PointCloud(ptcloud) => {
+                1u8.hash(&mut hasher);
+                ptcloud.num_vertices().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Computes smooth vertex normals from the current vertex positions.
+    ///
+    /// Each vertex normal is the area weighted average of the normals of its
+    /// adjacent triangles. Vertices not adjacent to any triangle with a
+    /// non-zero area get the +Z axis. Returns `None` for point clouds.
+    pub fn vertex_normals(&self) -> Option<Vec<[f32; 3]>> {
+        let Mesh::TriMesh(trimesh) = self else {
+            return None;
+        };
+        let positions = trimesh.vertex_positions();
+        let mut normals = vec![[0.0f32; 3]; positions.len()];
+        for &[a, b, c] in trimesh.indices.as_slice() {
+            let (pa, pb, pc) = (positions[a], positions[b], positions[c]);
+            let u = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+            let v = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+            // The length of the cross product is twice the triangle area.
+            let n = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            for vtx in [a, b, c] {
+                for (sum, x) in normals[vtx].iter_mut().zip(n) {
+                    *sum += x;
+                }
+            }
+        }
+        for n in normals.iter_mut() {
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            *n = if len > 0.0 && len.is_finite() {
+                [n[0] / len, n[1] / len, n[2] / len]
+            } else {
+                [0.0, 0.0, 1.0]
+            };
+        }
+        Some(normals)
+    }
+
+    /// Computes vertex tangents from the current vertex positions and the given texture
+    /// coordinates.
+    ///
+    /// Tangents point along increasing U and are made orthogonal to the given
+    /// vertex normals. Vertices without well defined texture coordinate
+    /// gradients get an arbitrary unit vector orthogonal to their normal.
+    /// Returns `None` for point clouds or if the number of normals or texture
+    /// coordinates doesn't match the number of vertices.
+    pub fn vertex_tangents(&self, normals: &[[f32; 3]], uvs: &[[f32; 2]]) -> Option<Vec<[f32; 3]>> {
+        let Mesh::TriMesh(trimesh) = self else {
+            return None;
+        };
+        let positions = trimesh.vertex_positions();
+        if normals.len() != positions.len() || uvs.len() != positions.len() {
+            return None;
+        }
+        let mut tangents = vec![[0.0f32; 3]; positions.len()];
+        for &[a, b, c] in trimesh.indices.as_slice() {
+            let (pa, pb, pc) = (positions[a], positions[b], positions[c]);
+            let e1 = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+            let e2 = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+            let (du1, dv1) = (uvs[b][0] - uvs[a][0], uvs[b][1] - uvs[a][1]);
+            let (du2, dv2) = (uvs[c][0] - uvs[a][0], uvs[c][1] - uvs[a][1]);
+            let det = du1 * dv2 - du2 * dv1;
+            if det == 0.0 || !det.is_finite() {
+                continue;
+            }
+            let t = [
+                (e1[0] * dv2 - e2[0] * dv1) / det,
+                (e1[1] * dv2 - e2[1] * dv1) / det,
+                (e1[2] * dv2 - e2[2] * dv1) / det,
+            ];
+            for vtx in [a, b, c] {
+                for (sum, x) in tangents[vtx].iter_mut().zip(t) {
+                    *sum += x;
+                }
+            }
+        }
+        for (t, n) in tangents.iter_mut().zip(normals.iter()) {
+            // Gram-Schmidt orthogonalization against the normal.
+            let ortho = |t: [f32; 3]| {
+                let d = n[0] * t[0] + n[1] * t[1] + n[2] * t[2];
+                let o = [t[0] - n[0] * d, t[1] - n[1] * d, t[2] - n[2] * d];
+                let len = (o[0] * o[0] + o[1] * o[1] + o[2] * o[2]).sqrt();
+                (len > 1e-6 && len.is_finite()).then(|| [o[0] / len, o[1] / len, o[2] / len])
+            };
+            *t = ortho(*t)
+                .or_else(|| ortho([1.0, 0.0, 0.0]))
+                .or_else(|| ortho([0.0, 1.0, 0.0]))
+                .unwrap_or([1.0, 0.0, 0.0]);
+        }
+        Some(tangents)
+    }
+
+    /// Extract the given faces and vertices into a new mesh.
+    ///
+    /// Face indices are re-based such that the `i`th vertex in `vertices` becomes vertex `i` in
+    /// the new mesh. Faces are ignored for point clouds.
+    pub fn subset(&self, faces: &[usize], vertices: &[usize]) -> Mesh {
+        let positions = self.vertex_positions();
+        let new_positions: Vec<_> = vertices.iter().map(|&v| positions[v]).collect();
+        match self {
+            Mesh::TriMesh(trimesh) => {
+                let mut new_index = vec![usize::MAX; positions.len()];
+                for (i, &v) in vertices.iter().enumerate() {
+                    new_index[v] = i;
+                }
+                let new_indices = faces
+                    .iter()
+                    .map(|&f| {
+                        let [a, b, c] = trimesh.indices[f];
+                        [new_index[a], new_index[b], new_index[c]]
+                    })
+                    .collect();
+                Mesh::from(TriMesh::new(new_positions, new_indices))
+            }
+            Mesh::PointCloud(_) => Mesh::from(PointCloud::new(new_positions)),
+        }
+    }
+
+    /// Finds how to split vertices so that edges with a dihedral angle above
+    /// `crease_angle` degrees are shaded as hard edges.
+    ///
+    /// Triangles meeting at a vertex across smooth edges keep sharing it,
+    /// while those separated by creases, boundaries or non-manifold edges get
+    /// their own copy of the vertex. Returns `None` for point clouds.
+    pub fn crease_split(&self, crease_angle: f32) -> Option<CreaseSplit> {
+        let Mesh::TriMesh(trimesh) = self else {
+            return None;
+        };
+        let positions = trimesh.vertex_positions();
+        let tris = trimesh.indices.as_slice();
+        let face_normals: Vec<_> = tris
+            .iter()
+            .map(|&[a, b, c]| {
+                let (pa, pb, pc) = (positions[a], positions[b], positions[c]);
+                let u = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+                let v = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+                let n = [
+                    u[1] * v[2] - u[2] * v[1],
+                    u[2] * v[0] - u[0] * v[2],
+                    u[0] * v[1] - u[1] * v[0],
+                ];
+                let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                (len > 0.0 && len.is_finite()).then(|| [n[0] / len, n[1] / len, n[2] / len])
+            })
+            .collect();
+
+        let mut edge_faces: HashMap<[usize; 2], Vec<usize>> = HashMap::new();
+        for (f, tri) in tris.iter().enumerate() {
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                edge_faces.entry([a.min(b), a.max(b)]).or_default().push(f);
+            }
+        }
+
+        // Group the corners (3 * face + k) of triangles meeting across smooth edges.
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        let mut parent: Vec<usize> = (0..3 * tris.len()).collect();
+        let cos_crease = crease_angle.to_radians().cos();
+        for (edge, faces) in edge_faces.iter() {
+            let &[f, g] = faces.as_slice() else {
+                continue;
+            };
+            // Degenerate triangles take the shading of their neighbours.
+            if let (Some(n), Some(m)) = (face_normals[f], face_normals[g]) {
+                if n[0] * m[0] + n[1] * m[1] + n[2] * m[2] < cos_crease {
+                    continue;
+                }
+            }
+            for &v in edge.iter() {
+                let corner = |f: usize| 3 * f + tris[f].iter().position(|&u| u == v).unwrap();
+                let (a, b) = (find(&mut parent, corner(f)), find(&mut parent, corner(g)));
+                parent[a] = b;
+            }
+        }
+
+        // The first group of corners at each vertex keeps the vertex, others get appended copies.
+        let mut vertices: Vec<usize> = (0..positions.len()).collect();
+        let mut taken = vec![false; positions.len()];
+        let mut group_vertex = HashMap::new();
+        let mut indices = Vec::with_capacity(tris.len());
+        for (f, tri) in tris.iter().enumerate() {
+            let mut new_tri = *tri;
+            for (k, new_vtx) in new_tri.iter_mut().enumerate() {
+                let group = find(&mut parent, 3 * f + k);
+                *new_vtx = *group_vertex.entry(group).or_insert_with(|| {
+                    let vtx = tri[k];
+                    if !taken[vtx] {
+                        taken[vtx] = true;
+                        vtx
+                    } else {
+                        vertices.push(vtx);
+                        vertices.len() - 1
+                    }
+                });
+            }
+            indices.push(new_tri);
+        }
+
+        Some(CreaseSplit {
+            topo_hash: self.topo_hash(),
+            vertices,
+            indices,
+        })
+    }
+
+    /// Splits vertices along creases found with [`Mesh::crease_split`].
+    ///
+    /// The split must have been found on a mesh with the same topology.
+    pub fn split_creases(&self, split: &CreaseSplit) -> Mesh {
+        let positions = self.vertex_positions();
+        let new_positions = split.vertices.iter().map(|&v| positions[v]).collect();
+        match self {
+            Mesh::TriMesh(_) => Mesh::from(TriMesh::new(new_positions, split.indices.clone())),
+            Mesh::PointCloud(_) => Mesh::from(PointCloud::new(new_positions)),
+        }
+    }
+
     pub fn build_topology(
         &self,
         attrib_transfer: &AttribTransfer,
         data: &mut Vec<u8>,
         buffer_views: &mut Vec<json::buffer::View>,
         accessors: &mut Vec<json::Accessor>,
-    ) -> (&[[f32; 3]], Option<Vec<json::Index<json::Accessor>>>) {
-        match self {
+    ) -> Result<(&[[f32; 3]], Option<Vec<json::Index<json::Accessor>>>), Error> {
+        Ok(match self {
             Mesh::TriMesh(ref trimesh) => (
                 trimesh.vertex_positions.as_slice(),
                 Some(build_indices(
@@ -66,12 +398,12 @@ impl Mesh {
                     data,
                     buffer_views,
                     accessors,
-                )),
+                )?),
             ),
             Mesh::PointCloud(PointCloud {
                 vertex_positions, ..
             }) => (vertex_positions.as_slice(), None),
-        }
+        })
     }
 }
 
@@ -91,6 +423,139 @@ impl VertexPositions for Mesh {
         }
     }
 }
+/// Values of a vertex attribute given as plain arrays with one element per vertex.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawAttribute {
+    F32(Vec<f32>),
+    Vec2F32(Vec<[f32; 2]>),
+    Vec3F32(Vec<[f32; 3]>),
+    Vec4F32(Vec<[f32; 4]>),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+macro_rules! impl_raw_attribute_from {
+    ($($variant:ident($t:ty)),* $(,)?) => {
+        $(
+            impl From<Vec<$t>> for RawAttribute {
+                fn from(values: Vec<$t>) -> Self {
+                    RawAttribute::$variant(values)
+                }
+            }
+        )*
+
+        impl RawAttribute {
+            /// Number of vertices this attribute has values for.
+            pub fn len(&self) -> usize {
+                match self {
+                    $(RawAttribute::$variant(values) => values.len(),)*
+                }
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            fn insert_into<M>(self, mesh: &mut M, name: &str) -> Result<(), Error>
+            where
+                M: Attrib + NumVertices,
+                VertexIndex: AttribIndex<M>,
+            {
+                if self.len() != mesh.num_vertices() {
+                    return Err(Error::InvalidMeshData(format!(
+                        "attribute '{}' has {} values but the mesh has {} vertices",
+                        name,
+                        self.len(),
+                        mesh.num_vertices()
+                    )));
+                }
+                match self {
+                    $(RawAttribute::$variant(values) => insert_vertex_attrib(mesh, name, values),)*
+                }
+            }
+        }
+    };
+}
+
+impl_raw_attribute_from!(
+    F32(f32),
+    Vec2F32([f32; 2]),
+    Vec3F32([f32; 3]),
+    Vec4F32([f32; 4]),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+);
+
+pub(crate) fn insert_vertex_attrib<M, T>(
+    mesh: &mut M,
+    name: &str,
+    values: Vec<T>,
+) -> Result<(), Error>
+where
+    M: Attrib,
+    VertexIndex: AttribIndex<M>,
+    T: AttributeValue + Default,
+{
+    mesh.insert_attrib_data::<T, VertexIndex>(name, values)
+        .map(|_| ())
+        .map_err(|e| Error::InvalidMeshData(format!("attribute '{}': {}", name, e)))
+}
+
+impl Mesh {
+    /// Builds a triangle mesh from plain arrays of vertex positions, triangle
+    /// vertex indices and named vertex attributes.
+    ///
+    /// This is an alternative to constructing `meshx` meshes for applications
+    /// that already store their geometry in flat arrays.
+    pub fn from_raw(
+        positions: Vec<[f32; 3]>,
+        indices: Vec<[u32; 3]>,
+        attributes: Vec<(String, RawAttribute)>,
+    ) -> Result<Mesh, Error> {
+        let num_vertices = positions.len();
+        let indices = indices
+            .into_iter()
+            .map(|tri| {
+                let tri = tri.map(|i| i as usize);
+                match tri.iter().find(|&&i| i >= num_vertices) {
+                    Some(i) => Err(Error::InvalidMeshData(format!(
+                        "vertex index {} is out of bounds for {} vertices",
+                        i, num_vertices
+                    ))),
+                    None => Ok(tri),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut mesh = Mesh::from(TriMesh::new(positions, indices));
+        for (name, values) in attributes {
+            mesh.insert_raw_attribute(&name, values)?;
+        }
+        Ok(mesh)
+    }
+
+    /// Builds a point cloud from plain arrays of vertex positions and named vertex attributes.
+    pub fn from_raw_points(
+        positions: Vec<[f32; 3]>,
+        attributes: Vec<(String, RawAttribute)>,
+    ) -> Result<Mesh, Error> {
+        let mut mesh = Mesh::from(PointCloud::new(positions));
+        for (name, values) in attributes {
+            mesh.insert_raw_attribute(&name, values)?;
+        }
+        Ok(mesh)
+    }
+
+    /// Adds a vertex attribute with one value per vertex.
+    pub fn insert_raw_attribute(&mut self, name: &str, values: RawAttribute) -> Result<(), Error> {
+        match self {
+            Mesh::TriMesh(mesh) => values.insert_into(&mut **mesh, name),
+            Mesh::PointCloud(mesh) => values.insert_into(mesh, name),
+        }
+    }
+}
+
 impl From<TriMesh<f32>> for Mesh {
     fn from(mesh: TriMesh<f32>) -> Self {
         Mesh::TriMesh(Box::new(mesh))
@@ -133,6 +598,217 @@ impl From<PointCloud<f64>> for Mesh {
     }
 }
 
+/// File formats meshes can be loaded from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MeshFormat {
+    /// Legacy VTK format ('.vtk').
+    LegacyVtk,
+    /// XML VTK formats ('.vtu', '.vtp' and their parallel variants '.pvtu' and '.pvtp').
+    XmlVtk,
+    /// Wavefront OBJ format ('.obj').
+    Obj,
+}
+
+impl MeshFormat {
+    /// Determines the format from a file extension ignoring case.
+    pub fn from_extension(extension: &str) -> Option<MeshFormat> {
+        match extension.to_lowercase().as_str() {
+            "vtk" => Some(MeshFormat::LegacyVtk),
+            "vtu" | "vtp" | "pvtu" | "pvtp" => Some(MeshFormat::XmlVtk),
+            "obj" => Some(MeshFormat::Obj),
+            _ => None,
+        }
+    }
+
+    /// Determines the format from the first few bytes of a file.
+    pub fn from_header(header: &[u8]) -> Option<MeshFormat> {
+        let header = String::from_utf8_lossy(header);
+        let header = header.trim_start();
+        if header.starts_with("# vtk DataFile") {
+            return Some(MeshFormat::LegacyVtk);
+        }
+        if header.starts_with("<?xml") || header.starts_with("<VTKFile") {
+            return Some(MeshFormat::XmlVtk);
+        }
+        // OBJ files have no magic number, so check that the first statement is a known one.
+        let first_statement = header
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+        let keyword = first_statement.split_whitespace().next()?;
+        matches!(
+            keyword,
+            "v" | "vt" | "vn" | "f" | "l" | "p" | "o" | "g" | "s" | "mtllib" | "usemtl"
+        )
+        .then_some(MeshFormat::Obj)
+    }
+
+    /// Determines the format of the file at `path` from its extension, or
+    /// from its contents if the extension is not recognized.
+    pub fn probe(path: &Path) -> Option<MeshFormat> {
+        use std::io::Read;
+
+        if let Some(format) = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(MeshFormat::from_extension)
+        {
+            return Some(format);
+        }
+        let mut header = Vec::with_capacity(256);
+        std::fs::File::open(path)
+            .ok()?
+            .take(256)
+            .read_to_end(&mut header)
+            .ok()?;
+        MeshFormat::from_header(&header)
+    }
+}
+
+/// Source of meshes loaded from a single file.
+///
+/// The file is parsed once, and meshes of different types are extracted from
+/// the parsed model.
+pub(crate) enum MeshFile {
+    Vtk(Box<Vtk>),
+    Obj(Box<ObjData>),
+}
+
+impl MeshFile {
+    /// Parses the file at `path`, probing its format if none is given.
+    pub(crate) fn open(
+        path: &Path,
+        format: Option<MeshFormat>,
+    ) -> Result<MeshFile, meshx::io::Error> {
+        let format = format
+            .or_else(|| MeshFormat::probe(path))
+            .ok_or(meshx::io::Error::UnsupportedFileFormat)?;
+
+        #[cfg(all(feature = "mmap", unix))]
+        let mut file = {
+            // The file is parsed directly from the mapping, which is released
+            // as soon as the parsed data is built.
+            let map = crate::mmap::Mmap::open(path)?;
+            MeshFile::parse(&map, format)?
+        };
+
+        #[cfg(not(all(feature = "mmap", unix)))]
+        let mut file = match format {
+            MeshFormat::LegacyVtk => MeshFile::Vtk(Box::new(Vtk::import_legacy_be(path)?)),
+            MeshFormat::XmlVtk => {
+                let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+                MeshFile::Vtk(Box::new(Vtk::parse_xml(reader)?))
+            }
+            MeshFormat::Obj => {
+                let config = meshx::io::obj::LoadConfig { strict: false };
+                let obj = meshx::io::obj::Obj::load_with_config(path, config)?;
+                MeshFile::Obj(Box::new(obj.data))
+            }
+        };
+
+        if let MeshFile::Vtk(vtk) = &mut file {
+            // Needed to find pieces of parallel formats.
+            vtk.file_path = Some(path.to_path_buf());
+        }
+        Ok(file)
+    }
+
+    /// Parses a mesh from the contents of a file with the given extension.
+    ///
+    /// The format is probed from the contents if the extension is not
+    /// recognized. Parallel VTK formats are not supported since their pieces
+    /// are stored in separate files.
+    pub(crate) fn from_bytes(bytes: &[u8], extension: &str) -> Result<MeshFile, meshx::io::Error> {
+        let format = MeshFormat::from_extension(extension)
+            .or_else(|| MeshFormat::from_header(&bytes[..bytes.len().min(256)]))
+            .ok_or(meshx::io::Error::UnsupportedFileFormat)?;
+        MeshFile::parse(bytes, format)
+    }
+
+    fn parse(bytes: &[u8], format: MeshFormat) -> Result<MeshFile, meshx::io::Error> {
+        match format {
+            MeshFormat::LegacyVtk => Ok(MeshFile::Vtk(Box::new(Vtk::parse_legacy_be(bytes)?))),
+            MeshFormat::XmlVtk => Ok(MeshFile::Vtk(Box::new(Vtk::parse_xml(bytes)?))),
+            MeshFormat::Obj => {
+                let config = meshx::io::obj::LoadConfig { strict: false };
+                let obj = ObjData::load_buf_with_config(bytes, config)?;
+                Ok(MeshFile::Obj(Box::new(obj)))
+            }
+        }
+    }
+
+    /// Drops VTK point, cell and field data arrays for which `keep` returns `false`.
+    ///
+    /// This avoids converting attributes that would be discarded anyway when
+    /// meshes are extracted. Pieces of parallel formats stored in other files
+    /// are not filtered.
+    pub(crate) fn retain_attributes(&mut self, keep: impl Fn(&str) -> bool) {
+        if let MeshFile::Vtk(vtk) = self {
+            retain_dataset_attributes(&mut vtk.data, &keep);
+        }
+    }
+
+    pub(crate) fn load_polymesh<T: meshx::io::Real>(
+        &self,
+    ) -> Result<PolyMesh<T>, meshx::io::Error> {
+        match self {
+            MeshFile::Vtk(vtk) => vtk.extract_polymesh(),
+            MeshFile::Obj(obj) => obj.extract_polymesh(),
+        }
+    }
+
+    pub(crate) fn load_tetmesh<T: meshx::io::Real>(&self) -> Result<TetMesh<T>, meshx::io::Error> {
+        match self {
+            MeshFile::Vtk(vtk) => vtk.extract_tetmesh(),
+            MeshFile::Obj(obj) => obj.extract_tetmesh(),
+        }
+    }
+
+    pub(crate) fn load_pointcloud<T: meshx::io::Real>(
+        &self,
+    ) -> Result<PointCloud<T>, meshx::io::Error> {
+        match self {
+            MeshFile::Vtk(vtk) => vtk.extract_pointcloud(),
+            MeshFile::Obj(obj) => obj.extract_pointcloud(),
+        }
+    }
+}
+
+fn retain_dataset_attributes(data: &mut model::DataSet, keep: &impl Fn(&str) -> bool) {
+    let retain = |attribs: &mut model::Attributes| {
+        for attribs in [&mut attribs.point, &mut attribs.cell] {
+            attribs.retain_mut(|attrib| match attrib {
+                model::Attribute::DataArray(array) => keep(&array.name),
+                model::Attribute::Field { data_array, .. } => {
+                    data_array.retain(|array| keep(&array.name));
+                    !data_array.is_empty()
+                }
+            });
+        }
+    };
+    match data {
+        model::DataSet::UnstructuredGrid { pieces, .. } => {
+            for piece in pieces.iter_mut() {
+                match piece {
+                    model::Piece::Inline(piece) => retain(&mut piece.data),
+                    model::Piece::Loaded(data) => retain_dataset_attributes(data, keep),
+                    model::Piece::Source(..) => {}
+                }
+            }
+        }
+        model::DataSet::PolyData { pieces, .. } => {
+            for piece in pieces.iter_mut() {
+                match piece {
+                    model::Piece::Inline(piece) => retain(&mut piece.data),
+                    model::Piece::Loaded(data) => retain_dataset_attributes(data, keep),
+                    model::Piece::Source(..) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 // Utility function that removes all orphaned vertices (ones not referenced by a triangle).
 // This algorithm tries to keep the vertices in the same order.
 pub fn remove_orphaned_vertices(mesh: TriMesh<f32>) -> TriMesh<f32> {
@@ -154,6 +830,50 @@ pub fn remove_orphaned_vertices(mesh: TriMesh<f32>) -> TriMesh<f32> {
     meshes.into_iter().next().unwrap()
 }
 
+/// Partition triangles into consecutive chunks such that each chunk references at most
+/// `max_vertices` unique vertices.
+///
+/// For each chunk, this returns the original indices of the faces in the chunk along with the
+/// original indices of the referenced vertices in order of first appearance. The position of a
+/// vertex in this list is its re-based index within the chunk.
+pub fn partition_faces_by_vertex_count(
+    indices: &[[usize; 3]],
+    num_vertices: usize,
+    max_vertices: usize,
+) -> Vec<(Vec<usize>, Vec<usize>)> {
+    // A chunk must be able to hold at least one triangle.
+    let max_vertices = max_vertices.max(3);
+
+    let mut chunks = Vec::new();
+    let mut faces = Vec::new();
+    let mut vertices = Vec::new();
+    // Index of the last chunk that referenced each vertex.
+    let mut last_chunk = vec![usize::MAX; num_vertices];
+
+    for (fidx, tri) in indices.iter().enumerate() {
+        let num_new = tri
+            .iter()
+            .enumerate()
+            .filter(|&(i, &v)| last_chunk[v] != chunks.len() && !tri[..i].contains(&v))
+            .count();
+        if vertices.len() + num_new > max_vertices {
+            chunks.push((std::mem::take(&mut faces), std::mem::take(&mut vertices)));
+        }
+        for &v in tri.iter() {
+            if last_chunk[v] != chunks.len() {
+                last_chunk[v] = chunks.len();
+                vertices.push(v);
+            }
+        }
+        faces.push(fidx);
+    }
+
+    if !faces.is_empty() {
+        chunks.push((faces, vertices));
+    }
+    chunks
+}
+
 pub fn trimesh_f64_to_f32(mesh: TriMesh<f64>) -> TriMesh<f32> {
     let TriMesh {
         vertex_positions,
@@ -204,7 +924,7 @@ fn push_indices(
     accessors: &mut Vec<json::Accessor>,
     indices: &mut Vec<json::Index<json::Accessor>>,
     name: String,
-) {
+) -> Result<(), Error> {
     use crate::export::{AccessorBuilder, BufferViewBuilder};
     use byteorder::{WriteBytesExt, LE};
     use num_traits::ToPrimitive;
@@ -212,6 +932,7 @@ fn push_indices(
     // Push indices to data buffer.
     let num_indices = face_indices.len() * 3;
     let byte_length = num_indices * std::mem::size_of::<u32>();
+    crate::export::align_data(data);
     let indices_view = json::buffer::View::new(byte_length, data.len())
         .with_target(json::buffer::Target::ElementArrayBuffer);
 
@@ -219,9 +940,7 @@ fn push_indices(
     let mut min_index = u32::MAX;
     for idx in face_indices {
         for &i in trimesh_indices[idx].iter() {
-            let vidx = i
-                .to_u32()
-                .expect("Vertex index does not fit into a 32 bit unsigned integer.");
+            let vidx = i.to_u32().ok_or(Error::IndexOverflow("vertices"))?;
             max_index = max_index.max(vidx);
             min_index = min_index.min(vidx);
             data.write_u32::<LE>(vidx).unwrap();
@@ -237,6 +956,7 @@ fn push_indices(
     let idx_acc_index = accessors.len() as u32;
     accessors.push(idx_acc);
     indices.push(json::Index::new(idx_acc_index));
+    Ok(())
 }
 
 fn build_indices(
@@ -245,7 +965,7 @@ fn build_indices(
     data: &mut Vec<u8>,
     buffer_views: &mut Vec<json::buffer::View>,
     accessors: &mut Vec<json::Accessor>,
-) -> Vec<json::Index<json::Accessor>> {
+) -> Result<Vec<json::Index<json::Accessor>>, Error> {
     // Sort indices by associated materials (if any).
     let mut indices = Vec::new();
 
@@ -264,7 +984,7 @@ fn build_indices(
                     accessors,
                     &mut indices,
                     format!("{}{}", INDEX_ATTRIB_NAME, i),
-                );
+                )?;
             }
         }
         None => {
@@ -277,8 +997,170 @@ fn build_indices(
                 accessors,
                 &mut indices,
                 INDEX_ATTRIB_NAME.to_string(),
-            );
+            )?;
         }
     }
-    indices
+    Ok(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportConfig, SequenceBuilder};
+    use crate::{load_mesh, AttribConfig, AttributeInfo, LoadConfig, TextureAttributeInfo};
+
+    #[test]
+    fn mesh_from_raw() {
+        let positions = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        let pressure = vec![1.0f32, 2.0, 3.0];
+        let mesh = Mesh::from_raw(
+            positions.clone(),
+            vec![[0, 1, 2]],
+            vec![("pressure".to_string(), RawAttribute::from(pressure.clone()))],
+        )
+        .unwrap();
+        let Mesh::TriMesh(trimesh) = &mesh else {
+            panic!("expected a triangle mesh");
+        };
+        assert_eq!(
+            trimesh
+                .attrib_as_slice::<f32, VertexIndex>("pressure")
+                .unwrap(),
+            &pressure[..]
+        );
+
+        // Indices and attributes must match the number of vertices.
+        assert!(matches!(
+            Mesh::from_raw(positions.clone(), vec![[0, 1, 3]], Vec::new()),
+            Err(Error::InvalidMeshData(_))
+        ));
+        assert!(matches!(
+            Mesh::from_raw_points(
+                positions,
+                vec![("pressure".to_string(), RawAttribute::F32(vec![1.0]))]
+            ),
+            Err(Error::InvalidMeshData(_))
+        ));
+    }
+
+    #[test]
+    fn topology_hash() {
+        let config = LoadConfig::default();
+        let box1 = load_mesh("assets/box_rotate_1.vtk", config).unwrap();
+        let box2 = load_mesh("assets/box_rotate_2.vtk", config).unwrap();
+        let tet = load_mesh("assets/tet_1.vtk", config).unwrap();
+        assert!(box1.eq_topo(&box2));
+        assert_eq!(box1.topo_hash(), box2.topo_hash());
+        assert!(!box1.eq_topo(&tet));
+        assert_ne!(box1.topo_hash(), tet.topo_hash());
+    }
+
+    #[test]
+    fn topology_change_diagnostics() {
+        let quad = Mesh::from(meshx::TriMesh::new(
+            vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]],
+            vec![[0, 1, 2], [1, 3, 2]],
+        ));
+        let flipped = Mesh::from(meshx::TriMesh::new(
+            vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]],
+            vec![[0, 1, 2], [1, 2, 3]],
+        ));
+        let triangle = Mesh::from(meshx::TriMesh::new(
+            vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            vec![[0, 1, 2]],
+        ));
+        assert_eq!(quad.topo_difference(&quad), None);
+        assert_eq!(
+            quad.topo_difference(&flipped).as_deref(),
+            Some("triangle 1 changed from [1, 3, 2] to [1, 2, 3]")
+        );
+        assert_eq!(
+            quad.topo_difference(&triangle).as_deref(),
+            Some("vertex count changed by -1 (4 to 3), triangle count changed by -1 (2 to 1)")
+        );
+
+        let ids = |map: &[(u32, Vec<usize>)]| MaterialIds::Global {
+            map: map.iter().cloned().collect(),
+        };
+        let before = ids(&[(0, vec![0]), (1, vec![1])]);
+        let after = ids(&[(0, vec![0, 1])]);
+        assert_eq!(MaterialIds::difference(Some(&before), Some(&before)), None);
+        assert_eq!(
+            MaterialIds::difference(Some(&before), Some(&after)).as_deref(),
+            Some("faces with material 0 changed (1 to 2 faces)")
+        );
+        assert_eq!(
+            MaterialIds::difference(None, Some(&after)).as_deref(),
+            Some("material ids were added")
+        );
+    }
+
+    #[test]
+    fn crease_angle() {
+        use gltf::json::mesh::Semantic;
+        use gltf::json::validation::Checked::Valid;
+
+        // A unit cube, which grows in the second frame.
+        let positions: Vec<[f32; 3]> = (0..8)
+            .map(|i| [(i >> 2) as f32, ((i >> 1) & 1) as f32, (i & 1) as f32])
+            .collect();
+        let quads = [
+            [0, 1, 3, 2],
+            [4, 6, 7, 5],
+            [0, 4, 5, 1],
+            [2, 3, 7, 6],
+            [0, 2, 6, 4],
+            [1, 5, 7, 3],
+        ];
+        let tris: Vec<[usize; 3]> = quads
+            .iter()
+            .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .collect();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let build = |crease_angle: f32| {
+            let config = ExportConfig::builder()
+                .crease_angle(Some(crease_angle))
+                .quiet(true)
+                .build();
+            let mut sequence = SequenceBuilder::new(attrib_config, config);
+            for frame in 1..=2 {
+                let scaled = positions
+                    .iter()
+                    .map(|p| p.map(|x| x * frame as f32))
+                    .collect();
+                let trimesh = meshx::TriMesh::new(scaled, tris.clone());
+                sequence
+                    .add_frame("cube", frame, Mesh::from(trimesh))
+                    .unwrap();
+            }
+            sequence.build().unwrap().0.root
+        };
+        let num_vertices = |root: &gltf::json::Root| {
+            let positions = root.meshes[0].primitives[0].attributes[&Valid(Semantic::Positions)];
+            root.accessors[positions.value()].count.0
+        };
+
+        // Each face of the cube gets its own vertices at sharp edges.
+        let root = build(30.0);
+        assert_eq!(root.meshes.len(), 1);
+        assert_eq!(num_vertices(&root), 24);
+        assert_eq!(
+            root.meshes[0].primitives[0].targets.as_ref().unwrap().len(),
+            1
+        );
+
+        // Right angles are smooth with a larger crease angle.
+        let root = build(100.0);
+        assert_eq!(root.meshes.len(), 1);
+        assert_eq!(num_vertices(&root), 8);
+    }
 }
@@ -0,0 +1,84 @@
+//! Read-only memory mapping of input files.
+//!
+//! Mapping a frame avoids copying its contents through intermediate buffers,
+//! which reduces peak memory and lets the OS page in large files on demand.
+
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Contents of a file mapped into memory.
+pub(crate) struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned exclusively by this value.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /// Maps the entire file at `path` for reading.
+    pub(crate) fn open(path: &Path) -> io::Result<Mmap> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large to map"))?;
+        if len == 0 {
+            // Empty mappings are not allowed.
+            return Ok(Mmap {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // Frames are parsed front to back, so let the OS read ahead aggressively.
+        unsafe { libc::madvise(ptr, len, libc::MADV_SEQUENTIAL) };
+        Ok(Mmap { ptr, len })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe { libc::munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_mesh, LoadConfig};
+
+    #[test]
+    fn mmap_matches_file_contents() {
+        let path = Path::new("assets/box_rotate_1.vtk");
+        let map = Mmap::open(path).unwrap();
+        assert_eq!(&*map, std::fs::read(path).unwrap().as_slice());
+        assert!(load_mesh(path, LoadConfig::default()).is_some());
+    }
+}
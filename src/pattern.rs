@@ -0,0 +1,157 @@
+//! Normalization of input patterns and matched paths across platforms.
+//!
+//! Patterns may be written with or without a leading `./`, and on Windows
+//! with `/` or `\` separators, depending on the shell they come from. Both
+//! patterns and the paths they match are brought into the same canonical form
+//! with `/` separators before matching, and regular expressions generated from
+//! patterns accept either separator, so the same command line works on Windows,
+//! WSL and Unix alike. Elsewhere a backslash escapes the character following
+//! it, e.g. `\*` matches a literal `*`.
+
+use std::path::Path;
+
+/// Regular expression matching a single path separator.
+pub const SEPARATOR: &str = r"[/\\]";
+
+/// Regular expression matching any character other than a path separator.
+pub const NON_SEPARATOR: &str = r"[^/\\]";
+
+/// Converts a pattern into its canonical form.
+///
+/// On Windows, backslashes are treated as separators and converted to `/`.
+/// Repeated separators are collapsed, and `.` components (e.g. a leading `./`)
+/// are removed. A leading `//` is kept to preserve UNC paths on Windows.
+pub fn normalize_pattern(pattern: &str) -> String {
+    let pattern = if cfg!(windows) {
+        pattern.replace('\\', "/")
+    } else {
+        pattern.to_string()
+    };
+    let (prefix, rest) = if let Some(rest) = pattern.strip_prefix("//") {
+        ("//", rest)
+    } else if let Some(rest) = pattern.strip_prefix('/') {
+        ("/", rest)
+    } else {
+        ("", pattern.as_str())
+    };
+    let components: Vec<_> = rest
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    if components.is_empty() && prefix.is_empty() && !rest.is_empty() {
+        return ".".to_string();
+    }
+    format!("{}{}", prefix, components.join("/"))
+}
+
+/// Converts a path into the canonical form used to match it against a normalized pattern.
+pub fn normalize_path(path: &Path) -> String {
+    normalize_pattern(&path.to_string_lossy())
+}
+
+/// Replaces the glob metacharacters `*`, `?`, `[` and `]` escaped with a
+/// backslash by character classes understood by the glob crate, e.g. `\*` by
+/// `[*]`. Escaped braces are kept for [`crate::utils::remove_braces`], and the
+/// backslash of any other escaped character is removed.
+///
+/// Patterns are returned unchanged on Windows, where backslashes are separators.
+pub fn bracket_escapes(pattern: &str) -> String {
+    if cfg!(windows) {
+        return pattern.to_string();
+    }
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(c @ ('*' | '?' | '[' | ']')) => {
+                out.push('[');
+                out.push(c);
+                out.push(']');
+            }
+            Some(c @ ('{' | '}')) => {
+                out.push('\\');
+                out.push(c);
+            }
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::glob_to_regex;
+
+    #[test]
+    #[cfg(windows)]
+    fn windows_separators() {
+        assert_eq!(
+            normalize_pattern(".\\meshes\\{box}_#.obj"),
+            "meshes/{box}_#.obj"
+        );
+        assert_eq!(
+            normalize_pattern("\\\\server\\share\\#.vtk"),
+            "//server/share/#.vtk"
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn escaped_glob_characters() {
+        // Backslashes are escapes rather than separators.
+        assert_eq!(
+            normalize_pattern("./meshes/a\\*_#.obj"),
+            "meshes/a\\*_#.obj"
+        );
+        assert_eq!(
+            bracket_escapes("meshes/a\\*_\\[#\\].obj"),
+            "meshes/a[*]_[[]#[]].obj"
+        );
+        assert_eq!(bracket_escapes("\\{a\\}_\\#"), "\\{a\\}_#");
+
+        let regex = glob_to_regex("meshes/{a\\*}_#.obj", false).unwrap();
+        let caps = regex.captures("meshes/a*_1.obj").unwrap();
+        assert_eq!(&caps[1], "a*");
+        assert_eq!(&caps["frame"], "1");
+        assert!(!regex.is_match("meshes/ab_1.obj"));
+
+        let dir = std::path::PathBuf::from("./tests/artifacts/escaped_glob");
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["a*_1.obj", "ab_1.obj"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let frames = crate::discover::discover_frames(
+            "./tests/artifacts/escaped_glob/{a\\*}_#.obj",
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].name, "a*");
+    }
+
+    #[test]
+    fn pattern_normalization() {
+        assert_eq!(normalize_pattern("./a//b/./c.obj"), "a/b/c.obj");
+        assert_eq!(normalize_pattern("/tmp/./#.obj"), "/tmp/#.obj");
+        assert_eq!(normalize_pattern("./"), ".");
+
+        // Generated regular expressions accept either separator.
+        let regex = glob_to_regex("meshes/{box}_#.obj", false).unwrap();
+        assert_eq!(&regex.captures("meshes\\box_1.obj").unwrap()["frame"], "1");
+        assert!(regex.is_match("meshes/box_1.obj"));
+
+        // Wildcards never cross separators, except for `**`.
+        let regex = glob_to_regex("*/{box}_?.obj", false).unwrap();
+        assert!(regex.is_match("a/box_1.obj"));
+        assert!(!regex.is_match("a\\b/box_1.obj"));
+        let regex = glob_to_regex("**/{box}_#.obj", false).unwrap();
+        assert!(regex.is_match("box_1.obj"));
+        assert!(regex.is_match("a\\b/box_1.obj"));
+    }
+}
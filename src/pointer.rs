@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use gltf::json;
+use serde::{Deserialize, Serialize};
+
+use crate::LightInfo;
+
+/*
+ * Parsing animated properties from command line
+ */
+
+/// Value of an animated property at a single frame.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PointerValue {
+    /// A scalar property like an emissive strength or a light intensity.
+    Scalar(f32),
+    /// A vector property like a color, given by its components.
+    Vector(Vec<f32>),
+}
+
+impl PointerValue {
+    /// Components of this value.
+    pub fn components(&self) -> &[f32] {
+        match self {
+            PointerValue::Scalar(x) => std::slice::from_ref(x),
+            PointerValue::Vector(v) => v.as_slice(),
+        }
+    }
+}
+
+/// A property of the output document animated with the KHR_animation_pointer extension.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PointerAnimationInfo {
+    /// JSON pointer to the animated property, e.g. `/materials/0/emissiveFactor`.
+    ///
+    /// Nodes, materials and punctual lights may be referred to by name instead
+    /// of by index, e.g. `/materials/lava/emissiveFactor`.
+    pub pointer: String,
+    /// Values of the property keyed by frame number.
+    #[serde(default, with = "crate::config::integer_keys")]
+    pub values: BTreeMap<u32, PointerValue>,
+    /// Hold each value until the next frame instead of interpolating linearly.
+    #[serde(default)]
+    pub step: bool,
+    /// Name of the node whose animation this property is grouped with (see
+    /// `AnimationGrouping`).
+    #[serde(default)]
+    pub group: String,
+}
+
+impl std::str::FromStr for PointerAnimationInfo {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<PointerAnimationInfo, Self::Err> {
+        ron::de::from_str::<PointerAnimationInfo>(input).map_err(Self::Err::from)
+    }
+}
+
+impl PointerAnimationInfo {
+    /// Replaces names of nodes, materials and lights in the pointer with their indices.
+    ///
+    /// Returns the name that could not be found on failure.
+    pub(crate) fn resolve(
+        &self,
+        nodes: &[json::Node],
+        materials: &[json::Material],
+        lights: &[LightInfo],
+    ) -> Result<String, String> {
+        let segments: Vec<&str> = self.pointer.split('/').collect();
+        let mut resolved = Vec::with_capacity(segments.len());
+        for (i, &segment) in segments.iter().enumerate() {
+            if i == 0 || segment.parse::<usize>().is_ok() {
+                resolved.push(segment.to_string());
+                continue;
+            }
+            let index = match (segments[i - 1], i.checked_sub(2).map(|j| segments[j])) {
+                ("nodes", _) => nodes
+                    .iter()
+                    .position(|node| node.name.as_deref() == Some(segment)),
+                ("materials", _) => materials
+                    .iter()
+                    .position(|material| material.name.as_deref() == Some(segment)),
+                ("lights", Some("KHR_lights_punctual")) => {
+                    lights.iter().position(|light| light.name == segment)
+                }
+                _ => {
+                    resolved.push(segment.to_string());
+                    continue;
+                }
+            };
+            match index {
+                Some(index) => resolved.push(index.to_string()),
+                None => return Err(segment.to_string()),
+            }
+        }
+        Ok(resolved.join("/"))
+    }
+}
@@ -0,0 +1,137 @@
+//! Self-contained HTML previews of glTF files used by the `preview` subcommand.
+//!
+//! The model is embedded into the page as a base64 encoded GLB and displayed
+//! with the `<model-viewer>` web component, so the page can be opened directly
+//! from disk without a local web server. The component itself is loaded from
+//! [`MODEL_VIEWER_URL`].
+
+use std::path::{Path, PathBuf};
+
+use gltf::json;
+
+use crate::export::{to_glb, write_atomic};
+use crate::merge::{merge_documents, AnimationMerge};
+use crate::Error;
+
+/// Location of the script defining the `<model-viewer>` element.
+pub const MODEL_VIEWER_URL: &str =
+    "https://ajax.googleapis.com/ajax/libs/model-viewer/3.5.0/model-viewer.min.js";
+
+/// Reads the glTF file at `path` as GLB.
+///
+/// Standard glTF files are packed into a single binary buffer along with
+/// their external buffers and images.
+pub fn read_glb(path: &Path) -> Result<Vec<u8>, Error> {
+    let is_glb = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("glb"));
+    if is_glb {
+        return std::fs::read(path).map_err(|err| Error::Gltf(gltf::Error::Io(err)));
+    }
+    let mut warnings = Vec::new();
+    let (mut root, data) = merge_documents(
+        &[path.to_path_buf()],
+        AnimationMerge::Separate,
+        &mut warnings,
+    )?;
+    crate::utils::print_warnings(warnings);
+    root.buffers.push(json::Buffer {
+        byte_length: data.len().into(),
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: None,
+        uri: None,
+    });
+    to_glb(&root.into(), data)
+}
+
+/// Builds an HTML page displaying the given GLB with the given title.
+pub fn preview_html(glb: &[u8], title: &str) -> String {
+    let src = format!("data:model/gltf-binary;base64,{}", base64(glb));
+    viewer_page(title, &src, "")
+}
+
+/// Builds an HTML page displaying the model at `src`, followed by additional `script` elements.
+pub(crate) fn viewer_page(title: &str, src: &str, script: &str) -> String {
+    let title = escape_html(title);
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<script type="module" src="{url}"></script>
+<style>
+  html, body {{ margin: 0; height: 100%; background: #202020; }}
+  model-viewer {{ width: 100%; height: 100%; }}
+</style>
+</head>
+<body>
+<model-viewer alt="{title}" camera-controls autoplay shadow-intensity="1"
+  src="{src}"></model-viewer>
+{script}</body>
+</html>
+"#,
+        title = title,
+        url = MODEL_VIEWER_URL,
+        src = escape_html(src),
+        script = script,
+    )
+}
+
+/// Writes an HTML preview of the glTF file at `input` to `html`.
+///
+/// If `html` is `None`, the preview is written next to `input` with the
+/// `.html` extension. Returns the path of the written preview.
+pub fn write_preview(input: &Path, html: Option<PathBuf>) -> Result<PathBuf, Error> {
+    let glb = read_glb(input)?;
+    let html = html.unwrap_or_else(|| input.with_extension("html"));
+    let title = input.file_name().map_or_else(
+        || input.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let page = preview_html(&glb, &title);
+    write_atomic(&html, true, |writer| {
+        use std::io::Write;
+        writer.write_all(page.as_bytes())
+    })
+    .map_err(Error::Write)?;
+    Ok(html)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Encodes `bytes` with the standard base64 alphabet and padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
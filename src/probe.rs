@@ -0,0 +1,314 @@
+//! Lightweight inspection of loaded meshes used for reporting without exporting.
+
+use std::any::TypeId;
+use std::path::{Path, PathBuf};
+
+use meshx::attrib::{Attrib, AttribIndex};
+use meshx::topology::{FaceIndex, FaceVertexIndex, NumFaces, NumVertices, VertexIndex};
+use serde::Serialize;
+
+use crate::attrib::{ComponentType, Type, VertexAttribute};
+use crate::mesh::Mesh;
+use crate::{discover_frames, load_mesh, DiscoverOptions, Error, LoadConfig};
+
+/// Summary of a single attribute found on a mesh.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AttributeSummary {
+    pub name: String,
+    /// One of "vertex", "face" or "face_vertex".
+    pub location: &'static str,
+    /// Rust type of the attribute elements, or "unknown" for unsupported types.
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    /// Number of elements in the attribute.
+    pub len: usize,
+}
+
+/// Summary of the contents of a single mesh.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MeshSummary {
+    pub num_vertices: usize,
+    pub num_faces: usize,
+    pub attributes: Vec<AttributeSummary>,
+}
+
+macro_rules! type_name_of {
+    ($id:expr => $($t:ty),* $(,)?) => {{
+        let id = $id;
+        $(if id == TypeId::of::<$t>() { return stringify!($t); })*
+        "unknown"
+    }};
+}
+
+fn type_name(id: TypeId) -> &'static str {
+    type_name_of!(id =>
+        i8, u8, i16, u16, i32, u32, i64, u64, f32, f64, usize, isize,
+        [i8; 2], [u8; 2], [i16; 2], [u16; 2], [i32; 2], [u32; 2], [f32; 2], [f64; 2],
+        [i8; 3], [u8; 3], [i16; 3], [u16; 3], [i32; 3], [u32; 3], [f32; 3], [f64; 3],
+        [i8; 4], [u8; 4], [i16; 4], [u16; 4], [i32; 4], [u32; 4], [f32; 4], [f64; 4],
+        [[f32; 2]; 2], [[f32; 3]; 3], [[f32; 4]; 4],
+        [[f64; 2]; 2], [[f64; 3]; 3], [[f64; 4]; 4],
+        String,
+    )
+}
+
+fn summarize_attribs<I: AttribIndex<M>, M: Attrib>(
+    mesh: &M,
+    location: &'static str,
+    out: &mut Vec<AttributeSummary>,
+) {
+    let mut attribs: Vec<_> = mesh
+        .attrib_dict::<I>()
+        .iter()
+        .map(|(name, attrib)| AttributeSummary {
+            name: name.clone(),
+            location,
+            type_name: type_name(attrib.data.element_type_id()),
+            len: attrib.len(),
+        })
+        .collect();
+    // Attribute dictionaries are unordered, sort for a stable report.
+    attribs.sort_by(|a, b| a.name.cmp(&b.name));
+    out.extend(attribs);
+}
+
+/// Names commonly given to texture coordinate attributes, in order of preference.
+///
+/// Names are compared ignoring case and underscores.
+const TEXCOORD_NAMES: &[&str] = &[
+    "uv",
+    "st",
+    "texcoord",
+    "texcoord0",
+    "texcoords",
+    "texturecoords",
+    "texturecoordinates",
+    "tcoords",
+];
+
+/// Finds the attribute most likely to hold the texture coordinates of `mesh`.
+///
+/// Only 2-component float vertex and face-vertex attributes are considered.
+/// Attributes with a name from [`TEXCOORD_NAMES`] are preferred, otherwise the
+/// only candidate is returned if there is exactly one.
+pub fn find_texcoords(mesh: &Mesh) -> Option<String> {
+    if !matches!(mesh, Mesh::TriMesh(_)) {
+        return None;
+    }
+    let summary = MeshSummary::new(mesh);
+    let candidates: Vec<_> = summary
+        .attributes
+        .iter()
+        .filter(|a| a.location != "face" && a.type_name == "[f32; 2]")
+        .map(|a| a.name.as_str())
+        .collect();
+    let rank = |name: &str| {
+        let name = name.replace('_', "").to_lowercase();
+        TEXCOORD_NAMES.iter().position(|&known| known == name)
+    };
+    candidates
+        .iter()
+        .filter_map(|&name| Some((rank(name)?, name)))
+        .min()
+        .map(|(_, name)| name)
+        .or_else(|| (candidates.len() == 1).then(|| candidates[0]))
+        .map(String::from)
+}
+
+/// Names commonly given to color attributes, in order of preference.
+///
+/// Names are compared ignoring case and underscores.
+const COLOR_NAMES: &[&str] = &[
+    "cd",
+    "color",
+    "colors",
+    "colour",
+    "colours",
+    "rgba",
+    "rgb",
+    "vertexcolor",
+    "vertexcolors",
+];
+
+/// Type of the given attribute if it holds valid colors.
+///
+/// Float components must lie between 0 and 1, integer components are
+/// normalized on export.
+fn color_type(attrib: &VertexAttribute) -> Option<Type> {
+    fn unit<const N: usize>(attrib: &VertexAttribute) -> Option<bool> {
+        let values = attrib.as_slice::<[f32; N]>().ok()?;
+        Some(values.iter().flatten().all(|c| (0.0..=1.0).contains(c)))
+    }
+    let id = attrib.data.element_type_id();
+    if id == TypeId::of::<[u8; 3]>() {
+        Some(Type::Vec3(ComponentType::U8))
+    } else if id == TypeId::of::<[u8; 4]>() {
+        Some(Type::Vec4(ComponentType::U8))
+    } else if id == TypeId::of::<[u16; 3]>() {
+        Some(Type::Vec3(ComponentType::U16))
+    } else if id == TypeId::of::<[u16; 4]>() {
+        Some(Type::Vec4(ComponentType::U16))
+    } else if unit::<3>(attrib) == Some(true) {
+        Some(Type::Vec3(ComponentType::F32))
+    } else if unit::<4>(attrib) == Some(true) {
+        Some(Type::Vec4(ComponentType::F32))
+    } else {
+        None
+    }
+}
+
+/// Finds the vertex attribute most likely to hold the colors of `mesh`.
+///
+/// Only attributes named as in [`COLOR_NAMES`] are considered, and their values
+/// must be valid colors. Returns the name of the attribute and its type.
+pub fn find_colors(mesh: &Mesh) -> Option<(String, Type)> {
+    let attribs = match mesh {
+        Mesh::TriMesh(trimesh) => trimesh.attrib_dict::<VertexIndex>(),
+        Mesh::PointCloud(ptcloud) => ptcloud.attrib_dict::<VertexIndex>(),
+    };
+    attribs
+        .iter()
+        .filter_map(|(name, attrib)| {
+            let key = name.replace('_', "").to_lowercase();
+            let rank = COLOR_NAMES.iter().position(|&known| known == key)?;
+            Some((rank, name, color_type(attrib)?))
+        })
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+        .map(|(_, name, type_)| (name.clone(), type_))
+}
+
+impl MeshSummary {
+    pub fn new(mesh: &Mesh) -> MeshSummary {
+        let mut attributes = Vec::new();
+        match mesh {
+            Mesh::TriMesh(trimesh) => {
+                let trimesh = trimesh.as_ref();
+                summarize_attribs::<VertexIndex, _>(trimesh, "vertex", &mut attributes);
+                summarize_attribs::<FaceIndex, _>(trimesh, "face", &mut attributes);
+                summarize_attribs::<FaceVertexIndex, _>(trimesh, "face_vertex", &mut attributes);
+                MeshSummary {
+                    num_vertices: trimesh.num_vertices(),
+                    num_faces: trimesh.num_faces(),
+                    attributes,
+                }
+            }
+            Mesh::PointCloud(ptcloud) => {
+                summarize_attribs::<VertexIndex, _>(ptcloud, "vertex", &mut attributes);
+                MeshSummary {
+                    num_vertices: ptcloud.num_vertices(),
+                    num_faces: 0,
+                    attributes,
+                }
+            }
+        }
+    }
+
+    /// Rough estimate of the number of bytes needed to store an animation of this mesh.
+    ///
+    /// This assumes the topology stays fixed, so each additional frame is stored as a
+    /// morph target of vertex position displacements.
+    pub fn estimate_output_size(&self, num_frames: usize) -> usize {
+        let positions = self.num_vertices * std::mem::size_of::<[f32; 3]>();
+        let indices = self.num_faces * std::mem::size_of::<[u32; 3]>();
+        positions * num_frames.max(1) + indices
+    }
+}
+
+/// Attributes of a representative frame of an input sequence.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProbeReport {
+    /// File the frame was loaded from.
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub mesh: MeshSummary,
+}
+
+/// Loads a representative frame of `input` and summarizes its attributes.
+///
+/// The input is either a mesh file or a pattern as accepted by the main
+/// command, in which case the first frame of the first animation is loaded.
+pub fn probe(input: &str, load_config: LoadConfig) -> Result<ProbeReport, Error> {
+    let path = if Path::new(input).is_file() {
+        PathBuf::from(input)
+    } else {
+        discover_frames(input, DiscoverOptions::default())?
+            .into_iter()
+            .min_by(|a, b| a.name.cmp(&b.name).then(a.frame.cmp(&b.frame)))
+            .ok_or(Error::NoMeshesFound)?
+            .path
+    };
+    let mesh = load_mesh(&path, load_config).ok_or_else(|| Error::MeshLoad(path.clone()))?;
+    Ok(ProbeReport {
+        mesh: MeshSummary::new(&mesh),
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_texcoords() {
+        let new_mesh = || meshx::TriMesh::new(vec![[0.0; 3]; 3], vec![[0, 1, 2]]);
+
+        // Known names are preferred over other 2-component attributes.
+        let mut trimesh = new_mesh();
+        trimesh
+            .insert_attrib_data::<[f32; 2], VertexIndex>("offset", vec![[0.0; 2]; 3])
+            .unwrap();
+        trimesh
+            .insert_attrib_data::<[f32; 2], FaceVertexIndex>("TEXCOORD_0", vec![[0.0; 2]; 3])
+            .unwrap();
+        let found = find_texcoords(&Mesh::from(trimesh.clone()));
+        assert_eq!(found.as_deref(), Some("TEXCOORD_0"));
+        trimesh
+            .insert_attrib_data::<[f32; 2], VertexIndex>("st", vec![[0.0; 2]; 3])
+            .unwrap();
+        let found = find_texcoords(&Mesh::from(trimesh));
+        assert_eq!(found.as_deref(), Some("st"));
+
+        // A single unknown candidate is used, but not one of several.
+        let mut trimesh = new_mesh();
+        trimesh
+            .insert_attrib_data::<[f32; 2], VertexIndex>("offset", vec![[0.0; 2]; 3])
+            .unwrap();
+        trimesh
+            .insert_attrib_data::<[f32; 3], VertexIndex>("velocity", vec![[0.0; 3]; 3])
+            .unwrap();
+        let found = find_texcoords(&Mesh::from(trimesh.clone()));
+        assert_eq!(found.as_deref(), Some("offset"));
+        trimesh
+            .insert_attrib_data::<[f32; 2], VertexIndex>("scale", vec![[0.0; 2]; 3])
+            .unwrap();
+        assert_eq!(find_texcoords(&Mesh::from(trimesh)), None);
+    }
+
+    #[test]
+    fn find_colors() {
+        let mut ptcloud = meshx::PointCloud::new(vec![[0.0_f32; 3]; 2]);
+        // Float colors outside of [0, 1] are not picked up.
+        ptcloud
+            .insert_attrib_data::<[f32; 3], VertexIndex>("color", vec![[0.5, 1.0, 255.0]; 2])
+            .unwrap();
+        ptcloud
+            .insert_attrib_data::<[f32; 3], VertexIndex>("velocity", vec![[0.5; 3]; 2])
+            .unwrap();
+        assert_eq!(find_colors(&Mesh::from(ptcloud.clone())), None);
+
+        ptcloud
+            .insert_attrib_data::<[u8; 4], VertexIndex>("RGBA", vec![[255; 4]; 2])
+            .unwrap();
+        assert_eq!(
+            find_colors(&Mesh::from(ptcloud.clone())),
+            Some(("RGBA".to_string(), Type::Vec4(ComponentType::U8)))
+        );
+        ptcloud
+            .insert_attrib_data::<[f32; 3], VertexIndex>("Cd", vec![[0.0, 0.5, 1.0]; 2])
+            .unwrap();
+        assert_eq!(
+            find_colors(&Mesh::from(ptcloud)),
+            Some(("Cd".to_string(), Type::Vec3(ComponentType::F32)))
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Progress and warning reporting for the export pipeline.
+//!
+//! Applications embedding `gltfgen` can implement [`ProgressSink`] to show
+//! progress in their own interface. With the `cli` feature, the command line
+//! tool uses `IndicatifProgress` to draw progress bars in the terminal.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "cli")]
+use std::sync::Mutex;
+
+#[cfg(feature = "cli")]
+use indicatif::ProgressBar;
+
+#[cfg(feature = "cli")]
+use crate::utils::{new_progress_bar, new_progress_bar_file};
+
+/// Units in which the work of a stage is measured.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProgressUnit {
+    Items,
+    Bytes,
+}
+
+/// Receiver of progress updates and warnings produced during export.
+pub trait ProgressSink: Send + Sync {
+    /// A new stage started with `len` units of work, or zero if unknown.
+    fn stage_started(&self, stage: &str, len: u64, unit: ProgressUnit);
+    /// `n` of `m` units of work in the current stage are done.
+    fn progress(&self, n: u64, m: u64);
+    /// The current stage is complete.
+    fn stage_finished(&self, message: &str);
+    /// A warning was produced.
+    fn warning(&self, message: &str);
+}
+
+impl std::fmt::Debug for dyn ProgressSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// Ignores all progress updates and warnings.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn stage_started(&self, _: &str, _: u64, _: ProgressUnit) {}
+    fn progress(&self, _: u64, _: u64) {}
+    fn stage_finished(&self, _: &str) {}
+    fn warning(&self, _: &str) {}
+}
+
+/// Ignores progress updates and logs warnings.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LogProgress;
+
+impl ProgressSink for LogProgress {
+    fn stage_started(&self, _: &str, _: u64, _: ProgressUnit) {}
+    fn progress(&self, _: u64, _: u64) {}
+    fn stage_finished(&self, _: &str) {}
+    fn warning(&self, message: &str) {
+        log::warn!("{}", message);
+    }
+}
+
+/// Draws a progress bar for each stage in the terminal and logs warnings.
+#[cfg(feature = "cli")]
+#[derive(Debug)]
+pub struct IndicatifProgress {
+    quiet: bool,
+    bar: Mutex<ProgressBar>,
+}
+
+#[cfg(feature = "cli")]
+impl IndicatifProgress {
+    /// Creates a new sink. If `quiet` is set, no progress bars are drawn.
+    pub fn new(quiet: bool) -> Self {
+        IndicatifProgress {
+            quiet,
+            bar: Mutex::new(ProgressBar::hidden()),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl ProgressSink for IndicatifProgress {
+    fn stage_started(&self, stage: &str, len: u64, unit: ProgressUnit) {
+        let bar = match unit {
+            ProgressUnit::Items => new_progress_bar(self.quiet, len as usize),
+            ProgressUnit::Bytes => new_progress_bar_file(self.quiet, len as usize),
+        };
+        bar.set_message(stage.to_string());
+        *self.bar.lock().unwrap() = bar;
+    }
+    fn progress(&self, n: u64, m: u64) {
+        let bar = self.bar.lock().unwrap();
+        bar.set_length(m);
+        bar.set_position(n);
+    }
+    fn stage_finished(&self, message: &str) {
+        self.bar
+            .lock()
+            .unwrap()
+            .finish_with_message(message.to_string());
+    }
+    fn warning(&self, message: &str) {
+        log::warn!("{}", message);
+    }
+}
+
+/// Progress of a single stage reported to a sink.
+pub(crate) struct Stage<'a> {
+    sink: &'a dyn ProgressSink,
+    len: AtomicU64,
+    pos: AtomicU64,
+}
+
+impl<'a> Stage<'a> {
+    pub(crate) fn start(
+        sink: &'a dyn ProgressSink,
+        name: &str,
+        len: u64,
+        unit: ProgressUnit,
+    ) -> Self {
+        sink.stage_started(name, len, unit);
+        Stage {
+            sink,
+            len: AtomicU64::new(len),
+            pos: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the total amount of work once it is known.
+    pub(crate) fn set_length(&self, len: u64) {
+        self.len.store(len, Ordering::Relaxed);
+    }
+
+    /// Marks `n` more units of work as done.
+    pub(crate) fn inc(&self, n: u64) {
+        let pos = self.pos.fetch_add(n, Ordering::Relaxed) + n;
+        let len = self.len.load(Ordering::Relaxed);
+        self.sink.progress(pos, len.max(pos));
+    }
+
+    pub(crate) fn finish(self, message: &str) {
+        self.sink.stage_finished(message);
+    }
+
+    /// Wraps a writer to report the number of bytes written.
+    pub(crate) fn wrap_write<W: std::io::Write>(&self, writer: W) -> StageWriter<'_, 'a, W> {
+        StageWriter {
+            stage: self,
+            writer,
+        }
+    }
+}
+
+pub(crate) struct StageWriter<'s, 'a, W> {
+    stage: &'s Stage<'a>,
+    writer: W,
+}
+
+impl<W: std::io::Write> std::io::Write for StageWriter<'_, '_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.stage.inc(n as u64);
+        Ok(n)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{build_gltf, ExportConfig};
+    use crate::{
+        load_and_clean_meshes, AttribConfig, AttributeInfo, ExtrasInfo, LoadConfig,
+        TextureAttributeInfo,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn progress_sink() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Recorder {
+            events: Mutex<Vec<String>>,
+        }
+        impl ProgressSink for Recorder {
+            fn stage_started(&self, stage: &str, len: u64, _: ProgressUnit) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("start {} {}", stage, len));
+            }
+            fn progress(&self, _: u64, _: u64) {}
+            fn stage_finished(&self, message: &str) {
+                self.events.lock().unwrap().push(format!("end {}", message));
+            }
+            fn warning(&self, message: &str) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("warn {}", message));
+            }
+        }
+
+        let mesh_meta = (1..=3)
+            .map(|frame| {
+                let path = format!("./assets/box_rotate_{}.vtk", frame);
+                (String::from("box_rotate"), frame, PathBuf::from(path))
+            })
+            .collect();
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let meshes = load_and_clean_meshes(mesh_meta, LoadConfig::default(), attrib_config);
+
+        let recorder = Arc::new(Recorder::default());
+        let config = ExportConfig::builder()
+            .extras(ExtrasInfo {
+                nodes: [(String::from("missing"), serde_json::json!({}))]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            })
+            .progress(recorder.clone())
+            .build();
+        build_gltf(meshes, config).unwrap();
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![
+                "start Extracting Animation 3",
+                "end Done extracting animation",
+                "start Constructing glTF 2",
+                "end Done constructing glTF",
+                "warn No node or material named 'missing' found to attach extras to. Skipping...",
+            ]
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! Simplified proxy shapes of meshes for collision and selection.
+//!
+//! A proxy is either the axis aligned bounding box or the convex hull of the
+//! vertices of a mesh. Animated proxies take their shape from the first frame
+//! and follow the bounding box of later frames by translation and scale,
+//! which engines can evaluate cheaply without morphing the proxy itself.
+
+use std::collections::HashSet;
+
+use crate::config::CollisionProxy;
+
+/// A proxy shape enclosing an animated mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proxy {
+    pub shape: CollisionProxy,
+    /// Translation of the proxy at the first frame.
+    pub translation: [f32; 3],
+    /// Vertex positions relative to the translation of the proxy.
+    pub positions: Vec<[f32; 3]>,
+    /// Outward facing triangles.
+    pub indices: Vec<[u32; 3]>,
+    /// Transform of the proxy at each keyframe, sorted by frame.
+    ///
+    /// Proxies that are not animated have a single key at the first frame.
+    pub keys: Vec<ProxyKey>,
+}
+
+/// Transform of a proxy at a given frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ProxyKey {
+    pub frame: u32,
+    pub translation: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+impl Proxy {
+    /// Builds a proxy from the vertex positions at each frame.
+    ///
+    /// With `animate`, the proxy has the shape of the first frame and a key
+    /// mapping its bounding box to that of every frame. Otherwise a single
+    /// key at the first frame places a proxy enclosing all frames. Returns
+    /// `None` if there are no frames or vertices.
+    pub fn new(
+        shape: CollisionProxy,
+        animate: bool,
+        frames: impl IntoIterator<Item = (u32, Vec<[f32; 3]>)>,
+    ) -> Option<Proxy> {
+        let mut frames = frames.into_iter();
+        let (first_frame, first) = frames.next()?;
+        let (min0, max0) = bounds(&first)?;
+
+        let (points, min, max, mut keys) = if animate {
+            let mut keys = vec![ProxyKey {
+                frame: first_frame,
+                translation: center(min0, max0),
+                scale: [1.0; 3],
+            }];
+            keys.extend(frames.filter_map(|(frame, positions)| {
+                let (min, max) = bounds(&positions)?;
+                // Flat proxies keep their size along axes without extent.
+                let scale = std::array::from_fn(|i| {
+                    let extent = max0[i] - min0[i];
+                    if extent > 0.0 {
+                        (max[i] - min[i]) / extent
+                    } else {
+                        1.0
+                    }
+                });
+                Some(ProxyKey {
+                    frame,
+                    translation: center(min, max),
+                    scale,
+                })
+            }));
+            (first, min0, max0, keys)
+        } else {
+            // Hulls of all frames are enclosed by the hull of their hull vertices.
+            let hull_points = |positions: Vec<[f32; 3]>| match shape {
+                CollisionProxy::Box => Vec::new(),
+                CollisionProxy::Hull => convex_hull(&positions).map_or(positions, |(p, _)| p),
+            };
+            let (mut min, mut max) = (min0, max0);
+            let mut points = hull_points(first);
+            for (_, positions) in frames {
+                if let Some((frame_min, frame_max)) = bounds(&positions) {
+                    for i in 0..3 {
+                        min[i] = min[i].min(frame_min[i]);
+                        max[i] = max[i].max(frame_max[i]);
+                    }
+                    points.extend(hull_points(positions));
+                }
+            }
+            let key = ProxyKey {
+                frame: first_frame,
+                translation: center(min, max),
+                scale: [1.0; 3],
+            };
+            (points, min, max, vec![key])
+        };
+
+        let (positions, indices) = match shape {
+            CollisionProxy::Box => box_mesh(min, max),
+            // Flat meshes have no hull, so they get a flat box instead.
+            CollisionProxy::Hull => convex_hull(&points).unwrap_or_else(|| box_mesh(min, max)),
+        };
+        let translation = center(min, max);
+        let positions = positions
+            .iter()
+            .map(|p| {
+                [
+                    p[0] - translation[0],
+                    p[1] - translation[1],
+                    p[2] - translation[2],
+                ]
+            })
+            .collect();
+        keys.sort_by_key(|key| key.frame);
+        Some(Proxy {
+            shape,
+            translation,
+            positions,
+            indices,
+            keys,
+        })
+    }
+
+    /// Adds keys at the given frames repeating the preceding key.
+    ///
+    /// This keeps the proxy still through held frames of the animation.
+    pub fn hold(&mut self, frames: &[u32]) {
+        for &frame in frames {
+            let Some(prev) = self
+                .keys
+                .iter()
+                .rev()
+                .find(|key| key.frame < frame)
+                .copied()
+            else {
+                continue;
+            };
+            let at = self.keys.partition_point(|key| key.frame < frame);
+            if self.keys.get(at).map_or(true, |key| key.frame != frame) {
+                self.keys.insert(at, ProxyKey { frame, ..prev });
+            }
+        }
+    }
+}
+
+/// Bounding box of the given points, or `None` if there are none.
+pub fn bounds(points: &[[f32; 3]]) -> Option<([f32; 3], [f32; 3])> {
+    let mut points = points.iter();
+    let first = *points.next()?;
+    Some(points.fold((first, first), |(min, max), p| {
+        (
+            std::array::from_fn(|i| min[i].min(p[i])),
+            std::array::from_fn(|i| max[i].max(p[i])),
+        )
+    }))
+}
+
+fn center(min: [f32; 3], max: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|i| 0.5 * (min[i] + max[i]))
+}
+
+/// Vertices and outward facing triangles of the box between `min` and `max`.
+pub fn box_mesh(min: [f32; 3], max: [f32; 3]) -> (Vec<[f32; 3]>, Vec<[u32; 3]>) {
+    // Corner `i` is at the maximum along x, y and z if bits 2, 1 and 0 of `i` are set.
+    let positions = (0..8)
+        .map(|i| {
+            let pick = |bit: usize, axis: usize| {
+                if (i >> bit) & 1 == 1 {
+                    max[axis]
+                } else {
+                    min[axis]
+                }
+            };
+            [pick(2, 0), pick(1, 1), pick(0, 2)]
+        })
+        .collect();
+    let quads = [
+        [0, 1, 3, 2],
+        [4, 6, 7, 5],
+        [0, 4, 5, 1],
+        [2, 3, 7, 6],
+        [0, 2, 6, 4],
+        [1, 5, 7, 3],
+    ];
+    let indices = quads
+        .iter()
+        .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+        .collect();
+    (positions, indices)
+}
+
+type Vec3 = [f64; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// A triangle of a convex hull under construction.
+struct Face {
+    vertices: [usize; 3],
+    normal: Vec3,
+    offset: f64,
+    /// Points above this face not yet on the hull.
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl Face {
+    fn new(points: &[Vec3], vertices: [usize; 3]) -> Face {
+        let [a, b, c] = vertices.map(|v| points[v]);
+        let n = cross(sub(b, a), sub(c, a));
+        let len = dot(n, n).sqrt();
+        let normal = if len > 0.0 {
+            n.map(|x| x / len)
+        } else {
+            [0.0; 3]
+        };
+        Face {
+            vertices,
+            normal,
+            offset: dot(normal, a),
+            outside: Vec::new(),
+            alive: true,
+        }
+    }
+
+    fn distance(&self, p: Vec3) -> f64 {
+        dot(self.normal, p) - self.offset
+    }
+}
+
+/// Convex hull of the given points as vertex positions and outward facing triangles.
+///
+/// Returns `None` if the points are coplanar, since they enclose no volume.
+pub fn convex_hull(points: &[[f32; 3]]) -> Option<(Vec<[f32; 3]>, Vec<[u32; 3]>)> {
+    let pts: Vec<Vec3> = points.iter().map(|p| p.map(f64::from)).collect();
+    let (min, max) = bounds(points)?;
+    let extent = sub(max.map(f64::from), min.map(f64::from));
+    let diagonal = dot(extent, extent).sqrt();
+    if !(diagonal > 0.0 && diagonal.is_finite()) {
+        return None;
+    }
+    // Points this close to a face are considered to lie on it.
+    let eps = diagonal * 1e-6;
+
+    // Start from a tetrahedron of extreme points.
+    let axis = (0..3).max_by(|&i, &j| extent[i].total_cmp(&extent[j]))?;
+    let by_axis = |i: &usize, j: &usize| pts[*i][axis].total_cmp(&pts[*j][axis]);
+    let a = (0..pts.len()).min_by(by_axis)?;
+    let b = (0..pts.len()).max_by(by_axis)?;
+    let line = sub(pts[b], pts[a]);
+    let line_distance = |p: Vec3| {
+        let c = cross(line, sub(p, pts[a]));
+        dot(c, c)
+    };
+    let c =
+        (0..pts.len()).max_by(|&i, &j| line_distance(pts[i]).total_cmp(&line_distance(pts[j])))?;
+    if line_distance(pts[c]).sqrt() <= eps * dot(line, line).sqrt() {
+        return None;
+    }
+    let base = Face::new(&pts, [a, b, c]);
+    let d = (0..pts.len()).max_by(|&i, &j| {
+        base.distance(pts[i])
+            .abs()
+            .total_cmp(&base.distance(pts[j]).abs())
+    })?;
+    if base.distance(pts[d]).abs() <= eps {
+        return None;
+    }
+
+    // Orient the faces of the tetrahedron away from its opposite vertex.
+    let mut faces: Vec<Face> = [[a, b, c, d], [a, b, d, c], [a, c, d, b], [b, c, d, a]]
+        .into_iter()
+        .map(|[u, v, w, opposite]| {
+            let face = Face::new(&pts, [u, v, w]);
+            if face.distance(pts[opposite]) > 0.0 {
+                Face::new(&pts, [u, w, v])
+            } else {
+                face
+            }
+        })
+        .collect();
+    let assign = |faces: &mut [Face], candidates: &[usize], point: usize| {
+        if let Some(face) = candidates
+            .iter()
+            .find(|&&f| faces[f].distance(pts[point]) > eps)
+        {
+            faces[*face].outside.push(point);
+        }
+    };
+    let initial: Vec<usize> = (0..faces.len()).collect();
+    for p in 0..pts.len() {
+        if ![a, b, c, d].contains(&p) {
+            assign(&mut faces, &initial, p);
+        }
+    }
+
+    // Repeatedly add the furthest point above a face, replacing all faces it sees.
+    while let Some(f) = faces
+        .iter()
+        .position(|face| face.alive && !face.outside.is_empty())
+    {
+        let face = &faces[f];
+        let eye = *face
+            .outside
+            .iter()
+            .max_by(|&&i, &&j| face.distance(pts[i]).total_cmp(&face.distance(pts[j])))?;
+        let visible: Vec<usize> = (0..faces.len())
+            .filter(|&g| faces[g].alive && faces[g].distance(pts[eye]) > eps)
+            .collect();
+        let edges: Vec<(usize, usize)> = visible
+            .iter()
+            .flat_map(|&g| {
+                let [u, v, w] = faces[g].vertices;
+                [(u, v), (v, w), (w, u)]
+            })
+            .collect();
+        let edge_set: HashSet<_> = edges.iter().copied().collect();
+        let mut orphans = Vec::new();
+        for &g in visible.iter() {
+            faces[g].alive = false;
+            orphans.append(&mut faces[g].outside);
+        }
+        // Edges on the boundary of the visible region are connected to the new point.
+        let first_new = faces.len();
+        for &(u, v) in edges.iter() {
+            if !edge_set.contains(&(v, u)) {
+                faces.push(Face::new(&pts, [u, v, eye]));
+            }
+        }
+        let new_faces: Vec<usize> = (first_new..faces.len()).collect();
+        for p in orphans {
+            if p != eye {
+                assign(&mut faces, &new_faces, p);
+            }
+        }
+    }
+
+    let mut new_index = vec![u32::MAX; pts.len()];
+    let mut positions = Vec::new();
+    let triangles = faces
+        .iter()
+        .filter(|face| face.alive)
+        .map(|face| {
+            face.vertices.map(|v| {
+                if new_index[v] == u32::MAX {
+                    new_index[v] = positions.len() as u32;
+                    positions.push(points[v]);
+                }
+                new_index[v]
+            })
+        })
+        .collect();
+    Some((positions, triangles))
+}
@@ -0,0 +1,398 @@
+//! Machine-readable summary of a conversion run written with `--report`.
+//!
+//! The same report is printed as a table at the end of a verbose run.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use gltf::json;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::export::MaterialIdBinding;
+use crate::info::GltfSummary;
+
+/// Structured summary of a single run of the generator.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    /// Input files found by the pattern, whether loaded or skipped.
+    pub files: Vec<FileReport>,
+    /// Number of skipped input files for each reason.
+    pub skipped: IndexMap<String, usize>,
+    /// Generated nodes along with their attributes and materials.
+    pub nodes: Vec<NodeReport>,
+    /// Size of the written output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputReport>,
+    /// Time in seconds spent in each stage of the conversion.
+    pub timings: IndexMap<&'static str, f64>,
+    /// Error that stopped the conversion, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Status of an input file.
+#[derive(Clone, Debug, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    /// Name of the node the file belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<u32>,
+    pub loaded: bool,
+    /// Reason the file was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Attributes and materials of a generated node.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NodeReport {
+    pub name: String,
+    /// Number of primitives in the mesh of this node.
+    pub primitives: usize,
+    /// Attributes written to the output.
+    pub attributes: Vec<String>,
+    /// Attributes that failed to transfer.
+    pub dropped_attributes: Vec<String>,
+    /// Names of the materials bound to the primitives of this node.
+    pub materials: Vec<String>,
+    /// Material ids found on this node along with the indices of the materials they were bound to.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub material_ids: Vec<MaterialIdBinding>,
+}
+
+/// Output file sizes.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OutputReport {
+    pub path: PathBuf,
+    /// Size of the output file on disk.
+    pub file_bytes: u64,
+    /// Total number of bytes in all buffers.
+    pub buffer_bytes: u64,
+    /// Number of bytes stored for each category of data.
+    pub buffer_breakdown: IndexMap<&'static str, u64>,
+    /// Number of bytes of morph target displacements for each node.
+    pub morph_breakdown: IndexMap<String, u64>,
+    pub num_nodes: usize,
+    pub num_primitives: usize,
+    /// Duration in seconds of each animation.
+    pub animations: IndexMap<String, f32>,
+    /// Number of images stored in buffers or data URIs.
+    pub embedded_images: usize,
+    /// Number of images referenced by a URI to a separate file.
+    pub referenced_images: usize,
+}
+
+impl OutputReport {
+    /// Suggestions for options that would reduce the output size.
+    pub fn hints(&self) -> Vec<String> {
+        let fraction = |category: &str| {
+            self.buffer_breakdown.get(category).copied().unwrap_or(0) as f64
+                / self.buffer_bytes.max(1) as f64
+        };
+        let mut hints = Vec::new();
+        if fraction("morph normals") >= HINT_FRACTION {
+            hints.push(format!(
+                "Animated normals take {:.1}% of the buffers; use '--no-animated-normals' to drop them.",
+                100.0 * fraction("morph normals")
+            ));
+        }
+        if fraction("morph tangents") >= HINT_FRACTION {
+            hints.push(format!(
+                "Animated tangents take {:.1}% of the buffers; use '--no-animated-tangents' to drop them.",
+                100.0 * fraction("morph tangents")
+            ));
+        }
+        if fraction("morph positions") >= 0.5 {
+            hints.push(format!(
+                "Morph targets take {:.1}% of the buffers; use '--step' to keep fewer frames.",
+                100.0 * fraction("morph positions")
+            ));
+        }
+        if fraction("images") >= HINT_FRACTION {
+            hints.push(format!(
+                "Embedded images take {:.1}% of the buffers; use 'Uri' images in '--textures' to reference them instead.",
+                100.0 * fraction("images")
+            ));
+        }
+        hints
+    }
+}
+
+/// Fraction of the buffer size above which a category is worth a hint.
+const HINT_FRACTION: f64 = 0.1;
+
+impl fmt::Display for OutputReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::utils::HumanBytes;
+        let percent = |bytes: u64| 100.0 * bytes as f64 / self.buffer_bytes.max(1) as f64;
+        writeln!(
+            f,
+            "{}: {} ({} in buffers)",
+            self.path.display(),
+            HumanBytes(self.file_bytes),
+            HumanBytes(self.buffer_bytes)
+        )?;
+        for (category, &bytes) in self.buffer_breakdown.iter() {
+            writeln!(
+                f,
+                "  {:<20} {:>12} {:>6.1}%",
+                category,
+                HumanBytes(bytes).to_string(),
+                percent(bytes)
+            )?;
+        }
+        if !self.morph_breakdown.is_empty() {
+            writeln!(f, "morph targets by node:")?;
+            for (node, &bytes) in self.morph_breakdown.iter() {
+                writeln!(
+                    f,
+                    "  {:<20} {:>12} {:>6.1}%",
+                    node,
+                    HumanBytes(bytes).to_string(),
+                    percent(bytes)
+                )?;
+            }
+        }
+        for hint in self.hints() {
+            writeln!(f, "hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl Report {
+    /// Records a file that was loaded successfully.
+    pub fn loaded(&mut self, path: PathBuf, name: String, frame: u32) {
+        self.files.push(FileReport {
+            path,
+            name: Some(name),
+            frame: Some(frame),
+            loaded: true,
+            reason: None,
+        });
+    }
+
+    /// Records a file that was skipped for the given reason.
+    pub fn skipped(
+        &mut self,
+        path: PathBuf,
+        name_and_frame: Option<(String, u32)>,
+        reason: impl Into<String>,
+    ) {
+        let (name, frame) = name_and_frame.unzip();
+        let reason = reason.into();
+        *self.skipped.entry(reason.clone()).or_default() += 1;
+        self.files.push(FileReport {
+            path,
+            name,
+            frame,
+            loaded: false,
+            reason: Some(reason),
+        });
+    }
+
+    /// Records the time spent in the given stage.
+    pub fn time(&mut self, stage: &'static str, duration: Duration) {
+        self.timings.insert(stage, duration.as_secs_f64());
+    }
+
+    /// Returns the report of the given node, adding it if necessary.
+    fn node_mut(&mut self, node: &str) -> &mut NodeReport {
+        let index = match self.nodes.iter().position(|n| n.name == node) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(NodeReport {
+                    name: node.to_string(),
+                    ..Default::default()
+                });
+                self.nodes.len() - 1
+            }
+        };
+        &mut self.nodes[index]
+    }
+
+    /// Records a failure to transfer an attribute of the given node.
+    pub fn dropped_attribute(&mut self, node: &str, error: String) {
+        let dropped = &mut self.node_mut(node).dropped_attributes;
+        if !dropped.contains(&error) {
+            dropped.push(error);
+        }
+    }
+
+    /// Records the material ids found on the given node and the materials they were bound to.
+    pub fn material_ids(&mut self, node: &str, material_ids: Vec<MaterialIdBinding>) {
+        let ids = &mut self.node_mut(node).material_ids;
+        for binding in material_ids {
+            if !ids.contains(&binding) {
+                ids.push(binding);
+            }
+        }
+    }
+
+    /// Fills in node and output information from the glTF file written to `path`.
+    pub fn read_output(&mut self, path: &Path) -> Result<(), gltf::Error> {
+        let gltf = gltf::Gltf::open(path)?;
+        let root = gltf.document.as_json();
+        for node in root.nodes.iter() {
+            let (Some(name), Some(mesh)) = (&node.name, node.mesh) else {
+                continue;
+            };
+            let primitives = &root.meshes[mesh.value()].primitives;
+            let mut attributes = Vec::new();
+            let mut materials = Vec::new();
+            for prim in primitives.iter() {
+                for semantic in prim.attributes.keys() {
+                    let semantic = json::serialize::to_value(semantic)
+                        .ok()
+                        .and_then(|v| v.as_str().map(String::from))
+                        .unwrap_or_default();
+                    if !attributes.contains(&semantic) {
+                        attributes.push(semantic);
+                    }
+                }
+                if let Some(material) = prim.material {
+                    let name = root.materials[material.value()]
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("#{}", material.value()));
+                    if !materials.contains(&name) {
+                        materials.push(name);
+                    }
+                }
+            }
+            match self.nodes.iter_mut().find(|n| &n.name == name) {
+                Some(report) => {
+                    report.primitives = primitives.len();
+                    report.attributes = attributes;
+                    report.materials = materials;
+                }
+                None => self.nodes.push(NodeReport {
+                    name: name.clone(),
+                    primitives: primitives.len(),
+                    attributes,
+                    materials,
+                    ..Default::default()
+                }),
+            }
+        }
+
+        let summary = GltfSummary::new(root);
+        let embedded_images = root
+            .images
+            .iter()
+            .filter(|image| {
+                image.buffer_view.is_some()
+                    || image
+                        .uri
+                        .as_ref()
+                        .is_some_and(|uri| uri.starts_with("data:"))
+            })
+            .count();
+        self.output = Some(OutputReport {
+            path: path.to_path_buf(),
+            file_bytes: std::fs::metadata(path)?.len(),
+            buffer_bytes: summary.buffers.iter().sum(),
+            buffer_breakdown: summary.buffer_breakdown,
+            morph_breakdown: summary.morph_breakdown,
+            num_nodes: summary.num_nodes,
+            num_primitives: summary.num_primitives,
+            animations: summary
+                .animations
+                .into_iter()
+                .enumerate()
+                .map(|(i, (name, duration))| (name.unwrap_or_else(|| format!("#{}", i)), duration))
+                .collect(),
+            embedded_images,
+            referenced_images: root.images.len() - embedded_images,
+        });
+        Ok(())
+    }
+
+    /// Table summarizing the run, printed at the end of a verbose run.
+    pub fn summary(&self) -> Summary<'_> {
+        Summary(self)
+    }
+
+    /// Writes the report in JSON format to the given file.
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Table of the frames, primitives and attributes of each node followed by
+/// totals for the written output.
+///
+/// Attributes that failed to transfer are listed under their node, since they
+/// are otherwise easy to miss among the warnings of a long run.
+pub struct Summary<'a>(&'a Report);
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::utils::HumanBytes;
+        let report = self.0;
+        writeln!(
+            f,
+            "{:<20} {:>12} {:>10}  attributes",
+            "node", "frames", "primitives"
+        )?;
+        for node in report.nodes.iter() {
+            let frames: Vec<u32> = report
+                .files
+                .iter()
+                .filter(|file| file.loaded && file.name.as_ref() == Some(&node.name))
+                .filter_map(|file| file.frame)
+                .collect();
+            let frames = match (frames.iter().min(), frames.iter().max()) {
+                (Some(first), Some(last)) if first == last => format!("{}", first),
+                (Some(first), Some(last)) => format!("{}-{} ({})", first, last, frames.len()),
+                _ => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:<20} {:>12} {:>10}  {} transferred, {} dropped",
+                node.name,
+                frames,
+                node.primitives,
+                node.attributes.len(),
+                node.dropped_attributes.len()
+            )?;
+            if !node.attributes.is_empty() {
+                writeln!(f, "  transferred: {}", node.attributes.join(", "))?;
+            }
+            for error in node.dropped_attributes.iter() {
+                writeln!(f, "  dropped: {}", error)?;
+            }
+        }
+
+        let Some(output) = &report.output else {
+            return Ok(());
+        };
+        writeln!(f, "animations: {}", output.animations.len())?;
+        for (name, duration) in output.animations.iter() {
+            writeln!(f, "  {}: {:.3}s", name, duration)?;
+        }
+        writeln!(
+            f,
+            "nodes: {}, primitives: {}",
+            output.num_nodes, output.num_primitives
+        )?;
+        writeln!(
+            f,
+            "texture images: {} embedded, {} referenced",
+            output.embedded_images, output.referenced_images
+        )?;
+        writeln!(
+            f,
+            "{}: {} ({} in buffers)",
+            output.path.display(),
+            HumanBytes(output.file_bytes),
+            HumanBytes(output.buffer_bytes)
+        )
+    }
+}
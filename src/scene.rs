@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::path::Path;
+
+use gltf::json;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// Automatic placement of generated animated nodes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum LayoutInfo {
+    /// All nodes are placed at the origin.
+    #[serde(alias = "none")]
+    #[default]
+    None,
+    /// Nodes are placed on a grid in the XZ plane centered at the origin.
+    #[serde(alias = "grid")]
+    Grid {
+        /// Distance between the origins of neighbouring nodes.
+        #[serde(default = "default_grid_spacing")]
+        spacing: f32,
+        /// Number of columns in the grid. Defaults to the smallest number
+        /// producing a square grid.
+        #[serde(default)]
+        columns: Option<u32>,
+    },
+}
+
+fn default_grid_spacing() -> f32 {
+    1.0
+}
+
+impl std::str::FromStr for LayoutInfo {
+    type Err = ron::de::Error;
+    fn from_str(input: &str) -> Result<LayoutInfo, Self::Err> {
+        ron::de::from_str::<LayoutInfo>(input).map_err(Self::Err::from)
+    }
+}
+
+impl LayoutInfo {
+    /// Sets the translation of each given node according to this layout.
+    pub(crate) fn apply(&self, nodes: &mut [json::Node]) {
+        if let LayoutInfo::Grid { spacing, columns } = *self {
+            let n = nodes.len();
+            if n == 0 {
+                return;
+            }
+            let columns = columns
+                .map_or_else(|| (n as f64).sqrt().ceil() as usize, |c| c as usize)
+                .max(1);
+            let rows = n.div_ceil(columns);
+            // Offsets to center the grid at the origin.
+            let x0 = 0.5 * (columns.min(n) - 1) as f32 * spacing;
+            let z0 = 0.5 * (rows - 1) as f32 * spacing;
+            for (i, node) in nodes.iter_mut().enumerate() {
+                let col = (i % columns) as f32;
+                let row = (i / columns) as f32;
+                node.translation = Some([col * spacing - x0, 0.0, row * spacing - z0]);
+            }
+        }
+    }
+}
+
+/// A node in the scene description tree.
+///
+/// If the name of the node matches the name of a generated node (an animated
+/// mesh or a light), then that node is slotted into the tree at this position.
+/// Otherwise a new group node with the given name is created.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneNodeInfo {
+    pub name: String,
+    #[serde(default)]
+    pub translation: Option<[f32; 3]>,
+    /// Rotation given as a unit quaternion `(x, y, z, w)`.
+    #[serde(default)]
+    pub rotation: Option<[f32; 4]>,
+    #[serde(default)]
+    pub scale: Option<[f32; 3]>,
+    #[serde(default)]
+    pub children: Vec<SceneNodeInfo>,
+}
+
+/// A named scene listing the root nodes it contains.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamedSceneInfo {
+    pub name: String,
+    /// Names of root nodes of the hierarchy shown in this scene.
+    #[serde(default)]
+    pub nodes: Vec<String>,
+}
+
+/// Description of the node hierarchy of the output scene.
+///
+/// Generated nodes not referenced in the description are placed at the root of
+/// the scene.
+///
+/// Root nodes can optionally be grouped into multiple named scenes, for
+/// instance one per take or per level of detail. Roots not listed in any of
+/// the scenes are shared by all of them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneInfo {
+    #[serde(default)]
+    pub nodes: Vec<SceneNodeInfo>,
+    /// Scenes of the output. A single scene with all roots is created if empty.
+    #[serde(default)]
+    pub scenes: Vec<NamedSceneInfo>,
+    /// Index of the scene shown by default.
+    #[serde(default)]
+    pub default_scene: Option<usize>,
+}
+
+impl SceneInfo {
+    /// Loads a scene description from a RON or JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<SceneInfo, Error> {
+        use std::fs::File;
+        let ext = path
+            .as_ref()
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let reader = BufReader::new(File::open(path).map_err(Error::SceneLoad)?);
+        if ext == "json" {
+            Ok(serde_json::de::from_reader(reader)?)
+        } else if ext == "ron" {
+            Ok(ron::de::from_reader(reader)?)
+        } else {
+            Err(Error::SceneUnsupported(ext))
+        }
+    }
+
+    /// Sets the given parts of the transform of the node with the given name.
+    ///
+    /// The node is added at the root of the description if it isn't already in it.
+    pub fn set_transform(
+        &mut self,
+        name: &str,
+        translation: Option<[f32; 3]>,
+        rotation: Option<[f32; 4]>,
+        scale: Option<[f32; 3]>,
+    ) {
+        fn find<'a>(nodes: &'a mut [SceneNodeInfo], name: &str) -> Option<&'a mut SceneNodeInfo> {
+            for node in nodes {
+                if node.name == name {
+                    return Some(node);
+                }
+                if let Some(found) = find(&mut node.children, name) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+        let node = match find(&mut self.nodes, name) {
+            Some(node) => node,
+            None => {
+                self.nodes.push(SceneNodeInfo {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.nodes.last_mut().unwrap()
+            }
+        };
+        if translation.is_some() {
+            node.translation = translation;
+        }
+        if rotation.is_some() {
+            node.rotation = rotation;
+        }
+        if scale.is_some() {
+            node.scale = scale;
+        }
+    }
+
+    /// Arranges the given nodes according to this scene description.
+    ///
+    /// Group nodes are appended to `nodes`, so existing node indices remain
+    /// valid. Returns the indices of the root nodes of the scene.
+    pub(crate) fn build_hierarchy(
+        &self,
+        nodes: &mut Vec<json::Node>,
+        warnings: &mut Vec<(usize, String)>,
+    ) -> Vec<json::Index<json::Node>> {
+        let num_generated = nodes.len();
+        let mut generated: HashMap<String, usize> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            if let Some(name) = &node.name {
+                generated.entry(name.clone()).or_insert(i);
+            }
+        }
+
+        // Generated nodes already placed in the hierarchy.
+        let mut placed = vec![false; num_generated];
+
+        let desc_roots: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|info| build_node(info, nodes, &generated, &mut placed, warnings))
+            .collect();
+
+        (0..num_generated)
+            .filter(|&i| !placed[i])
+            .chain(desc_roots)
+            .map(|i| json::Index::new(i as u32))
+            .collect()
+    }
+
+    /// Distributes the given root nodes among the scenes of this description.
+    ///
+    /// Returns the name and root nodes of each scene, along with the index of
+    /// the default scene, if any. Without described scenes, all roots are
+    /// placed in a single unnamed scene. `shared` roots, like those of attached
+    /// documents, are added to every scene.
+    pub(crate) fn build_scenes(
+        &self,
+        nodes: &[json::Node],
+        roots: Vec<json::Index<json::Node>>,
+        shared: &[json::Index<json::Node>],
+        warnings: &mut Vec<(usize, String)>,
+    ) -> (
+        Vec<(Option<String>, Vec<json::Index<json::Node>>)>,
+        Option<usize>,
+    ) {
+        if self.scenes.is_empty() {
+            if self.default_scene.is_some_and(|i| i > 0) {
+                log!(warnings; "Default scene index is out of range with a single scene. Using the first scene instead.");
+            }
+            let roots = roots.into_iter().chain(shared.iter().copied()).collect();
+            return (vec![(None, roots)], self.default_scene.map(|_| 0));
+        }
+
+        let name_of = |root: &json::Index<json::Node>| nodes[root.value()].name.as_deref();
+        let mut listed = vec![false; roots.len()];
+        let mut scenes: Vec<_> = self
+            .scenes
+            .iter()
+            .map(|info| {
+                let mut scene_roots = Vec::new();
+                for name in info.nodes.iter() {
+                    match roots.iter().position(|root| name_of(root) == Some(name)) {
+                        Some(i) => {
+                            listed[i] = true;
+                            scene_roots.push(roots[i]);
+                        }
+                        None => {
+                            log!(warnings; "No root node named '{}' found for scene '{}'. Skipping...", name, info.name);
+                        }
+                    }
+                }
+                (Some(info.name.clone()), scene_roots)
+            })
+            .collect();
+
+        let unlisted: Vec<_> = roots
+            .iter()
+            .zip(listed)
+            .filter(|(_, listed)| !listed)
+            .map(|(&root, _)| root)
+            .chain(shared.iter().copied())
+            .collect();
+        for (_, scene_roots) in scenes.iter_mut() {
+            scene_roots.extend(unlisted.iter().copied());
+        }
+
+        let default_scene = match self.default_scene {
+            Some(i) if i >= scenes.len() => {
+                log!(warnings; "Default scene index {} is out of range for {} scenes. Using the first scene instead.", i, scenes.len());
+                0
+            }
+            Some(i) => i,
+            None => 0,
+        };
+        (scenes, Some(default_scene))
+    }
+}
+
+/// Recursively builds the subtree rooted at `info` and returns the index of its root.
+fn build_node(
+    info: &SceneNodeInfo,
+    nodes: &mut Vec<json::Node>,
+    generated: &HashMap<String, usize>,
+    placed: &mut [bool],
+    warnings: &mut Vec<(usize, String)>,
+) -> usize {
+    let index = match generated.get(&info.name) {
+        Some(&i) if !placed[i] => {
+            placed[i] = true;
+            i
+        }
+        found => {
+            if found.is_some() {
+                log!(warnings;
+                    "Node '{}' appears multiple times in the scene description. Adding an empty group instead.",
+                    info.name
+                );
+            }
+            nodes.push(json::Node {
+                camera: None,
+                children: None,
+                extensions: Default::default(),
+                extras: Default::default(),
+                matrix: None,
+                mesh: None,
+                name: Some(info.name.clone()),
+                rotation: None,
+                scale: None,
+                translation: None,
+                skin: None,
+                weights: None,
+            });
+            nodes.len() - 1
+        }
+    };
+
+    let children: Vec<_> = info
+        .children
+        .iter()
+        .map(|child| json::Index::new(build_node(child, nodes, generated, placed, warnings) as u32))
+        .collect();
+
+    let node = &mut nodes[index];
+    if info.translation.is_some() {
+        node.translation = info.translation;
+    }
+    if let Some(rotation) = info.rotation {
+        node.rotation = Some(json::scene::UnitQuaternion(rotation));
+    }
+    if info.scale.is_some() {
+        node.scale = info.scale;
+    }
+    if !children.is_empty() {
+        node.children.get_or_insert_with(Vec::new).extend(children);
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, ExportConfig, SequenceBuilder};
+    use crate::mesh::Mesh;
+    use crate::tests::load_single_box;
+    use crate::{load_mesh, AttribConfig, AttributeInfo, LoadConfig, TextureAttributeInfo};
+    use gltf::Gltf;
+
+    #[test]
+    fn scene_hierarchy() {
+        let meshes = load_single_box();
+
+        let artifact = "./tests/artifacts/scene_hierarchy.glb";
+
+        let scene: SceneInfo = ron::de::from_str(
+            "(nodes: [(name:\"rig\", translation:Some((0.0, 1.0, 0.0)), children:[(name:\"box_rotate\"), (name:\"sun\")])])",
+        )
+        .unwrap();
+
+        export::export_clean_meshes(
+            meshes,
+            ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .lights(vec!["(name:\"sun\")".parse().unwrap()])
+                .scene(scene)
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
+
+        let gltf = Gltf::open(artifact).unwrap();
+        let scene = gltf.scenes().next().unwrap();
+        let roots: Vec<_> = scene.nodes().collect();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].name(), Some("rig"));
+        assert_eq!(roots[0].transform().decomposed().0, [0.0, 1.0, 0.0]);
+        let children: Vec<_> = roots[0].children().map(|n| n.name().unwrap()).collect();
+        assert_eq!(children, vec!["box_rotate", "sun"]);
+    }
+
+    #[test]
+    fn grid_layout() {
+        let load_config = LoadConfig::default();
+
+        let meshes: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|&name| {
+                (
+                    name.to_owned(),
+                    load_mesh("./assets/box_triangulated.vtk", load_config).unwrap(),
+                )
+            })
+            .collect();
+
+        let artifact = "./tests/artifacts/grid_layout.glb";
+
+        let attrib_config = AttribConfig {
+            attributes: &AttributeInfo::default(),
+            colors: &AttributeInfo::default(),
+            texcoords: &TextureAttributeInfo::default(),
+            material_attribute: "mtl_id",
+        };
+
+        export::export_named_meshes(
+            meshes,
+            attrib_config,
+            ExportConfig::builder()
+                .output(artifact)
+                .animate_normals(false)
+                .animate_tangents(false)
+                .layout("grid(spacing: 2.0)".parse().unwrap())
+                .quiet(true)
+                .build(),
+        )
+        .unwrap();
+
+        let gltf = Gltf::open(artifact).unwrap();
+        let translations: Vec<_> = gltf.nodes().map(|n| n.transform().decomposed().0).collect();
+        // Three nodes are placed on a 2x2 grid centered at the origin.
+        assert_eq!(
+            translations,
+            vec![[-1.0, 0.0, -1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn multiple_scenes() {
+        let scene: SceneInfo = ron::de::from_str(
+            "(scenes: [(name:\"high\", nodes:[\"hi\"]), (name:\"low\", nodes:[\"lo\", \"missing\"])], default_scene:Some(1))",
+        )
+        .unwrap();
+
+        let attributes = AttributeInfo::default();
+        let colors = AttributeInfo::default();
+        let texcoords = TextureAttributeInfo::default();
+        let attrib_config = AttribConfig {
+            attributes: &attributes,
+            colors: &colors,
+            texcoords: &texcoords,
+            material_attribute: "mtl_id",
+        };
+        let config = ExportConfig::builder().scene(scene).quiet(true).build();
+        let mut sequence = SequenceBuilder::new(attrib_config, config);
+        for name in ["hi", "lo", "ground"] {
+            let trimesh = meshx::TriMesh::new(
+                vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0, 1, 2]],
+            );
+            sequence.add_frame(name, 1, Mesh::from(trimesh)).unwrap();
+        }
+        let root = sequence.build().unwrap().0.root;
+
+        // Nodes not listed in any scene are shared by all scenes.
+        let scene_nodes = |i: usize| -> Vec<_> {
+            root.scenes[i]
+                .nodes
+                .iter()
+                .map(|n| root.nodes[n.value()].name.clone().unwrap())
+                .collect()
+        };
+        assert_eq!(root.scenes.len(), 2);
+        assert_eq!(root.scenes[0].name.as_deref(), Some("high"));
+        assert_eq!(root.scenes[1].name.as_deref(), Some("low"));
+        assert_eq!(scene_nodes(0), vec!["hi", "ground"]);
+        assert_eq!(scene_nodes(1), vec!["lo", "ground"]);
+        assert_eq!(root.scene.map(|i| i.value()), Some(1));
+    }
+}
@@ -0,0 +1,699 @@
+//! JSON Schema describing the JSON configuration format.
+//!
+//! Property descriptions and defaults are taken from the command line help of
+//! the corresponding options, so they stay in sync with the documentation.
+//! Without the `cli` feature the schema only describes the types of properties.
+//! Names of enum variants are taken from the deserializers of the enums along
+//! with the lower case aliases they accept.
+
+#[cfg(feature = "cli")]
+use clap::{CommandFactory, Parser};
+use serde::de::{DeserializeOwned, Visitor};
+use serde_json::{json, Map, Value};
+
+#[cfg(feature = "cli")]
+use crate::config::Config;
+use crate::config::{
+    AnimationGrouping, AttributeMismatchPolicy, CollisionProxy, Dcc, FrameOrder, GapPolicy,
+    NonFinitePolicy, PointsAs, Triangulation, UnparsableFramePolicy, WindingFix,
+};
+use crate::light::LightType;
+use crate::scene::LayoutInfo;
+use crate::texture::{ImageInfo, MagFilter, MinFilter, WrappingMode};
+use crate::{ComponentType, Type};
+
+const SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Schema of each configuration field.
+fn property_schemas() -> Vec<(&'static str, Value)> {
+    let string = json!({ "type": "string" });
+    let boolean = json!({ "type": "boolean" });
+    let string_list = json!({ "type": "array", "items": { "type": "string" } });
+    vec![
+        ("pattern", string.clone()),
+        ("case_insensitive", boolean.clone()),
+        ("output", string.clone()),
+        (
+            "fps",
+            json!({ "type": ["number", "string"], "exclusiveMinimum": 0 }),
+        ),
+        (
+            "time_step",
+            json!({ "type": ["number", "string", "null"], "exclusiveMinimum": 0 }),
+        ),
+        (
+            "duration",
+            json!({ "type": ["number", "string", "null"], "exclusiveMinimum": 0 }),
+        ),
+        ("reverse", boolean.clone()),
+        ("fix_winding", unit_enum::<WindingFix>()),
+        ("invert_tets", boolean.clone()),
+        ("triangulation", unit_enum::<Triangulation>()),
+        ("polygon_ids", boolean.clone()),
+        ("step", json!({ "type": "integer", "minimum": 1 })),
+        ("frame_offset", json!({ "type": "integer" })),
+        ("substeps", json!({ "type": "integer", "minimum": 1 })),
+        ("unparsable_frames", unit_enum::<UnparsableFramePolicy>()),
+        (
+            "order",
+            json!({ "oneOf": [unit_enum::<FrameOrder>(), { "type": "null" }] }),
+        ),
+        ("include_names", json!({ "type": ["string", "null"] })),
+        ("exclude_names", json!({ "type": ["string", "null"] })),
+        ("concat", string_list.clone()),
+        ("crossfade", json!({ "type": "integer", "minimum": 0 })),
+        ("colors", json!({ "$ref": "#/definitions/AttributeInfo" })),
+        (
+            "attributes",
+            json!({ "$ref": "#/definitions/AttributeInfo" }),
+        ),
+        (
+            "attribute_names",
+            json!({ "type": "object", "additionalProperties": { "type": "string" } }),
+        ),
+        (
+            "texcoords",
+            json!({
+                "type": "object",
+                "additionalProperties": { "$ref": "#/definitions/ComponentType" }
+            }),
+        ),
+        ("no_auto_attributes", boolean.clone()),
+        (
+            "textures",
+            json!({ "type": "array", "items": { "$ref": "#/definitions/TextureInfo" } }),
+        ),
+        (
+            "materials",
+            json!({ "type": "array", "items": { "$ref": "#/definitions/MaterialInfo" } }),
+        ),
+        ("material_attribute", string.clone()),
+        (
+            "material_id_map",
+            json!({
+                "type": "object",
+                "propertyNames": { "pattern": "^[0-9]+$" },
+                "additionalProperties": { "type": "integer", "minimum": 0 }
+            }),
+        ),
+        ("default_material", boolean.clone()),
+        ("split_materials", boolean.clone()),
+        (
+            "texture_atlas",
+            json!({ "type": ["integer", "null"], "minimum": 1 }),
+        ),
+        ("insert_vanishing_frames", boolean.clone()),
+        ("no_animated_normals", boolean.clone()),
+        ("no_animated_tangents", boolean.clone()),
+        ("animate_colors", boolean.clone()),
+        ("recompute_normals", boolean.clone()),
+        (
+            "crease_angle",
+            json!({ "type": ["number", "null"], "minimum": 0, "maximum": 180 }),
+        ),
+        ("bake_fields", string_list.clone()),
+        (
+            "max_primitive_vertices",
+            json!({ "type": ["integer", "null"], "minimum": 1 }),
+        ),
+        (
+            "point_size",
+            json!({ "type": ["number", "null"], "exclusiveMinimum": 0 }),
+        ),
+        ("point_radius", json!({ "type": ["string", "null"] })),
+        (
+            "points_as",
+            json!({ "oneOf": [{ "$ref": "#/definitions/PointsAs" }, { "type": "null" }] }),
+        ),
+        (
+            "animation_groups",
+            json!({ "$ref": "#/definitions/AnimationGrouping" }),
+        ),
+        (
+            "dcc",
+            json!({ "oneOf": [unit_enum::<Dcc>(), { "type": "null" }] }),
+        ),
+        (
+            "dcc_max_targets",
+            json!({ "type": "integer", "minimum": 1 }),
+        ),
+        (
+            "collision_proxy",
+            json!({ "oneOf": [unit_enum::<CollisionProxy>(), { "type": "null" }] }),
+        ),
+        ("animate_proxy", boolean.clone()),
+        (
+            "max_morph_targets",
+            json!({ "type": ["integer", "null"], "minimum": 2 }),
+        ),
+        (
+            "lights",
+            json!({ "type": "array", "items": { "$ref": "#/definitions/LightInfo" } }),
+        ),
+        ("studio_lighting", boolean.clone()),
+        ("frame_camera", boolean.clone()),
+        (
+            "pointer_animations",
+            json!({ "type": "array", "items": { "$ref": "#/definitions/PointerAnimationInfo" } }),
+        ),
+        ("scene", json!({ "type": ["string", "null"] })),
+        ("layout", json!({ "$ref": "#/definitions/LayoutInfo" })),
+        ("attach", string_list),
+        ("extras", json!({ "$ref": "#/definitions/ExtrasInfo" })),
+        ("sidecar", boolean.clone()),
+        ("copyright", json!({ "type": ["string", "null"] })),
+        ("generator_suffix", json!({ "type": ["string", "null"] })),
+        ("license", json!({ "type": ["string", "null"] })),
+        ("validate", boolean.clone()),
+        ("strict", boolean.clone()),
+        (
+            "threads",
+            json!({ "type": ["integer", "null"], "minimum": 0 }),
+        ),
+        ("no_overwrite", boolean.clone()),
+        ("dedup_buffers", boolean.clone()),
+        ("split_buffers", boolean.clone()),
+        ("strip_names", boolean.clone()),
+        ("minimal", boolean.clone()),
+        ("manifest", boolean.clone()),
+        ("deterministic", boolean.clone()),
+        ("resume", boolean),
+        ("cache", json!({ "type": ["string", "null"] })),
+        ("non_finite", unit_enum::<NonFinitePolicy>()),
+        ("gap_policy", unit_enum::<GapPolicy>()),
+        ("attribute_mismatch", unit_enum::<AttributeMismatchPolicy>()),
+        (
+            "overrides",
+            json!({
+                "description": "Settings changed for specific animations, keyed by animation name.",
+                "type": "object",
+                "additionalProperties": { "$ref": "#/definitions/AnimationOverride" }
+            }),
+        ),
+    ]
+}
+
+/// Deserializer recording the variant names an enum passes to `deserialize_enum`.
+struct VariantNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> serde::Deserializer<'de> for VariantNames<'_> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("expected an enum"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = variants;
+        Err(serde::de::Error::custom("variant names recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// Names of the variants of the enum `T` as given to its deserializer.
+fn variant_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut names: &'static [&'static str] = &[];
+    let _ = T::deserialize(VariantNames(&mut names));
+    names
+}
+
+/// Spellings of `variant` accepted when deserializing `T`, i.e. the variant
+/// name and its lower case alias.
+///
+/// Unit variants are checked as strings, and other variants as objects holding
+/// the given `example` value under the variant name.
+fn spellings<T: DeserializeOwned>(variant: &str, example: Option<&Value>) -> Vec<String> {
+    let mut spellings: Vec<String> = Vec::new();
+    for name in [variant.to_string(), heck::AsSnakeCase(variant).to_string()] {
+        let value = match example {
+            Some(example) => json!({ name.clone(): example }),
+            None => Value::String(name.clone()),
+        };
+        if !spellings.contains(&name) && serde_json::from_value::<T>(value).is_ok() {
+            spellings.push(name);
+        }
+    }
+    spellings
+}
+
+/// Schema of the enum `T` as written in JSON.
+///
+/// Unit variants are written as strings. Other variants are written as objects
+/// with the variant name as the only key, and `data` gives an example and the
+/// schema of the value held by each of them.
+fn enum_schema<T: DeserializeOwned>(data: impl Fn(&str) -> Option<(Value, Value)>) -> Value {
+    let mut units: Vec<String> = Vec::new();
+    let mut variants = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for &variant in variant_names::<T>() {
+        if seen.iter().any(|name| name == variant) {
+            continue;
+        }
+        let unit = spellings::<T>(variant, None);
+        if !unit.is_empty() {
+            seen.extend(unit.iter().cloned());
+            units.extend(unit);
+            continue;
+        }
+        let (example, schema) = data(variant).unwrap_or_default();
+        let keys = spellings::<T>(variant, Some(&example));
+        debug_assert!(
+            !keys.is_empty(),
+            "no schema for variant '{}' of {}",
+            variant,
+            std::any::type_name::<T>()
+        );
+        for key in keys {
+            variants.push(json!({
+                "type": "object",
+                "properties": { key.clone(): schema.clone() },
+                "required": [key.clone()],
+                "additionalProperties": false
+            }));
+            seen.push(key);
+        }
+    }
+    if variants.is_empty() {
+        return json!({ "type": "string", "enum": units });
+    }
+    if !units.is_empty() {
+        variants.insert(0, json!({ "type": "string", "enum": units }));
+    }
+    json!({ "oneOf": variants })
+}
+
+/// Schema of the enum `T` with only unit variants.
+fn unit_enum<T: DeserializeOwned>() -> Value {
+    enum_schema::<T>(|_| None)
+}
+
+fn vector(n: usize) -> Value {
+    json!({ "type": "array", "items": { "type": "number" }, "minItems": n, "maxItems": n })
+}
+
+/// Schemas of types shared between configuration fields.
+fn definitions() -> Map<String, Value> {
+    let component_type = json!({ "$ref": "#/definitions/ComponentType" });
+    let vector_type = enum_schema::<Type>(|_| Some((json!("F32"), component_type.clone())));
+
+    let mut image =
+        enum_schema::<ImageInfo>(|_| Some((json!("image.png"), json!({ "type": "string" }))));
+    image["description"] = json!("Image used by the texture: either a path to be determined automatically (Auto), referenced by URI (Uri) or embedded (Embed).");
+
+    let layout = enum_schema::<LayoutInfo>(|variant| {
+        (variant == "Grid").then(|| {
+            let grid = json!({
+                "type": "object",
+                "properties": {
+                    "spacing": { "type": "number" },
+                    "columns": { "type": ["integer", "null"], "minimum": 1 }
+                },
+                "additionalProperties": false
+            });
+            (json!({}), grid)
+        })
+    });
+
+    let points_as = enum_schema::<PointsAs>(|variant| {
+        (variant == "Sphere").then(|| {
+            (
+                json!(1.0),
+                json!({ "type": "number", "exclusiveMinimum": 0 }),
+            )
+        })
+    });
+
+    let animation_grouping = enum_schema::<AnimationGrouping>(|variant| {
+        (variant == "Groups").then(|| {
+            let groups = json!({ "type": "object", "additionalProperties": { "type": "string" } });
+            (json!({}), groups)
+        })
+    });
+
+    let json = json!({
+        "ComponentType": unit_enum::<ComponentType>(),
+        "Type": vector_type,
+        "AttributeInfo": {
+            "type": "object",
+            "additionalProperties": { "$ref": "#/definitions/Type" }
+        },
+        "TextureInfo": {
+            "type": "object",
+            "properties": {
+                "image": image,
+                "wrap_s": unit_enum::<WrappingMode>(),
+                "wrap_t": unit_enum::<WrappingMode>(),
+                "mag_filter": unit_enum::<MagFilter>(),
+                "min_filter": unit_enum::<MinFilter>()
+            },
+            "required": ["image"],
+            "additionalProperties": false
+        },
+        "MaterialInfo": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "base_color": vector(4),
+                "base_texture": {
+                    "description": "Index of the texture and of the texture coordinate attribute used by the material.",
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "properties": {
+                                "index": { "type": "integer", "minimum": 0 },
+                                "texcoord": { "type": "integer", "minimum": 0 }
+                            },
+                            "required": ["index", "texcoord"],
+                            "additionalProperties": false
+                        },
+                        { "type": "null" }
+                    ]
+                },
+                "metallic": { "type": "number", "minimum": 0, "maximum": 1 },
+                "roughness": { "type": "number", "minimum": 0, "maximum": 1 }
+            },
+            "additionalProperties": false
+        },
+        "LightInfo": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "type": unit_enum::<LightType>(),
+                "color": vector(3),
+                "intensity": { "type": "number", "minimum": 0 },
+                "range": { "type": ["number", "null"], "exclusiveMinimum": 0 },
+                "inner_cone_angle": { "type": "number" },
+                "outer_cone_angle": { "type": "number" },
+                "translation": vector(3),
+                "rotation": vector(4)
+            },
+            "additionalProperties": false
+        },
+        "PointerAnimationInfo": {
+            "type": "object",
+            "properties": {
+                "pointer": { "type": "string", "pattern": "^/" },
+                "values": {
+                    "type": "object",
+                    "patternProperties": {
+                        "^[0-9]+$": {
+                            "oneOf": [
+                                { "type": "number" },
+                                {
+                                    "type": "array",
+                                    "items": { "type": "number" },
+                                    "minItems": 1,
+                                    "maxItems": 4
+                                }
+                            ]
+                        }
+                    },
+                    "additionalProperties": false
+                },
+                "step": { "type": "boolean" },
+                "group": { "type": "string" }
+            },
+            "required": ["pointer"],
+            "additionalProperties": false
+        },
+        "LayoutInfo": layout,
+        "PointsAs": points_as,
+        "AnimationGrouping": animation_grouping,
+        "AnimationOverride": {
+            "type": "object",
+            "properties": {
+                "step": { "type": ["integer", "null"], "minimum": 1 },
+                "material": { "type": ["string", "null"] },
+                "attributes": { "$ref": "#/definitions/AttributeInfo" },
+                "colors": { "$ref": "#/definitions/AttributeInfo" },
+                "texcoords": {
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/definitions/ComponentType" }
+                },
+                "translation": vector(3),
+                "rotation": vector(4),
+                "scale": vector(3)
+            },
+            "additionalProperties": false
+        },
+        "ExtrasInfo": {
+            "type": "object",
+            "properties": {
+                "asset": {},
+                "scene": {},
+                "nodes": { "type": "object" },
+                "materials": { "type": "object" }
+            },
+            "additionalProperties": false
+        }
+    });
+    match json {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+/// Adds the command line help and default values of the corresponding options
+/// to the given property schemas.
+#[cfg(feature = "cli")]
+fn describe_properties(properties: &mut [(&'static str, Value)]) {
+    let command = Config::command();
+    // Defaults are taken from a configuration built without any arguments.
+    let defaults = Config::try_parse_from(["gltfgen"])
+        .ok()
+        .and_then(|config| serde_json::to_value(config).ok())
+        .unwrap_or(Value::Null);
+
+    for (name, schema) in properties.iter_mut() {
+        let Value::Object(map) = schema else {
+            continue;
+        };
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_id().as_str().eq_ignore_ascii_case(name));
+        if let Some(help) = arg.and_then(|arg| arg.get_long_help().or_else(|| arg.get_help())) {
+            map.insert("description".into(), Value::String(help.to_string()));
+        }
+        if let Some(default) = defaults.get(*name) {
+            map.insert("default".into(), default.clone());
+        }
+    }
+}
+
+/// Builds a JSON Schema for configuration files in JSON format.
+pub fn config_schema() -> Value {
+    #[allow(unused_mut)]
+    let mut schemas = property_schemas();
+    #[cfg(feature = "cli")]
+    describe_properties(&mut schemas);
+
+    let mut properties = Map::new();
+    for (name, schema) in schemas {
+        properties.insert(name.to_string(), schema);
+    }
+    properties.insert(
+        "include".to_string(),
+        json!({
+            "description": "Configuration files this file is based on, relative to this file. Later files override earlier ones, and this file overrides all of them.",
+            "type": "array",
+            "items": { "type": "string" }
+        }),
+    );
+    properties.insert(
+        "profiles".to_string(),
+        json!({
+            "description": "Named sets of configuration overrides selected with '--profile'.",
+            "type": "object",
+            "additionalProperties": { "$ref": "#" }
+        }),
+    );
+
+    json!({
+        "$schema": SCHEMA_DRAFT,
+        "title": "gltfgen configuration",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+        "definitions": definitions()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    /// Checks `value` against the subset of JSON Schema used by [`config_schema`].
+    fn is_valid(value: &Value, schema: &Value, root: &Value) -> bool {
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            let target = match reference.strip_prefix("#/definitions/") {
+                Some(name) => &root["definitions"][name],
+                None => root,
+            };
+            return is_valid(value, target, root);
+        }
+        if let Some(Value::Array(options)) = schema.get("oneOf") {
+            return options.iter().filter(|s| is_valid(value, s, root)).count() == 1;
+        }
+        let has_type = |ty: &Value| match ty.as_str() {
+            Some("null") => value.is_null(),
+            Some("boolean") => value.is_boolean(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("number") => value.is_number(),
+            Some("string") => value.is_string(),
+            Some("array") => value.is_array(),
+            Some("object") => value.is_object(),
+            _ => false,
+        };
+        match schema.get("type") {
+            Some(Value::Array(types)) if !types.iter().any(has_type) => return false,
+            Some(ty @ Value::String(_)) if !has_type(ty) => return false,
+            _ => {}
+        }
+        if let Some(Value::Array(names)) = schema.get("enum") {
+            if !names.contains(value) {
+                return false;
+            }
+        }
+        if let Some(x) = value.as_f64() {
+            let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+            if bound("minimum").is_some_and(|min| x < min)
+                || bound("exclusiveMinimum").is_some_and(|min| x <= min)
+                || bound("maximum").is_some_and(|max| x > max)
+            {
+                return false;
+            }
+        }
+        let matches = |pattern: &str, s: &str| Regex::new(pattern).unwrap().is_match(s);
+        match value {
+            Value::String(s) => schema
+                .get("pattern")
+                .and_then(Value::as_str)
+                .map_or(true, |pattern| matches(pattern, s)),
+            Value::Array(items) => {
+                let len = |key: &str| schema.get(key).and_then(Value::as_u64);
+                let n = items.len() as u64;
+                len("minItems").map_or(true, |min| n >= min)
+                    && len("maxItems").map_or(true, |max| n <= max)
+                    && schema
+                        .get("items")
+                        .map_or(true, |s| items.iter().all(|item| is_valid(item, s, root)))
+            }
+            Value::Object(map) => {
+                let required = schema.get("required").and_then(Value::as_array);
+                if required.is_some_and(|keys| {
+                    keys.iter()
+                        .any(|key| !map.contains_key(key.as_str().unwrap()))
+                }) {
+                    return false;
+                }
+                map.iter().all(|(key, item)| {
+                    if let Some(s) = schema.get("properties").and_then(|p| p.get(key)) {
+                        return is_valid(item, s, root);
+                    }
+                    let pattern_property = schema
+                        .get("patternProperties")
+                        .and_then(Value::as_object)
+                        .and_then(|p| p.iter().find(|(pattern, _)| matches(pattern, key)));
+                    if let Some((_, s)) = pattern_property {
+                        return is_valid(item, s, root);
+                    }
+                    if let Some(names) = schema.get("propertyNames") {
+                        if !is_valid(&Value::String(key.clone()), names, root) {
+                            return false;
+                        }
+                    }
+                    match schema.get("additionalProperties") {
+                        Some(Value::Bool(allowed)) => *allowed,
+                        Some(s) => is_valid(item, s, root),
+                        None => true,
+                    }
+                })
+            }
+            _ => true,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn config_schema_covers_all_fields() {
+        let schema = config_schema();
+        let config = Config::try_parse_from(["gltfgen"]).unwrap();
+        let defaults = serde_json::to_value(config).unwrap();
+        let serde_json::Value::Object(fields) = &defaults else {
+            panic!("configuration is not serialized as an object");
+        };
+        for name in fields.keys() {
+            let property = &schema["properties"][name];
+            assert!(property.is_object(), "no schema for '{}'", name);
+            assert!(
+                property.get("description").is_some(),
+                "no description for '{}'",
+                name
+            );
+        }
+        // Only fields of the config and the include and profiles fields are described.
+        assert_eq!(
+            schema["properties"].as_object().unwrap().len(),
+            fields.len() + 2
+        );
+        assert!(is_valid(&defaults, &schema, &schema));
+    }
+
+    #[test]
+    fn config_schema_matches_deserialization() {
+        let schema = config_schema();
+        let config = json!({
+            "pattern": "./{cloth,body}_#.obj",
+            "output": "./out.gltf",
+            "fps": "30000/1001",
+            "fix_winding": "outward",
+            "triangulation": "EarClip",
+            "order": "natural",
+            "attributes": { "pressure": "f32", "velocity": { "vec3": "F32" } },
+            "texcoords": { "uv": "f32" },
+            "textures": [{
+                "image": { "Embed": "checker.png" },
+                "wrap_s": "mirrored_repeat",
+                "mag_filter": "None",
+                "min_filter": "LinearMipmapLinear"
+            }],
+            "lights": [{ "type": "spot", "intensity": 2.0 }],
+            "layout": { "grid": { "spacing": 2.0 } },
+            "points_as": { "Sphere": 0.1 },
+            "animation_groups": { "Groups": { "cloth": "sim" } },
+            "collision_proxy": "hull",
+            "gap_policy": "interpolate"
+        });
+        assert!(is_valid(&config, &schema, &schema));
+        serde_json::from_value::<crate::config::Config>(config).unwrap();
+
+        // Misspelled variants are rejected by both the schema and the deserializer.
+        let invalid = [
+            ("triangulation", json!("ear")),
+            ("gap_policy", json!("None")),
+            ("order", json!("alphabetical")),
+            ("points_as", json!("sphere")),
+            ("layout", json!({ "Grid": 1 })),
+            (
+                "textures",
+                json!([{ "image": { "Auto": "a.png" }, "mag_filter": "none" }]),
+            ),
+        ];
+        for (name, value) in invalid {
+            let config = json!({ "pattern": "./#.obj", "output": "./out.glb", name: value });
+            assert!(!is_valid(&config, &schema, &schema), "'{}' is valid", name);
+            assert!(serde_json::from_value::<crate::config::Config>(config).is_err());
+        }
+    }
+}
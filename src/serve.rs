@@ -0,0 +1,286 @@
+//! A minimal static HTTP server for reviewing exported glTF files.
+//!
+//! Only the output file, the buffers and images it references and a viewer
+//! page at `/` displaying the output are served. The page polls
+//! [`VERSION_PATH`] and reloads the model whenever the output file changes on
+//! disk, so the result of another export to the same file shows up without
+//! reloading the page.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::preview::viewer_page;
+use crate::uri::uri_to_path;
+use crate::Error;
+
+/// Path of the resource identifying the current version of the served output.
+pub const VERSION_PATH: &str = "/__version";
+
+/// Time a client may take to send its request or receive the response.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum number of bytes read from the request line and headers.
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+/// A server hosting an output file along with the files it references.
+pub struct Server {
+    listener: TcpListener,
+    root: PathBuf,
+    file_name: String,
+}
+
+impl Server {
+    /// Binds a server for `output` on all interfaces at the given port.
+    ///
+    /// If `port` is zero, a free port is chosen by the operating system.
+    pub fn bind(output: &Path, port: u16) -> Result<Server, Error> {
+        let file_name = output
+            .file_name()
+            .ok_or_else(|| Error::InvalidOutputPath(output.to_path_buf()))?
+            .to_string_lossy()
+            .into_owned();
+        let root = output
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf();
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(Error::Serve)?;
+        Ok(Server {
+            listener,
+            root,
+            file_name,
+        })
+    }
+
+    /// Port the server is listening on.
+    pub fn port(&self) -> Result<u16, Error> {
+        Ok(self.listener.local_addr().map_err(Error::Serve)?.port())
+    }
+
+    /// Responds to requests on separate threads until the process is terminated.
+    pub fn run(self) {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    log::warn!("Failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+            let root = self.root.clone();
+            let file_name = self.file_name.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = handle(stream, &root, &file_name) {
+                    log::debug!("Failed to respond to request: {}", err);
+                }
+            });
+        }
+    }
+}
+
+/// Responds to a single request for the output `file_name` in `root`, a file
+/// it references or its viewer page.
+fn handle(stream: TcpStream, root: &Path, file_name: &str) -> std::io::Result<()> {
+    // Idle or slow clients would otherwise hold on to their thread forever.
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    let (status, content_type, body) = if method != "GET" && method != "HEAD" {
+        (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method not allowed".to_vec(),
+        )
+    } else if path == "/" || path == "/index.html" {
+        let page = viewer_page(
+            file_name,
+            &format!("/{}", file_name),
+            &reload_script(file_name),
+        );
+        ("200 OK", "text/html; charset=utf-8", page.into_bytes())
+    } else if path == VERSION_PATH {
+        let version = version(&root.join(file_name));
+        ("200 OK", "text/plain", version.into_bytes())
+    } else {
+        let file = resolve(root, file_name, path)
+            .and_then(|path| Some((content_type(&path), std::fs::read(path).ok()?)));
+        match file {
+            Some((content_type, data)) => ("200 OK", content_type, data),
+            None => ("404 Not Found", "text/plain", b"Not found".to_vec()),
+        }
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
+
+/// Maps a request path to the output `file_name` in `root` or one of the files it references.
+///
+/// Paths are canonicalized, so symbolic links leading outside of `root` are rejected.
+fn resolve(root: &Path, file_name: &str, path: &str) -> Option<PathBuf> {
+    let root = root.canonicalize().ok()?;
+    let requested = root
+        .join(percent_decode(path.trim_start_matches('/'))?)
+        .canonicalize()
+        .ok()?;
+    if !requested.starts_with(&root) || !requested.is_file() {
+        return None;
+    }
+    let output = root.join(file_name).canonicalize().ok()?;
+    let served = requested == output
+        || referenced_files(&output)
+            .iter()
+            .filter_map(|path| root.join(path).canonicalize().ok())
+            .any(|path| path == requested);
+    served.then_some(requested)
+}
+
+/// Relative paths of the buffers and images referenced by the glTF file at `path`.
+///
+/// The file is read on every request, since it may be rewritten while the server is running.
+fn referenced_files(path: &Path) -> Vec<PathBuf> {
+    let Ok(gltf) = gltf::Gltf::open(path) else {
+        return Vec::new();
+    };
+    let root = gltf.document.as_json();
+    root.buffers
+        .iter()
+        .filter_map(|buffer| buffer.uri.as_deref())
+        .chain(root.images.iter().filter_map(|image| image.uri.as_deref()))
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(uri_to_path)
+        .collect()
+}
+
+/// Decodes percent encoded bytes in a URL path.
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "glb" => "model/gltf-binary",
+        "gltf" => "model/gltf+json",
+        "bin" => "application/octet-stream",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "ktx2" => "image/ktx2",
+        "html" => "text/html; charset=utf-8",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Identifies the current contents of the file at `path` by its size and modification time.
+fn version(path: &Path) -> String {
+    std::fs::metadata(path)
+        .map(|meta| {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |time| time.as_nanos());
+            format!("{}-{}", meta.len(), modified)
+        })
+        .unwrap_or_default()
+}
+
+/// Script reloading the model whenever its version changes.
+fn reload_script(file_name: &str) -> String {
+    format!(
+        r#"<script>
+  let version = null;
+  setInterval(async () => {{
+    try {{
+      const next = await (await fetch("{version}", {{ cache: "no-store" }})).text();
+      if (version !== null && next !== version) {{
+        document.querySelector("model-viewer").src = {src} + "?v=" + encodeURIComponent(next);
+      }}
+      version = next;
+    }} catch (err) {{}}
+  }}, 1000);
+</script>
+"#,
+        version = VERSION_PATH,
+        src = serde_json::Value::from(format!("/{}", file_name)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serve_output() {
+        let server = Server::bind(Path::new("./assets/box_rotate_expected.glb"), 0).unwrap();
+        let port = server.port().unwrap();
+        std::thread::spawn(move || server.run());
+
+        let get = |path: &str| {
+            let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).unwrap();
+            response
+        };
+        let contains = |haystack: &[u8], needle: &[u8]| {
+            haystack
+                .windows(needle.len())
+                .any(|window| window == needle)
+        };
+
+        let page = get("/");
+        assert!(page.starts_with(b"HTTP/1.1 200 OK"));
+        assert!(contains(&page, b"src=\"/box_rotate_expected.glb\""));
+        let glb = get("/box_rotate_expected.glb");
+        assert!(contains(&glb, b"Content-Type: model/gltf-binary"));
+        assert!(contains(&glb, b"\r\n\r\nglTF"));
+        // Files outside of the output directory are not served.
+        assert!(get("/../Cargo.toml").starts_with(b"HTTP/1.1 404 Not Found"));
+        // Neither are files next to the output that it doesn't reference.
+        assert!(get("/box_rotate_1.vtk").starts_with(b"HTTP/1.1 404 Not Found"));
+        assert!(!contains(&glb, b"Access-Control-Allow-Origin"));
+    }
+}
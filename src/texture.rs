@@ -126,6 +126,7 @@ impl From<WrappingMode> for Checked<json::texture::WrappingMode> {
 }
 
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TextureInfo {
     pub image: ImageInfo,
     #[serde(default)]
@@ -153,9 +154,12 @@ impl Default for ImageInfo {
 }
 
 impl std::str::FromStr for TextureInfo {
-    type Err = ron::de::Error;
+    type Err = crate::lint::ParseError;
     fn from_str(input: &str) -> Result<TextureInfo, Self::Err> {
-        ron::de::from_str::<TextureInfo>(input).map_err(Self::Err::from)
+        crate::lint::parse_ron(
+            input,
+            "(image: Embed(\"./texture.png\"), wrap_s: Repeat, wrap_t: Repeat, mag_filter: Linear, min_filter: Linear)",
+        )
     }
 }
 
@@ -0,0 +1,189 @@
+//! Frame rates and time spans given as numbers, fractions or with units.
+//!
+//! Broadcast rates like 29.97 fps can't be expressed exactly as a float time
+//! step, so both frame rates and time spans also accept fractions (e.g.
+//! `30000/1001` or `1/30`). Time spans may additionally carry a unit suffix
+//! (`ms`, `s`, `min` or `h`), as in `5s` or `40ms`.
+
+use serde::{Deserialize, Serialize};
+
+/// Error produced when parsing a frame rate or a time span.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid {kind} '{input}': expected a positive number or fraction{units}")]
+pub struct ParseTimeError {
+    kind: &'static str,
+    input: String,
+    units: &'static str,
+}
+
+/// Parses a positive number or a fraction of two positive numbers.
+fn parse_ratio(input: &str) -> Option<f64> {
+    let value = match input.split_once('/') {
+        Some((num, den)) => num.trim().parse::<f64>().ok()? / den.trim().parse::<f64>().ok()?,
+        None => input.trim().parse::<f64>().ok()?,
+    };
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+/// Number of frames per second.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameRate(pub f64);
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        FrameRate(24.0)
+    }
+}
+
+impl FrameRate {
+    /// Time between consecutive frames in seconds.
+    pub fn time_step(self) -> f64 {
+        1.0 / self.0
+    }
+}
+
+impl std::str::FromStr for FrameRate {
+    type Err = ParseTimeError;
+    fn from_str(input: &str) -> Result<FrameRate, Self::Err> {
+        parse_ratio(input).map(FrameRate).ok_or(ParseTimeError {
+            kind: "frame rate",
+            input: input.to_string(),
+            units: "",
+        })
+    }
+}
+
+/// A span of time in seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Seconds(pub f64);
+
+impl std::str::FromStr for Seconds {
+    type Err = ParseTimeError;
+    fn from_str(input: &str) -> Result<Seconds, Self::Err> {
+        let trimmed = input.trim();
+        // Check "ms" before "s" since it also ends in "s".
+        let (value, scale) = [("ms", 1e-3), ("min", 60.0), ("s", 1.0), ("h", 3600.0)]
+            .into_iter()
+            .find_map(|(unit, scale)| Some((trimmed.strip_suffix(unit)?, scale)))
+            .unwrap_or((trimmed, 1.0));
+        parse_ratio(value)
+            .map(|value| Seconds(value * scale))
+            .ok_or(ParseTimeError {
+                kind: "time",
+                input: input.to_string(),
+                units: " with an optional unit (ms, s, min or h)",
+            })
+    }
+}
+
+/// A number or a string to be parsed, as accepted in configuration files.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn parse<T: std::str::FromStr<Err = ParseTimeError>, E: serde::de::Error>(
+        self,
+        wrap: fn(f64) -> T,
+    ) -> Result<T, E> {
+        match self {
+            NumberOrString::Number(value) if value.is_finite() && value > 0.0 => Ok(wrap(value)),
+            NumberOrString::Number(value) => value.to_string().parse().map_err(E::custom),
+            NumberOrString::String(input) => input.parse().map_err(E::custom),
+        }
+    }
+}
+
+impl Serialize for FrameRate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Whole frame rates are written as integers as they were before
+        // fractional rates were supported.
+        if self.0.fract() == 0.0 && self.0 <= f64::from(u32::MAX) {
+            serializer.serialize_u32(self.0 as u32)
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FrameRate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NumberOrString::deserialize(deserializer)?.parse(FrameRate)
+    }
+}
+
+impl Serialize for Seconds {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Seconds {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NumberOrString::deserialize(deserializer)?.parse(Seconds)
+    }
+}
+
+/// Computes the time step between consecutive frame numbers.
+///
+/// A `duration` takes precedence and stretches the `frame_span` frame numbers
+/// between the first and the last frame over the given time. Otherwise the
+/// `time_step` is used if given, and the frame rate if not, each divided
+/// into `substeps` for sub-stepped sequences.
+pub fn frame_time_step(
+    fps: FrameRate,
+    time_step: Option<Seconds>,
+    duration: Option<Seconds>,
+    frame_span: u32,
+    substeps: u32,
+) -> f32 {
+    let dt = match (duration, time_step) {
+        (Some(duration), _) if frame_span > 0 => {
+            return (duration.0 / f64::from(frame_span)) as f32
+        }
+        (_, Some(time_step)) => time_step.0,
+        _ => fps.time_step(),
+    };
+    (dt / f64::from(substeps.max(1))) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_parsing() {
+        let fps: FrameRate = "30000/1001".parse().unwrap();
+        assert_eq!(fps.time_step(), 1001.0 / 30000.0);
+        assert_eq!("29.97".parse::<FrameRate>().unwrap(), FrameRate(29.97));
+        assert!("0".parse::<FrameRate>().is_err());
+        assert!("fast".parse::<FrameRate>().is_err());
+
+        assert_eq!("1/30".parse::<Seconds>().unwrap(), Seconds(1.0 / 30.0));
+        assert_eq!("40ms".parse::<Seconds>().unwrap(), Seconds(0.04));
+        assert_eq!("5s".parse::<Seconds>().unwrap(), Seconds(5.0));
+        assert_eq!("2.5min".parse::<Seconds>().unwrap(), Seconds(150.0));
+        assert!("-1s".parse::<Seconds>().is_err());
+
+        // Numbers and strings are both accepted in configuration files, and
+        // whole frame rates are written back as integers.
+        let fps: FrameRate = serde_json::from_str("24").unwrap();
+        assert_eq!(serde_json::to_string(&fps).unwrap(), "24");
+        let fps: FrameRate = serde_json::from_str("\"30000/1001\"").unwrap();
+        assert_eq!(fps, FrameRate(30000.0 / 1001.0));
+
+        // The duration takes precedence over the time step and the frame rate.
+        let dt = frame_time_step(fps, Some(Seconds(0.25)), Some(Seconds(5.0)), 10, 1);
+        assert_eq!(dt, 0.5);
+        assert_eq!(frame_time_step(fps, Some(Seconds(0.25)), None, 10, 1), 0.25);
+        // Substeps divide the time step.
+        assert_eq!(frame_time_step(fps, Some(Seconds(0.5)), None, 10, 4), 0.125);
+        assert_eq!(
+            frame_time_step(fps, None, None, 10, 1),
+            (1001.0 / 30000.0) as f32
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Triangulation of quads and n-gons loaded from polygon meshes.
+//!
+//! meshx splits every polygon into a fan of triangles around its first vertex,
+//! which produces slivers, or even overlapping triangles, on concave polygons.
+//! Polygons are re-triangulated here after that conversion, keeping face and
+//! face-vertex attributes attached to the same polygon corners.
+
+use meshx::attrib::Attrib;
+use meshx::mesh::vertex_positions::VertexPositions;
+use meshx::mesh::{PolyMesh, TriMesh};
+use meshx::topology::{FaceIndex, FaceVertexIndex};
+
+use crate::config::{Triangulation, POLYGON_ID_ATTRIB_NAME};
+
+/// Vertex indices of the polygons of the given mesh.
+pub fn polygons<T: meshx::io::Real>(polymesh: &PolyMesh<T>) -> Vec<Vec<usize>> {
+    polymesh.face_iter().map(|face| face.to_vec()).collect()
+}
+
+/// Records the polygon each triangle came from in a [`POLYGON_ID_ATTRIB_NAME`] face attribute.
+///
+/// The first `num_surface_tris` triangles are taken from the surface of a
+/// tetrahedral mesh and are their own polygons. The remaining triangles are
+/// expected to be split from the given `polygons` in order, as done by
+/// [`retriangulate`]. An existing attribute loaded with the mesh is kept.
+pub fn insert_polygon_ids(
+    trimesh: &mut TriMesh<f32>,
+    num_surface_tris: usize,
+    polygons: &[Vec<usize>],
+) {
+    let ids: Vec<u32> = (0..num_surface_tris)
+        .chain(polygons.iter().enumerate().flat_map(|(i, poly)| {
+            std::iter::repeat(num_surface_tris + i).take(poly.len().saturating_sub(2))
+        }))
+        .map(|id| id as u32)
+        .collect();
+    if ids.len() != trimesh.indices.len() {
+        log::debug!("Triangles don't match the loaded polygons, skipping polygon ids");
+        return;
+    }
+    // Fails only if the mesh already has polygon ids, which are used instead.
+    let _ = trimesh.insert_attrib_data::<u32, FaceIndex>(POLYGON_ID_ATTRIB_NAME, ids);
+}
+
+/// Re-triangulates the polygons of a mesh fan-triangulated by meshx.
+///
+/// `polygons` are the polygons the triangles of `trimesh` were created from,
+/// as returned by [`polygons`]. The mesh is returned unchanged with
+/// [`Triangulation::Fan`], or if its triangles don't match the polygons.
+pub fn retriangulate(
+    mut trimesh: TriMesh<f32>,
+    polygons: &[Vec<usize>],
+    triangulation: Triangulation,
+) -> TriMesh<f32> {
+    if triangulation == Triangulation::Fan || polygons.iter().all(|poly| poly.len() <= 3) {
+        return trimesh;
+    }
+    let num_tris: usize = polygons
+        .iter()
+        .map(|poly| poly.len().saturating_sub(2))
+        .sum();
+    if num_tris != trimesh.indices.len() {
+        log::debug!("Triangles don't match the loaded polygons, keeping fan triangulation");
+        return trimesh;
+    }
+
+    let positions = trimesh.vertex_positions().to_vec();
+    // Face-vertex index in the fan triangulation of each face-vertex of the new triangulation.
+    let mut face_vertex_order = Vec::with_capacity(3 * num_tris);
+    let mut first_tri = 0;
+    for poly in polygons.iter() {
+        let n = poly.len().saturating_sub(2);
+        let old_tris = first_tri..first_tri + n;
+        first_tri += n;
+        let corners: Vec<_> = poly.iter().map(|&v| positions[v]).collect();
+        let new_tris = if n <= 1 {
+            None
+        } else {
+            triangulate(&corners, triangulation)
+        };
+        let Some(new_tris) = new_tris else {
+            face_vertex_order.extend(3 * old_tris.start..3 * old_tris.end);
+            continue;
+        };
+        for tri in new_tris.iter() {
+            for &corner in tri.iter() {
+                let vertex = poly[corner];
+                let old = old_tris
+                    .clone()
+                    .flat_map(|old| (0..3).map(move |c| (old, c)))
+                    .find(|&(old, c)| trimesh.indices[old][c] == vertex);
+                let Some((old, old_c)) = old else {
+                    log::debug!(
+                        "Triangles don't match the loaded polygons, keeping fan triangulation"
+                    );
+                    return trimesh;
+                };
+                face_vertex_order.push(3 * old + old_c);
+            }
+        }
+    }
+
+    // Rewrite triangles from the collected face-vertex order.
+    let old_indices: Vec<[usize; 3]> = trimesh.indices.as_slice().to_vec();
+    for (tri, fv) in trimesh
+        .indices
+        .as_mut_slice()
+        .iter_mut()
+        .zip(face_vertex_order.chunks_exact(3))
+    {
+        *tri = [
+            old_indices[fv[0] / 3][fv[0] % 3],
+            old_indices[fv[1] / 3][fv[1] % 3],
+            old_indices[fv[2] / 3][fv[2] % 3],
+        ];
+    }
+    for attrib in trimesh.attrib_dict_mut::<FaceVertexIndex>().values_mut() {
+        *attrib = attrib.duplicate_with(|new, old| {
+            for &fv in face_vertex_order.iter() {
+                new.push_cloned(old.get(fv));
+            }
+        });
+    }
+    trimesh
+}
+
+/// Triangles of the polygon with the given corners as indices into `corners`.
+///
+/// Returns `None` if the polygon is degenerate or self-intersecting, in which
+/// case the fan triangulation is kept.
+fn triangulate(corners: &[[f32; 3]], triangulation: Triangulation) -> Option<Vec<[usize; 3]>> {
+    let points = project(corners)?;
+    let mut tris = ear_clip(&points)?;
+    if triangulation == Triangulation::Delaunay {
+        flip_to_delaunay(&points, &mut tris);
+    }
+    Some(tris)
+}
+
+/// Projects the polygon onto the coordinate plane it is most aligned with.
+///
+/// The projection is oriented so that the polygon winds counter-clockwise.
+fn project(corners: &[[f32; 3]]) -> Option<Vec<[f64; 2]>> {
+    // Newell's method gives the normal of concave and slightly non-planar polygons.
+    let mut normal = [0.0_f64; 3];
+    for (i, p) in corners.iter().enumerate() {
+        let q = corners[(i + 1) % corners.len()];
+        let (p, q) = (p.map(f64::from), q.map(f64::from));
+        normal[0] += (p[1] - q[1]) * (p[2] + q[2]);
+        normal[1] += (p[2] - q[2]) * (p[0] + q[0]);
+        normal[2] += (p[0] - q[0]) * (p[1] + q[1]);
+    }
+    let axis = (0..3).max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))?;
+    if normal[axis] == 0.0 || !normal[axis].is_finite() {
+        return None;
+    }
+    // The remaining axes in cyclic order keep the winding about the dominant axis.
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let flip = if normal[axis] < 0.0 { -1.0 } else { 1.0 };
+    Some(
+        corners
+            .iter()
+            .map(|p| [f64::from(p[u]), flip * f64::from(p[v])])
+            .collect(),
+    )
+}
+
+/// Twice the signed area of the triangle `abc`, positive if counter-clockwise.
+fn cross(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Splits a counter-clockwise polygon into triangles by repeatedly clipping ears.
+fn ear_clip(points: &[[f64; 2]]) -> Option<Vec<[usize; 3]>> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut tris = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let [a, b, c] = [
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            ];
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            cross(pa, pb, pc) > 0.0
+                && remaining.iter().all(|&p| {
+                    p == a
+                        || p == b
+                        || p == c
+                        || cross(pa, pb, points[p]) < 0.0
+                        || cross(pb, pc, points[p]) < 0.0
+                        || cross(pc, pa, points[p]) < 0.0
+                })
+        })?;
+        tris.push([
+            remaining[(ear + m - 1) % m],
+            remaining[ear],
+            remaining[(ear + 1) % m],
+        ]);
+        remaining.remove(ear);
+    }
+    tris.push([remaining[0], remaining[1], remaining[2]]);
+    Some(tris)
+}
+
+/// Smallest angle of the triangle `abc` in radians.
+fn min_angle(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    let angle = |p: [f64; 2], q: [f64; 2], r: [f64; 2]| {
+        let (u, v) = ([q[0] - p[0], q[1] - p[1]], [r[0] - p[0], r[1] - p[1]]);
+        (u[0] * v[1] - u[1] * v[0])
+            .abs()
+            .atan2(u[0] * v[0] + u[1] * v[1])
+    };
+    angle(a, b, c).min(angle(b, c, a)).min(angle(c, a, b))
+}
+
+/// Flips diagonals shared by two triangles while that increases their smallest angle.
+///
+/// Diagonals never cross the polygon boundary, so this approximates the
+/// constrained Delaunay triangulation of the polygon.
+fn flip_to_delaunay(points: &[[f64; 2]], tris: &mut [[usize; 3]]) {
+    // Each flip strictly increases the smallest angle of a pair, but bound the
+    // number of passes in case of rounding.
+    for _ in 0..tris.len() * tris.len() {
+        let mut flipped = false;
+        for t in 0..tris.len() {
+            for u in t + 1..tris.len() {
+                // Find an edge a->b of `t` traversed as b->a by `u`.
+                let shared = (0..3).find_map(|i| {
+                    let (a, b) = (tris[t][i], tris[t][(i + 1) % 3]);
+                    let j = (0..3).find(|&j| tris[u][j] == b && tris[u][(j + 1) % 3] == a)?;
+                    Some((a, b, tris[t][(i + 2) % 3], tris[u][(j + 2) % 3]))
+                });
+                let Some((a, b, c, d)) = shared else {
+                    continue;
+                };
+                let [pa, pb, pc, pd] = [points[a], points[b], points[c], points[d]];
+                // The quad a, d, b, c must be convex for the flipped diagonal c-d to lie inside.
+                if cross(pa, pd, pc) <= 0.0 || cross(pd, pb, pc) <= 0.0 {
+                    continue;
+                }
+                let before = min_angle(pa, pb, pc).min(min_angle(pb, pa, pd));
+                let after = min_angle(pa, pd, pc).min(min_angle(pd, pb, pc));
+                if after > before + 1e-9 {
+                    tris[t] = [a, d, c];
+                    tris[u] = [d, b, c];
+                    flipped = true;
+                }
+            }
+        }
+        if !flipped {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangulation() {
+        let triangulate = |positions: Vec<[f32; 3]>, triangulation| {
+            let polymesh = meshx::PolyMesh::new(positions, &[4, 0, 1, 2, 3]);
+            let faces = polygons(&polymesh);
+            let trimesh = meshx::TriMesh::from(polymesh);
+            retriangulate(trimesh, &faces, triangulation)
+        };
+        let area = |trimesh: &meshx::TriMesh<f32>, [a, b, c]: [usize; 3]| {
+            let [p, q, r] = [a, b, c].map(|v| trimesh.vertex_positions[v]);
+            (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])
+        };
+
+        // A concave quad whose fan around the first vertex overlaps itself.
+        let dart = vec![
+            [0.0, 4.0, 0.0],
+            [0.0, 0.0, 0.0],
+            [4.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ];
+        let fan = triangulate(dart.clone(), Triangulation::Fan);
+        assert!(fan.indices.iter().any(|&tri| area(&fan, tri) < 0.0));
+        let clipped = triangulate(dart, Triangulation::EarClip);
+        assert_eq!(clipped.indices.len(), 2);
+        assert!(clipped.indices.iter().all(|&tri| area(&clipped, tri) > 0.0));
+
+        // The short diagonal of a thin rhombus is preferred.
+        let rhombus = vec![
+            [0.0, -1.0, 0.0],
+            [3.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [-3.0, 0.0, 0.0],
+        ];
+        let delaunay = triangulate(rhombus, Triangulation::Delaunay);
+        assert!(delaunay
+            .indices
+            .iter()
+            .all(|tri| tri.contains(&0) && tri.contains(&2)));
+        assert!(delaunay
+            .indices
+            .iter()
+            .all(|&tri| area(&delaunay, tri) > 0.0));
+    }
+}
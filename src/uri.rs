@@ -0,0 +1,101 @@
+//! Relative URIs of files written or read alongside a glTF document.
+//!
+//! glTF requires URIs to be valid according to RFC 3986, so file names with
+//! spaces, non-ASCII characters or reserved characters are percent-encoded.
+//! File names are encoded byte by byte, which on Unix also covers names that
+//! aren't valid UTF-8. On other platforms such names are converted lossily,
+//! since there is no portable way to express them in a URI.
+
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+/// Bytes of the given file name as they appear in a URI.
+fn name_bytes(name: &OsStr) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        name.as_bytes().to_vec()
+    }
+    #[cfg(not(unix))]
+    {
+        name.to_string_lossy().into_owned().into_bytes()
+    }
+}
+
+/// File name with the given bytes decoded from a URI.
+fn name_from_bytes(bytes: Vec<u8>) -> OsString {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(bytes)
+    }
+    #[cfg(not(unix))]
+    {
+        OsString::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// Builds the URI of a file with the given name in the directory of the glTF document.
+pub fn relative_uri(file_name: &OsStr) -> String {
+    let mut uri = String::from("./");
+    for byte in name_bytes(file_name) {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Converts a relative URI into a path by decoding percent-encoded bytes.
+///
+/// Malformed escapes are kept as they are.
+pub fn uri_to_path(uri: &str) -> PathBuf {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(name_from_bytes(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_uris() {
+        use std::path::Path;
+
+        let uri = relative_uri(OsStr::new("out file é.bin"));
+        assert_eq!(uri, "./out%20file%20%C3%A9.bin");
+        assert_eq!(uri_to_path(&uri), Path::new("./out file é.bin"));
+        assert_eq!(uri_to_path("a%zz%4"), Path::new("a%zz%4"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let name = OsStr::from_bytes(b"f\xff.bin");
+            let uri = relative_uri(name);
+            assert_eq!(uri, "./f%FF.bin");
+            assert_eq!(uri_to_path(&uri), Path::new("./").join(name));
+        }
+    }
+}
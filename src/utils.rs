@@ -1,7 +1,12 @@
+#[cfg(feature = "cli")]
 use indicatif::{ProgressBar, ProgressStyle};
 use log;
 use regex::Regex;
 
+use crate::error::Error;
+use crate::pattern::{NON_SEPARATOR, SEPARATOR};
+use crate::progress::ProgressSink;
+
 #[macro_export]
 macro_rules! log {
     ($msgs:ident; $($arg:tt)*) => ({
@@ -19,50 +24,121 @@ macro_rules! log {
 }
 
 enum MessageType {
+    Error,
     Warn,
     Info,
 }
 
-pub fn print_warnings(messages: Vec<(usize, String)>) {
+pub fn print_errors(messages: Vec<(usize, String)>) {
+    print_messages(messages, MessageType::Error);
+}
+/// Prints the given warnings and returns how many there were, counting repeats.
+pub fn print_warnings(messages: Vec<(usize, String)>) -> usize {
+    let count = message_count(&messages);
     print_messages(messages, MessageType::Warn);
+    count
+}
+/// Sends warnings to the given progress sink and returns how many there were, counting repeats.
+pub fn report_warnings(sink: &dyn ProgressSink, messages: Vec<(usize, String)>) -> usize {
+    let count = message_count(&messages);
+    for msg in format_messages(messages) {
+        sink.warning(&msg);
+    }
+    count
 }
 pub fn print_info(messages: Vec<(usize, String)>) {
     print_messages(messages, MessageType::Info);
 }
 
-fn print_messages(messages: Vec<(usize, String)>, msg_type: MessageType) {
-    for (count, warning) in messages {
-        let msg = if count > 1 {
-            format!("({}) {}", count, warning)
+fn message_count(messages: &[(usize, String)]) -> usize {
+    messages.iter().map(|(count, _)| count).sum()
+}
+
+fn format_messages(messages: Vec<(usize, String)>) -> impl Iterator<Item = String> {
+    messages.into_iter().map(|(count, msg)| {
+        if count > 1 {
+            format!("({}) {}", count, msg)
         } else {
-            format!("{}", warning)
-        };
+            msg
+        }
+    })
+}
+
+fn print_messages(messages: Vec<(usize, String)>, msg_type: MessageType) {
+    for msg in format_messages(messages) {
         match msg_type {
+            MessageType::Error => log::error!("{}", msg),
             MessageType::Warn => log::warn!("{}", msg),
             MessageType::Info => log::info!("{}", msg),
         }
     }
 }
 
-pub fn glob_to_regex(glob: &str) -> Regex {
+/// Converts a glob pattern with frame number placeholders into a regular expression.
+///
+/// Returns an error if the pattern has unbalanced braces or alternatives.
+pub fn glob_to_regex(glob: &str, case_insensitive: bool) -> Result<Regex, Error> {
     let mut regex = String::from("^");
 
     let mut prev_c = None;
+    // Depth of nested '{...}' name groups and '@(...)' alternatives.
+    let mut braces = 0;
+    let mut alternatives = 0;
     let mut glob_iter = glob.chars().peekable();
     while let Some(c) = glob_iter.next() {
         match c {
             '#' => {
                 // Special character indicating a frame number digit
-                regex.push_str("(?P<frame>[0-9]+)");
+                match frame_width(&mut glob_iter) {
+                    Some(width) => regex.push_str(&format!("(?P<frame>[0-9]{{{}}})", width)),
+                    None => regex.push_str("(?P<frame>[0-9]+)"),
+                }
+                // '#.#' additionally matches an optional fractional part of the frame number.
+                let mut rest = glob_iter.clone();
+                if rest.next() == Some('.') && rest.next() == Some('#') {
+                    glob_iter = rest;
+                    match frame_width(&mut glob_iter) {
+                        Some(width) => {
+                            regex.push_str(&format!("(?:\\.(?P<substep>[0-9]{{{}}}))?", width))
+                        }
+                        None => regex.push_str("(?:\\.(?P<substep>[0-9]+))?"),
+                    }
+                }
+            }
+            '@' if glob_iter.peek() == Some(&'(') => {
+                // Alternatives that are not part of the name.
+                glob_iter.next();
+                alternatives += 1;
+                regex.push_str("(?:");
+            }
+            ')' if alternatives > 0 => {
+                alternatives -= 1;
+                regex.push(')');
             }
+            '|' if alternatives > 0 => regex.push('|'),
+            ',' if braces > 0 => regex.push('|'),
             // Escape special characters
             '$' | '^' | '+' | '.' | '(' | ')' | '=' | '!' | '|' => {
                 regex.push('\\');
                 regex.push(c);
             }
-            '{' => regex.push('('),
-            '}' => regex.push(')'),
-            '?' => regex.push('.'),
+            '{' => {
+                braces += 1;
+                regex.push('(');
+            }
+            '}' => {
+                braces -= 1;
+                regex.push(')');
+            }
+            '\\' if !cfg!(windows) => {
+                // Escaped characters are matched literally.
+                let escaped = glob_iter.next().unwrap_or('\\');
+                regex.push_str(&regex::escape(&escaped.to_string()));
+                prev_c = Some(escaped);
+                continue;
+            }
+            '/' | '\\' => regex.push_str(SEPARATOR),
+            '?' => regex.push_str(NON_SEPARATOR),
             '*' => {
                 // Check if there are multiple consecutive ** in the pattern.
                 let mut count = 1;
@@ -70,19 +146,27 @@ pub fn glob_to_regex(glob: &str) -> Regex {
                     count += 1;
                     glob_iter.next();
                 }
-                let next_c = glob_iter.peek();
+                let next_c = glob_iter.peek().copied();
+                let is_separator =
+                    |c: Option<char>| c == Some('/') || (cfg!(windows) && c == Some('\\'));
 
                 if count > 1
-                    && (next_c == Some(&'/') || next_c.is_none())
-                    && (prev_c == Some('/') || prev_c.is_none())
+                    && (is_separator(next_c) || next_c.is_none())
+                    && (is_separator(prev_c) || prev_c.is_none())
                 {
                     // Multiple * detected
-                    // match zero or more path segments
-                    regex.push_str("?:[^/]*?:/|$*");
-                    glob_iter.next(); // consume '/' if any.
+                    if next_c.is_none() {
+                        // match everything including separators
+                        regex.push_str(".*");
+                    } else {
+                        // match zero or more path segments
+                        regex.push_str(&format!("(?:{}*{})*", NON_SEPARATOR, SEPARATOR));
+                        glob_iter.next(); // consume the separator.
+                    }
                 } else {
                     // Single * detected
-                    regex.push_str("[^/]*"); // match one path segment
+                    // match within one path segment
+                    regex.push_str(&format!("{}*", NON_SEPARATOR));
                 }
             }
             _ => regex.push(c),
@@ -92,7 +176,141 @@ pub fn glob_to_regex(glob: &str) -> Regex {
 
     regex.push('$');
 
-    Regex::new(&regex).expect("ERROR: Failed to convert glob to regular expression")
+    regex::RegexBuilder::new(&regex)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|err| Error::InvalidPattern(glob.to_string(), err))
+}
+
+/// Consumes a frame number width given in braces (e.g. '{4}') following a '#'.
+fn frame_width(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+    let rest = chars.clone().collect::<String>();
+    let (width, _) = rest.strip_prefix('{')?.split_once('}')?;
+    let parsed = width
+        .parse()
+        .ok()
+        .filter(|_| width.bytes().all(|b| b.is_ascii_digit()))?;
+    for _ in 0..width.len() + 2 {
+        chars.next();
+    }
+    Some(parsed)
+}
+
+/// Replace frame numbers with a given width like '#{4}' in the pattern with
+/// the equivalent glob pattern matching the given number of digits.
+pub fn expand_frame_widths(pattern: &str) -> String {
+    let re = Regex::new(r"#\{([0-9]+)\}").unwrap();
+    re.replace_all(pattern, |caps: &regex::Captures| {
+        "[0-9]".repeat(caps[1].parse().unwrap_or(1))
+    })
+    .into_owned()
+}
+
+/// Expands alternatives in the pattern into separate patterns.
+///
+/// Alternatives are given either as comma separated strings in braces (e.g.
+/// '{cloth,body}') or as '|' separated strings in '@(...)' (e.g.
+/// '@(cloth|body)'). Braces without commas are left as is.
+pub fn expand_alternatives(pattern: &str) -> Vec<String> {
+    let bytes = pattern.as_bytes();
+    let mut start = 0;
+    while start < bytes.len() {
+        let (open, close, separator, skip) = match bytes[start] {
+            b'\\' => {
+                start += 2;
+                continue;
+            }
+            b'{' => (start, '}', ',', 1),
+            b'@' if bytes.get(start + 1) == Some(&b'(') => (start, ')', '|', 2),
+            _ => {
+                start += 1;
+                continue;
+            }
+        };
+        let Some(len) = pattern[open + skip..].find(close) else {
+            break;
+        };
+        let group = &pattern[open + skip..open + skip + len];
+        if !group.contains(separator) {
+            start += 1;
+            continue;
+        }
+        let prefix = &pattern[..open];
+        let suffix = &pattern[open + skip + len + 1..];
+        // Keep braces so that the alternatives remain a named group.
+        let (left, right) = if skip == 1 { ("{", "}") } else { ("", "") };
+        return group
+            .split(separator)
+            .flat_map(|alternative| {
+                expand_alternatives(&format!(
+                    "{}{}{}{}{}",
+                    prefix, left, alternative, right, suffix
+                ))
+            })
+            .collect();
+    }
+    vec![pattern.to_string()]
+}
+
+/// Replace non-ASCII letters in the glob pattern with '?'.
+///
+/// The glob crate only ignores the case of ASCII characters, so in case
+/// insensitive mode non-ASCII letters are matched by the regular expression
+/// produced by `glob_to_regex` instead.
+pub fn relax_non_ascii_letters(pattern: &str) -> String {
+    pattern
+        .chars()
+        .map(|c| {
+            if !c.is_ascii() && c.is_alphabetic() {
+                '?'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Compares strings such that runs of digits are ordered by their numeric value.
+///
+/// For example, "frame2" comes before "frame10".
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                let (x_trimmed, y_trimmed) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ord = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
 }
 
 /// Remove braces from the pattern.
@@ -113,6 +331,28 @@ pub fn remove_braces(pattern: &str) -> String {
     out_pattern
 }
 
+/// Formats a number of bytes using binary prefixes, e.g. "1.50 KiB".
+#[derive(Copy, Clone, Debug)]
+pub struct HumanBytes(pub u64);
+
+impl std::fmt::Display for HumanBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const PREFIXES: [&str; 8] = ["Ki", "Mi", "Gi", "Ti", "Pi", "Ei", "Zi", "Yi"];
+        let mut amount = self.0 as f64;
+        let mut prefix = 0;
+        while amount >= 1024.0 && prefix < PREFIXES.len() {
+            amount /= 1024.0;
+            prefix += 1;
+        }
+        if prefix == 0 {
+            write!(f, "{:.0} B", amount)
+        } else {
+            write!(f, "{:.2} {}B", amount, PREFIXES[prefix - 1])
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
 pub fn new_progress_bar(quiet: bool, len: usize) -> ProgressBar {
     if !quiet {
         ProgressBar::new(len as u64).with_style(
@@ -126,6 +366,7 @@ pub fn new_progress_bar(quiet: bool, len: usize) -> ProgressBar {
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn new_progress_bar_file(quiet: bool, num_bytes: usize) -> ProgressBar {
     if !quiet {
         ProgressBar::new(num_bytes as u64).with_style(
@@ -139,6 +380,7 @@ pub fn new_progress_bar_file(quiet: bool, num_bytes: usize) -> ProgressBar {
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn new_spinner(quiet: bool) -> ProgressBar {
     let spinner = if !quiet {
         ProgressBar::new_spinner().with_style(
@@ -153,3 +395,70 @@ pub fn new_spinner(quiet: bool) -> ProgressBar {
     spinner.enable_steady_tick(std::time::Duration::from_millis(100));
     spinner
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_number_width() {
+        let regex = glob_to_regex("{box}_#{4}.obj", false).unwrap();
+        let caps = regex.captures("box_0012.obj").unwrap();
+        assert_eq!(&caps["frame"], "0012");
+        assert!(!regex.is_match("box_12.obj"));
+        assert!(!regex.is_match("box_00012.obj"));
+
+        // Braces not following a '#' are still names.
+        assert_eq!(
+            &glob_to_regex("{box}_#.obj", false)
+                .unwrap()
+                .captures("box_1.obj")
+                .unwrap()[1],
+            "box"
+        );
+
+        assert_eq!(
+            expand_frame_widths("{box}_#{3}.obj"),
+            "{box}_[0-9][0-9][0-9].obj"
+        );
+    }
+
+    #[test]
+    fn pattern_alternatives() {
+        assert_eq!(
+            expand_alternatives("{cloth,body}_#.@(vtk|obj)"),
+            vec![
+                "{cloth}_#.vtk",
+                "{cloth}_#.obj",
+                "{body}_#.vtk",
+                "{body}_#.obj"
+            ]
+        );
+        assert_eq!(expand_alternatives("{box}_#.vtk"), vec!["{box}_#.vtk"]);
+
+        let regex = glob_to_regex("{cloth,body}_#.@(vtk|obj)", false).unwrap();
+        let caps = regex.captures("body_3.obj").unwrap();
+        assert_eq!(&caps[1], "body");
+        assert_eq!(&caps["frame"], "3");
+        // Only the braces form a named group.
+        assert_eq!(caps.len(), 3);
+        assert!(!regex.is_match("hair_3.obj"));
+        assert!(!regex.is_match("body_3.ply"));
+
+        // Unbalanced groups are reported instead of panicking.
+        assert!(matches!(
+            glob_to_regex("{cloth_#.obj", false),
+            Err(Error::InvalidPattern(..))
+        ));
+    }
+
+    #[test]
+    fn natural_order() {
+        use std::cmp::Ordering;
+        assert_eq!(natural_cmp("frame2.obj", "frame10.obj"), Ordering::Less);
+        assert_eq!(natural_cmp("frame10.obj", "frame10.obj"), Ordering::Equal);
+        assert_eq!(natural_cmp("frame010.obj", "frame9.obj"), Ordering::Greater);
+        assert_eq!(natural_cmp("a10b2", "a10b10"), Ordering::Less);
+        assert_eq!(natural_cmp("b1", "a2"), Ordering::Greater);
+    }
+}
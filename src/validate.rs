@@ -0,0 +1,472 @@
+//! Structural validation of generated glTF documents.
+
+use gltf::json;
+use json::accessor::{ComponentType, GenericComponentType};
+use json::validation::{Checked::Valid, USize64, Validate};
+
+/// Validates the given glTF document against its binary buffer data.
+///
+/// In addition to the index and schema checks provided by `gltf-json`, this
+/// verifies that accessors fit into their buffer views, that data is properly
+/// aligned, that recorded min/max bounds match the data, that vertex indices
+/// are within range of the referenced vertex attributes, and that morph target
+/// weights have one value per morph target of the animated mesh.
+///
+/// Returns a list of errors found, which is empty if the document is valid.
+pub fn validate(root: &json::Root, buffers: &[&[u8]]) -> Vec<(usize, String)> {
+    let mut errors = Vec::new();
+
+    root.validate(root, json::Path::new, &mut |path, err| {
+        log!(errors; "{}: {}", path(), err);
+    });
+
+    // Index errors are reported above, and would make further checks unreliable.
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    for (i, buffer) in root.buffers.iter().enumerate() {
+        let actual = buffers.get(i).map_or(0, |b| b.len()) as u64;
+        if buffer.byte_length.0 > actual {
+            log!(errors;
+                "buffers[{}]: byte length ({}) exceeds the size of the buffer data ({})",
+                i, buffer.byte_length.0, actual
+            );
+        }
+    }
+
+    for (i, view) in root.buffer_views.iter().enumerate() {
+        let end = offset(view.byte_offset) + view.byte_length.0;
+        let buffer_len = root.buffers[view.buffer.value()].byte_length.0;
+        if end > buffer_len {
+            log!(errors;
+                "bufferViews[{}]: range ends at byte {} past the end of buffers[{}] ({} bytes)",
+                i, end, view.buffer.value(), buffer_len
+            );
+        }
+    }
+
+    for (i, acc) in root.accessors.iter().enumerate() {
+        validate_accessor(root, buffers, i, acc, &mut errors);
+    }
+
+    for (m, mesh) in root.meshes.iter().enumerate() {
+        for (p, prim) in mesh.primitives.iter().enumerate() {
+            let Some(indices) = prim.indices else {
+                continue;
+            };
+            let Some(positions) = prim.attributes.get(&Valid(json::mesh::Semantic::Positions))
+            else {
+                log!(errors; "meshes[{}].primitives[{}]: missing POSITION attribute", m, p);
+                continue;
+            };
+            let num_vertices = root.accessors[positions.value()].count.0;
+            if let Some(values) = read_accessor(root, buffers, indices.value()) {
+                if let Some(max) = values.into_iter().map(|v| v as u64).max() {
+                    if max >= num_vertices {
+                        log!(errors;
+                            "meshes[{}].primitives[{}]: vertex index {} is out of range for {} vertices",
+                            m, p, max, num_vertices
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    for m in 0..root.meshes.len() {
+        for err in morph_target_errors(root, m) {
+            log!(errors; "meshes[{}]: {}", m, err);
+        }
+    }
+
+    errors
+}
+
+/// Reads the glTF file at `path` along with its buffers and validates it.
+pub fn validate_file(path: &std::path::Path) -> Result<Vec<(usize, String)>, gltf::Error> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::open(path)?;
+    let buffers = gltf::import_buffers(&document, path.parent(), blob)?;
+    let buffers: Vec<&[u8]> = buffers.iter().map(|b| b.0.as_slice()).collect();
+    Ok(validate(document.as_json(), &buffers))
+}
+
+/// Returns the indices of accessors whose data does not start at a multiple of
+/// their component size, including the indices and values of sparse accessors.
+pub(crate) fn misaligned_accessors(root: &json::Root) -> Vec<usize> {
+    let view_offset = |view: json::Index<json::buffer::View>| {
+        root.buffer_views
+            .get(view.value())
+            .map_or(0, |view| offset(view.byte_offset))
+    };
+    let is_aligned = |view, byte_offset: u64, component_type: ComponentType| {
+        (view_offset(view) + byte_offset).is_multiple_of(component_type.size() as u64)
+    };
+    root.accessors
+        .iter()
+        .enumerate()
+        .filter(|(_, acc)| {
+            let Valid(GenericComponentType(component_type)) = acc.component_type else {
+                return false;
+            };
+            let data_aligned = acc
+                .buffer_view
+                .is_none_or(|view| is_aligned(view, offset(acc.byte_offset), component_type));
+            let sparse_aligned = acc.sparse.as_ref().is_none_or(|sparse| {
+                let indices_aligned = match sparse.indices.component_type {
+                    Valid(json::accessor::IndexComponentType(index_type)) => is_aligned(
+                        sparse.indices.buffer_view,
+                        sparse.indices.byte_offset.0,
+                        index_type,
+                    ),
+                    _ => true,
+                };
+                indices_aligned
+                    && is_aligned(
+                        sparse.values.buffer_view,
+                        sparse.values.byte_offset.0,
+                        component_type,
+                    )
+            });
+            !data_aligned || !sparse_aligned
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the indices of meshes whose primitives disagree on the number of
+/// morph targets, or whose default or animated weights don't match it.
+pub(crate) fn mismatched_morph_targets(root: &json::Root) -> Vec<usize> {
+    (0..root.meshes.len())
+        .filter(|&m| !morph_target_errors(root, m).is_empty())
+        .collect()
+}
+
+/// Checks that all primitives of a mesh have the same number of morph targets,
+/// and that every set of weights applied to the mesh has one weight per target.
+fn morph_target_errors(root: &json::Root, m: usize) -> Vec<String> {
+    let mesh = &root.meshes[m];
+    let counts: Vec<usize> = mesh
+        .primitives
+        .iter()
+        .map(|prim| prim.targets.as_ref().map_or(0, Vec::len))
+        .collect();
+    let Some(&num_targets) = counts.first() else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+    if counts.iter().any(|&count| count != num_targets) {
+        errors.push(format!(
+            "primitives have different numbers of morph targets {:?}",
+            counts
+        ));
+        return errors;
+    }
+    if let Some(weights) = mesh.weights.as_ref() {
+        if weights.len() != num_targets {
+            errors.push(format!(
+                "{} default weights for {} morph targets",
+                weights.len(),
+                num_targets
+            ));
+        }
+    }
+    for (a, animation) in root.animations.iter().enumerate() {
+        for (c, channel) in animation.channels.iter().enumerate() {
+            if channel.target.path != Valid(json::animation::Property::MorphTargetWeights) {
+                continue;
+            }
+            let node = root.nodes.get(channel.target.node.value());
+            if node.and_then(|node| node.mesh).map(|mesh| mesh.value()) != Some(m) {
+                continue;
+            }
+            let Some(sampler) = animation.samplers.get(channel.sampler.value()) else {
+                continue;
+            };
+            let count = |acc: json::Index<json::Accessor>| {
+                root.accessors.get(acc.value()).map_or(0, |acc| acc.count.0)
+            };
+            // Cubic spline samplers hold an in-tangent, value and out-tangent per keyframe.
+            let values_per_key = match sampler.interpolation {
+                Valid(json::animation::Interpolation::CubicSpline) => 3,
+                _ => 1,
+            };
+            let expected = count(sampler.input) * values_per_key * num_targets as u64;
+            let actual = count(sampler.output);
+            if actual != expected {
+                errors.push(format!(
+                    "animations[{}].channels[{}] has {} weights, expected {} for {} morph targets",
+                    a, c, actual, expected, num_targets
+                ));
+            }
+        }
+    }
+    errors
+}
+
+fn offset(byte_offset: Option<USize64>) -> u64 {
+    byte_offset.map_or(0, |USize64(o)| o)
+}
+
+fn validate_accessor(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    i: usize,
+    acc: &json::Accessor,
+    errors: &mut Vec<(usize, String)>,
+) {
+    let (Valid(GenericComponentType(component_type)), Valid(type_)) =
+        (&acc.component_type, &acc.type_)
+    else {
+        return;
+    };
+    let Some(view_index) = acc.buffer_view else {
+        return;
+    };
+    let view = &root.buffer_views[view_index.value()];
+    let component_size = component_type.size() as u64;
+    let element_size = component_size * type_.multiplicity() as u64;
+    let stride = view.byte_stride.map_or(element_size, |s| s.0 as u64);
+
+    if acc.count.0 > 0 {
+        let end = offset(acc.byte_offset) + stride * (acc.count.0 - 1) + element_size;
+        if end > view.byte_length.0 {
+            log!(errors;
+                "accessors[{}]: data ends at byte {} past the end of bufferViews[{}] ({} bytes)",
+                i, end, view_index.value(), view.byte_length.0
+            );
+            return;
+        }
+    }
+
+    let start = offset(view.byte_offset) + offset(acc.byte_offset);
+    if !start.is_multiple_of(component_size) || !stride.is_multiple_of(component_size) {
+        log!(errors;
+            "accessors[{}]: data is not aligned to its component size ({} bytes)",
+            i, component_size
+        );
+    }
+
+    // Check the recorded bounds against the actual data.
+    if acc.sparse.is_some() {
+        return;
+    }
+    let (Some(min), Some(max)) = (&acc.min, &acc.max) else {
+        return;
+    };
+    let Some(values) = read_accessor(root, buffers, i) else {
+        return;
+    };
+    let n = type_.multiplicity();
+    let mut actual_min = vec![f64::INFINITY; n];
+    let mut actual_max = vec![f64::NEG_INFINITY; n];
+    for element in values.chunks(n) {
+        for (c, &v) in element.iter().enumerate() {
+            actual_min[c] = actual_min[c].min(v);
+            actual_max[c] = actual_max[c].max(v);
+        }
+    }
+    for (label, recorded, actual) in [("min", min, actual_min), ("max", max, actual_max)] {
+        let recorded: Option<Vec<f64>> = recorded
+            .as_array()
+            .and_then(|a| a.iter().map(|v| v.as_f64()).collect());
+        let matches = recorded.as_ref().is_some_and(|r| {
+            r.len() == n
+                && r.iter()
+                    .zip(actual.iter())
+                    .all(|(r, a)| (r - a).abs() <= 1e-6 * r.abs().max(a.abs()).max(1.0))
+        });
+        if !matches {
+            log!(errors;
+                "accessors[{}]: recorded {} {:?} does not match the data {:?}",
+                i, label, recorded.unwrap_or_default(), actual
+            );
+        }
+    }
+}
+
+/// Reads all components of the given accessor as `f64` values.
+///
+/// Sparse accessors are resolved by substituting the sparse values into the
+/// base data, which is all zeros if the accessor has no buffer view.
+///
+/// Returns `None` if the data doesn't fit into its buffer.
+pub(crate) fn read_accessor(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    index: usize,
+) -> Option<Vec<f64>> {
+    let acc = &root.accessors[index];
+    let (Valid(GenericComponentType(component_type)), Valid(type_)) =
+        (&acc.component_type, &acc.type_)
+    else {
+        return None;
+    };
+    let n = type_.multiplicity();
+    let count = acc.count.0 as usize;
+    let mut values = match acc.buffer_view {
+        Some(view) => read_elements(
+            root,
+            buffers,
+            view.value(),
+            offset(acc.byte_offset),
+            *component_type,
+            n,
+            count,
+        )?,
+        None => vec![0.0; count * n],
+    };
+
+    if let Some(sparse) = acc.sparse.as_ref() {
+        let Valid(index_type) = sparse.indices.component_type else {
+            return None;
+        };
+        let num = sparse.count.0 as usize;
+        let indices = read_elements(
+            root,
+            buffers,
+            sparse.indices.buffer_view.value(),
+            sparse.indices.byte_offset.0,
+            index_type.0,
+            1,
+            num,
+        )?;
+        let sparse_values = read_elements(
+            root,
+            buffers,
+            sparse.values.buffer_view.value(),
+            sparse.values.byte_offset.0,
+            *component_type,
+            n,
+            num,
+        )?;
+        for (&i, element) in indices.iter().zip(sparse_values.chunks(n)) {
+            let i = i as usize;
+            values.get_mut(i * n..(i + 1) * n)?.copy_from_slice(element);
+        }
+    }
+    Some(values)
+}
+
+/// Reads the vertex indices stored in the given accessor.
+///
+/// Components are read as `f64`, which represents every `u32` exactly, so
+/// indices are never rounded unlike values converted to `f32`.
+///
+/// Returns `None` if the accessor doesn't hold unsigned integer scalars or
+/// the data doesn't fit into its buffer.
+pub(crate) fn read_indices(root: &json::Root, buffers: &[&[u8]], index: usize) -> Option<Vec<u32>> {
+    let acc = &root.accessors[index];
+    let (Valid(GenericComponentType(component_type)), Valid(json::accessor::Type::Scalar)) =
+        (&acc.component_type, &acc.type_)
+    else {
+        return None;
+    };
+    if !matches!(
+        component_type,
+        ComponentType::U8 | ComponentType::U16 | ComponentType::U32
+    ) {
+        return None;
+    }
+    let values = read_accessor(root, buffers, index)?;
+    Some(values.into_iter().map(|i| i as u32).collect())
+}
+
+/// Reads `count` elements of `n` components each from the given buffer view.
+fn read_elements(
+    root: &json::Root,
+    buffers: &[&[u8]],
+    view_index: usize,
+    byte_offset: u64,
+    component_type: ComponentType,
+    n: usize,
+    count: usize,
+) -> Option<Vec<f64>> {
+    let view = &root.buffer_views[view_index];
+    let buffer = buffers.get(view.buffer.value())?;
+    let component_size = component_type.size();
+    let element_size = component_size * n;
+    let stride = view.byte_stride.map_or(element_size, |s| s.0);
+    let start = (offset(view.byte_offset) + byte_offset) as usize;
+
+    let mut values = Vec::with_capacity(count * n);
+    for e in 0..count {
+        let element_start = start + e * stride;
+        for c in 0..n {
+            let b = element_start + c * component_size;
+            let bytes = buffer.get(b..b + component_size)?;
+            values.push(read_component(component_type, bytes));
+        }
+    }
+    Some(values)
+}
+
+fn read_component(component_type: ComponentType, bytes: &[u8]) -> f64 {
+    match component_type {
+        ComponentType::I8 => bytes[0] as i8 as f64,
+        ComponentType::U8 => bytes[0] as f64,
+        ComponentType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+        ComponentType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+        ComponentType::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+        ComponentType::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gltf::Gltf;
+
+    #[test]
+    fn indices_beyond_f32_precision() {
+        // 2^24 + 1 is the smallest integer that can't be represented by an f32.
+        let indices = [0u32, (1 << 24) + 1, u32::MAX];
+        let data: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let root = json::Root {
+            buffer_views: vec![json::buffer::View {
+                buffer: json::Index::new(0),
+                byte_length: data.len().into(),
+                byte_offset: None,
+                byte_stride: None,
+                name: None,
+                target: None,
+                extensions: Default::default(),
+                extras: Default::default(),
+            }],
+            accessors: vec![json::Accessor {
+                buffer_view: Some(json::Index::new(0)),
+                byte_offset: None,
+                count: indices.len().into(),
+                component_type: Valid(GenericComponentType(ComponentType::U32)),
+                extensions: Default::default(),
+                extras: Default::default(),
+                type_: Valid(json::accessor::Type::Scalar),
+                min: None,
+                max: None,
+                name: None,
+                normalized: false,
+                sparse: None,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(read_indices(&root, &[&data], 0), Some(indices.to_vec()));
+    }
+
+    #[test]
+    fn validate_output() {
+        let gltf = Gltf::open("./assets/box_rotate_expected.glb").unwrap();
+        let blob = gltf.blob.clone().unwrap();
+        let mut root = gltf.document.into_json();
+        assert!(validate(&root, &[&blob]).is_empty());
+
+        // Corrupt the recorded bounds of the first accessor.
+        root.accessors[0].max = Some(serde_json::json!([1.0e6, 1.0e6, 1.0e6]));
+        let errors = validate(&root, &[&blob]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].1.starts_with("accessors[0]: recorded max"));
+
+        // Truncated buffers are detected.
+        let errors = validate(&root, &[&blob[..blob.len() / 2]]);
+        assert!(errors[0].1.starts_with("buffers[0]: byte length"));
+    }
+}
@@ -0,0 +1,77 @@
+//! JavaScript bindings for converting mesh sequences in the browser.
+//!
+//! Build the module with `cargo rustc --lib --release --target
+//! wasm32-unknown-unknown --no-default-features --features wasm --crate-type
+//! cdylib` and generate the JavaScript glue with `wasm-bindgen`. Meshes are
+//! passed in as file contents and the result is returned as GLB bytes, so no
+//! file system access is needed:
+//!
+//! ```js
+//! const converter = new Converter('{"fps": 30}');
+//! converter.addFrame("cloth", 0, bytes0, "obj");
+//! converter.addFrame("cloth", 1, bytes1, "obj");
+//! const glb = converter.toGlb();
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::bindings::Options;
+use crate::export::{build_gltf, to_glb};
+use crate::mesh::Mesh;
+
+/// Converts frames given as file contents into an animated glTF.
+#[wasm_bindgen]
+pub struct Converter {
+    options: Options,
+    meshes: Vec<(String, u32, Mesh)>,
+}
+
+#[wasm_bindgen]
+impl Converter {
+    /// Creates a converter with the given JSON options, or the defaults if none are given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: Option<String>) -> Result<Converter, JsError> {
+        let options = match options {
+            Some(options) => serde_json::from_str(&options)?,
+            None => Options::default(),
+        };
+        Ok(Converter {
+            options,
+            meshes: Vec::new(),
+        })
+    }
+
+    /// Adds a frame from the contents of a mesh file with the given extension.
+    #[wasm_bindgen(js_name = addFrame)]
+    pub fn add_frame(
+        &mut self,
+        name: &str,
+        frame: u32,
+        bytes: &[u8],
+        extension: &str,
+    ) -> Result<(), JsError> {
+        let load_config = self.options.load_config();
+        let mesh = crate::load_mesh_from_bytes(bytes, extension, load_config).ok_or_else(|| {
+            JsError::new(&format!(
+                "Failed to load frame {} of '{}' as '{}'",
+                frame, name, extension
+            ))
+        })?;
+        self.meshes.push((name.to_string(), frame, mesh));
+        Ok(())
+    }
+
+    /// Number of frames added so far.
+    #[wasm_bindgen(js_name = numFrames)]
+    pub fn num_frames(&self) -> usize {
+        self.meshes.len()
+    }
+
+    /// Builds the animated glTF from all added frames and returns it as GLB bytes.
+    #[wasm_bindgen(js_name = toGlb)]
+    pub fn to_glb(self) -> Result<Vec<u8>, JsError> {
+        let (meshes, export_config) = self.options.prepare(self.meshes, None);
+        let (document, data) = build_gltf(meshes, export_config)?;
+        Ok(to_glb(&document, data)?)
+    }
+}
@@ -0,0 +1,209 @@
+//! Consistent orientation of triangles in meshes with mixed winding.
+//!
+//! Neighbouring triangles are consistently oriented when they traverse their
+//! shared edge in opposite directions. Triangles are visited across manifold
+//! edges starting from the first triangle of each connected component, and
+//! those disagreeing with the triangle they were reached from are flipped.
+//! Each component then keeps the orientation of the majority of its triangles,
+//! or the one enclosing a positive volume.
+
+use std::collections::{HashMap, VecDeque};
+
+use meshx::attrib::Attrib;
+use meshx::mesh::vertex_positions::VertexPositions;
+use meshx::mesh::TriMesh;
+use meshx::topology::{FaceEdgeIndex, FaceVertexIndex};
+
+use crate::config::WindingFix;
+
+/// Flips triangles of the given mesh to orient them consistently.
+///
+/// Face-vertex and face-edge attributes are flipped along with their
+/// triangles. Returns the number of flipped triangles.
+pub fn fix_winding(trimesh: &mut TriMesh<f32>, fix: WindingFix) -> usize {
+    if fix == WindingFix::Off {
+        return 0;
+    }
+    let flips = find_flips(trimesh, fix == WindingFix::Outward);
+    let num_flipped = flips.iter().filter(|&&flip| flip).count();
+    if num_flipped == 0 {
+        return 0;
+    }
+
+    // Triangle [a, b, c] becomes [a, c, b], and its edges ab, bc, ca become ac, cb, ba.
+    let order = |corners: [usize; 3]| -> Vec<usize> {
+        flips
+            .iter()
+            .enumerate()
+            .flat_map(|(f, &flip)| {
+                let [i, j, k] = if flip { corners } else { [0, 1, 2] };
+                [3 * f + i, 3 * f + j, 3 * f + k]
+            })
+            .collect()
+    };
+    for (tri, &flip) in trimesh.indices.as_mut_slice().iter_mut().zip(flips.iter()) {
+        if flip {
+            tri.swap(1, 2);
+        }
+    }
+    let vertex_order = order([0, 2, 1]);
+    for attrib in trimesh.attrib_dict_mut::<FaceVertexIndex>().values_mut() {
+        *attrib = attrib.duplicate_with(|new, old| {
+            for &fv in vertex_order.iter() {
+                new.push_cloned(old.get(fv));
+            }
+        });
+    }
+    let edge_order = order([2, 1, 0]);
+    for attrib in trimesh.attrib_dict_mut::<FaceEdgeIndex>().values_mut() {
+        *attrib = attrib.duplicate_with(|new, old| {
+            for &fe in edge_order.iter() {
+                new.push_cloned(old.get(fe));
+            }
+        });
+    }
+    log::debug!(
+        "Flipped {} of {} triangles to fix their winding",
+        num_flipped,
+        trimesh.indices.len()
+    );
+    num_flipped
+}
+
+/// Determines which triangles to flip for a consistent orientation.
+fn find_flips(trimesh: &TriMesh<f32>, outward: bool) -> Vec<bool> {
+    let tris = trimesh.indices.as_slice();
+
+    // Triangles adjacent to each undirected edge, and whether they traverse it from the
+    // smaller to the larger vertex.
+    let mut edge_faces: HashMap<[usize; 2], Vec<(usize, bool)>> = HashMap::new();
+    for (f, tri) in tris.iter().enumerate() {
+        for k in 0..3 {
+            let (a, b) = (tri[k], tri[(k + 1) % 3]);
+            if a != b {
+                edge_faces
+                    .entry([a.min(b), a.max(b)])
+                    .or_default()
+                    .push((f, a < b));
+            }
+        }
+    }
+    let mut neighbours = vec![Vec::new(); tris.len()];
+    for faces in edge_faces.values() {
+        // Orientation is ambiguous across non-manifold edges.
+        if let &[(f, f_forward), (g, g_forward)] = faces.as_slice() {
+            // Consistent neighbours traverse the edge in opposite directions.
+            let opposite = f_forward != g_forward;
+            neighbours[f].push((g, opposite));
+            neighbours[g].push((f, opposite));
+        }
+    }
+
+    let mut flips = vec![false; tris.len()];
+    let mut visited = vec![false; tris.len()];
+    let mut queue = VecDeque::new();
+    for start in 0..tris.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        let mut component = Vec::new();
+        while let Some(f) = queue.pop_front() {
+            component.push(f);
+            for &(g, opposite) in neighbours[f].iter() {
+                if !visited[g] {
+                    visited[g] = true;
+                    flips[g] = flips[f] == opposite;
+                    queue.push_back(g);
+                }
+            }
+        }
+
+        let flip_all = if outward {
+            signed_volume(trimesh, &component, &flips) < 0.0
+        } else {
+            2 * component.iter().filter(|&&f| flips[f]).count() > component.len()
+        };
+        if flip_all {
+            for &f in component.iter() {
+                flips[f] = !flips[f];
+            }
+        }
+    }
+    flips
+}
+
+/// Six times the signed volume enclosed by the given triangles after flipping.
+fn signed_volume(trimesh: &TriMesh<f32>, faces: &[usize], flips: &[bool]) -> f64 {
+    let positions = trimesh.vertex_positions();
+    faces
+        .iter()
+        .map(|&f| {
+            let [a, b, c] = trimesh.indices[f];
+            let (b, c) = if flips[f] { (c, b) } else { (b, c) };
+            let [p, q, r] = [a, b, c].map(|v| positions[v].map(f64::from));
+            p[0] * (q[1] * r[2] - q[2] * r[1])
+                + p[1] * (q[2] * r[0] - q[0] * r[2])
+                + p[2] * (q[0] * r[1] - q[1] * r[0])
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fix_winding() {
+        // An outward facing unit cube.
+        let positions: Vec<[f32; 3]> = (0..8)
+            .map(|i| [(i >> 2) as f32, ((i >> 1) & 1) as f32, (i & 1) as f32])
+            .collect();
+        let quads = [
+            [0, 1, 3, 2],
+            [4, 6, 7, 5],
+            [0, 4, 5, 1],
+            [2, 3, 7, 6],
+            [0, 2, 6, 4],
+            [1, 5, 7, 3],
+        ];
+        let outward: Vec<[usize; 3]> = quads
+            .iter()
+            .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .collect();
+        // Face-vertex attributes follow the corners of flipped triangles.
+        let fix = |tris: Vec<[usize; 3]>, fix| {
+            let corners: Vec<u32> = tris.iter().flatten().map(|&v| v as u32).collect();
+            let mut trimesh = meshx::TriMesh::new(positions.clone(), tris);
+            trimesh
+                .insert_attrib_data::<u32, FaceVertexIndex>("corner", corners)
+                .unwrap();
+            let num_flipped = fix_winding(&mut trimesh, fix);
+            let corners = trimesh
+                .attrib_as_slice::<u32, FaceVertexIndex>("corner")
+                .unwrap();
+            assert!(trimesh
+                .indices
+                .iter()
+                .flatten()
+                .zip(corners)
+                .all(|(&v, &corner)| v as u32 == corner));
+            (num_flipped, trimesh.indices.as_slice().to_vec())
+        };
+
+        let mut mixed = outward.clone();
+        mixed[1].swap(1, 2);
+        mixed[4].swap(1, 2);
+        assert_eq!(fix(mixed.clone(), WindingFix::Off), (0, mixed.clone()));
+        assert_eq!(fix(mixed, WindingFix::Consistent), (2, outward.clone()));
+
+        // Consistently inward facing triangles are only flipped to face outward.
+        let inward: Vec<_> = outward.iter().map(|&[a, b, c]| [a, c, b]).collect();
+        assert_eq!(
+            fix(inward.clone(), WindingFix::Consistent),
+            (0, inward.clone())
+        );
+        assert_eq!(fix(inward, WindingFix::Outward), (12, outward));
+    }
+}
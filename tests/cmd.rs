@@ -22,6 +22,7 @@ fn box_triangulated() -> Result<(), Error> {
         .arg("-r") // reverse polygon orientation
         .arg("-a")
         .arg("{\"pressure\": f32}") // Suppress reading normals
+        .arg("-vv") // Show info messages
         .assert()
         .stderr(predicate::str::contains(stderr))
         .success();
@@ -46,6 +47,7 @@ fn box_rotate_simple() -> Result<(), Error> {
         .arg("{}") // Suppress reading texture coordinates
         .arg("-a")
         .arg("{}") // Suppress reading normals
+        .arg("-vv") // Show info messages
         .assert()
         .stderr(predicate::str::contains(stderr))
         .success();
@@ -204,6 +206,7 @@ fn tet() -> Result<(), Error> {
         .arg("./assets/{tet}_#.vtk")
         .arg("-a")
         .arg("{\"pressure\": f32}")
+        .arg("-vv") // Show info messages
         .assert()
         .stderr(predicate::str::contains(warning))
         .success();
@@ -225,6 +228,7 @@ fn tet_and_tri() -> Result<(), Error> {
         .arg("./assets/{tet_and_tri}_#.vtk")
         .arg("-a")
         .arg("{\"pressure\": f32}")
+        .arg("-vv") // Show info messages
         .assert()
         .stderr(predicate::str::contains(warning))
         .success();
@@ -241,7 +245,7 @@ fn multi() -> Result<(), Error> {
     // Capture both tet and box_rotate animations in one glb file.
     let mut cmd = Command::cargo_bin("gltfgen").unwrap();
     let warning1 = "Material ID was found but no materials were specified.";
-    let warning2 = "Path 'assets/box_triangulated.vtk' skipped since regex '^assets/([^/]*)_(?P<frame>[0-9]+)\\.vtk$' did not match.";
+    let warning2 = "Path 'assets/box_triangulated.vtk' skipped since regex '^assets[/\\\\]([^/\\\\]*)_(?P<frame>[0-9]+)\\.vtk$' did not match.";
     let artifact = "./tests/artifacts/multi.glb";
     cmd.arg("-o")
         .arg(artifact)
@@ -251,6 +255,7 @@ fn multi() -> Result<(), Error> {
         .arg("{}") // suppress loading texture coords
         .arg("-a")
         .arg("{\"pressure\": f32}") // Ignore normals
+        .arg("-vv") // Show info messages
         .assert()
         .stderr(predicate::str::contains(warning1).and(predicate::str::contains(warning2)))
         .success();
@@ -261,3 +266,1139 @@ fn multi() -> Result<(), Error> {
     assert_eq_gltf_with_bytes(&expected, &actual);
     Ok(())
 }
+
+#[test]
+fn asset_metadata() -> Result<(), Error> {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let artifact = "./tests/artifacts/asset_metadata.glb";
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("--copyright")
+        .arg("2024 Example Studio")
+        .arg("--generator-suffix")
+        .arg("(pipeline 1.2)")
+        .arg("--license")
+        .arg("CC-BY-4.0")
+        .assert()
+        .success();
+
+    let actual = Gltf::open(artifact)?;
+    let asset = &actual.document.as_json().asset;
+
+    assert_eq!(asset.copyright.as_deref(), Some("2024 Example Studio"));
+    assert!(asset
+        .generator
+        .as_ref()
+        .unwrap()
+        .ends_with(" (pipeline 1.2)"));
+    assert_eq!(
+        asset.extras.as_ref().unwrap().get(),
+        "{\"license\":\"CC-BY-4.0\"}"
+    );
+    Ok(())
+}
+
+#[test]
+fn dry_run() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let artifact = "./tests/artifacts/dry_run.glb";
+    let _ = std::fs::remove_file(artifact);
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--dry-run")
+        .assert()
+        .stdout(predicate::str::contains(
+            "\"box_rotate\": 12 frame(s) (1-12)",
+        ))
+        .stdout(predicate::str::contains("\"pressure\": f32"))
+        .success();
+
+    assert!(!std::path::Path::new(artifact).exists());
+}
+
+#[test]
+fn info() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("info")
+        .arg("./assets/box_rotate_expected.glb")
+        .assert()
+        .stdout(predicate::str::contains(
+            "meshes: 1 (1 primitives, 11 morph targets)",
+        ))
+        .stdout(predicate::str::contains("animations: 1"))
+        .success();
+}
+
+#[test]
+fn merge() -> Result<(), Error> {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let artifact = "./tests/artifacts/merge.glb";
+    cmd.arg("merge")
+        .arg("-o")
+        .arg(artifact)
+        .arg("./assets/box_rotate_expected.glb")
+        .arg("./assets/box_rotate_expected.glb")
+        .arg("--animations")
+        .arg("Concatenate")
+        .assert()
+        .stderr(b"" as &[u8]) // No errors
+        .success();
+
+    let (doc, _, _) = gltf::import(artifact)?;
+    assert_eq!(doc.nodes().count(), 2);
+    assert_eq!(doc.scenes().next().unwrap().nodes().count(), 2);
+    // Identical materials and textures are stored once.
+    assert_eq!(doc.materials().count(), 1);
+    assert_eq!(doc.textures().count(), 1);
+    assert_eq!(doc.images().count(), 1);
+    // A single animation plays both inputs in sequence, toggling their visibility.
+    assert_eq!(doc.animations().count(), 1);
+    let anim = doc.animations().next().unwrap();
+    let scale_channels = anim
+        .channels()
+        .filter(|c| c.target().property() == gltf::animation::Property::Scale)
+        .count();
+    assert_eq!(scale_channels, 2);
+    Ok(())
+}
+
+#[test]
+fn append() -> Result<(), Error> {
+    let artifact = "./tests/artifacts/append.glb";
+    std::fs::copy("./assets/box_rotate_expected.glb", artifact).unwrap();
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("append")
+        .arg("--into")
+        .arg(artifact)
+        .arg("./assets/{tet}_#.vtk")
+        .assert()
+        .success();
+
+    let (doc, _, _) = gltf::import(artifact)?;
+    let mut nodes: Vec<_> = doc.nodes().filter_map(|n| n.name()).collect();
+    nodes.sort();
+    assert_eq!(nodes, vec!["box_rotate", "tet"]);
+    // The new animation is added alongside the existing one.
+    assert_eq!(doc.animations().count(), 2);
+    // The generated file is removed once merged.
+    assert!(!std::path::Path::new("./tests/artifacts/append.append.glb").exists());
+    Ok(())
+}
+
+#[test]
+fn diff() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("diff")
+        .arg("./assets/box_rotate_expected.glb")
+        .arg("./assets/box_rotate_expected.glb")
+        .assert()
+        .stdout(b"" as &[u8])
+        .success();
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("diff")
+        .arg("./assets/box_rotate_expected.glb")
+        .arg("./assets/box_rotate_no_animated_normals_expected.glb")
+        .assert()
+        .stdout(predicate::str::contains(
+            "meshes[0].primitives[0].targets[0].NORMAL: missing in second file",
+        ))
+        .stderr(predicate::str::contains("difference(s) between glTF files"))
+        .failure();
+}
+
+#[test]
+fn extract() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let dir = "./tests/artifacts/extract";
+    let _ = std::fs::remove_dir_all(dir);
+    cmd.arg("extract")
+        .arg("./assets/box_rotate_expected.glb")
+        .arg("-o")
+        .arg(format!("{}/box_#.obj", dir))
+        .assert()
+        .stderr(b"" as &[u8]) // No errors
+        .success();
+
+    assert_eq!(std::fs::read_dir(dir).unwrap().count(), 12);
+    // Frames are evaluated from morph targets and match the input sequence.
+    let frame = std::fs::read_to_string(format!("{}/box_2.obj", dir)).unwrap();
+    assert!(frame.contains("v -0.6642261 -0.5 -0.32607418"));
+}
+
+#[test]
+fn preview() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let html = "./tests/artifacts/box_rotate_preview.html";
+    cmd.arg("preview")
+        .arg("./assets/box_rotate_expected.glb")
+        .arg("--html")
+        .arg(html)
+        .assert()
+        .stderr(b"" as &[u8]) // No errors
+        .success();
+
+    let page = std::fs::read_to_string(html).unwrap();
+    assert!(page.contains("<model-viewer"));
+    assert!(page.contains("<title>box_rotate_expected.glb</title>"));
+    // The GLB is embedded starting with its "glTF" magic.
+    assert!(page.contains("src=\"data:model/gltf-binary;base64,Z2xURg"));
+}
+
+#[test]
+fn probe() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let output = cmd
+        .arg("probe")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert!(report["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("box_rotate_1.vtk"));
+    assert_eq!(report["num_vertices"], 8);
+    let attributes = report["attributes"].as_array().unwrap();
+    assert!(attributes.iter().any(|a| a["name"] == "pressure"
+        && a["location"] == "vertex"
+        && a["type"].is_string()
+        && a["len"] == 8));
+}
+
+#[test]
+fn toml_config() {
+    let config = "./tests/artifacts/gltfgen.toml";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let output = cmd
+        .arg("-f")
+        .arg("60")
+        .arg("-a")
+        .arg("{\"pressure\": f32}")
+        .arg("--material-id-map")
+        .arg("{3: 0}")
+        .arg("--print-toml-config")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .assert()
+        .success();
+    std::fs::write(config, &output.get_output().stdout).unwrap();
+
+    // Loading the printed config reproduces the original configuration.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(config)
+        .arg("--print-json-config")
+        .assert()
+        .stdout(predicate::str::contains("\"fps\":60"))
+        .stdout(predicate::str::contains(
+            "\"attributes\":{\"pressure\":\"F32\"}",
+        ))
+        .stdout(predicate::str::contains("\"material_id_map\":{\"3\":0}"))
+        .stdout(predicate::str::contains(
+            "\"pattern\":\"./assets/{box_rotate}_#.vtk\"",
+        ))
+        .success();
+}
+
+#[test]
+fn yaml_config() {
+    let config = "./tests/artifacts/gltfgen.yaml";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let output = cmd
+        .arg("-f")
+        .arg("60")
+        .arg("-a")
+        .arg("{\"pressure\": f32}")
+        .arg("--material-id-map")
+        .arg("{3: 0}")
+        .arg("--print-yaml-config")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .assert()
+        .success();
+    std::fs::write(config, &output.get_output().stdout).unwrap();
+
+    // Loading the printed config reproduces the original configuration.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(config)
+        .arg("--print-json-config")
+        .assert()
+        .stdout(predicate::str::contains("\"fps\":60"))
+        .stdout(predicate::str::contains(
+            "\"attributes\":{\"pressure\":\"F32\"}",
+        ))
+        .stdout(predicate::str::contains("\"material_id_map\":{\"3\":0}"))
+        .stdout(predicate::str::contains(
+            "\"pattern\":\"./assets/{box_rotate}_#.vtk\"",
+        ))
+        .success();
+}
+
+#[test]
+fn config_include_and_profile() {
+    let base = "./tests/artifacts/include_base.ron";
+    let shot = "./tests/artifacts/include_shot.json";
+    std::fs::write(
+        base,
+        r#"(
+            pattern: "./assets/{box_rotate}_#.vtk",
+            fps: 24,
+            textures: [(image: Embed("./assets/checker16.png"))],
+            profiles: { "preview": (fps: 12, step: 2) },
+        )"#,
+    )
+    .unwrap();
+    std::fs::write(
+        shot,
+        r#"{ "include": ["include_base.ron"], "output": "./shot.glb", "profiles": { "preview": { "step": 3 } } }"#,
+    )
+    .unwrap();
+
+    // Fields of the included file are overridden by the including file.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(shot)
+        .arg("--print-json-config")
+        .assert()
+        .stdout(predicate::str::contains(
+            "\"pattern\":\"./assets/{box_rotate}_#.vtk\"",
+        ))
+        .stdout(predicate::str::contains("\"output\":\"./shot.glb\""))
+        .stdout(predicate::str::contains("\"fps\":24,"))
+        .stdout(predicate::str::contains(
+            "\"image\":{\"Embed\":\"./assets/checker16.png\"}",
+        ))
+        .success();
+
+    // Profiles from all files are combined and applied last.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(shot)
+        .arg("--profile")
+        .arg("preview")
+        .arg("--print-json-config")
+        .assert()
+        .stdout(predicate::str::contains("\"fps\":12,"))
+        .stdout(predicate::str::contains("\"step\":3,"))
+        .success();
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(shot)
+        .arg("--profile")
+        .arg("final")
+        .arg("--print-json-config")
+        .assert()
+        .stderr(predicate::str::contains(
+            "Configuration profile not found: final",
+        ))
+        .failure();
+}
+
+#[test]
+fn strict_exit_codes() {
+    let artifact = "./tests/artifacts/strict.glb";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("-a")
+        .arg("{\"pressure\": f32}")
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("-a")
+        .arg("{\"pressure\": f32}")
+        .arg("--strict")
+        .assert()
+        .stderr(predicate::str::contains("attribute error(s) occurred"))
+        .code(5);
+
+    // Warnings reported while building the glTF fail before the output is written.
+    let unwritten = "./tests/artifacts/strict_warnings.glb";
+    let _ = std::fs::remove_file(unwritten);
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(unwritten)
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("-m")
+        .arg("(name:\"red\")")
+        .arg("--material-id-map")
+        .arg("{0: 1}")
+        .arg("--strict")
+        .assert()
+        .stderr(predicate::str::contains(
+            "warning(s) were reported in strict mode",
+        ))
+        .code(1);
+    assert!(!std::path::Path::new(unwritten).exists());
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/missing_#.vtk")
+        .assert()
+        .stderr(predicate::str::contains("No valid meshes were found"))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/missing/strict.glb")
+        .arg("./assets/{box_triangulated}.vtk")
+        .assert()
+        .stderr(predicate::str::contains("Failed to write output"))
+        .code(6);
+}
+
+#[test]
+fn report() {
+    let report = "./tests/artifacts/report.json";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/report.glb")
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("-a")
+        .arg("{\"pressure\": f32}")
+        .arg("--report")
+        .arg(report)
+        .assert()
+        .success();
+
+    let report: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(report).unwrap()).unwrap();
+    assert_eq!(report["files"][0]["loaded"], true);
+    assert_eq!(report["nodes"][0]["name"], "box_triangulated");
+    assert!(report["nodes"][0]["attributes"]
+        .as_array()
+        .unwrap()
+        .contains(&"_PRESSURE".into()));
+    assert_eq!(
+        report["nodes"][0]["dropped_attributes"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(report["output"]["buffer_bytes"].as_u64().unwrap() > 0);
+    assert!(report["timings"]["total"].is_number());
+}
+
+#[test]
+fn box_rotate_single_thread() -> Result<(), Error> {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    let artifact = "./tests/artifacts/box_rotate_single_thread.glb";
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("-r") // reverse polygon orientation
+        .arg("-x")
+        .arg("(image: Embed(\"./assets/checker16.png\"))")
+        .arg("-m")
+        .arg("(name:\"checkerboard\", base_texture:(index:0,texcoord:0))")
+        .arg("--threads")
+        .arg("1")
+        .assert()
+        .stderr(b"" as &[u8]) // No errors
+        .success();
+
+    let expected = Gltf::open("./assets/box_rotate_expected.glb")?;
+    let actual = Gltf::open(artifact)?;
+
+    assert_eq_gltf_with_bytes(&expected, &actual);
+    Ok(())
+}
+
+#[test]
+fn no_overwrite() {
+    let artifact = "./tests/artifacts/no_overwrite.gltf";
+    let _ = std::fs::remove_file(artifact);
+    let _ = std::fs::remove_file("./tests/artifacts/no_overwrite.bin");
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+        cmd.arg("-o")
+            .arg(artifact)
+            .arg("./assets/{box_triangulated}.vtk")
+            .arg("--no-overwrite")
+            .args(args)
+            .assert()
+    };
+
+    run(&[]).success();
+    run(&[])
+        .stderr(predicate::str::contains("Output file already exists"))
+        .code(6);
+    run(&["--force"]).success();
+
+    // No temporary files are left behind.
+    let leftovers = std::fs::read_dir("./tests/artifacts")
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+#[test]
+fn frame_width_and_offset() {
+    let report = "./tests/artifacts/frame_width_and_offset.json";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/frame_width_and_offset.glb")
+        .arg("./assets/{box_rotate}_#{2}.vtk")
+        .arg("--frame-offset=-11")
+        .arg("--report")
+        .arg(report)
+        .assert()
+        .success();
+
+    let report: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(report).unwrap()).unwrap();
+    let mut frames: Vec<_> = report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| (f["frame"].as_u64(), f["loaded"].as_bool().unwrap()))
+        .collect();
+    frames.sort();
+    // Frame 10 becomes negative and is skipped.
+    assert_eq!(
+        frames,
+        vec![(None, false), (Some(0), true), (Some(1), true)]
+    );
+}
+
+#[test]
+fn pattern_alternatives() {
+    let report = "./tests/artifacts/pattern_alternatives.json";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/pattern_alternatives.glb")
+        .arg("./assets/{tet,box_rotate}_#.vtk")
+        .arg("--report")
+        .arg(report)
+        .assert()
+        .success();
+
+    let report: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(report).unwrap()).unwrap();
+    let loaded = report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|f| f["loaded"] == true)
+        .count();
+    assert_eq!(loaded, 14);
+    let mut nodes: Vec<_> = report["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["name"].as_str().unwrap())
+        .collect();
+    nodes.sort();
+    assert_eq!(nodes, vec!["box_rotate", "tet"]);
+}
+
+#[test]
+fn case_insensitive() {
+    let dir = "./tests/artifacts/case_insensitive";
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::copy("./assets/box_rotate_1.vtk", format!("{}/Box_1.VTK", dir)).unwrap();
+    std::fs::copy("./assets/box_rotate_2.obj", format!("{}/Box_2.Obj", dir)).unwrap();
+    std::fs::copy("./assets/box_rotate_3.vtk", format!("{}/Bóx_3.vtk", dir)).unwrap();
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/case_insensitive.glb")
+        .arg(format!("{}/box_#.*", dir))
+        .arg("-v") // Show warnings
+        .assert()
+        .stderr(predicate::str::contains("Use '--case-insensitive'"))
+        .code(3);
+
+    let report = "./tests/artifacts/case_insensitive.json";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/case_insensitive.glb")
+        .arg(format!("{}/{{box,BÓX}}_#.*", dir))
+        .arg("--case-insensitive")
+        .arg("--report")
+        .arg(report)
+        .assert()
+        .success();
+
+    let report: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(report).unwrap()).unwrap();
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 3);
+    assert!(files.iter().all(|f| f["loaded"] == true));
+}
+
+#[test]
+fn sidecar_extras() {
+    let dir = "./tests/artifacts/sidecar";
+    std::fs::create_dir_all(dir).unwrap();
+    for frame in 1..=3 {
+        std::fs::copy(
+            format!("./assets/box_rotate_{}.vtk", frame),
+            format!("{}/box_{}.vtk", dir, frame),
+        )
+        .unwrap();
+    }
+    // Frame 2 has no sidecar.
+    for frame in [1, 3] {
+        std::fs::write(
+            format!("{}/box_{}.json", dir, frame),
+            format!("{{\"iterations\": {}}}", frame * 10),
+        )
+        .unwrap();
+    }
+
+    let artifact = "./tests/artifacts/sidecar.gltf";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg(format!("{}/box_#.vtk", dir))
+        .arg("--sidecar")
+        .arg("--extras")
+        .arg("(nodes:{\"box\":{\"solver\":\"v2.1\"}})")
+        .assert()
+        .success();
+
+    let gltf: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(artifact).unwrap()).unwrap();
+    let extras = &gltf["nodes"][0]["extras"];
+    assert_eq!(extras["solver"], "v2.1");
+    assert_eq!(
+        extras["frames"],
+        serde_json::json!({ "1": { "iterations": 10 }, "3": { "iterations": 30 } })
+    );
+}
+
+#[test]
+fn order_frames() {
+    let frame_of = |order: &str, file: &str| {
+        let report = format!("./tests/artifacts/order_{}.json", order);
+        let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+        cmd.arg("-o")
+            .arg(format!("./tests/artifacts/order_{}.glb", order))
+            .arg("./assets/{box_rotate}_*.vtk")
+            .arg("--order")
+            .arg(order)
+            .arg("--report")
+            .arg(&report)
+            .assert()
+            .success();
+        let report: serde_json::Value =
+            serde_json::from_reader(std::fs::File::open(report).unwrap()).unwrap();
+        report["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|f| f["path"].as_str().unwrap().ends_with(file))
+            .unwrap()["frame"]
+            .as_u64()
+            .unwrap()
+    };
+
+    assert_eq!(frame_of("name", "box_rotate_10.vtk"), 1);
+    assert_eq!(frame_of("natural", "box_rotate_10.vtk"), 9);
+    assert_eq!(frame_of("natural", "box_rotate_1.vtk"), 0);
+}
+
+#[test]
+fn unsupported_attribute_type() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/unsupported_attribute.glb")
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("-a")
+        .arg("{\"pressure\": Vec3(f32)}")
+        .assert()
+        .stderr(predicate::str::contains("does not have the specified type"))
+        .code(1);
+}
+
+#[test]
+fn dedup_buffers() -> Result<(), Error> {
+    let generate = |artifact: &str, dedup: bool| {
+        let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+        cmd.arg("-o")
+            .arg(artifact)
+            .arg("./assets/{box_rotate}_#.vtk")
+            .arg("--validate")
+            .arg("-vv"); // Show validation results
+        if dedup {
+            cmd.arg("--dedup-buffers");
+        }
+        cmd.assert()
+            .stderr(
+                predicate::str::contains("passed").and(predicate::str::contains("failed").not()),
+            )
+            .success();
+    };
+    let artifact = "./tests/artifacts/dedup_buffers.glb";
+    let reference = "./tests/artifacts/dedup_buffers_reference.glb";
+    generate(artifact, true);
+    generate(reference, false);
+
+    let (expected, expected_buffers, _) = gltf::import(reference)?;
+    let (actual, actual_buffers, _) = gltf::import(artifact)?;
+    assert!(actual.views().len() < expected.views().len());
+    assert!(actual_buffers[0].len() < expected_buffers[0].len());
+
+    // Accessors still read the same data.
+    for (a, b) in actual.accessors().zip(expected.accessors()) {
+        let read = |acc: &gltf::Accessor, buffers: &[gltf::buffer::Data]| {
+            let view = acc.view().unwrap();
+            let start = view.offset() + acc.offset();
+            buffers[view.buffer().index()][start..start + acc.count() * acc.size()].to_vec()
+        };
+        assert_eq!(a.count(), b.count());
+        if a.view().is_some() {
+            assert_eq!(read(&a, &actual_buffers), read(&b, &expected_buffers));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn minimal_output() {
+    let generate = |artifact: &str, minimal: bool| {
+        let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+        cmd.arg("-o")
+            .arg(artifact)
+            .arg("./assets/{box_rotate}_#.vtk")
+            .arg("--extras")
+            .arg("(nodes:{\"box_rotate\":{\"solver\":\"v2.1\"}})");
+        if minimal {
+            cmd.arg("--minimal");
+        }
+        cmd.assert().success();
+        std::fs::read_to_string(artifact).unwrap()
+    };
+    let reference = generate("./tests/artifacts/minimal_reference.gltf", false);
+    let minimal = generate("./tests/artifacts/minimal.gltf", true);
+    assert!(minimal.len() < reference.len());
+    assert!(!minimal.contains('\n'));
+
+    let gltf: serde_json::Value = serde_json::from_str(&minimal).unwrap();
+    assert!(gltf["nodes"][0].get("name").is_none());
+    assert!(gltf["nodes"][0].get("extras").is_none());
+    let accessors = gltf["accessors"].as_array().unwrap();
+    assert!(accessors.iter().all(|acc| acc.get("name").is_none()));
+}
+
+#[test]
+fn split_buffers() -> Result<(), Error> {
+    let artifact = "./tests/artifacts/split_buffers.gltf";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate,tet}_#.vtk")
+        .arg("--split-buffers")
+        .arg("--validate")
+        .arg("-vv") // Show validation results
+        .assert()
+        .stderr(predicate::str::contains("passed").and(predicate::str::contains("failed").not()))
+        .success();
+
+    let (doc, buffers, _) = gltf::import(artifact)?;
+    let mut names: Vec<_> = doc.buffers().filter_map(|b| b.name()).collect();
+    names.sort();
+    assert_eq!(names, vec!["box_rotate", "tet"]);
+    assert!(std::path::Path::new("./tests/artifacts/split_buffers_tet.bin").exists());
+    // Each animated node reads its data from its own buffer.
+    for node in doc.nodes() {
+        let buffer = node
+            .mesh()
+            .unwrap()
+            .primitives()
+            .next()
+            .unwrap()
+            .get(&gltf::Semantic::Positions)
+            .unwrap()
+            .view()
+            .unwrap()
+            .buffer();
+        assert_eq!(buffer.name(), node.name());
+        assert!(!buffers[buffer.index()].is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn size_report() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/size_report.glb")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--size-report")
+        .assert()
+        .stdout(predicate::str::contains("morph normals"))
+        .stdout(predicate::str::contains("morph targets by node:"))
+        .stdout(predicate::str::contains("  box_rotate "))
+        .stdout(predicate::str::contains("'--no-animated-normals'"))
+        .success();
+}
+
+#[test]
+fn verbose_summary() {
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/verbose_summary.glb")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("-v")
+        .assert()
+        .stdout(predicate::str::is_match(r"box_rotate +1-12 \(12\) +1  ").unwrap())
+        .stdout(predicate::str::contains("animations: 1"))
+        .stdout(predicate::str::contains(
+            "texture images: 0 embedded, 0 referenced",
+        ))
+        .stdout(predicate::str::contains("verbose_summary.glb: "))
+        .success();
+
+    // The summary is only printed with -v.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/verbose_summary.glb")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .assert()
+        .stdout(predicate::str::contains("texture images").not())
+        .success();
+}
+
+#[test]
+fn material_id_map() {
+    let report = "./tests/artifacts/material_id_map.json";
+    let run = |default_material: bool| {
+        let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+        cmd.arg("-o")
+            .arg("./tests/artifacts/material_id_map.glb")
+            .arg("./assets/{box_triangulated}.vtk")
+            .arg("-m")
+            .arg("(name:\"red\")")
+            .arg("--material-id-map")
+            .arg("{0: 1}")
+            .arg("--report")
+            .arg(report)
+            .arg("-v"); // Show warnings
+        if default_material {
+            cmd.arg("--default-material");
+        }
+        let assert = cmd.assert().success();
+        let report: serde_json::Value =
+            serde_json::from_reader(std::fs::File::open(report).unwrap()).unwrap();
+        (assert, report)
+    };
+
+    // Material id 0 is remapped past the only specified material.
+    let (assert, report) = run(false);
+    assert.stderr(predicate::str::contains(
+        "Material ID 0 of 'box_triangulated' does not refer to any of the 1 specified materials.",
+    ));
+    let node = &report["nodes"][0];
+    assert_eq!(node["material_ids"][0]["id"], 0);
+    assert!(node["material_ids"][0]["material"].is_null());
+    assert!(node["materials"].as_array().unwrap().is_empty());
+
+    let (_, report) = run(true);
+    let node = &report["nodes"][0];
+    assert_eq!(node["material_ids"][0]["material"], 1);
+    assert_eq!(node["materials"][0], "Default");
+}
+
+#[test]
+fn config_lint() {
+    let run = |material: &str| {
+        Command::cargo_bin("gltfgen")
+            .unwrap()
+            .arg("-o")
+            .arg("./tests/artifacts/config_lint.glb")
+            .arg("./assets/{box_triangulated}.vtk")
+            .arg("-m")
+            .arg(material)
+            .assert()
+            .failure()
+    };
+
+    // Misspelled fields and variants are rejected with a suggestion.
+    run("(name:\"red\", metalic:0.5)").code(2).stderr(
+        predicate::str::contains("did you mean `metallic`?")
+            .and(predicate::str::contains("^^^^^^^"))
+            .and(predicate::str::contains("For example:")),
+    );
+
+    // Values are checked before any meshes are loaded.
+    run("(name:\"red\", roughness:1.5, base_texture:(index:1, texcoord:0))")
+        .code(1)
+        .stderr(
+            predicate::str::contains("materials[0].roughness: factor 1.5 is outside")
+                .and(predicate::str::contains(
+                    "materials[0].base_texture: texture index 1 is out of range for 0 specified texture(s)",
+                ))
+                .and(predicate::str::contains(
+                    "2 problem(s) found in the configuration",
+                )),
+        );
+
+    // KTX2 images are rejected instead of being written without mip chains.
+    Command::cargo_bin("gltfgen")
+        .unwrap()
+        .arg("-o")
+        .arg("./tests/artifacts/config_lint.glb")
+        .arg("./assets/{box_triangulated}.vtk")
+        .arg("-x")
+        .arg("(image: Embed(\"./assets/checker16.ktx2\"), min_filter: LinearMipmapLinear)")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "textures[0].image: KTX2 images and mip chain generation are not supported",
+        ));
+}
+
+#[test]
+fn manifest() {
+    let artifact = "./tests/artifacts/manifest.gltf";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--manifest")
+        .assert()
+        .success();
+
+    let manifest = std::fs::read_to_string("./tests/artifacts/manifest.manifest.json").unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    assert_eq!(manifest["tool"], "gltfgen");
+    assert_eq!(manifest["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest["config"]["manifest"], true);
+    assert_eq!(manifest["inputs"].as_array().unwrap().len(), 12);
+    // Both the .gltf and the .bin files are recorded.
+    let outputs = manifest["outputs"].as_array().unwrap();
+    assert_eq!(outputs.len(), 2);
+    for output in outputs {
+        let path = output["path"].as_str().unwrap();
+        let digest = gltfgen::manifest::FileDigest::from_path(path).unwrap();
+        assert_eq!(output["sha256"], digest.sha256);
+        assert_eq!(output["bytes"], digest.bytes);
+    }
+}
+
+#[test]
+fn deterministic() {
+    // Two objects using the same two textured materials in every frame.
+    let dir = "./tests/artifacts/deterministic";
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(
+        format!("{}/shared.mtl", dir),
+        "newmtl red\nKd 1 0 0\nmap_Kd ./assets/checker16.png\n\nnewmtl blue\nKd 0 0 1\n",
+    )
+    .unwrap();
+    for name in ["left", "right"] {
+        for frame in 1..=3 {
+            std::fs::write(
+                format!("{}/{}_{}.obj", dir, name, frame),
+                format!(
+                    "mtllib shared.mtl\n\
+                     v 0 0 {}\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                     vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+                     usemtl red\nf 1/1 2/2 3/3\n\
+                     usemtl blue\nf 1/1 3/3 4/4\n",
+                    frame
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    let export = |threads: &str| {
+        let artifact = format!("./tests/artifacts/deterministic_{}.glb", threads);
+        let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+        cmd.arg("-o")
+            .arg(&artifact)
+            .arg(format!("{}/{{*}}_#.obj", dir))
+            .arg("--deterministic")
+            .arg("--threads")
+            .arg(threads)
+            .assert()
+            .success();
+        std::fs::read(artifact).unwrap()
+    };
+    let glb = export("1");
+    assert_eq!(glb, export("4"));
+
+    // Materials and textures of each object are kept rather than shared.
+    let gltf = Gltf::from_slice(&glb).unwrap();
+    let names: Vec<_> = gltf.materials().map(|m| m.name().unwrap_or("")).collect();
+    assert_eq!(names, ["red", "blue", "red", "blue"]);
+    assert_eq!(gltf.textures().count(), 2);
+
+    // Cached frames can't be used for deterministic output.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/deterministic_cache.glb")
+        .arg(format!("{}/{{*}}_#.obj", dir))
+        .arg("--deterministic")
+        .arg("--cache")
+        .arg("./tests/artifacts/deterministic_cache")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("can't be combined with 'cache'"));
+}
+
+#[test]
+fn resume() {
+    let artifact = "./tests/artifacts/resume.glb";
+    let checkpoints = "./tests/artifacts/resume.glb.resume";
+    // Checkpoint all frames as an interrupted export would.
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg("./tests/artifacts/resume_interrupted.glb")
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--cache")
+        .arg(checkpoints)
+        .assert()
+        .success();
+    assert!(std::fs::read_dir(checkpoints).unwrap().next().is_some());
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--resume")
+        .assert()
+        .success();
+    assert!(std::path::Path::new(artifact).exists());
+    // Checkpoints are removed once the export succeeds.
+    assert!(!std::path::Path::new(checkpoints).exists());
+}
+
+#[test]
+fn duration() -> Result<(), Error> {
+    let artifact = "./tests/artifacts/duration.glb";
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--duration")
+        .arg("5.5s")
+        .assert()
+        .success();
+
+    // Frames 1 to 12 are spread over 5.5 seconds.
+    let (doc, _, _) = gltf::import(artifact)?;
+    let anim = doc.animations().next().unwrap();
+    let input = anim.channels().next().unwrap().sampler().input();
+    let time = |value: Option<gltf::json::Value>| value.unwrap()[0].as_f64().unwrap();
+    assert!((time(input.min()) - 0.5).abs() < 1e-6);
+    assert!((time(input.max()) - 6.0).abs() < 1e-6);
+
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("-o")
+        .arg(artifact)
+        .arg("./assets/{box_rotate}_#.vtk")
+        .arg("--fps")
+        .arg("fast")
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn animation_overrides() -> Result<(), Error> {
+    let config = "./tests/artifacts/overrides.ron";
+    let artifact = "./tests/artifacts/overrides.glb";
+    std::fs::write(
+        config,
+        r#"(
+            pattern: "./assets/{box_rotate}_#.vtk",
+            materials: [(name: "first"), (name: "second")],
+            overrides: {
+                "box_rotate": (material: Some("second"), step: Some(2), translation: Some((1.0, 2.0, 3.0))),
+            },
+        )"#,
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(config)
+        .arg("-o")
+        .arg(artifact)
+        .arg("--report")
+        .arg("./tests/artifacts/overrides.json")
+        .assert()
+        .success();
+
+    let (doc, _, _) = gltf::import(artifact)?;
+    let node = doc
+        .nodes()
+        .find(|n| n.name() == Some("box_rotate"))
+        .unwrap();
+    assert_eq!(node.transform().decomposed().0, [1.0, 2.0, 3.0]);
+    let primitive = node.mesh().unwrap().primitives().next().unwrap();
+    assert_eq!(primitive.material().name(), Some("second"));
+    // Every other frame of the 12 frames is kept.
+    let report = std::fs::read_to_string("./tests/artifacts/overrides.json").unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["skipped"]["pruned by step"], 6);
+    Ok(())
+}
+
+#[test]
+fn texture_atlas() -> Result<(), Error> {
+    let config = "./tests/artifacts/texture_atlas.ron";
+    let artifact = "./tests/artifacts/texture_atlas.glb";
+    std::fs::write(
+        config,
+        r#"(
+            pattern: "./assets/{box_rotate}_#.obj",
+            textures: [
+                (image: Embed("./assets/checker16.png")),
+                (image: Embed("./assets/checker16.png")),
+                (image: Embed("./assets/checker16.png")),
+            ],
+            materials: [
+                (name: "box", base_texture: (index: 0, texcoord: 0)),
+                (name: "left", base_texture: (index: 1, texcoord: 0)),
+                (name: "right", base_texture: (index: 2, texcoord: 0)),
+            ],
+            texture_atlas: Some(64),
+        )"#,
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("gltfgen").unwrap();
+    cmd.arg("--config")
+        .arg(config)
+        .arg("-o")
+        .arg(artifact)
+        .assert()
+        .success();
+
+    let (doc, _, images) = gltf::import(artifact)?;
+    // The box repeats its texture, so only the other two are packed.
+    assert_eq!(doc.images().count(), 2);
+    let atlas = doc.images().find(|i| i.name() == Some("atlas")).unwrap();
+    assert_eq!(
+        (images[atlas.index()].width, images[atlas.index()].height),
+        (32, 40)
+    );
+    let textures: Vec<_> = doc.textures().map(|t| t.source().index()).collect();
+    assert_ne!(textures[0], atlas.index());
+    assert_eq!(textures[1..], [atlas.index(), atlas.index()]);
+    // Materials sampling the atlas only differed by name and are merged.
+    let materials: Vec<_> = doc.materials().map(|m| m.name().unwrap()).collect();
+    assert_eq!(materials, ["box", "left"]);
+    Ok(())
+}